@@ -1,19 +1,41 @@
 // Re-export Identifier so it can be used directly by users of this module.
 use shared_context::{Identifier, OperandSize, StaticVariable};
+use std::collections::HashMap;
 
 /// Represents an entire assembly-level program.
 ///
 /// A Program consists of a collection of functions, each represented
 /// by a FunctionDef. This is the final stage before emitting actual
 /// assembly text or binary output.
+#[derive(Clone)]
 pub struct Program {
     items: Vec<TopLevel>,
+    frame_sizes: HashMap<Identifier, u64>, // each function's stack frame size, once allocated
 }
 
 impl Program {
     /// Creates a new Program from a list of function definitions.
     pub fn new(items: Vec<TopLevel>) -> Self {
-        Self { items }
+        Self {
+            items,
+            frame_sizes: HashMap::new(),
+        }
+    }
+
+    /// Records the stack frame size of each function, set by register allocation.
+    pub fn set_frame_sizes(&mut self, frame_sizes: HashMap<Identifier, u64>) {
+        self.frame_sizes = frame_sizes;
+    }
+
+    /// Returns the stack frame size of each function in bytes: its stack slots,
+    /// saved registers and canary, rounded up to a multiple of 16.
+    pub fn get_frame_sizes(&self) -> &HashMap<Identifier, u64> {
+        &self.frame_sizes
+    }
+
+    /// Returns the top level items.
+    pub fn get_functions(&self) -> &[TopLevel] {
+        &self.items
     }
 
     /// Consumes the Program and returns the list of contained functions.
@@ -29,17 +51,34 @@ impl Program {
 
 /// represent a global object in the assembly
 ///
-/// can be a static variable definition or a function definition
+/// can be a static variable definition, a function definition,
+/// or a read-only double constant identified by its bit pattern
+#[derive(Clone)]
 pub enum TopLevel {
     S(StaticVariable),
     F(FunctionDef),
+    C(u64),
 }
 
 /// Represents a single function in the generated assembly program.
+#[derive(Clone)]
 pub struct FunctionDef {
     name: Identifier,
     external: bool,
     instructions: Vec<Instruction>,
+    annotations: Vec<String>, // text referred to by `Instruction::Annotation`
+    callee_saved: Vec<Register>, // saved by the prologue and restored before each `ret`
+    canary: Option<i64>,      // stack slot of the stack protector's canary, when enabled
+}
+
+/// The parts of a `FunctionDef`, as returned by `FunctionDef::into_parts`
+pub struct FunctionParts {
+    pub name: Identifier,
+    pub external: bool,
+    pub instructions: Vec<Instruction>,
+    pub annotations: Vec<String>, // text referred to by `Instruction::Annotation`
+    pub callee_saved: Vec<Register>, // saved by the prologue and restored before each `ret`
+    pub canary: Option<i64>,      // stack slot of the stack protector's canary, when enabled
 }
 
 impl FunctionDef {
@@ -49,18 +88,55 @@ impl FunctionDef {
             name,
             external,
             instructions,
+            annotations: Vec::new(),
+            callee_saved: Vec::new(),
+            canary: None,
         }
     }
 
-    /// Consumes the `FunctionDef` and returns its name and instructions.
-    pub fn into_parts(self) -> (Identifier, bool, Vec<Instruction>) {
-        (self.name, self.external, self.instructions)
+    /// Attaches the annotation texts indexed by `Instruction::Annotation`.
+    pub fn with_annotations(mut self, annotations: Vec<String>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Records the callee-saved registers the function's body uses, in the order they're pushed.
+    pub fn set_callee_saved(&mut self, registers: Vec<Register>) {
+        self.callee_saved = registers;
+    }
+
+    /// Records the stack slot the prologue stores the stack protector's canary in.
+    pub fn set_canary(&mut self, offset: i64) {
+        self.canary = Some(offset);
+    }
+
+    /// Consumes the `FunctionDef` and returns its name, instructions, annotations,
+    /// the callee-saved registers it uses and its canary slot.
+    pub fn into_parts(self) -> FunctionParts {
+        FunctionParts {
+            name: self.name,
+            external: self.external,
+            instructions: self.instructions,
+            annotations: self.annotations,
+            callee_saved: self.callee_saved,
+            canary: self.canary,
+        }
     }
 
     /// Returns a mutable reference to the function’s instruction list.
     pub fn get_mut_instructions(&mut self) -> &mut Vec<Instruction> {
         &mut self.instructions
     }
+
+    /// Returns the function’s instruction list.
+    pub fn get_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Returns the function's name.
+    pub fn get_name(&self) -> Identifier {
+        self.name
+    }
 }
 
 /// Represents a single assembly instruction in the program.
@@ -87,6 +163,35 @@ pub enum Instruction {
         dst: Operand,
     },
 
+    /// Zero extend a byte to a longword or a quadword (`movzbl`, `movzbq`),
+    /// `dst` is a register once instructions are fixed
+    Movzb {
+        size: OperandSize,
+        src: Operand,
+        dst: Operand,
+    },
+
+    /// Convert a double to a signed integer of the given size, truncating toward zero
+    Cvttsd2si {
+        size: OperandSize,
+        src: Operand,
+        dst: Operand,
+    },
+
+    /// Convert a signed integer of the given size to a double
+    Cvtsi2sd {
+        size: OperandSize,
+        src: Operand,
+        dst: Operand,
+    },
+
+    /// Load the address of `src` into `dst`, `src` is a memory operand
+    /// (`&local` is `leaq offset(%rbp), dst`), `dst` is a register once instructions are fixed
+    Lea {
+        src: Operand,
+        dst: Operand,
+    },
+
     /// Unary operation (e.g., `neg`, `not`)
     Unary {
         op: UnaryOP,
@@ -109,6 +214,14 @@ pub enum Instruction {
         dst: Operand,
     },
 
+    /// Bitwise AND of two operands that only sets the flags,
+    /// `test x, x` compares `x` with 0 like `cmp $0, x` in fewer bytes
+    Test {
+        size: OperandSize,
+        src: Operand,
+        dst: Operand,
+    },
+
     /// Signed integer division
     Idiv(OperandSize, Operand),
 
@@ -127,6 +240,15 @@ pub enum Instruction {
     /// Set destination byte based on condition flags
     SetCC(Cond, Operand),
 
+    /// Move `src` into `dst` only when the condition holds (`cmovcc`),
+    /// `dst` must be a register
+    Cmov {
+        cond: Cond,
+        size: OperandSize,
+        src: Operand,
+        dst: Operand,
+    },
+
     /// Marks a label within the instruction stream
     Label(Identifier),
 
@@ -136,11 +258,54 @@ pub enum Instruction {
     /// Call a function
     Call(Identifier),
 
+    /// Call the function whose address is in the operand
+    CallIndirect(Operand),
+
     /// Return from function
     Ret,
+
+    /// Marks the start of the code generated for a source line (emitted as `.loc`)
+    SourceLine(usize),
+
+    /// Marks the start of the code generated for a Tacky instruction,
+    /// the index refers to the function's annotations.
+    Annotation(usize),
+}
+
+impl Instruction {
+    /// Returns every operand the instruction reads or writes.
+    pub fn get_operands(&self) -> Vec<Operand> {
+        match *self {
+            Instruction::Mov { src, dst, .. }
+            | Instruction::Movsx { src, dst }
+            | Instruction::Movzx { src, dst }
+            | Instruction::Movzb { src, dst, .. }
+            | Instruction::Cvttsd2si { src, dst, .. }
+            | Instruction::Cvtsi2sd { src, dst, .. }
+            | Instruction::Lea { src, dst }
+            | Instruction::Binary { src, dst, .. }
+            | Instruction::Cmp { src, dst, .. }
+            | Instruction::Test { src, dst, .. }
+            | Instruction::Cmov { src, dst, .. } => vec![src, dst],
+            Instruction::Unary { dst: op, .. }
+            | Instruction::Idiv(_, op)
+            | Instruction::Div(_, op)
+            | Instruction::SetCC(_, op)
+            | Instruction::Push(op)
+            | Instruction::CallIndirect(op) => vec![op],
+            Instruction::Cdq(_)
+            | Instruction::Jmp(_)
+            | Instruction::JmpCC(..)
+            | Instruction::Label(_)
+            | Instruction::Call(_)
+            | Instruction::Ret
+            | Instruction::SourceLine(_)
+            | Instruction::Annotation(_) => Vec::new(),
+        }
+    }
 }
 
-/// Represents possible jump or comparison conditions (for `JmpCC` / `SetCC`).
+/// Represents possible jump or comparison conditions (for `JmpCC`, `SetCC` and `Cmov`).
 #[derive(Clone, Debug, Copy)]
 pub enum Cond {
     E,  // Equal
@@ -153,24 +318,70 @@ pub enum Cond {
     AE, // Greater than or equal for unsigned
     B,  // Less than for unsigned,
     BE, // Less than or equal for unsigned
+    P,  // Parity, set by a comparison of doubles when either is a NaN
+    NP, // No parity
+}
+
+impl Cond {
+    /// The condition that holds for a comparison of the same operands in the other order,
+    /// `a > b` is `b < a`. Only valid for integer comparisons, a comparison with a NaN
+    /// sets the flags of every unsigned condition but `A` and `AE`.
+    pub fn swapped(self) -> Cond {
+        match self {
+            Cond::E | Cond::NE | Cond::P | Cond::NP => self,
+            Cond::G => Cond::L,
+            Cond::GE => Cond::LE,
+            Cond::L => Cond::G,
+            Cond::LE => Cond::GE,
+            Cond::A => Cond::B,
+            Cond::AE => Cond::BE,
+            Cond::B => Cond::A,
+            Cond::BE => Cond::AE,
+        }
+    }
+
+    /// The condition that holds when this one doesn't, `a < b` is false when `a >= b`.
+    /// The flags of a comparison of doubles are adjusted so that it's also valid for them,
+    /// see `AsmGen::gen_double_compare`.
+    pub fn negated(self) -> Cond {
+        match self {
+            Cond::E => Cond::NE,
+            Cond::NE => Cond::E,
+            Cond::G => Cond::LE,
+            Cond::GE => Cond::L,
+            Cond::L => Cond::GE,
+            Cond::LE => Cond::G,
+            Cond::A => Cond::BE,
+            Cond::AE => Cond::B,
+            Cond::B => Cond::AE,
+            Cond::BE => Cond::A,
+            Cond::P => Cond::NP,
+            Cond::NP => Cond::P,
+        }
+    }
 }
 
 /// Represents the types of operands that can appear in an instruction.
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Operand {
-    Reg(Register),      // Physical CPU register
-    Pseudo(Identifier), // Compiler-generated pseudo-register (before allocation)
-    Stack(i64),         // Stack slot (offset from base pointer)
-    Immediate(u64),     // Immediate constant value
-    Data(Identifier),   // For RIP relative addressing
+    Reg(Register),                    // Physical CPU register
+    Pseudo(Identifier),               // Compiler-generated pseudo-register (before allocation)
+    Stack(i64),                       // Stack slot (offset from base pointer)
+    Memory(Register, i64),            // Memory at an offset from the address in a register
+    Indexed(Register, Register, u64), // Memory at `base + index * scale`, the scale is 1, 2, 4 or 8
+    Immediate(u64),                   // Immediate constant value
+    Data(Identifier),                 // For RIP relative addressing
+    DoubleConst(u64), // A read-only double constant, by bit pattern (doubles have no immediates)
 }
 
 /// Enumerates the general-purpose registers available for use.
 ///
 /// These correspond to x86-64 registers typically used for temporaries or arguments.
+/// `BX` and `R12`-`R15` are callee-saved, a function using one has to restore it before returning.
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Register {
     AX,
+    BX,
     CX,
     DX,
     DI,
@@ -179,15 +390,62 @@ pub enum Register {
     R9,
     R10,
     R11,
+    R12,
+    R13,
+    R14,
+    R15,
     SP,
+    XMM0,
+    XMM1,
+    XMM2,
+    XMM3,
+    XMM4,
+    XMM5,
+    XMM6,
+    XMM7,
+    XMM14,
+    XMM15,
+}
+
+impl Operand {
+    /// check if an Operand is an immediate that can't be encoded in an instruction of the given size
+    ///
+    /// Negative constants are stored as their 64 bit pattern, so the immediate is interpreted by size:
+    /// a LongWord instruction only uses the low 32 bits, which always fit,
+    /// and a QuadWord instruction sign-extends a 32 bit immediate,
+    /// so the value fits only if sign-extending its low 32 bits gives it back.
+    /// Only `movabsq` into a register takes a full 64 bit immediate.
+    pub fn is_large_immediate(self, size: OperandSize) -> bool {
+        match (self, size) {
+            (Operand::Immediate(int), OperandSize::QuadWord) => int as i32 as i64 as u64 != int,
+            _ => false,
+        }
+    }
+}
+
+impl Register {
+    /// Whether the System V ABI requires a function to preserve the register for its caller.
+    pub fn is_callee_saved(self) -> bool {
+        matches!(
+            self,
+            Register::BX | Register::R12 | Register::R13 | Register::R14 | Register::R15
+        )
+    }
 }
 
 /// Binary arithmetic operations supported in the assembly layer.
 #[derive(Clone, Debug, Copy)]
 pub enum BinaryOP {
-    Add, // Addition
-    Sub, // Subtraction
-    Mul, // Multiplication
+    Add,       // Addition
+    Sub,       // Subtraction
+    Mul,       // Multiplication
+    DivDouble, // Floating point division, integer division has dedicated instructions
+    And,       // Bitwise AND
+    Or,        // Bitwise OR
+    Xor,       // Bitwise XOR, used on doubles to flip the sign bit
+    Shl,       // Shift left
+    Shr,       // Logical shift right
+    Sar,       // Arithmetic shift right
 }
 
 /// Unary operations supported in the assembly layer.
@@ -196,3 +454,34 @@ pub enum UnaryOP {
     Not, // Bitwise NOT
     Neg, // Arithmetic negation
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_large(value: i64, size: OperandSize) -> bool {
+        Operand::Immediate(value as u64).is_large_immediate(size)
+    }
+
+    #[test]
+    fn sign_extended_32_bit_immediates_fit_in_a_quadword() {
+        assert!(!is_large(-1, OperandSize::QuadWord));
+        assert!(!is_large(i32::MIN as i64, OperandSize::QuadWord));
+        assert!(!is_large(i32::MAX as i64, OperandSize::QuadWord));
+    }
+
+    #[test]
+    fn immediates_past_32_bits_are_large_in_a_quadword() {
+        assert!(is_large(i32::MAX as i64 + 1, OperandSize::QuadWord));
+        assert!(is_large(i32::MIN as i64 - 1, OperandSize::QuadWord));
+        // an unsigned int above `i32::MAX` isn't sign-extended back to itself
+        assert!(is_large(u32::MAX as i64, OperandSize::QuadWord));
+    }
+
+    #[test]
+    fn longword_immediates_always_fit() {
+        assert!(!is_large(-1, OperandSize::LongWord));
+        assert!(!is_large(i32::MAX as i64 + 1, OperandSize::LongWord));
+        assert!(!Operand::Reg(Register::AX).is_large_immediate(OperandSize::QuadWord));
+    }
+}
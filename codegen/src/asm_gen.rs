@@ -1,9 +1,12 @@
 use crate::{asm, asm::Operand, asm::Operand::Reg, asm::Register};
-use ir_gen::tacky;
+use ir_gen::{print_ir, tacky};
+use shared_context::symbol_interner::SymbolInterner;
 use shared_context::type_interner::FunctionType;
 use shared_context::{Const, OperandSize, SymbolRegistery, Type};
 use shared_context::{Identifier, convert_type_to_operand_size, type_interner::TypeInterner};
+use std::collections::BTreeSet;
 
+mod gen_casts;
 mod gen_control_flow;
 mod gen_operations;
 
@@ -12,6 +15,9 @@ pub(crate) struct AsmGen<'ctx, 'src> {
     ty_interner: &'ctx TypeInterner<'src>, // getting the type of each function
     symbol_reg: &'ctx SymbolRegistery,
     args_registers: Vec<asm::Register>, // predefined list of argument registers (ABI-dependent)
+    double_args_registers: Vec<asm::Register>, // argument registers for doubles
+    annotator: Option<print_ir::DebuggingPrinter<'src, 'ctx>>, // formats Tacky for annotations
+    strength_reduction: bool,           // multiply and divide by powers of two with shifts
 }
 
 /// Implementation of the AsmGen struct, responsible for converting
@@ -29,13 +35,40 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
             Register::R8,
             Register::R9,
         ];
+        let double_args_registers = vec![
+            Register::XMM0,
+            Register::XMM1,
+            Register::XMM2,
+            Register::XMM3,
+            Register::XMM4,
+            Register::XMM5,
+            Register::XMM6,
+            Register::XMM7,
+        ];
         Self {
             ty_interner,
             symbol_reg,
             args_registers,
+            double_args_registers,
+            annotator: None,
+            strength_reduction: false,
         }
     }
 
+    /// Lowers a multiplication by a power of two to a left shift, and an unsigned
+    /// division by one to a logical right shift. A signed division rounds toward zero,
+    /// which an arithmetic shift doesn't for a negative dividend, so it keeps its `idiv`.
+    pub fn with_strength_reduction(mut self, enabled: bool) -> Self {
+        self.strength_reduction = enabled;
+        self
+    }
+
+    /// Marks the code generated for each Tacky instruction with the instruction's text.
+    pub fn with_annotations(mut self, sy_interner: &'ctx SymbolInterner<'src>) -> Self {
+        self.annotator = Some(print_ir::DebuggingPrinter::new(sy_interner));
+        self
+    }
+
     /// returns the type of the function
     fn get_function_type<'a>(&self, iden: Identifier) -> &FunctionType<'src> {
         let fun_sy = self.symbol_reg.get_function(&iden);
@@ -55,6 +88,8 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
                 Const::ConstUint(_) => Type::Uint,
                 Const::ConstLong(_) => Type::Long,
                 Const::ConstUlong(_) => Type::Ulong,
+                Const::ConstDouble(_) => Type::Double,
+                Const::ConstBool(_) => Type::Bool,
             },
             tacky::Value::Var(iden) => self.get_variable_type(iden),
         }
@@ -65,6 +100,8 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
             tacky::Value::Constant(cons) => match cons {
                 Const::ConstInt(_) | Const::ConstUint(_) => OperandSize::LongWord,
                 Const::ConstLong(_) | Const::ConstUlong(_) => OperandSize::QuadWord,
+                Const::ConstDouble(_) => OperandSize::Double,
+                Const::ConstBool(_) => OperandSize::Byte,
             },
             tacky::Value::Var(iden) => {
                 let var_type = self.get_variable_type(iden);
@@ -74,7 +111,8 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
     }
 
     /// Converts a Tacky value into an assembly operand.
-    // the type of the immediate dosen't matter, as it will have the same bit representation
+    // the type of the immediate dosen't matter, as it will have the same bit representation,
+    // doubles can't be immediates so they are read from a constant in read-only data
     fn convert_val(val: tacky::Value) -> Operand {
         match val {
             tacky::Value::Var(identifier) => Operand::Pseudo(identifier),
@@ -83,16 +121,27 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
                 Const::ConstUint(uint) => Operand::Immediate(uint as u64),
                 Const::ConstUlong(ulong) => Operand::Immediate(ulong),
                 Const::ConstLong(long) => Operand::Immediate(long as u64),
+                Const::ConstDouble(double) => Operand::DoubleConst(double.to_bits()),
+                Const::ConstBool(bool) => Operand::Immediate(bool as u64),
             },
         }
     }
 
+    /// A function has no pseudo register, its address is taken from its symbol.
+    fn convert_address_src(&self, val: tacky::Value) -> Operand {
+        match val {
+            tacky::Value::Var(iden) if self.symbol_reg.is_function(&iden) => Operand::Data(iden),
+            _ => Self::convert_val(val),
+        }
+    }
+
     /// Entry point for assembly generation.
     ///
     /// Consumes a tacky::Program and returns an asm::Program.
     /// Each function in the Tacky IR is lowered to a corresponding
     /// assembly function using `gen_function_def`.
-    /// static variables remain unchanged
+    /// static variables remain unchanged,
+    /// and every double constant the functions use is appended at the end
     pub fn gen_asm(&self, program: tacky::Program) -> asm::Program {
         let items = program.into_parts();
         let mut asm_items = Vec::new();
@@ -106,9 +155,64 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
             }
         }
 
+        let constants = Self::collect_double_constants(&asm_items);
+        asm_items.extend(constants.into_iter().map(asm::TopLevel::C));
+
         asm::Program::new(asm_items)
     }
 
+    /// Returns the bit patterns of every double constant used by the functions,
+    /// each one is emitted once no matter how many times it is used.
+    fn collect_double_constants(items: &[asm::TopLevel]) -> BTreeSet<u64> {
+        let mut constants = BTreeSet::new();
+        for item in items {
+            if let asm::TopLevel::F(fun_def) = item {
+                for instruction in fun_def.get_instructions() {
+                    for operand in instruction.get_operands() {
+                        if let Operand::DoubleConst(bits) = operand {
+                            constants.insert(bits);
+                        }
+                    }
+                }
+            }
+        }
+        constants
+    }
+
+    /// Splits values into those passed in registers, paired with their register,
+    /// and those passed on the stack.
+    ///
+    /// Doubles take the next free XMM register and everything else
+    /// the next free integer register, once a class runs out its values go on the stack.
+    fn classify<T: Copy>(&self, values: &[(T, Type)]) -> (Vec<(T, Register)>, Vec<T>) {
+        let mut register_values = Vec::new();
+        let mut stack_values = Vec::new();
+        let mut int_regs = self.args_registers.iter();
+        let mut double_regs = self.double_args_registers.iter();
+
+        for (value, value_type) in values {
+            let register = if value_type.is_double() {
+                double_regs.next()
+            } else {
+                int_regs.next()
+            };
+            match register {
+                Some(register) => register_values.push((*value, *register)),
+                None => stack_values.push(*value),
+            }
+        }
+        (register_values, stack_values)
+    }
+
+    /// Returns the register a value of the given type is returned in.
+    fn return_register(value_type: Type) -> Register {
+        if value_type.is_double() {
+            Register::XMM0
+        } else {
+            Register::AX
+        }
+    }
+
     /// Converts a single Tacky function definition into an assembly-level one.
     fn gen_function_def(&self, function: tacky::FunctionDef) -> asm::FunctionDef {
         let (name, external, params, tacky_instructions) = function.into_parts();
@@ -127,14 +231,22 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         self.push_params_into_stack(name, params, &mut asm_instructions);
 
         // Translate each Tacky instruction into assembly.
-        self.gen_instructions(tacky_instructions, &mut asm_instructions);
-
-        asm::FunctionDef::new(name, external, asm_instructions)
+        let ret_type = self.get_function_type(name).ret;
+        let mut annotations = Vec::new();
+        self.gen_instructions(
+            tacky_instructions,
+            ret_type,
+            &mut asm_instructions,
+            &mut annotations,
+        );
+
+        asm::FunctionDef::new(name, external, asm_instructions).with_annotations(annotations)
     }
 
     /// Moves function parameters from argument registers or stack into pseudo-registers.
     ///
-    /// - The first 6 parameters go into registers (`DI`, `SI`, etc.).
+    /// - The first 6 integer parameters go into registers (`DI`, `SI`, etc.).
+    /// - The first 8 double parameters go into `XMM0`-`XMM7`.
     /// - Remaining ones are read from the stack, starting at offset 16.
     fn push_params_into_stack(
         &self,
@@ -144,34 +256,49 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
     ) {
         // it is quaranteed that the function is in the symbol table at this stage
         let fun_type = self.get_function_type(iden);
-        for ((i, param), param_type) in params.iter().enumerate().zip(fun_type.params) {
-            if i <= 5 {
-                asm_instructions.push(asm::Instruction::Mov {
-                    size: convert_type_to_operand_size(*param_type),
-                    src: Reg(self.args_registers[i]),
-                    dst: Operand::Pseudo(*param),
-                });
-            } else {
-                // Stack parameters start after return address and saved base pointer.
-                let stack_index = 16 + ((i as i64) - 6) * 8;
-                asm_instructions.push(asm::Instruction::Mov {
-                    size: convert_type_to_operand_size(*param_type),
-                    src: Operand::Stack(stack_index),
-                    dst: Operand::Pseudo(*param),
-                });
-            }
+        let typed_params: Vec<(Identifier, Type)> = params
+            .into_iter()
+            .zip(fun_type.params.iter().copied())
+            .collect();
+        let (register_params, stack_params) = self.classify(&typed_params);
+
+        for (param, register) in register_params {
+            asm_instructions.push(asm::Instruction::Mov {
+                size: convert_type_to_operand_size(self.get_variable_type(param)),
+                src: Reg(register),
+                dst: Operand::Pseudo(param),
+            });
+        }
+
+        for (i, param) in stack_params.into_iter().enumerate() {
+            // Stack parameters start after return address and saved base pointer,
+            // the offsets are from RBP so the callee-saved registers pushed after it don't move them.
+            let stack_index = 16 + (i as i64) * 8;
+            asm_instructions.push(asm::Instruction::Mov {
+                size: convert_type_to_operand_size(self.get_variable_type(param)),
+                src: Operand::Stack(stack_index),
+                dst: Operand::Pseudo(param),
+            });
         }
     }
 
     /// Translates a list of Tacky instructions into assembly instructions.
+    /// `ret_type` is the return type of the function they're in, it sizes the returned value.
     fn gen_instructions(
         &self,
-        tacky_instructions: Vec<tacky::Instruction>,
+        tacky_instructions: Vec<tacky::SpannedInstruction>,
+        ret_type: Type,
         asm_instructions: &mut Vec<asm::Instruction>,
+        annotations: &mut Vec<String>,
     ) {
-        for tacky_instruction in tacky_instructions {
+        for (tacky_instruction, _) in tacky_instructions {
+            if let Some(annotator) = &self.annotator {
+                asm_instructions.push(asm::Instruction::Annotation(annotations.len()));
+                annotations.push(annotator.format_instruction(&tacky_instruction));
+            }
+
             match tacky_instruction {
-                tacky::Instruction::Ret(val) => self.handle_ret(val, asm_instructions),
+                tacky::Instruction::Ret(val) => self.handle_ret(val, ret_type, asm_instructions),
 
                 tacky::Instruction::Unary { op, src, dst } => {
                     self.handle_unary(op, src, dst, asm_instructions)
@@ -194,15 +321,35 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
                     self.handle_jump_if_not_zero(pred, tar, asm_instructions)
                 }
 
+                tacky::Instruction::CompareAndJump {
+                    op,
+                    src1,
+                    src2,
+                    jump_if,
+                    target,
+                } => {
+                    self.handle_compare_and_jump(op, src1, src2, jump_if, target, asm_instructions)
+                }
+
                 tacky::Instruction::Label(tar) => Self::handle_label(tar, asm_instructions),
 
                 tacky::Instruction::Copy { src, dst } => {
                     self.handle_copy(src, dst, asm_instructions)
                 }
 
+                tacky::Instruction::Select {
+                    cond,
+                    src1,
+                    src2,
+                    dst,
+                } => self.handle_select(cond, src1, src2, dst, asm_instructions),
+
                 tacky::Instruction::FunCall { name, args, dst } => {
                     self.handle_function_call(name, args, dst, asm_instructions);
                 }
+                tacky::Instruction::IndirectCall { fun_ptr, args, dst } => {
+                    self.handle_indirect_call(fun_ptr, args, dst, asm_instructions);
+                }
                 tacky::Instruction::SignExtend { src, dst } => {
                     self.handle_sign_extention(src, dst, asm_instructions)
                 }
@@ -212,17 +359,59 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
                 tacky::Instruction::ZeroExtend { src, dst } => {
                     self.handle_zero_extention(src, dst, asm_instructions)
                 }
+                tacky::Instruction::DoubleToInt { src, dst } => {
+                    self.handle_double_to_int(src, dst, asm_instructions)
+                }
+                tacky::Instruction::DoubleToUInt { src, dst } => {
+                    self.handle_double_to_uint(src, dst, asm_instructions)
+                }
+                tacky::Instruction::IntToDouble { src, dst } => {
+                    self.handle_int_to_double(src, dst, asm_instructions)
+                }
+                tacky::Instruction::UIntToDouble { src, dst } => {
+                    self.handle_uint_to_double(src, dst, asm_instructions)
+                }
+                tacky::Instruction::GetAddress { src, dst } => {
+                    asm_instructions.push(asm::Instruction::Lea {
+                        src: self.convert_address_src(src),
+                        dst: Self::convert_val(dst),
+                    })
+                }
+                tacky::Instruction::Load { src_ptr, dst } => {
+                    self.handle_load(src_ptr, dst, asm_instructions)
+                }
+                tacky::Instruction::Store { src, dst_ptr } => {
+                    self.handle_store(src, dst_ptr, asm_instructions)
+                }
+                tacky::Instruction::AddPtr {
+                    ptr,
+                    index,
+                    scale,
+                    dst,
+                } => Self::handle_add_ptr(ptr, index, scale, dst, asm_instructions),
+                tacky::Instruction::SourceLine(line) => {
+                    asm_instructions.push(asm::Instruction::SourceLine(line))
+                }
             }
         }
     }
 
-    /// Handle Zero extension instruction
+    /// Handle Zero extension instruction, a `_Bool` byte is widened with `movzb`
     fn handle_zero_extention(
         &self,
         src: tacky::Value,
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
+        if self.get_val_size(src) == OperandSize::Byte {
+            asm_instructions.push(asm::Instruction::Movzb {
+                size: self.get_val_size(dst),
+                src: Self::convert_val(src),
+                dst: Self::convert_val(dst),
+            });
+            return;
+        }
+
         asm_instructions.push(asm::Instruction::Movzx {
             src: Self::convert_val(src),
             dst: Self::convert_val(dst),
@@ -244,59 +433,253 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         });
     }
 
-    /// Handle Sign extension instruction
+    /// Handle Sign extension instruction, `movslq` only widens a longword to a quadword
+    /// so a sign extension between operands of any other sizes falls back to a `mov`
     fn handle_sign_extention(
         &self,
         src: tacky::Value,
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
-        asm_instructions.push(asm::Instruction::Movsx {
-            src: Self::convert_val(src),
+        let (src_size, dst_size) = (self.get_val_size(src), self.get_val_size(dst));
+        debug_assert!(
+            src_size == OperandSize::LongWord && dst_size == OperandSize::QuadWord,
+            "sign extension from {src_size:?} to {dst_size:?} doesn't widen"
+        );
+
+        if src_size == OperandSize::LongWord && dst_size == OperandSize::QuadWord {
+            asm_instructions.push(asm::Instruction::Movsx {
+                src: Self::convert_val(src),
+                dst: Self::convert_val(dst),
+            });
+        } else {
+            asm_instructions.push(asm::Instruction::Mov {
+                size: dst_size,
+                src: Self::convert_val(src),
+                dst: Self::convert_val(dst),
+            });
+        }
+    }
+
+    /// Handles a load through a pointer, the pointer is moved into RAX to address the object.
+    fn handle_load(
+        &self,
+        src_ptr: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::QuadWord,
+            src: Self::convert_val(src_ptr),
+            dst: Reg(Register::AX),
+        });
+        asm_instructions.push(asm::Instruction::Mov {
+            size: self.get_val_size(dst),
+            src: Operand::Memory(Register::AX, 0),
             dst: Self::convert_val(dst),
         });
     }
 
-    /// Handles return statements by moving the result into RAX and emitting `ret`.
-    fn handle_ret(&self, val: tacky::Value, asm_instructions: &mut Vec<asm::Instruction>) {
+    /// Handles a store through a pointer, the pointer is moved into RAX to address the object.
+    fn handle_store(
+        &self,
+        src: tacky::Value,
+        dst_ptr: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::QuadWord,
+            src: Self::convert_val(dst_ptr),
+            dst: Reg(Register::AX),
+        });
+        asm_instructions.push(asm::Instruction::Mov {
+            size: self.get_val_size(src),
+            src: Self::convert_val(src),
+            dst: Operand::Memory(Register::AX, 0),
+        });
+    }
+
+    /// Handles advancing a pointer by `index` elements with `lea`, the pointer is moved into RAX.
+    ///
+    /// A constant index is folded into the offset, any other is moved into RDX and scaled
+    /// by the addressing mode, or multiplied first for a scale `lea` can't encode.
+    fn handle_add_ptr(
+        ptr: tacky::Value,
+        index: tacky::Value,
+        scale: u64,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
         asm_instructions.push(asm::Instruction::Mov {
-            size: self.get_val_size(val),
+            size: OperandSize::QuadWord,
+            src: Self::convert_val(ptr),
             dst: Reg(Register::AX),
-            src: Self::convert_val(val),
         });
+
+        let address = match index {
+            tacky::Value::Constant(Const::ConstLong(index)) => {
+                Operand::Memory(Register::AX, index.wrapping_mul(scale as i64))
+            }
+            _ => {
+                asm_instructions.push(asm::Instruction::Mov {
+                    size: OperandSize::QuadWord,
+                    src: Self::convert_val(index),
+                    dst: Reg(Register::DX),
+                });
+                if matches!(scale, 1 | 2 | 4 | 8) {
+                    Operand::Indexed(Register::AX, Register::DX, scale)
+                } else {
+                    asm_instructions.push(asm::Instruction::Binary {
+                        op: asm::BinaryOP::Mul,
+                        size: OperandSize::QuadWord,
+                        src: Operand::Immediate(scale),
+                        dst: Reg(Register::DX),
+                    });
+                    Operand::Indexed(Register::AX, Register::DX, 1)
+                }
+            }
+        };
+        asm_instructions.push(asm::Instruction::Lea {
+            src: address,
+            dst: Self::convert_val(dst),
+        });
+    }
+
+    /// Handles return statements by moving the result into RAX (XMM0 for doubles) and emitting `ret`.
+    /// The type checker converts the value to the function's return type, so a `long`
+    /// function returning an `int` moves the extended quadword and RAX has no stale upper half.
+    /// A `void` function's `ret` leaves RAX as it is.
+    /// The value is moved into the return register sized by the function's return type.
+    fn handle_ret(
+        &self,
+        val: Option<tacky::Value>,
+        ret_type: Type,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        if let Some(val) = val {
+            let val = Self::convert_constant_to(val, ret_type);
+            asm_instructions.push(asm::Instruction::Mov {
+                size: convert_type_to_operand_size(ret_type),
+                dst: Reg(Self::return_register(ret_type)),
+                src: Self::convert_val(val),
+            });
+        }
         asm_instructions.push(asm::Instruction::Ret);
     }
 
-    /// Handles copy (assignment) instructions by emitting a simple `mov`.
+    /// Handles copy (assignment) instructions by emitting a simple `mov`,
+    /// sized by the destination so a constant fills the whole slot.
     fn handle_copy(
         &self,
         src: tacky::Value,
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
+        let dst_type = self.get_val_type(dst);
+        let src = Self::convert_constant_to(src, dst_type);
         asm_instructions.push(asm::Instruction::Mov {
-            size: self.get_val_size(src),
+            size: convert_type_to_operand_size(dst_type),
             src: Self::convert_val(src),
             dst: Self::convert_val(dst),
         });
     }
 
+    /// Lowers a select as `mov src2, dst` then `cmovne src1, dst` after testing the condition,
+    /// the `mov` leaves the flags alone.
+    fn handle_select(
+        &self,
+        cond: tacky::Value,
+        src1: tacky::Value,
+        src2: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let dst_type = self.get_val_type(dst);
+        let size = convert_type_to_operand_size(dst_type);
+        debug_assert!(size != OperandSize::Double, "cmov only moves integers");
+
+        self.handle_copy(src2, dst, asm_instructions);
+        self.compare_with_zero(cond, asm_instructions);
+        let src1 = Self::convert_constant_to(src1, dst_type);
+        asm_instructions.push(asm::Instruction::Cmov {
+            cond: asm::Cond::NE,
+            size,
+            src: Self::convert_val(src1),
+            dst: Self::convert_val(dst),
+        });
+    }
+
+    /// A constant converted to the type it's moved into, so the immediate
+    /// is sign or zero extended like its value. Variables are returned unchanged.
+    fn convert_constant_to(val: tacky::Value, target: Type) -> tacky::Value {
+        match val {
+            tacky::Value::Constant(cons) => tacky::Value::Constant(cons.convert_to(target)),
+            var => var,
+        }
+    }
+
     /// Handles function calls according to the System V AMD64 calling convention.
     ///
-    /// 1. Pass up to 6 arguments via registers.
+    /// 1. Pass up to 6 integer and 8 double arguments via registers.
     /// 2. Push additional arguments on the stack in reverse order.
     /// 3. Maintain stack alignment (16-byte).
-    /// 4. Move the return value (in RAX) into the destination pseudo-register.
+    /// 4. For a variadic callee, put the number of XMM registers used in AL.
+    /// 5. Move the return value (in RAX or XMM0) into the destination pseudo-register,
+    ///    a call to a `void` function has no destination.
     fn handle_function_call(
         &self,
         name: Identifier,
         args: Vec<tacky::Value>,
-        tacky_dst: tacky::Value,
+        tacky_dst: Option<tacky::Value>,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let is_variadic = self.get_function_type(name).is_variadic;
+        let call = [asm::Instruction::Call(name)];
+        self.lower_call(is_variadic, args, tacky_dst, &call, asm_instructions);
+    }
+
+    /// Handles a call through a function pointer like a direct call, the pointer is
+    /// moved into R11 right before the call since R11 never holds an argument.
+    fn handle_indirect_call(
+        &self,
+        fun_ptr: tacky::Value,
+        args: Vec<tacky::Value>,
+        tacky_dst: Option<tacky::Value>,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let is_variadic = match self.get_val_type(fun_ptr) {
+            Type::Pointer(id) => match self.ty_interner.get_referenced(id) {
+                Type::Function(type_id) => self.ty_interner.get(type_id).is_variadic,
+                _ => false,
+            },
+            _ => false,
+        };
+        let call = [
+            asm::Instruction::Mov {
+                size: OperandSize::QuadWord,
+                src: Self::convert_val(fun_ptr),
+                dst: Reg(Register::R11),
+            },
+            asm::Instruction::CallIndirect(Reg(Register::R11)),
+        ];
+        self.lower_call(is_variadic, args, tacky_dst, &call, asm_instructions);
+    }
+
+    /// Lowers a call whose `call` instructions are emitted once the arguments are in place.
+    fn lower_call(
+        &self,
+        is_variadic: bool,
+        args: Vec<tacky::Value>,
+        tacky_dst: Option<tacky::Value>,
+        call: &[asm::Instruction],
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
         // Split args into those passed in registers and those passed on the stack.
-        let mid = if 6 > args.len() { args.len() } else { 6 };
-        let (register_args, stack_args) = args.split_at(mid);
+        let typed_args: Vec<(tacky::Value, Type)> = args
+            .into_iter()
+            .map(|arg| (arg, self.get_val_type(arg)))
+            .collect();
+        let (register_args, stack_args) = self.classify(&typed_args);
 
         // Stack must remain 16-byte aligned before a `call`.
         let stack_padding = Self::calculate_stack_padding(stack_args.len());
@@ -310,19 +693,35 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         }
 
         // Move arguments into the appropriate registers.
-        self.move_register_args(register_args, asm_instructions);
+        self.move_register_args(&register_args, asm_instructions);
 
         // Push remaining arguments to stack (right-to-left).
-        self.push_stack_args(stack_args, asm_instructions);
+        self.push_stack_args(&stack_args, asm_instructions);
+
+        // A variadic callee reads AL to know how many vector registers to save,
+        // it's set last since pushing stack arguments goes through RAX.
+        if is_variadic {
+            let vector_args = register_args
+                .iter()
+                .filter(|(_, register)| self.double_args_registers.contains(register))
+                .count();
+            asm_instructions.push(asm::Instruction::Mov {
+                size: OperandSize::LongWord,
+                src: asm::Operand::Immediate(vector_args as u64),
+                dst: Reg(Register::AX),
+            });
+        }
 
         // Emit the call instruction.
-        asm_instructions.push(asm::Instruction::Call(name));
+        asm_instructions.extend_from_slice(call);
 
         // Clean up the stack (if we pushed arguments).
         self.cleanup_stack(stack_args.len(), stack_padding, asm_instructions);
 
         // Move the return value from RAX to the destination pseudo-register.
-        self.move_return_value(tacky_dst, asm_instructions);
+        if let Some(tacky_dst) = tacky_dst {
+            self.move_return_value(tacky_dst, asm_instructions);
+        }
     }
 
     /// Calculates stack padding to maintain 16-byte alignment.
@@ -330,24 +729,32 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         if stack_args_len % 2 == 0 { 0 } else { 8 }
     }
 
-    /// Moves up to 6 function arguments into argument registers.
+    /// Moves function arguments into their argument registers.
     fn move_register_args(
         &self,
-        register_args: &[tacky::Value],
+        register_args: &[(tacky::Value, Register)],
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
-        for (i, tacky_arg) in register_args.iter().enumerate() {
-            let asm_arg = Self::convert_val(*tacky_arg);
-            let register = Reg(self.args_registers[i]);
+        for (tacky_arg, register) in register_args {
+            let size = self.get_val_size(*tacky_arg);
+            // callers extend a `_Bool` argument to 32 bits, other compilers rely on it
+            if size == OperandSize::Byte {
+                asm_instructions.push(asm::Instruction::Movzb {
+                    size: OperandSize::LongWord,
+                    src: Self::convert_val(*tacky_arg),
+                    dst: Reg(*register),
+                });
+                continue;
+            }
             asm_instructions.push(asm::Instruction::Mov {
-                size: self.get_val_size(*tacky_arg),
-                src: asm_arg,
-                dst: register,
+                size,
+                src: Self::convert_val(*tacky_arg),
+                dst: Reg(*register),
             });
         }
     }
 
-    /// Pushes extra (beyond six) function arguments onto the stack in reverse order.
+    /// Pushes the arguments that didn't fit in registers onto the stack in reverse order.
     /// pad the with empty space if necessary to keep alignment
     fn push_stack_args(
         &self,
@@ -363,9 +770,14 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
                     asm_instructions.push(asm::Instruction::Push(asm_arg));
                 }
                 // Otherwise, move into RAX first, then push (x86 requires a register source).
+                // doubles are moved as plain quadwords since RAX isn't an XMM register
                 _ => {
+                    let size = match self.get_val_size(*tacky_arg) {
+                        OperandSize::Double => OperandSize::QuadWord,
+                        size => size,
+                    };
                     asm_instructions.push(asm::Instruction::Mov {
-                        size: self.get_val_size(*tacky_arg),
+                        size,
                         src: asm_arg,
                         dst: Reg(Register::AX),
                     });
@@ -393,7 +805,7 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         }
     }
 
-    /// Moves a function's return value from RAX into the destination pseudo-register.
+    /// Moves a function's return value from RAX (XMM0 for doubles) into the destination pseudo-register.
     fn move_return_value(
         &self,
         tacky_dst: tacky::Value,
@@ -402,7 +814,7 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         let asm_dst = Self::convert_val(tacky_dst);
         asm_instructions.push(asm::Instruction::Mov {
             size: self.get_val_size(tacky_dst),
-            src: Reg(Register::AX),
+            src: Reg(Self::return_register(self.get_val_type(tacky_dst))),
             dst: asm_dst,
         });
     }
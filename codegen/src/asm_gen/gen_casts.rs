@@ -0,0 +1,192 @@
+use crate::asm_gen::AsmGen;
+use crate::{asm, asm::Operand::Reg, asm::Register};
+use ir_gen::tacky;
+use shared_context::{OperandSize, Type, convert_type_to_operand_size};
+
+// This file contains implementations for lowering conversions between doubles and integers.
+// x86 only converts between doubles and signed integers, so unsigned conversions are
+// built out of signed ones.
+
+impl<'ctx, 'src> AsmGen<'ctx, 'src> {
+    /// Lower a double to signed integer conversion as a single `cvttsd2si`.
+    pub(super) fn handle_double_to_int(
+        &self,
+        src: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        asm_instructions.push(asm::Instruction::Cvttsd2si {
+            size: self.get_val_size(dst),
+            src: Self::convert_val(src),
+            dst: Self::convert_val(dst),
+        });
+    }
+
+    /// Lower a signed integer to double conversion as a single `cvtsi2sd`.
+    pub(super) fn handle_int_to_double(
+        &self,
+        src: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        asm_instructions.push(asm::Instruction::Cvtsi2sd {
+            size: self.get_val_size(src),
+            src: Self::convert_val(src),
+            dst: Self::convert_val(dst),
+        });
+    }
+
+    /// Lower a double to unsigned integer conversion.
+    ///
+    /// - `unsigned int` fits in a signed quadword, so a 64 bit conversion is truncated.
+    /// - `unsigned long` converts both `src` and `src - 2^63`, the first result is
+    ///   only valid below 2^63 and is the "integer indefinite" value 2^63 otherwise,
+    ///   so its sign bit selects whether the second one is ORed in.
+    pub(super) fn handle_double_to_uint(
+        &self,
+        src: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let src = Self::convert_val(src);
+        if self.get_val_type(dst) == Type::Uint {
+            asm_instructions.push(asm::Instruction::Cvttsd2si {
+                size: OperandSize::QuadWord,
+                src,
+                dst: Reg(Register::AX),
+            });
+            asm_instructions.push(asm::Instruction::Mov {
+                size: OperandSize::LongWord,
+                src: Reg(Register::AX),
+                dst: Self::convert_val(dst),
+            });
+            return;
+        }
+
+        asm_instructions.push(asm::Instruction::Cvttsd2si {
+            size: OperandSize::QuadWord,
+            src,
+            dst: Reg(Register::AX),
+        });
+        // DX is all ones when the conversion overflowed, zero otherwise
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::AX),
+            dst: Reg(Register::DX),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Sar,
+            size: OperandSize::QuadWord,
+            src: asm::Operand::Immediate(63),
+            dst: Reg(Register::DX),
+        });
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::Double,
+            src,
+            dst: Reg(Register::XMM0),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Sub,
+            size: OperandSize::Double,
+            src: asm::Operand::DoubleConst(9223372036854775808.0f64.to_bits()),
+            dst: Reg(Register::XMM0),
+        });
+        asm_instructions.push(asm::Instruction::Cvttsd2si {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::XMM0),
+            dst: Reg(Register::CX),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::And,
+            size: OperandSize::QuadWord,
+            src: Reg(Register::DX),
+            dst: Reg(Register::CX),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Or,
+            size: OperandSize::QuadWord,
+            src: Reg(Register::CX),
+            dst: Reg(Register::AX),
+        });
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::AX),
+            dst: Self::convert_val(dst),
+        });
+    }
+
+    /// Lower an unsigned integer to double conversion.
+    ///
+    /// - `unsigned int` is zero extended and converted as a signed quadword.
+    /// - `unsigned long` converts its low and high 32 bit halves separately,
+    ///   both fit in a signed quadword, and combines them as `high * 2^32 + low`.
+    pub(super) fn handle_uint_to_double(
+        &self,
+        src: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let src_ty = self.get_val_type(src);
+        let src = Self::convert_val(src);
+
+        // a 32 bit move into a register clears its upper half
+        asm_instructions.push(asm::Instruction::Mov {
+            size: convert_type_to_operand_size(src_ty),
+            src,
+            dst: Reg(Register::AX),
+        });
+        if src_ty == Type::Uint {
+            asm_instructions.push(asm::Instruction::Cvtsi2sd {
+                size: OperandSize::QuadWord,
+                src: Reg(Register::AX),
+                dst: Self::convert_val(dst),
+            });
+            return;
+        }
+
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::AX),
+            dst: Reg(Register::DX),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Shr,
+            size: OperandSize::QuadWord,
+            src: asm::Operand::Immediate(32),
+            dst: Reg(Register::DX),
+        });
+        // keep only the low half in AX
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::LongWord,
+            src: Reg(Register::AX),
+            dst: Reg(Register::AX),
+        });
+        asm_instructions.push(asm::Instruction::Cvtsi2sd {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::AX),
+            dst: Reg(Register::XMM0),
+        });
+        asm_instructions.push(asm::Instruction::Cvtsi2sd {
+            size: OperandSize::QuadWord,
+            src: Reg(Register::DX),
+            dst: Reg(Register::XMM1),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Mul,
+            size: OperandSize::Double,
+            src: asm::Operand::DoubleConst(4294967296.0f64.to_bits()),
+            dst: Reg(Register::XMM1),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Add,
+            size: OperandSize::Double,
+            src: Reg(Register::XMM0),
+            dst: Reg(Register::XMM1),
+        });
+        asm_instructions.push(asm::Instruction::Mov {
+            size: OperandSize::Double,
+            src: Reg(Register::XMM1),
+            dst: Self::convert_val(dst),
+        });
+    }
+}
@@ -1,7 +1,8 @@
 use ir_gen::tacky;
-use shared_context::Identifier;
+use shared_context::{Identifier, OperandSize};
 
 use crate::asm;
+use crate::asm::{Operand::Reg, Register};
 use crate::asm_gen::AsmGen;
 
 impl<'ctx, 'src> AsmGen<'ctx, 'src> {
@@ -12,6 +13,42 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         asm_instructions.push(asm::Instruction::Jmp(tar));
     }
 
+    /// Emit a comparison of the value with 0, setting the flags for a following jump or set.
+    ///
+    /// Integers are tested against themselves, `fix_instructions` turns the test
+    /// back into a `cmp $0` when the value isn't in a register.
+    /// doubles can't be compared with an immediate, so they are compared
+    /// with a zeroed XMM0 instead, a NaN isn't equal to zero so ZF is only left
+    /// set for an ordered `0.0`.
+    pub(super) fn compare_with_zero(
+        &self,
+        val: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let size = self.get_val_size(val);
+        if size == OperandSize::Double {
+            asm_instructions.push(asm::Instruction::Binary {
+                op: asm::BinaryOP::Xor,
+                size,
+                src: Reg(Register::XMM0),
+                dst: Reg(Register::XMM0),
+            });
+            asm_instructions.push(asm::Instruction::Cmp {
+                size,
+                src: Self::convert_val(val),
+                dst: Reg(Register::XMM0),
+            });
+            Self::fold_parity_into_zero(asm_instructions);
+        } else {
+            let val = Self::convert_val(val);
+            asm_instructions.push(asm::Instruction::Test {
+                size,
+                src: val,
+                dst: val,
+            });
+        }
+    }
+
     /// Emit a conditional jump if the predicate is non-zero.
     ///
     /// Logic:
@@ -23,11 +60,7 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         tar: Identifier,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
-        asm_instructions.push(asm::Instruction::Cmp {
-            size: self.get_val_size(pred),
-            src: asm::Operand::Immediate(0),
-            dst: Self::convert_val(pred),
-        });
+        self.compare_with_zero(pred, asm_instructions);
         asm_instructions.push(asm::Instruction::JmpCC(asm::Cond::NE, tar));
     }
 
@@ -42,14 +75,30 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         tar: Identifier,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
-        asm_instructions.push(asm::Instruction::Cmp {
-            size: self.get_val_size(pred),
-            src: asm::Operand::Immediate(0),
-            dst: Self::convert_val(pred),
-        });
+        self.compare_with_zero(pred, asm_instructions);
         asm_instructions.push(asm::Instruction::JmpCC(asm::Cond::E, tar));
     }
 
+    /// Emit a conditional jump on a comparison.
+    ///
+    /// The `cmp` of the comparison is followed by the jump on its condition, or on the
+    /// negated condition when it jumps if the comparison is false, with no `setcc`.
+    /// A comparison of doubles with a NaN is false, except `!=`, so `if (x < y)` skips its
+    /// body for a NaN `x`.
+    pub(super) fn handle_compare_and_jump(
+        &self,
+        op: tacky::BinaryOP,
+        src1: tacky::Value,
+        src2: tacky::Value,
+        jump_if: bool,
+        tar: Identifier,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let cond = self.gen_compare(op, src1, src2, asm_instructions);
+        let cond = if jump_if { cond } else { cond.negated() };
+        asm_instructions.push(asm::Instruction::JmpCC(cond, tar));
+    }
+
     /// Emit a label in the assembly instructions.
     ///
     /// Labels are used as jump targets in control flow.
@@ -1,12 +1,14 @@
 use crate::asm_gen::AsmGen;
 use crate::{asm, asm::Operand::Reg, asm::Register};
 use ir_gen::tacky;
+use shared_context::{Const, OperandSize};
 
 // This file contains implementations for lowering binary and unary operations.
 
 impl<'ctx, 'src> AsmGen<'ctx, 'src> {
     /// Dispatches a Tacky binary operation to the correct handler.
     /// Some operations (div/mod) require special handling, comparisons generate `cmp` + `setcc`.
+    /// division of doubles has a regular form instruction.
     pub(super) fn handle_binary(
         &self,
         op: tacky::BinaryOP,
@@ -15,7 +17,14 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
+        if self.strength_reduction && self.reduce_to_shift(&op, src1, src2, dst, asm_instructions) {
+            return;
+        }
+
         match op {
+            tacky::BinaryOP::Div if self.get_val_type(src1).is_double() => {
+                self.handle_regular_form(op, src1, src2, dst, asm_instructions)
+            }
             tacky::BinaryOP::Div | tacky::BinaryOP::Mod => {
                 self.handle_div_mod(op, src1, src2, dst, asm_instructions)
             }
@@ -33,6 +42,11 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
 
     /// Dispatches a Tacky unary operation.
     /// `LogicalNot` is handled specially, other unary ops are lowered to `mov + unary`.
+    /// negating a double flips its sign bit with `xorpd` against `-0.0`.
+    ///
+    /// The instructions are sized by the operand's type, `neg` and `not` work on the bit pattern,
+    /// so on an `unsigned int` they wrap around modulo 2^32 (`-1u` and `~0u` are 4294967295)
+    /// and only write the 4 bytes of its slot. Widening the result is a separate `ZeroExtend`.
     pub(super) fn handle_unary(
         &self,
         op: tacky::UnaryOP,
@@ -43,6 +57,19 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         let size = self.get_val_size(src);
         match op {
             tacky::UnaryOP::LogicalNot => self.handle_logical_not(src, dst, asm_instructions),
+            tacky::UnaryOP::Neg if size == OperandSize::Double => {
+                asm_instructions.push(asm::Instruction::Mov {
+                    size,
+                    dst: Self::convert_val(dst),
+                    src: Self::convert_val(src),
+                });
+                asm_instructions.push(asm::Instruction::Binary {
+                    size,
+                    op: asm::BinaryOP::Xor,
+                    src: asm::Operand::DoubleConst((-0.0f64).to_bits()),
+                    dst: Self::convert_val(dst),
+                });
+            }
             _ => {
                 asm_instructions.push(asm::Instruction::Mov {
                     size,
@@ -58,7 +85,7 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         }
     }
 
-    /// Lower a regular arithmetic operation (`+`, `-`, `*`) as `mov src1, dst` + `op src2, dst`.
+    /// Lower a regular arithmetic operation (`+`, `-`, `*`, and `/` on doubles) as `mov src1, dst` + `op src2, dst`.
     fn handle_regular_form(
         &self,
         op: tacky::BinaryOP,
@@ -81,6 +108,56 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         });
     }
 
+    /// Lower `x * 2^k` (or `2^k * x`) to `shl $k` and an unsigned `x / 2^k` to `shr $k`,
+    /// the bits a multiplication keeps are the same for signed and unsigned operands.
+    ///
+    /// Returns false, without lowering anything, for any other operation.
+    fn reduce_to_shift(
+        &self,
+        op: &tacky::BinaryOP,
+        src1: tacky::Value,
+        src2: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        let (shift_op, src, shift) = match (op, Self::power_of_two(src1), Self::power_of_two(src2))
+        {
+            (tacky::BinaryOP::Mul, _, Some(shift)) => (asm::BinaryOP::Shl, src1, shift),
+            (tacky::BinaryOP::Mul, Some(shift), _) => (asm::BinaryOP::Shl, src2, shift),
+            (tacky::BinaryOP::Div, _, Some(shift)) if !self.get_val_type(src1).is_signed() => {
+                (asm::BinaryOP::Shr, src1, shift)
+            }
+            _ => return false,
+        };
+
+        let size = self.get_val_size(src);
+        asm_instructions.push(asm::Instruction::Mov {
+            size,
+            dst: Self::convert_val(dst),
+            src: Self::convert_val(src),
+        });
+        asm_instructions.push(asm::Instruction::Binary {
+            size,
+            op: shift_op,
+            src: asm::Operand::Immediate(shift),
+            dst: Self::convert_val(dst),
+        });
+        true
+    }
+
+    /// The exponent of an integer constant whose bits are a power of two greater than one,
+    /// a multiplication wraps around, so `x * INT_MIN` is `x << 31`
+    fn power_of_two(val: tacky::Value) -> Option<u64> {
+        let bits = match val {
+            tacky::Value::Constant(Const::ConstInt(v)) => v as u32 as u64,
+            tacky::Value::Constant(Const::ConstUint(v)) => v as u64,
+            tacky::Value::Constant(Const::ConstLong(v)) => v as u64,
+            tacky::Value::Constant(Const::ConstUlong(v)) => v,
+            _ => return None,
+        };
+        (bits > 1 && bits.is_power_of_two()).then(|| bits.trailing_zeros() as u64)
+    }
+
     /// Lower division and modulus, which use AX/DX registers in x86-64.
     fn handle_div_mod(
         &self,
@@ -93,6 +170,14 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         let size = self.get_val_size(src1);
         let operand_ty = self.get_val_type(src1);
 
+        if matches!(
+            src2,
+            tacky::Value::Constant(Const::ConstInt(-1) | Const::ConstLong(-1))
+        ) {
+            self.handle_div_mod_by_minus_one(op, src1, dst, asm_instructions);
+            return;
+        }
+
         asm_instructions.push(asm::Instruction::Mov {
             size,
             src: Self::convert_val(src1),
@@ -124,21 +209,51 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         });
     }
 
+    /// Lower a signed division or modulus by the constant `-1` without `idiv`.
+    ///
+    /// `idiv` faults when the quotient overflows, which happens for the most negative
+    /// value divided by `-1`, so the quotient is computed as a wrapping `neg` of the
+    /// dividend and the remainder is always `0`.
+    fn handle_div_mod_by_minus_one(
+        &self,
+        op: tacky::BinaryOP,
+        src1: tacky::Value,
+        dst: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) {
+        let size = self.get_val_size(src1);
+        if let tacky::BinaryOP::Mod = op {
+            asm_instructions.push(asm::Instruction::Mov {
+                size,
+                src: asm::Operand::Immediate(0),
+                dst: Self::convert_val(dst),
+            });
+            return;
+        }
+
+        asm_instructions.push(asm::Instruction::Mov {
+            size,
+            src: Self::convert_val(src1),
+            dst: Self::convert_val(dst),
+        });
+        asm_instructions.push(asm::Instruction::Unary {
+            op: asm::UnaryOP::Neg,
+            size,
+            dst: Self::convert_val(dst),
+        });
+    }
+
     /// Lower logical NOT (`!`) operation as `cmp + mov 0 + setcc`.
+    /// `setcc` only writes the low byte, so the `mov` clears the whole destination first.
     fn handle_logical_not(
         &self,
         src: tacky::Value,
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
-        let size = self.get_val_size(src);
-        asm_instructions.push(asm::Instruction::Cmp {
-            size,
-            src: asm::Operand::Immediate(0),
-            dst: Self::convert_val(src),
-        });
+        self.compare_with_zero(src, asm_instructions);
         asm_instructions.push(asm::Instruction::Mov {
-            size,
+            size: self.get_val_size(dst),
             src: asm::Operand::Immediate(0),
             dst: Self::convert_val(dst),
         });
@@ -149,6 +264,7 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
     }
 
     /// Lower comparison operations (`>`, `<`, `==`, etc.) as `cmp + mov 0 + setcc`.
+    /// `setcc` only writes the low byte, so the `mov` clears the whole destination first.
     fn handle_comparison(
         &self,
         op: tacky::BinaryOP,
@@ -157,23 +273,94 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
         dst: tacky::Value,
         asm_instructions: &mut Vec<asm::Instruction>,
     ) {
+        let cond = self.gen_compare(op, src1, src2, asm_instructions);
+        asm_instructions.push(asm::Instruction::Mov {
+            size: self.get_val_size(dst),
+            src: asm::Operand::Immediate(0),
+            dst: Self::convert_val(dst),
+        });
+        asm_instructions.push(asm::Instruction::SetCC(cond, Self::convert_val(dst)));
+    }
+
+    /// Emit the `cmp` of a comparison and return the condition the flags are tested on.
+    ///
+    /// `cmp` can't compare with an immediate destination, so `5 > x` is compared
+    /// as `x < 5` instead of moving the `5` into a register.
+    pub(super) fn gen_compare(
+        &self,
+        op: tacky::BinaryOP,
+        src1: tacky::Value,
+        src2: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) -> asm::Cond {
         let size = self.get_val_size(src1);
         let operand_ty = self.get_val_type(src1);
+        if operand_ty.is_double() {
+            return Self::gen_double_compare(op, src1, src2, asm_instructions);
+        }
+
+        let mut cond = Self::convert_comparison_op(op, operand_ty.is_signed());
+        let (mut src1, mut src2) = (Self::convert_val(src1), Self::convert_val(src2));
+        if matches!(src1, asm::Operand::Immediate(_)) && !matches!(src2, asm::Operand::Immediate(_))
+        {
+            (src1, src2) = (src2, src1);
+            cond = cond.swapped();
+        }
 
         asm_instructions.push(asm::Instruction::Cmp {
             size,
-            src: Self::convert_val(src2),
-            dst: Self::convert_val(src1),
+            src: src2,
+            dst: src1,
         });
-        asm_instructions.push(asm::Instruction::Mov {
-            size,
-            src: asm::Operand::Immediate(0),
-            dst: Self::convert_val(dst),
+        cond
+    }
+
+    /// Emit the `comisd` of a comparison of doubles and return the condition the flags
+    /// are tested on, `comisd` sets the flags like an unsigned comparison.
+    ///
+    /// A comparison with a NaN is unordered, `comisd` then sets ZF, PF and CF as if the
+    /// operands were equal and the first was lesser, so the comparisons are arranged to be
+    /// false for a NaN, except `!=`:
+    /// - `<` and `<=` compare the operands in the other order, as `>` and `>=`,
+    ///   `A` and `AE` need CF clear.
+    /// - `==` and `!=` fold PF into ZF, which is then only set for ordered equal operands.
+    ///
+    /// The condition and its negation are then both right for NaN operands.
+    fn gen_double_compare(
+        op: tacky::BinaryOP,
+        src1: tacky::Value,
+        src2: tacky::Value,
+        asm_instructions: &mut Vec<asm::Instruction>,
+    ) -> asm::Cond {
+        let mut cond = Self::convert_comparison_op(op, false);
+        let (mut src1, mut src2) = (Self::convert_val(src1), Self::convert_val(src2));
+        if matches!(cond, asm::Cond::B | asm::Cond::BE) {
+            (src1, src2) = (src2, src1);
+            cond = cond.swapped();
+        }
+
+        asm_instructions.push(asm::Instruction::Cmp {
+            size: OperandSize::Double,
+            src: src2,
+            dst: src1,
+        });
+        if matches!(cond, asm::Cond::E | asm::Cond::NE) {
+            Self::fold_parity_into_zero(asm_instructions);
+        }
+        cond
+    }
+
+    /// After a `comisd`, set ZF only when the operands are ordered and equal: ZF is also set
+    /// for unordered operands, which set PF too. The scratch registers only hold the flags.
+    pub(super) fn fold_parity_into_zero(asm_instructions: &mut Vec<asm::Instruction>) {
+        asm_instructions.push(asm::Instruction::SetCC(asm::Cond::NE, Reg(Register::R11)));
+        asm_instructions.push(asm::Instruction::SetCC(asm::Cond::P, Reg(Register::R10)));
+        asm_instructions.push(asm::Instruction::Binary {
+            op: asm::BinaryOP::Or,
+            size: OperandSize::Byte,
+            src: Reg(Register::R10),
+            dst: Reg(Register::R11),
         });
-        asm_instructions.push(asm::Instruction::SetCC(
-            Self::convert_comparison_op(op, operand_ty.is_signed()),
-            Self::convert_val(dst),
-        ));
     }
 
     /// Convert Tacky binary operator to ASM binary operator.
@@ -182,7 +369,8 @@ impl<'ctx, 'src> AsmGen<'ctx, 'src> {
             tacky::BinaryOP::Add => asm::BinaryOP::Add,
             tacky::BinaryOP::Sub => asm::BinaryOP::Sub,
             tacky::BinaryOP::Mul => asm::BinaryOP::Mul,
-            _ => asm::BinaryOP::Add, // unreachable for div/mod/comparison
+            tacky::BinaryOP::Div => asm::BinaryOP::DivDouble, // only reached for doubles
+            _ => asm::BinaryOP::Add,                          // unreachable for mod/comparison
         }
     }
 
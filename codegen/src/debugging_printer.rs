@@ -1,4 +1,4 @@
-use shared_context::Identifier;
+use shared_context::{Identifier, SymbolRegistery};
 use shared_context::{StaticVariable, symbol_interner::SymbolInterner};
 
 use crate::DebuggingPrinter;
@@ -17,10 +17,72 @@ impl<'a> DebuggingPrinter<'a> {
             match item {
                 asm::TopLevel::F(fun_def) => self.print_function(fun_def),
                 asm::TopLevel::S(var_def) => self.print_static_variable(var_def),
+                asm::TopLevel::C(bits) => {
+                    println!(
+                        "  DoubleConstant({}, bits: {:#x})",
+                        f64::from_bits(bits),
+                        bits
+                    )
+                }
             }
         }
     }
 
+    /// Prints every entry of the symbol registry used during code generation,
+    /// variables first, each group sorted by name.
+    pub fn print_symbols(&self, symbol_reg: &SymbolRegistery) {
+        println!("SymbolRegistery");
+
+        let mut variables: Vec<_> = symbol_reg
+            .get_variables()
+            .map(|(iden, var_sy)| (self.format_unique_identifier(*iden), var_sy))
+            .collect();
+        variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, var_sy) in variables {
+            println!(
+                "  Obj(name: {}, type: {:?}, size: {}, is_static: {})",
+                name,
+                var_sy.get_type(),
+                var_sy.get_size(),
+                var_sy.is_static()
+            );
+        }
+
+        let mut functions: Vec<_> = symbol_reg
+            .get_functions()
+            .map(|(iden, fun_sy)| (self.format_unique_identifier(*iden), fun_sy))
+            .collect();
+        functions.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, fun_sy) in functions {
+            println!("  Fun(name: {}, is_def: {})", name, fun_sy.is_def());
+        }
+    }
+
+    /// Prints the stack frame size of each function, in the order they're defined.
+    pub fn print_stack_report(&self, program: &asm::Program) {
+        println!("Stack usage");
+
+        let frame_sizes = program.get_frame_sizes();
+        for item in program.get_functions() {
+            if let asm::TopLevel::F(fun_def) = item {
+                let name = fun_def.get_name();
+                println!(
+                    "  {}: {} bytes",
+                    self.format_identifier(name),
+                    frame_sizes[&name]
+                );
+            }
+        }
+    }
+
+    // locals with the same name are told apart by their id, like in the emitted assembly
+    fn format_unique_identifier(&self, identifier: Identifier) -> String {
+        match identifier.get_id() {
+            0 => self.format_identifier(identifier),
+            id => format!("{}.{}", self.format_identifier(identifier), id),
+        }
+    }
+
     fn format_identifier(&self, identifier: Identifier) -> String {
         format!("{}", self.sy_interner.lookup(identifier.get_symbol()))
     }
@@ -44,7 +106,13 @@ impl<'a> DebuggingPrinter<'a> {
     }
 
     fn print_function(&self, function: asm::FunctionDef) {
-        let (name, external, instructions) = function.into_parts();
+        let asm::FunctionParts {
+            name,
+            external,
+            instructions,
+            annotations,
+            ..
+        } = function.into_parts();
 
         let indent = " ".repeat(2);
         println!(
@@ -55,7 +123,10 @@ impl<'a> DebuggingPrinter<'a> {
         );
 
         for instr in instructions {
-            self.print_instruction(instr);
+            match instr {
+                asm::Instruction::Annotation(index) => println!("  # {}", annotations[index]),
+                _ => self.print_instruction(instr),
+            }
         }
     }
 
@@ -75,6 +146,27 @@ impl<'a> DebuggingPrinter<'a> {
             asm::Instruction::Movzx { src, dst } => {
                 println!("{}Movzx(src: {:?}, dst: {:?})", indent, src, dst)
             }
+            asm::Instruction::Movzb { size, src, dst } => {
+                println!(
+                    "{}Movzb(size: {:?}, src: {:?}, dst: {:?})",
+                    indent, size, src, dst
+                )
+            }
+            asm::Instruction::Cvttsd2si { size, src, dst } => {
+                println!(
+                    "{}Cvttsd2si(size: {:?}, src: {:?}, dst: {:?})",
+                    indent, size, src, dst
+                );
+            }
+            asm::Instruction::Cvtsi2sd { size, src, dst } => {
+                println!(
+                    "{}Cvtsi2sd(size: {:?}, src: {:?}, dst: {:?})",
+                    indent, size, src, dst
+                );
+            }
+            asm::Instruction::Lea { src, dst } => {
+                println!("{}Lea(src: {:?}, dst: {:?})", indent, src, dst)
+            }
             asm::Instruction::Unary { size, op, dst } => {
                 println!(
                     "{}Unary(size: {:?}, op: {:?}, dst: {:?})",
@@ -93,6 +185,12 @@ impl<'a> DebuggingPrinter<'a> {
                     indent, size, src, dst
                 );
             }
+            asm::Instruction::Test { size, src, dst } => {
+                println!(
+                    "{}Test(size: {:?}, src: {:?}, dst: {:?})",
+                    indent, size, src, dst
+                );
+            }
             asm::Instruction::Idiv(size, src) => {
                 println!("{}Idiv(size: {:?}, src: {:?})", indent, size, src);
             }
@@ -116,6 +214,17 @@ impl<'a> DebuggingPrinter<'a> {
             asm::Instruction::SetCC(cond, dst) => {
                 println!("{}SetCC(cond: {:?}, dst: {:?})", indent, cond, dst);
             }
+            asm::Instruction::Cmov {
+                cond,
+                size,
+                src,
+                dst,
+            } => {
+                println!(
+                    "{}Cmov(cond: {:?}, size: {:?}, src: {:?}, dst: {:?})",
+                    indent, cond, size, src, dst
+                );
+            }
             asm::Instruction::Label(label) => {
                 println!("{}Label({:?})", indent, self.format_identifier(label));
             }
@@ -128,6 +237,15 @@ impl<'a> DebuggingPrinter<'a> {
             asm::Instruction::Call(label) => {
                 println!("{}Call({})", indent, self.format_identifier(label))
             }
+            asm::Instruction::CallIndirect(target) => {
+                println!("{}CallIndirect({:?})", indent, target)
+            }
+            asm::Instruction::SourceLine(line) => {
+                println!("{}SourceLine({})", indent, line)
+            }
+            asm::Instruction::Annotation(index) => {
+                println!("{}Annotation({})", indent, index)
+            }
         }
     }
 }
@@ -11,9 +11,9 @@ impl InstructionFix {
         for item in asm_items {
             match item {
                 asm::TopLevel::F(fun_def) => Self::handle_function(fun_def),
-                // since all static variable defintion are at the end
-                // we return when we see the first static variable defintion
-                asm::TopLevel::S(_) => return,
+                // since all static variable and constant defintions are at the end
+                // we return when we see the first one
+                asm::TopLevel::S(_) | asm::TopLevel::C(_) => return,
             }
         }
     }
@@ -49,16 +49,37 @@ impl InstructionFix {
 
         match instr {
             Mov { size, src, dst } => Self::fix_mov(size, src, dst, new_instructions),
+            Cmp {
+                size: OperandSize::Double,
+                src,
+                dst,
+            } => Self::fix_double_cmp(src, dst, new_instructions),
             Cmp { size, src, dst } => Self::fix_cmp(size, src, dst, new_instructions),
+            Test { size, src, dst } => Self::fix_test(size, src, dst, new_instructions),
+            Cmov {
+                cond,
+                size,
+                src,
+                dst,
+            } => Self::fix_cmov(cond, size, src, dst, new_instructions),
+            Binary {
+                size: OperandSize::Double,
+                op,
+                src,
+                dst,
+            } => Self::fix_double_binary(op, src, dst, new_instructions),
             Binary { size, op, src, dst } => match op {
-                asm::BinaryOP::Add | asm::BinaryOP::Sub => {
-                    Self::fix_add_sub(size, op, src, dst, new_instructions)
-                }
                 asm::BinaryOP::Mul => Self::fix_mul(size, src, dst, new_instructions),
+                // the bitwise operations follow the same operand rules as add and sub
+                _ => Self::fix_add_sub(size, op, src, dst, new_instructions),
             },
+            Cvttsd2si { size, src, dst } => Self::fix_cvttsd2si(size, src, dst, new_instructions),
+            Cvtsi2sd { size, src, dst } => Self::fix_cvtsi2sd(size, src, dst, new_instructions),
+            Lea { src, dst } => Self::fix_lea(src, dst, new_instructions),
             Push(src) => Self::fix_push(src, new_instructions),
             Movsx { src, dst } => Self::fix_movsx(src, dst, new_instructions),
             Movzx { src, dst } => Self::fix_movzx(src, dst, new_instructions),
+            Movzb { size, src, dst } => Self::fix_movzb(size, src, dst, new_instructions),
             Idiv(size, src) => Self::fix_idiv(size, src, new_instructions),
             Div(size, src) => Self::fix_div(size, src, new_instructions),
             // other instructions do not need fixing
@@ -72,7 +93,7 @@ impl InstructionFix {
         use asm::Instruction::Push;
         use asm::Register::R10;
 
-        let needs_fix = Self::is_immediate(src);
+        let needs_fix = src.is_large_immediate(QuadWord);
 
         let fixed_src =
             Self::mov_operand(src, R10, QuadWord, needs_fix, new_instructions).unwrap_or(src);
@@ -84,6 +105,30 @@ impl InstructionFix {
         needs_fix
     }
 
+    /// Fix `lea` when the destination isn't a register,
+    /// loading the address into R11 and moving it to the destination.
+    fn fix_lea(
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use asm::Instruction::{Lea, Mov};
+        use asm::Register::R11;
+
+        let needs_fix = !Self::is_register(dst);
+
+        if needs_fix {
+            new_instructions.push(Lea { src, dst: Reg(R11) });
+            new_instructions.push(Mov {
+                size: OperandSize::QuadWord,
+                src: Reg(R11),
+                dst,
+            });
+        }
+
+        needs_fix
+    }
+
     /// Replace Movzx instruction by one or two Mov instruction
     fn fix_movzx(
         src: asm::Operand,
@@ -117,6 +162,45 @@ impl InstructionFix {
         true
     }
 
+    /// Fix Movzb when the source is an immediate, moved into R10 first,
+    /// or the destination isn't a register, zero extended into R11 and moved from there.
+    fn fix_movzb(
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use Instruction::{Mov, Movzb};
+        use Register::{R10, R11};
+
+        let src_need_fix = Self::is_immediate(src);
+        let dst_need_fix = !Self::is_register(dst);
+        let need_fix = src_need_fix || dst_need_fix;
+
+        let fixed_src =
+            Self::mov_operand(src, R10, OperandSize::Byte, src_need_fix, new_instructions)
+                .unwrap_or(src);
+        let fixed_dst = if dst_need_fix { Reg(R11) } else { dst };
+
+        if need_fix {
+            new_instructions.push(Movzb {
+                size,
+                src: fixed_src,
+                dst: fixed_dst,
+            });
+
+            if dst_need_fix {
+                new_instructions.push(Mov {
+                    size,
+                    src: Reg(R11),
+                    dst,
+                });
+            }
+        }
+
+        need_fix
+    }
+
     /// Fix Movsx instruction when either the source is immediate, the destination is Stack, or both
     /// return True if an instruction fix happens, false otherwise
     fn fix_movsx(
@@ -155,7 +239,8 @@ impl InstructionFix {
     }
 
     /// Fix MOV instructions when both operands are stack addresses.
-    /// Stack-to-stack MOV is illegal in x86_64, so use a temporary register.
+    /// Stack-to-stack MOV is illegal in x86_64, so use a temporary register,
+    /// XMM14 for doubles and R10 otherwise.
     fn fix_mov(
         size: OperandSize,
         src: asm::Operand,
@@ -163,12 +248,17 @@ impl InstructionFix {
         new_instructions: &mut Vec<asm::Instruction>,
     ) -> bool {
         use Instruction::Mov;
-        use Register::R10;
+        use Register::{R10, XMM14};
 
         let need_fix = (Self::is_mem(dst) && Self::is_mem(src))
-            || (Self::is_large_immediate(src) && Self::is_mem(dst));
+            || (src.is_large_immediate(size) && Self::is_mem(dst));
+        let scratch = if size == OperandSize::Double {
+            XMM14
+        } else {
+            R10
+        };
         let fixed_src =
-            Self::mov_operand(src, R10, size, need_fix, new_instructions).unwrap_or(src);
+            Self::mov_operand(src, scratch, size, need_fix, new_instructions).unwrap_or(src);
 
         if need_fix {
             new_instructions.push(Mov {
@@ -192,7 +282,7 @@ impl InstructionFix {
         use Instruction::Binary;
         use Register::R10;
 
-        let need_fix = (Self::is_mem(dst) && Self::is_mem(src)) || Self::is_large_immediate(src);
+        let need_fix = (Self::is_mem(dst) && Self::is_mem(src)) || src.is_large_immediate(size);
         let fixed_src =
             Self::mov_operand(src, R10, size, need_fix, new_instructions).unwrap_or(src);
 
@@ -208,7 +298,7 @@ impl InstructionFix {
     }
 
     /// Fix MUL instructions when the destination is a stack address
-    /// or when the source is an immediate larger than i32::MAX.
+    /// or when the source is an immediate that doesn't fit in 32 bits.
     /// Uses temporary registers R10 (for source) and R11 (for destination)
     /// to ensure valid operands, moving the result back if needed.
     fn fix_mul(
@@ -220,7 +310,7 @@ impl InstructionFix {
         use asm::Instruction::{Binary, Mov};
         use asm::Register::{R10, R11};
 
-        let src_needs_fix = Self::is_large_immediate(src);
+        let src_needs_fix = src.is_large_immediate(size);
         let dst_needs_fix = Self::is_mem(dst);
         let needs_fix = src_needs_fix || dst_needs_fix;
 
@@ -297,7 +387,7 @@ impl InstructionFix {
 
     /// Fix CMP instructions when both operands are memory addresses,
     /// when the destination is an immediate,
-    /// or when the source is an immediate larger than i32::MAX.
+    /// or when the source is an immediate that doesn't fit in 32 bits.
     /// Uses temporary registers R10 and R11 as needed to hold operands.
     fn fix_cmp(
         size: OperandSize,
@@ -310,7 +400,7 @@ impl InstructionFix {
 
         let src_dst_mem = Self::is_mem(src) && Self::is_mem(dst);
         let dst_imm = Self::is_immediate(dst);
-        let src_large_imm = Self::is_large_immediate(src);
+        let src_large_imm = src.is_large_immediate(size);
 
         let needs_fix = src_dst_mem || dst_imm || src_large_imm;
 
@@ -335,6 +425,206 @@ impl InstructionFix {
         needs_fix
     }
 
+    /// Fix CMOV instructions, the source can't be an immediate and the destination
+    /// must be a register. The source goes through R10, the destination through R11
+    /// and back, the movs don't change the flags the condition reads.
+    fn fix_cmov(
+        cond: asm::Cond,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use asm::Instruction::{Cmov, Mov};
+        use asm::Register::{R10, R11};
+
+        let src_needs_fix = Self::is_immediate(src);
+        let dst_needs_fix = !Self::is_register(dst);
+        let needs_fix = src_needs_fix || dst_needs_fix;
+
+        let fixed_src =
+            Self::mov_operand(src, R10, size, src_needs_fix, new_instructions).unwrap_or(src);
+        let fixed_dst =
+            Self::mov_operand(dst, R11, size, dst_needs_fix, new_instructions).unwrap_or(dst);
+
+        if needs_fix {
+            new_instructions.push(Cmov {
+                cond,
+                size,
+                src: fixed_src,
+                dst: fixed_dst,
+            });
+
+            if dst_needs_fix {
+                new_instructions.push(Mov {
+                    size,
+                    src: Reg(R11),
+                    dst,
+                });
+            }
+        }
+
+        needs_fix
+    }
+
+    /// Fix `test` of a value with itself when the value isn't in a register.
+    /// Two memory operands aren't allowed, `cmp $0, mem` sets the flags the same way
+    /// without a load. An immediate is moved into R11 first.
+    fn fix_test(
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use asm::Instruction::{Cmp, Test};
+        use asm::Register::R11;
+
+        debug_assert_eq!(src, dst, "only tests of a value with itself are generated");
+
+        if Self::is_mem(dst) {
+            new_instructions.push(Cmp {
+                size,
+                src: asm::Operand::Immediate(0),
+                dst,
+            });
+            true
+        } else if Self::is_immediate(dst) {
+            Self::mov_operand(dst, R11, size, true, new_instructions);
+            new_instructions.push(Test {
+                size,
+                src: Reg(R11),
+                dst: Reg(R11),
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fix double arithmetic and `xorpd`, which need their destination in an XMM register.
+    /// Uses XMM15 to hold the destination, moving the result back afterwards.
+    fn fix_double_binary(
+        op: asm::BinaryOP,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use OperandSize::Double;
+        use asm::Instruction::{Binary, Mov};
+        use asm::Register::XMM15;
+
+        let needs_fix = !Self::is_register(dst);
+
+        if needs_fix {
+            Self::mov_operand(dst, XMM15, Double, true, new_instructions);
+            new_instructions.push(Binary {
+                size: Double,
+                op,
+                src,
+                dst: Reg(XMM15),
+            });
+            new_instructions.push(Mov {
+                size: Double,
+                src: Reg(XMM15),
+                dst,
+            });
+        }
+
+        needs_fix
+    }
+
+    /// Fix `comisd` when the destination isn't an XMM register,
+    /// by moving it into XMM15 first.
+    fn fix_double_cmp(
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use OperandSize::Double;
+        use asm::Instruction::Cmp;
+        use asm::Register::XMM15;
+
+        let needs_fix = !Self::is_register(dst);
+
+        if needs_fix {
+            Self::mov_operand(dst, XMM15, Double, true, new_instructions);
+            new_instructions.push(Cmp {
+                size: Double,
+                src,
+                dst: Reg(XMM15),
+            });
+        }
+
+        needs_fix
+    }
+
+    /// Fix `cvttsd2si` when the destination isn't a register,
+    /// converting into R11 and moving the result to the destination.
+    fn fix_cvttsd2si(
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use asm::Instruction::{Cvttsd2si, Mov};
+        use asm::Register::R11;
+
+        let needs_fix = !Self::is_register(dst);
+
+        if needs_fix {
+            new_instructions.push(Cvttsd2si {
+                size,
+                src,
+                dst: Reg(R11),
+            });
+            new_instructions.push(Mov {
+                size,
+                src: Reg(R11),
+                dst,
+            });
+        }
+
+        needs_fix
+    }
+
+    /// Fix `cvtsi2sd` when the source is an immediate or the destination isn't a register.
+    /// Uses R10 to hold the source and XMM15 for the result.
+    fn fix_cvtsi2sd(
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        new_instructions: &mut Vec<asm::Instruction>,
+    ) -> bool {
+        use asm::Instruction::{Cvtsi2sd, Mov};
+        use asm::Register::{R10, XMM15};
+
+        let src_needs_fix = Self::is_immediate(src);
+        let dst_needs_fix = !Self::is_register(dst);
+        let needs_fix = src_needs_fix || dst_needs_fix;
+
+        let fixed_src =
+            Self::mov_operand(src, R10, size, src_needs_fix, new_instructions).unwrap_or(src);
+        let fixed_dst = if dst_needs_fix { Reg(XMM15) } else { dst };
+
+        if needs_fix {
+            new_instructions.push(Cvtsi2sd {
+                size,
+                src: fixed_src,
+                dst: fixed_dst,
+            });
+
+            if dst_needs_fix {
+                new_instructions.push(Mov {
+                    size: OperandSize::Double,
+                    src: Reg(XMM15),
+                    dst,
+                });
+            }
+        }
+
+        needs_fix
+    }
+
     /// fix an operand by moving it into a register
     fn mov_operand(
         operand: asm::Operand,
@@ -355,9 +645,17 @@ impl InstructionFix {
         }
     }
 
-    /// Helper: check if an operand is a stack address
+    /// Helper: check if an operand is in memory, a stack slot, memory reached through
+    /// a pointer, or a static variable (`Data`, RIP-relative)
     fn is_mem(op: asm::Operand) -> bool {
-        matches!(op, asm::Operand::Stack(_) | asm::Operand::Data(_))
+        matches!(
+            op,
+            asm::Operand::Stack(_)
+                | asm::Operand::Memory(..)
+                | asm::Operand::Indexed(..)
+                | asm::Operand::Data(_)
+                | asm::Operand::DoubleConst(_)
+        )
     }
 
     /// Helper: check if an operand is an immediate value
@@ -368,13 +666,4 @@ impl InstructionFix {
     fn is_register(op: asm::Operand) -> bool {
         matches!(op, asm::Operand::Reg(_))
     }
-
-    /// check if an Operand is an immediate that can't fit into 4 bytes
-    fn is_large_immediate(op: asm::Operand) -> bool {
-        if let asm::Operand::Immediate(int) = op {
-            if int > i32::MAX as u64 { true } else { false }
-        } else {
-            false
-        }
-    }
 }
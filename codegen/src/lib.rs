@@ -2,7 +2,7 @@ use asm_gen::AsmGen;
 use ir_gen::tacky;
 use reg_alloc::RegisterAllocation;
 use shared_context::{
-    SymbolRegistery, symbol_interner::SymbolInterner, type_interner::TypeInterner,
+    OptPasses, SymbolRegistery, symbol_interner::SymbolInterner, type_interner::TypeInterner,
 };
 
 // These modules implement different parts of the code generation pipeline.
@@ -11,6 +11,7 @@ pub mod asm;
 mod asm_gen;
 mod debugging_printer;
 mod fix_instructions;
+mod peephole;
 mod reg_alloc;
 
 // Overview of this stage:
@@ -20,10 +21,14 @@ mod reg_alloc;
 //   1. Generate an assembly-level abstract syntax tree (AST).
 //   2. Allocate hardware registers for pseudo-registers.
 //   3. Fix or rewrite invalid instructions that violate constraints.
+// When enabled, a peephole pass removes redundant instructions between 2 and 3.
 
 // Empty struct used as a namespace for instruction fix-up methods.
 struct InstructionFix;
 
+// Empty struct used as a namespace for peephole optimization methods.
+struct Peephole;
+
 // Provides debugging utilities to print IR and assembly with resolved identifiers.
 pub struct DebuggingPrinter<'a> {
     sy_interner: &'a SymbolInterner<'a>, // allows mapping identifiers to their string names
@@ -32,18 +37,35 @@ pub struct DebuggingPrinter<'a> {
 // Main entry point for the code generation pipeline.
 //
 // Takes a Tacky IR program and returns a final assembly program.
+// When `annotate` is given, the instructions are annotated with the Tacky they came from.
+// With `stack_protector` every function gets a stack slot for a canary, the emitter
+// stores it in the prologue and checks it before each `ret`.
 pub fn codegen<'ctx, 'src>(
     program_tacky: tacky::Program,
     ty_interner: &'ctx TypeInterner<'src>,
     symbol_reg: &'ctx SymbolRegistery,
+    passes: &OptPasses,
+    annotate: Option<&'ctx SymbolInterner<'src>>,
+    stack_protector: bool,
 ) -> asm::Program {
     // 1. Convert Tacky IR into an assembly AST (still uses pseudo-registers).
-    let mut program_asm = AsmGen::new(ty_interner, symbol_reg).gen_asm(program_tacky);
+    let mut asm_gen =
+        AsmGen::new(ty_interner, symbol_reg).with_strength_reduction(passes.strength_reduction);
+    if let Some(sy_interner) = annotate {
+        asm_gen = asm_gen.with_annotations(sy_interner);
+    }
+    let mut program_asm = asm_gen.gen_asm(program_tacky);
 
     // 2. Allocate real machine registers or stack slots to pseudo-registers.
-    let mut codegen = RegisterAllocation::new(symbol_reg);
+    let mut codegen =
+        RegisterAllocation::new(symbol_reg, stack_protector).with_linear_scan(passes.linear_scan);
     codegen.allocate_registers(&mut program_asm);
 
+    // Remove redundant instructions, now that operands are concrete.
+    if passes.peephole {
+        Peephole::optimize(&mut program_asm);
+    }
+
     // 3. Fix invalid or non-encodable instructions.
     InstructionFix::fix_instructions(&mut program_asm);
 
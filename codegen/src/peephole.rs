@@ -0,0 +1,66 @@
+use shared_context::OperandSize;
+
+use crate::Peephole;
+use crate::asm::{self, Instruction, Operand};
+
+impl Peephole {
+    /// Remove redundant instructions from every function in the program.
+    ///
+    /// Runs after register allocation, so operands are already concrete
+    /// and two operands referring to the same location compare equal.
+    pub fn optimize(program: &mut asm::Program) {
+        let asm_items = program.get_mut_functions();
+        for item in asm_items {
+            match item {
+                asm::TopLevel::F(fun_def) => Self::handle_function(fun_def),
+                // since all static variable and constant defintions are at the end
+                // we return when we see the first one
+                asm::TopLevel::S(_) | asm::TopLevel::C(_) => return,
+            }
+        }
+    }
+
+    fn handle_function(function: &mut asm::FunctionDef) {
+        let instructions = function.get_mut_instructions();
+        let mut new_instructions: Vec<asm::Instruction> = Vec::with_capacity(instructions.len());
+
+        for instr in instructions.drain(..) {
+            match instr {
+                Instruction::Mov { size, src, dst } if Self::is_redundant_mov(size, src, dst) => {
+                    continue;
+                }
+                Instruction::Label(label) => {
+                    // a jump to the next instruction falls through anyway,
+                    // the markers in between generate no code
+                    let previous = new_instructions
+                        .iter()
+                        .rposition(|instr| !Self::is_marker(instr));
+                    if let Some(index) = previous
+                        && matches!(new_instructions[index], Instruction::Jmp(target) if target == label)
+                    {
+                        new_instructions.remove(index);
+                    }
+                }
+                _ => (),
+            }
+            new_instructions.push(instr);
+        }
+
+        *instructions = new_instructions;
+    }
+
+    /// Source line and annotation markers sit between the instructions,
+    /// the patterns look through them.
+    fn is_marker(instr: &Instruction) -> bool {
+        matches!(
+            instr,
+            Instruction::SourceLine(_) | Instruction::Annotation(_)
+        )
+    }
+
+    /// A move of a location into itself does nothing,
+    /// except for a 32 bit move into a register which clears its upper half.
+    fn is_redundant_mov(size: OperandSize, src: Operand, dst: Operand) -> bool {
+        src == dst && !(size == OperandSize::LongWord && matches!(dst, Operand::Reg(_)))
+    }
+}
@@ -1,27 +1,51 @@
 use shared_context::Identifier;
 use shared_context::OperandSize;
 use shared_context::SymbolRegistery;
+use shared_context::convert_type_to_operand_size;
 
 use crate::asm;
 use std::collections::HashMap;
 
+mod linear_scan;
+
 // Stores the mapping from Tacky-level pseudo-registers to real registers or stack offsets.
 pub(super) struct RegisterAllocation<'ctx> {
     pseudo_reg_map: HashMap<Identifier, i64>, // maps each variable to a register or stack slot
     symbol_reg: &'ctx SymbolRegistery,        // used to resolve which variables are static
     sp_offset: i64,                           // current stack pointer offset (for spilled vars)
+
+    linear_scan: bool, // assign registers to variables before giving the others stack slots
+    register_map: HashMap<Identifier, asm::Register>, // variables kept in a register
+
+    // callee-saved registers assigned in the current function, in the order the prologue
+    // pushes them
+    callee_saved: Vec<asm::Register>,
+    stack_protector: bool, // reserve a slot for a stack canary in each function
+    frame_sizes: HashMap<Identifier, u64>, // the rounded frame size of each allocated function
 }
 
 impl<'ctx> RegisterAllocation<'ctx> {
     /// Create a new RegisterAllocation instance
-    pub fn new(symbol_reg: &'ctx SymbolRegistery) -> Self {
+    pub fn new(symbol_reg: &'ctx SymbolRegistery, stack_protector: bool) -> Self {
         Self {
             pseudo_reg_map: HashMap::new(), // Maps pseudo-register IDs to stack offsets
             symbol_reg,
             sp_offset: 0, // Tracks the current stack offset
+            linear_scan: false,
+            register_map: HashMap::new(),
+            callee_saved: Vec::new(),
+            stack_protector,
+            frame_sizes: HashMap::new(),
         }
     }
 
+    /// Keeps the variables of each function in callee-saved registers, chosen by linear scan,
+    /// the variables that don't get one are given stack slots as usual.
+    pub fn with_linear_scan(mut self, enabled: bool) -> Self {
+        self.linear_scan = enabled;
+        self
+    }
+
     /// Round the stack pointer offset to the next multiple of 16.
     /// x86-64 ABI requires 16-byte alignment for stack before function calls.
     fn get_sp_offset_rounded_to_16(&self) -> u64 {
@@ -34,20 +58,38 @@ impl<'ctx> RegisterAllocation<'ctx> {
     }
 
     /// Top-level function: allocate memory for all pseudo registers in all functions.
+    /// The frame size of each function is recorded in the program.
     pub fn allocate_registers(&mut self, program: &mut asm::Program) {
         let asm_items = program.get_mut_functions();
         for item in asm_items {
             match item {
                 asm::TopLevel::F(fun_def) => self.handle_function(fun_def),
-                // since all static variable defintion are at the end
-                // we return when we see the first static variable defintion
-                asm::TopLevel::S(_) => return,
+                // since all static variable and constant defintions are at the end
+                // we stop at the first one
+                asm::TopLevel::S(_) | asm::TopLevel::C(_) => break,
             }
         }
+        program.set_frame_sizes(std::mem::take(&mut self.frame_sizes));
     }
 
-    /// Allocate stack space for pseudo registers in a single function.
+    /// Allocate registers or stack space for pseudo registers in a single function.
     pub fn handle_function(&mut self, function: &mut asm::FunctionDef) {
+        if self.linear_scan {
+            self.assign_registers(function.get_instructions());
+        }
+
+        // the prologue pushes the callee-saved registers right below the saved `%rbp`,
+        // the stack slots start below them
+        let save_area = 8 * self.callee_saved.len() as i64;
+        self.sp_offset = -save_area;
+
+        // the canary sits above every other slot, so an overflowing write from a slot
+        // reaches it before the saved registers and the return address
+        if self.stack_protector {
+            self.sp_offset -= 8;
+            function.set_canary(self.sp_offset);
+        }
+
         let instructions = function.get_mut_instructions();
 
         // Replace pseudo-registers in each instruction with stack addresses
@@ -55,13 +97,22 @@ impl<'ctx> RegisterAllocation<'ctx> {
             self.replace_pseudo_reg(instruction);
         }
 
-        // Reserve actual stack space at the start of function
+        // Reserve actual stack space at the start of function, the pushes already
+        // reserved the save area and the whole frame stays a multiple of 16
+        let frame_size = self.get_sp_offset_rounded_to_16();
         function.get_mut_instructions()[0] = asm::Instruction::Binary {
             op: asm::BinaryOP::Sub,
             size: OperandSize::QuadWord,
-            src: asm::Operand::Immediate(self.get_sp_offset_rounded_to_16()),
+            src: asm::Operand::Immediate(frame_size - save_area as u64),
             dst: asm::Operand::Reg(asm::Register::SP),
         };
+        self.frame_sizes.insert(function.get_name(), frame_size);
+        debug_assert!(
+            self.callee_saved.iter().all(|reg| reg.is_callee_saved()),
+            "a caller-saved register is recorded as callee-saved"
+        );
+        function.set_callee_saved(std::mem::take(&mut self.callee_saved));
+        self.register_map.clear();
 
         // Reset stack pointer offset for next function
         self.sp_offset = 0;
@@ -97,16 +148,21 @@ impl<'ctx> RegisterAllocation<'ctx> {
                 self.to_stack(src, *size);
             }
 
-            asm::Instruction::Cmp { size, src, dst } => {
+            asm::Instruction::Cmp { size, src, dst }
+            | asm::Instruction::Test { size, src, dst }
+            | asm::Instruction::Cmov { size, src, dst, .. } => {
                 self.to_stack(src, *size);
                 self.to_stack(dst, *size);
             }
 
+            // setcc writes a single byte, the slot is sized by the destination's type
+            // so it matches the `mov $0` clearing it
             asm::Instruction::SetCC(_, src) => {
-                self.to_stack(src, OperandSize::LongWord);
+                let size = self.get_pseudo_size(*src);
+                self.to_stack(src, size);
             }
 
-            asm::Instruction::Push(src) => {
+            asm::Instruction::Push(src) | asm::Instruction::CallIndirect(src) => {
                 self.to_stack(src, OperandSize::QuadWord);
             }
 
@@ -120,14 +176,64 @@ impl<'ctx> RegisterAllocation<'ctx> {
                 self.to_stack(dst, OperandSize::QuadWord);
             }
 
+            asm::Instruction::Movzb { size, src, dst } => {
+                self.to_stack(src, OperandSize::Byte);
+                self.to_stack(dst, *size);
+            }
+
+            // the slot of the variable whose address is taken is sized by its own type
+            asm::Instruction::Lea { src, dst } => {
+                let size = self.get_pseudo_size(*src);
+                self.to_stack(src, size);
+                self.to_stack(dst, OperandSize::QuadWord);
+            }
+
+            asm::Instruction::Cvttsd2si { size, src, dst } => {
+                self.to_stack(src, OperandSize::Double);
+                self.to_stack(dst, *size);
+            }
+
+            asm::Instruction::Cvtsi2sd { size, src, dst } => {
+                self.to_stack(src, *size);
+                self.to_stack(dst, OperandSize::Double);
+            }
+
             // Instructions without pseudo-register operands are ignored
             _ => return,
         }
     }
 
-    /// Convert a pseudo-register operand to a stack location if needed.
+    /// Returns the operand size of a pseudo-register's type, LongWord for other operands.
+    fn get_pseudo_size(&self, operand: asm::Operand) -> OperandSize {
+        match operand {
+            asm::Operand::Pseudo(id) => {
+                convert_type_to_operand_size(self.symbol_reg.get_variable(&id).get_type())
+            }
+            _ => OperandSize::LongWord,
+        }
+    }
+
+    /// Convert a pseudo-register operand to its register or a stack location if needed.
+    ///
+    /// A static variable becomes the same `Data` operand wherever it's used, so `g = g`
+    /// reads and writes `g(%rip)` in one `mov`. Fixing the instructions moves the source
+    /// through a scratch register, like any other memory to memory operation.
     fn to_stack(&mut self, operand: &mut asm::Operand, size: OperandSize) {
+        // locals live below the base pointer, stack parameters start after the saved
+        // base pointer and the return address, any other offset is a code generation bug
+        if let asm::Operand::Stack(offset) = *operand {
+            debug_assert!(
+                !(0..16).contains(&offset),
+                "stack operand {offset}(%rbp) is neither a local nor a parameter slot"
+            );
+        }
+
         if let asm::Operand::Pseudo(id) = operand {
+            if let Some(register) = self.register_map.get(id) {
+                *operand = asm::Operand::Reg(*register);
+                return;
+            }
+
             // Already mapped? Replace and return.
             if let Some(offset) = self.pseudo_reg_map.get(id) {
                 *operand = asm::Operand::Stack(*offset);
@@ -145,19 +251,30 @@ impl<'ctx> RegisterAllocation<'ctx> {
         }
     }
 
+    /// Gives the pseudo-register a new slot below the ones already allocated.
+    /// Slots are negative offsets from `%rbp`, the positive ones hold stack parameters.
+    ///
+    /// An array gets one slot for all of its elements, the slot's offset is the address
+    /// of the first element.
     fn allocate_stack(&mut self, iden: Identifier, operand: &mut asm::Operand, size: OperandSize) {
-        match size {
-            OperandSize::LongWord => {
-                self.sp_offset -= 4;
-                self.pseudo_reg_map.insert(iden, self.sp_offset);
-                *operand = asm::Operand::Stack(self.sp_offset);
-            }
-            OperandSize::QuadWord => {
-                let alloc_amount = if (-self.sp_offset) % 8 == 0 { 8 } else { 12 };
-                self.sp_offset -= alloc_amount;
-                self.pseudo_reg_map.insert(iden, self.sp_offset);
-                *operand = asm::Operand::Stack(self.sp_offset);
-            }
-        }
+        // each slot is aligned to its own size, an array to its element size
+        let align = match size {
+            OperandSize::Byte => 1,
+            OperandSize::LongWord => 4,
+            OperandSize::QuadWord | OperandSize::Double => 8,
+        };
+        let var_sy = self.symbol_reg.get_variable(&iden);
+        let bytes = match var_sy.get_array_len() {
+            Some(_) => var_sy.get_size() as i64,
+            None => align,
+        };
+        self.sp_offset = (self.sp_offset - bytes).div_euclid(align) * align;
+        self.pseudo_reg_map.insert(iden, self.sp_offset);
+        *operand = asm::Operand::Stack(self.sp_offset);
+        debug_assert!(
+            self.sp_offset < 0,
+            "stack slot {}(%rbp) isn't below the base pointer",
+            self.sp_offset
+        );
     }
 }
@@ -0,0 +1,159 @@
+// Linear scan register allocation.
+//
+// Each variable that can live in a register gets a live interval, from the first to
+// the last instruction that uses it. A loop is a backward jump, a variable used inside
+// one has to survive every iteration, so an interval overlapping a loop is widened to
+// cover all of it. The intervals are visited by start, a variable gets a free register
+// when there's one; otherwise the variable among it and the ones holding a register
+// whose interval ends last is left to a stack slot.
+//
+// Only the callee-saved registers are handed out: a call preserves them, so a variable
+// can stay in one across calls, and the caller-saved ones stay free for the instructions
+// that use them on their own (arguments, return values, division and the scratch
+// registers of the instruction fixes). The prologue saves the ones a function uses.
+
+use shared_context::Identifier;
+use std::collections::{HashMap, HashSet};
+
+use super::RegisterAllocation;
+use crate::asm::{Instruction, Operand, Register};
+
+/// The registers variables are assigned, in the order they're handed out.
+const ALLOCATABLE: [Register; 5] = [
+    Register::BX,
+    Register::R12,
+    Register::R13,
+    Register::R14,
+    Register::R15,
+];
+
+/// The instructions a variable is live across, by index in the function's instructions.
+struct Interval {
+    pseudo: Identifier,
+    start: usize,
+    end: usize,
+}
+
+impl<'ctx> RegisterAllocation<'ctx> {
+    /// Assign registers to the variables of a function, recording the callee-saved
+    /// registers used so the prologue saves them.
+    pub(super) fn assign_registers(&mut self, instructions: &[Instruction]) {
+        let intervals = self.live_intervals(instructions);
+        let mut assigned: Vec<Option<Register>> = vec![None; intervals.len()];
+        let mut active: Vec<usize> = Vec::new(); // the intervals holding a register
+
+        for (index, interval) in intervals.iter().enumerate() {
+            // the registers of the intervals that ended before this one are free again
+            active.retain(|&other| intervals[other].end >= interval.start);
+
+            let free = ALLOCATABLE.into_iter().find(|&register| {
+                active
+                    .iter()
+                    .all(|&other| assigned[other] != Some(register))
+            });
+            if let Some(register) = free {
+                assigned[index] = Some(register);
+                active.push(index);
+                continue;
+            }
+
+            // spill the interval that ends last, this one when it does
+            let (position, &last) = active
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, &other)| intervals[other].end)
+                .expect("every register is taken");
+            if intervals[last].end > interval.end {
+                assigned[index] = assigned[last].take();
+                active[position] = index;
+            }
+        }
+
+        for (interval, register) in intervals.iter().zip(assigned) {
+            if let Some(register) = register {
+                self.register_map.insert(interval.pseudo, register);
+            }
+        }
+        self.callee_saved = ALLOCATABLE
+            .into_iter()
+            .filter(|register| self.register_map.values().any(|used| used == register))
+            .collect();
+    }
+
+    /// The live interval of each variable that can be kept in a register, ordered by start.
+    fn live_intervals(&self, instructions: &[Instruction]) -> Vec<Interval> {
+        let mut intervals: Vec<Interval> = Vec::new();
+        let mut positions: HashMap<Identifier, usize> = HashMap::new();
+        let mut address_taken: HashSet<Identifier> = HashSet::new();
+        let mut labels: HashMap<Identifier, usize> = HashMap::new();
+        let mut loops: Vec<(usize, usize)> = Vec::new(); // the label and the backward jump to it
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            match *instruction {
+                Instruction::Label(label) => {
+                    labels.insert(label, index);
+                }
+                Instruction::Jmp(target) | Instruction::JmpCC(_, target) => {
+                    if let Some(&label) = labels.get(&target) {
+                        loops.push((label, index));
+                    }
+                }
+                // a variable whose address is taken has to be in memory
+                Instruction::Lea {
+                    src: Operand::Pseudo(id),
+                    ..
+                } => {
+                    address_taken.insert(id);
+                }
+                _ => (),
+            }
+
+            for operand in instruction.get_operands() {
+                let Operand::Pseudo(id) = operand else {
+                    continue;
+                };
+                match positions.get(&id) {
+                    Some(&position) => intervals[position].end = index,
+                    None if self.fits_in_register(id) => {
+                        positions.insert(id, intervals.len());
+                        intervals.push(Interval {
+                            pseudo: id,
+                            start: index,
+                            end: index,
+                        });
+                    }
+                    None => (),
+                }
+            }
+        }
+        intervals.retain(|interval| !address_taken.contains(&interval.pseudo));
+
+        // widen the intervals overlapping a loop to the whole loop, until none changes
+        // since widening one can make it overlap an enclosing loop
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for interval in intervals.iter_mut() {
+                for &(label, jump) in &loops {
+                    let overlaps = interval.start <= jump && interval.end >= label;
+                    if overlaps && (interval.start > label || interval.end < jump) {
+                        interval.start = interval.start.min(label);
+                        interval.end = interval.end.max(jump);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // widening can move a start back, the scan visits the intervals by start
+        intervals.sort_by_key(|interval| interval.start);
+        intervals
+    }
+
+    /// Whether a variable can be kept in a general purpose register: an automatic
+    /// scalar that isn't a double. Static variables and arrays stay in memory.
+    fn fits_in_register(&self, id: Identifier) -> bool {
+        let var_sy = self.symbol_reg.get_variable(&id);
+        !var_sy.is_static() && var_sy.get_array_len().is_none() && !var_sy.get_type().is_double()
+    }
+}
@@ -1,10 +1,30 @@
-use std::process::Command;
+use lilcc::CompileError;
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
 
 // this module contain file management functions
 
+/// the input path that tells the driver to read the program from stdin
+pub const STDIN_PATH: &str = "-";
+
+/// the output path that tells the driver to write the assembly to stdout
+pub const STDOUT_PATH: &str = "-";
+
 /// pre-process the source file, return a path to the pre-processed file
+/// when the path is `-`, the program is piped from stdin through the preprocessor
 pub fn pre_process_file(file_path: &str) -> String {
-    let pre_processed_file = set_file_name(file_path, "pre_process.i");
+    if file_path == STDIN_PATH {
+        let pre_processed_file = format!("{}.i", output_stem(file_path));
+        Command::new("gcc")
+            .args(["-E", "-x", "c", "-", "-o", &pre_processed_file])
+            .stdin(Stdio::inherit())
+            .output()
+            .expect("failed to pre-process the program");
+        return pre_processed_file;
+    }
+
+    let file_name = format!("{}.i", remove_file_extension(get_file_name(file_path)));
+    let pre_processed_file = set_file_name(file_path, &file_name);
     Command::new("gcc")
         .args(["-E", file_path, "-o", &pre_processed_file])
         .output()
@@ -12,19 +32,65 @@ pub fn pre_process_file(file_path: &str) -> String {
     pre_processed_file
 }
 
-/// compile assembly file
-pub fn compile_assembly_file(file_path: &str, output_file_path: &str) {
-    Command::new("gcc")
-        .args(["-c", file_path, "-o", output_file_path])
-        .output()
-        .expect("failed to compile assembly file");
+/// write the program read from stdin to a file, without pre-processing it,
+/// return the path to the file
+pub fn copy_stdin_to_file() -> String {
+    let mut source = String::new();
+    io::stdin()
+        .read_to_string(&mut source)
+        .expect("failed to read the program from stdin");
+    let copied_file = format!("{}.i", output_stem(STDIN_PATH));
+    std::fs::write(&copied_file, source).expect("failed to write the program read from stdin");
+    copied_file
 }
 
-pub fn compile_and_link_assembly_file(file_path: &str, output_file_path: &str) {
-    Command::new("gcc")
-        .args([file_path, "-o", output_file_path])
+/// compile assembly file, gcc's diagnostics are returned when it fails
+pub fn compile_assembly_file(file_path: &str, output_file_path: &str) -> Result<(), CompileError> {
+    let mut command = Command::new("gcc");
+    command.args(["-c", file_path, "-o", output_file_path]);
+    run_gcc(command).map_err(CompileError::Assemble)
+}
+
+/// compile and link one or more assembly files into an executable,
+/// gcc's diagnostics are returned when it fails, e.g. for an undefined symbol
+pub fn compile_and_link_assembly_files(
+    file_paths: &[String],
+    output_file_path: &str,
+) -> Result<(), CompileError> {
+    let mut command = Command::new("gcc");
+    command.args(file_paths).args(["-o", output_file_path]);
+    run_gcc(command).map_err(CompileError::Link)
+}
+
+/// run a gcc command, return its stderr when it can't be run or exits with an error
+fn run_gcc(mut command: Command) -> Result<(), String> {
+    let output = command
         .output()
-        .expect("failed to compile assembly file");
+        .map_err(|err| format!("gcc couldn't be run: {err}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr.trim_end().to_string())
+}
+
+/// return the path used as the base for derived output files
+/// programs read from stdin are written next to the current directory as `stdin.*`
+pub fn output_stem(file_path: &str) -> &str {
+    if file_path == STDIN_PATH {
+        "stdin"
+    } else {
+        remove_file_extension(file_path)
+    }
+}
+
+/// return the name used for the source file in diagnostics
+pub fn display_name(file_path: &str) -> &str {
+    if file_path == STDIN_PATH {
+        "<stdin>"
+    } else {
+        get_file_name(file_path)
+    }
 }
 
 /// remove the file extension from a path
@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use emitter::AsmSyntax;
+use lilcc::{CompileError, CompileOptions};
+use shared_context::OptPasses;
 use std::error::Error;
 
 mod files;
@@ -7,6 +10,7 @@ use files::*;
 use stages::*;
 
 enum Stage {
+    Preprocess,
     Lex,
     Parse,
     Tacky,
@@ -17,35 +21,147 @@ enum Stage {
     None,
 }
 
+/// Warnings that are only reported when enabled with `-W<name>`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Warning {
+    /// implicit conversions between signed and unsigned types that can change a value's sign
+    SignConversion,
+}
+
 #[derive(Parser)]
 struct Cli {
+    /// only run the preprocessor, print the preprocessed source or write it to `-o`
+    #[arg(short = 'E', group = "stage")]
+    preprocess: bool,
+
     #[arg(long, group = "stage")]
     lex: bool,
 
-    #[arg(long, group = "stage")]
+    #[arg(long, group = "stage", group = "ast_stage")]
     parse: bool,
 
+    /// with `--parse` or `--validate`, print the AST as JSON instead of an indented tree,
+    /// `--validate` shows the names identifier resolution gave the variables
+    #[arg(long, requires = "ast_stage")]
+    json: bool,
+
     #[arg(long, group = "stage")]
     tacky: bool,
 
     #[arg(long, group = "stage")]
     codegen: bool,
 
-    #[arg(long, group = "stage")]
+    #[arg(long, group = "stage", group = "ast_stage")]
     validate: bool,
 
-    #[arg(long, group = "stage")]
+    /// only compile to assembly, `-o -` writes it to stdout
+    #[arg(short = 'S', long, group = "stage")]
     asm: bool,
 
     #[arg(short = 'c', group = "stage")]
     obj: bool,
 
-    file_path: String,
+    /// optimization level: 0 runs no passes, 1 folds constants, removes dead code and
+    /// replaces multiplications and unsigned divisions by powers of two with shifts,
+    /// 2 also runs the peephole pass, lowers simple conditionals to `cmov`, inlines
+    /// small functions and keeps variables in registers with linear-scan allocation
+    #[arg(short = 'O', long = "opt-level", default_value_t = 0,
+          value_parser = clap::value_parser!(u8).range(0..=2))]
+    opt_level: u8,
+
+    /// enable an optional warning, `-Wsign-conversion`
+    #[arg(short = 'W', value_enum, value_name = "WARNING")]
+    warnings: Vec<Warning>,
+
+    /// report all the errors identifier resolution or type checking finds, not only the first
+    #[arg(long)]
+    keep_going: bool,
+
+    /// store a canary below each function's saved registers and check it before returning
+    #[arg(long = "fstack-protector")]
+    stack_protector: bool,
+
+    /// write assembly in Intel syntax instead of AT&T syntax
+    #[arg(long)]
+    intel: bool,
+
+    /// emit line number debug info for source-level debugging
+    #[arg(short = 'g')]
+    debug_info: bool,
+
+    /// annotate each assembly instruction with the Tacky instruction it was generated from
+    #[arg(long)]
+    annotate: bool,
+
+    /// also write a `.lst` listing next to the assembly, each source line above its instructions
+    #[arg(long)]
+    listing: bool,
+
+    /// check the IR invariants (jump targets, labels, returns, symbols) before code generation
+    #[arg(long)]
+    verify_ir: bool,
+
+    /// print the basic blocks of each function's IR and their successors
+    #[arg(long)]
+    dump_cfg: bool,
+
+    /// print the symbols code generation sees (variable sizes and storage, defined functions)
+    #[arg(long)]
+    dump_asm_symbols: bool,
+
+    /// print the stack frame size of each function after register allocation
+    #[arg(long)]
+    stack_report: bool,
+
+    /// print the symbol table after type checking (types, linkage and initializers)
+    #[arg(long)]
+    print_symbol_table: bool,
+
+    /// lex the source as it is instead of running the preprocessor on it,
+    /// for programs without `#include`s or macros
+    #[arg(long)]
+    no_cpp: bool,
+
+    /// keep the intermediate files, the preprocessed `.i` and the assembly `.s` next to the source
+    #[arg(long)]
+    save_temps: bool,
+
+    /// write the final output (executable, object or assembly file) to this path
+    #[arg(short = 'o', long = "output")]
+    output: Option<String>,
+
+    #[arg(required = true)]
+    file_paths: Vec<String>,
 }
 
 impl Cli {
+    fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            passes: OptPasses::from_level(self.opt_level),
+            debug_info: self.debug_info,
+            annotate: self.annotate,
+            listing: self.listing,
+            verify_ir: self.verify_ir,
+            dump_cfg: self.dump_cfg,
+            dump_symbols: self.dump_asm_symbols,
+            stack_report: self.stack_report,
+            print_symbol_table: self.print_symbol_table,
+            warn_sign_conversion: self.warnings.contains(&Warning::SignConversion),
+            keep_going: self.keep_going,
+            stack_protector: self.stack_protector,
+            no_cpp: self.no_cpp,
+            syntax: if self.intel {
+                AsmSyntax::Intel
+            } else {
+                AsmSyntax::Att
+            },
+        }
+    }
+
     fn selected_stage(&self) -> Stage {
-        if self.lex {
+        if self.preprocess {
+            Stage::Preprocess
+        } else if self.lex {
             Stage::Lex
         } else if self.parse {
             Stage::Parse
@@ -68,53 +184,121 @@ impl Cli {
 fn main() {
     if let Err(e) = run() {
         eprintln!("error: {}", e);
-        std::process::exit(1);
+        // compile errors exit with a code telling the stage that failed apart
+        let code = e
+            .downcast_ref::<CompileError>()
+            .map_or(1, CompileError::exit_code);
+        std::process::exit(code);
     }
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
     let arg = Cli::parse();
 
-    // Preprocess file first
-    let pre_path = pre_process_file(&arg.file_path);
-    let result = run_stage(&arg, &pre_path);
+    if arg.output.is_some()
+        && arg.file_paths.len() > 1
+        && matches!(
+            arg.selected_stage(),
+            Stage::Preprocess | Stage::Asm | Stage::Obj
+        )
+    {
+        return Err("cannot specify '-o' with '-E', '-c' or '--asm' with multiple files".into());
+    }
+
+    let mut asm_paths = Vec::new();
+
+    for file_path in &arg.file_paths {
+        // Preprocess file first, without the preprocessor the source itself is compiled
+        let pre_path = match (arg.no_cpp, file_path.as_str()) {
+            (false, _) => pre_process_file(file_path),
+            (true, STDIN_PATH) => copy_stdin_to_file(),
+            (true, _) => file_path.clone(),
+        };
+        let result = run_stage(&arg, file_path, &pre_path);
+
+        // clean up preprocessed file, even on failure
+        if !arg.save_temps && pre_path != *file_path {
+            delete_file(&pre_path);
+        }
+
+        match result {
+            Ok(Some(asm_path)) => asm_paths.push(asm_path),
+            Ok(None) => (),
+            // the files compiled so far won't be linked
+            Err(err) => {
+                delete_assembly_files(&arg, &asm_paths);
+                return Err(err);
+            }
+        }
+    }
+
+    // link every translation unit into a single executable
+    if let Stage::None = arg.selected_stage() {
+        let exe_path = match &arg.output {
+            Some(output) => output,
+            // like gcc, a program read from stdin links to a.out
+            None if arg.file_paths[0] == STDIN_PATH => "a.out",
+            None => remove_file_extension(&arg.file_paths[0]),
+        };
+        let linked = compile_and_link_assembly_files(&asm_paths, exe_path);
+        delete_assembly_files(&arg, &asm_paths);
+        linked?;
+    }
 
-    // clean up preprocessed file, even on failure
-    delete_file(&pre_path);
+    Ok(())
+}
 
-    result
+/// Deletes the assembly files written for linking, unless `--save-temps` keeps them.
+fn delete_assembly_files(arg: &Cli, asm_paths: &[String]) {
+    if !arg.save_temps {
+        for asm_path in asm_paths {
+            delete_file(asm_path);
+        }
+    }
 }
 
-/// Dispatch to the appropriate compilation stage.
-/// Handles all temporary files within a single scope.
-fn run_stage(arg: &Cli, pre_path: &str) -> Result<(), Box<dyn Error>> {
-    let file_name = get_file_name(&arg.file_path);
+/// Dispatch to the appropriate compilation stage for a single source file.
+/// Returns the path of the emitted assembly file when it's still needed for linking.
+fn run_stage(arg: &Cli, file_path: &str, pre_path: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let file_name = display_name(file_path);
+    let options = arg.compile_options();
 
     match arg.selected_stage() {
-        Stage::Lex => lexer_stage(pre_path)?,
-        Stage::Parse => parser_stage(pre_path, file_name)?,
-        Stage::Validate => validate_stage(pre_path, file_name)?,
-        Stage::Tacky => tacky_stage(pre_path, file_name)?,
-        Stage::Codegen => codegen_stage(pre_path, file_name)?,
+        Stage::Preprocess => preprocess_stage(pre_path, arg.output.as_deref())?,
+        Stage::Lex => lexer_stage(pre_path, arg.no_cpp)?,
+        Stage::Parse => parser_stage(pre_path, file_name, arg.json, arg.no_cpp)?,
+        Stage::Validate => validate_stage(pre_path, file_name, arg.json, &options)?,
+        Stage::Tacky => tacky_stage(pre_path, file_name, &options)?,
+        Stage::Codegen => codegen_stage(pre_path, file_name, &options)?,
 
         Stage::Asm => {
-            emit_assembly(pre_path, file_name)?;
+            let asm_path = match &arg.output {
+                Some(output) => output.clone(),
+                None => format!("{}.s", output_stem(file_path)),
+            };
+            emit_assembly(pre_path, file_name, &asm_path, &options)?;
         }
 
         Stage::Obj => {
-            let asm_path = emit_assembly(pre_path, file_name)?;
-            let obj_path = format!("{}.o", remove_file_extension(&arg.file_path));
-            compile_assembly_file(&asm_path, &obj_path);
-            delete_file(&asm_path);
+            let asm_path = format!("{}.s", output_stem(file_path));
+            let obj_path = match &arg.output {
+                Some(output) => output.clone(),
+                None => format!("{}.o", output_stem(file_path)),
+            };
+            emit_assembly(pre_path, file_name, &asm_path, &options)?;
+            let assembled = compile_assembly_file(&asm_path, &obj_path);
+            if !arg.save_temps {
+                delete_file(&asm_path);
+            }
+            assembled?;
         }
 
         Stage::None => {
-            let asm_path = emit_assembly(pre_path, file_name)?;
-            let exe_path = remove_file_extension(&arg.file_path);
-            compile_and_link_assembly_file(&asm_path, exe_path);
-            delete_file(&asm_path);
+            let asm_path = format!("{}.s", output_stem(file_path));
+            emit_assembly(pre_path, file_name, &asm_path, &options)?;
+            return Ok(Some(asm_path));
         }
     }
 
-    Ok(())
+    Ok(None)
 }
@@ -1,18 +1,37 @@
-use crate::files::*;
-use codegen::{DebuggingPrinter, codegen};
+use crate::files::STDOUT_PATH;
+use codegen::{DebuggingPrinter, asm, codegen};
 use emitter::Emitter;
-use ir_gen::{lower_to_tacky, print_ir};
+use ir_gen::{print_ir, tacky};
+use lilcc::{CompileError, CompileOptions, SemanticWarning, analyze, front_end};
 use parser::parse;
-use semantic_analysis::analize;
-use std::{error::Error, fs};
+use std::{fs, io, path::Path};
 
 use shared_context::{Bump, Interner, SymbolRegistery, SymbolTable, source_map::SourceMap};
 
+// print the semantic warnings, they never stop compilation
+fn report_warnings(warnings: &[SemanticWarning]) {
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+// print the preprocessed program, or write it to `output` unless it's `-`, without lexing it
+pub fn preprocess_stage(file_path: &str, output: Option<&str>) -> Result<(), CompileError> {
+    let preprocessed = fs::read_to_string(file_path)?;
+
+    match output {
+        None | Some(STDOUT_PATH) => print!("{}", preprocessed),
+        Some(output) => fs::write(output, preprocessed)?,
+    }
+
+    Ok(())
+}
+
 // lex the program then exit without starting the other stages
-pub fn lexer_stage(file_path: &str) -> Result<(), Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+pub fn lexer_stage(file_path: &str, no_cpp: bool) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let mut lexer = lexer::Lexer::new(&input_string);
+    let mut lexer = lexer::Lexer::new(&input_string).with_raw_source(no_cpp);
 
     while let Some(tok) = lexer.next() {
         println!(
@@ -25,93 +44,125 @@ pub fn lexer_stage(file_path: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub fn parser_stage(file_path: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+// parse the program and print its AST, as JSON when `json` is set
+pub fn parser_stage(
+    file_path: &str,
+    file_name: &str,
+    json: bool,
+    no_cpp: bool,
+) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let lexer = lexer::Lexer::new(&input_string);
+    let lexer = lexer::Lexer::new(&input_string).with_raw_source(no_cpp);
     let arena = Bump::new();
     let mut interner = Interner::new(&arena);
     let smap = SourceMap::new(file_name, &input_string);
     let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, &smap)?;
 
-    parser::print_ast::DebugTreePrinter::new(&interner.ty, &interner.sy).print(program_ast);
+    if json {
+        parser::print_json_ast::JsonTreePrinter::new(&interner.ty, &interner.sy).print(program_ast);
+    } else {
+        parser::print_ast::DebugTreePrinter::new(&interner.ty, &interner.sy).print(program_ast);
+    }
 
     Ok(())
 }
 
-pub fn validate_stage(file_path: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+// analyze the program and print the resolved AST, as JSON when `json` is set
+pub fn validate_stage(
+    file_path: &str,
+    file_name: &str,
+    json: bool,
+    options: &CompileOptions,
+) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let lexer = lexer::Lexer::new(&input_string);
     let arena = Bump::new();
     let mut interner = Interner::new(&arena);
     let smap = SourceMap::new(file_name, &input_string);
     let mut sytab = SymbolTable::new();
 
-    let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, &smap)?;
-
-    let (analized_program, _) = analize(
-        &interner.ty,
-        &mut interner.sy,
-        &mut sytab,
-        &smap,
-        program_ast,
-    )?;
+    let (analized_program, _, warnings) =
+        analyze(&input_string, &smap, &mut interner, &mut sytab, options)?;
+    report_warnings(&warnings);
 
-    parser::print_ast::DebugTreePrinter::new(&interner.ty, &interner.sy).print(analized_program);
+    if json {
+        parser::print_json_ast::JsonTreePrinter::new(&interner.ty, &interner.sy)
+            .print(analized_program);
+    } else {
+        parser::print_ast::DebugTreePrinter::new(&interner.ty, &interner.sy)
+            .print(analized_program);
+    }
 
     Ok(())
 }
 
-pub fn tacky_stage(file_path: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+pub fn tacky_stage(
+    file_path: &str,
+    file_name: &str,
+    options: &CompileOptions,
+) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let lexer = lexer::Lexer::new(&input_string);
     let arena = Bump::new();
     let mut interner = Interner::new(&arena);
     let smap = SourceMap::new(file_name, &input_string);
     let mut sytab = SymbolTable::new();
 
-    let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, &smap)?;
-
-    let (analized_program, counter) = analize(
-        &interner.ty,
-        &mut interner.sy,
-        &mut sytab,
-        &smap,
-        program_ast,
-    )?;
+    let (program_tacky, warnings) =
+        front_end(&input_string, &smap, &mut interner, &mut sytab, options)?;
+    report_warnings(&warnings);
 
-    let program_tacky = lower_to_tacky(analized_program, &mut interner.sy, &mut sytab, counter);
     print_ir::DebuggingPrinter::new(&interner.sy).print(program_tacky);
 
     Ok(())
 }
 
-pub fn codegen_stage(file_path: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+/// Lowers a Tacky program to assembly, printing the symbols and the stack report on request.
+fn back_end<'ctx, 'src>(
+    program_tacky: tacky::Program,
+    interner: &'ctx Interner<'src>,
+    syreg: &'ctx SymbolRegistery,
+    options: &CompileOptions,
+) -> asm::Program {
+    if options.dump_symbols {
+        DebuggingPrinter::new(&interner.sy).print_symbols(syreg);
+    }
+
+    let annotate = options.annotate.then_some(&interner.sy);
+    let program_asm = codegen(
+        program_tacky,
+        &interner.ty,
+        syreg,
+        &options.passes,
+        annotate,
+        options.stack_protector,
+    );
+
+    if options.stack_report {
+        DebuggingPrinter::new(&interner.sy).print_stack_report(&program_asm);
+    }
+
+    program_asm
+}
+
+pub fn codegen_stage(
+    file_path: &str,
+    file_name: &str,
+    options: &CompileOptions,
+) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let lexer = lexer::Lexer::new(&input_string);
     let arena = Bump::new();
     let mut interner = Interner::new(&arena);
     let smap = SourceMap::new(file_name, &input_string);
     let mut sytab = SymbolTable::new();
 
-    let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, &smap)?;
-
-    let (analized_program, counter) = analize(
-        &interner.ty,
-        &mut interner.sy,
-        &mut sytab,
-        &smap,
-        program_ast,
-    )?;
-
-    let program_tacky = lower_to_tacky(analized_program, &mut interner.sy, &mut sytab, counter);
-
+    let (program_tacky, warnings) =
+        front_end(&input_string, &smap, &mut interner, &mut sytab, options)?;
+    report_warnings(&warnings);
     let syreg = SymbolRegistery::build(sytab);
-
-    let program_asm = codegen(program_tacky, &interner.ty, &syreg);
+    let program_asm = back_end(program_tacky, &interner, &syreg, options);
 
     let asm_printer = DebuggingPrinter::new(&interner.sy);
     asm_printer.print(program_asm);
@@ -119,34 +170,47 @@ pub fn codegen_stage(file_path: &str, file_name: &str) -> Result<(), Box<dyn Err
     Ok(())
 }
 
-pub fn emit_assembly(file_path: &str, file_name: &str) -> Result<String, Box<dyn Error>> {
-    let input_string = fs::read_to_string(&file_path)?;
+pub fn emit_assembly(
+    file_path: &str,
+    file_name: &str,
+    output_path: &str,
+    options: &CompileOptions,
+) -> Result<(), CompileError> {
+    let input_string = fs::read_to_string(file_path)?;
 
-    let lexer = lexer::Lexer::new(&input_string);
     let arena = Bump::new();
     let mut interner = Interner::new(&arena);
     let smap = SourceMap::new(file_name, &input_string);
     let mut sytab = SymbolTable::new();
 
-    let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, &smap)?;
-
-    let (analized_program, counter) = analize(
-        &interner.ty,
-        &mut interner.sy,
-        &mut sytab,
-        &smap,
-        program_ast,
-    )?;
-
-    let program_tacky = lower_to_tacky(analized_program, &mut interner.sy, &mut sytab, counter);
-
+    let (program_tacky, warnings) =
+        front_end(&input_string, &smap, &mut interner, &mut sytab, options)?;
+    report_warnings(&warnings);
     let syreg = SymbolRegistery::build(sytab);
+    let program_asm = back_end(program_tacky, &interner, &syreg, options);
 
-    let program_asm = codegen(program_tacky, &interner.ty, &syreg);
+    let mut emitter = Emitter::new(&interner.sy, &syreg).with_syntax(options.syntax);
+    if options.debug_info {
+        emitter = emitter.with_debug_info(file_name);
+    }
 
-    let asm_file_name = format!("{}.s", remove_file_extension(file_name));
-    let output_path = set_file_name(file_path, &asm_file_name);
-    Emitter::new(&interner.sy, &syreg).write_program(program_asm, &output_path)?;
+    // the listing is written next to the assembly, `prog.s` and `prog.lst`,
+    // or next to the source when the assembly goes to stdout
+    if options.listing {
+        let listing_path = match output_path {
+            STDOUT_PATH => Path::new(file_path).with_extension("lst"),
+            _ => Path::new(output_path).with_extension("lst"),
+        };
+        let listing_emitter = Emitter::new(&interner.sy, &syreg)
+            .with_syntax(options.syntax)
+            .with_listing(smap.main_file_lines());
+        listing_emitter.write_program(program_asm.clone(), &listing_path.to_string_lossy())?;
+    }
 
-    Ok(output_path)
+    match output_path {
+        STDOUT_PATH => emitter.write_program_to(program_asm, &mut io::stdout().lock())?,
+        _ => emitter.write_program(program_asm, output_path)?,
+    }
+
+    Ok(())
 }
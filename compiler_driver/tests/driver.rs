@@ -0,0 +1,716 @@
+// End-to-end tests of the driver binary, each test writes its sources to its own
+// temporary directory and runs `compiler_driver` on them like a user would.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A directory under the system temp dir, removed when the test ends
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(test_name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("lilcc-{}-{}", test_name, std::process::id()));
+        fs::create_dir_all(&dir).expect("failed to create the test directory");
+        Self(dir)
+    }
+
+    /// write a file in the directory, return its path
+    fn write(&self, name: &str, contents: &str) -> String {
+        let path = self.0.join(name);
+        fs::write(&path, contents).expect("failed to write the test source");
+        path.to_string_lossy().into_owned()
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// run an executable, return its exit code
+fn run(exe_path: &str) -> i32 {
+    let status = Command::new(exe_path)
+        .status()
+        .expect("failed to run the compiled program");
+    status
+        .code()
+        .expect("the compiled program was killed by a signal")
+}
+
+fn lilcc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_compiler_driver"))
+        .args(args)
+        .output()
+        .expect("failed to run the compiler driver")
+}
+
+/// run the driver in `dir` with `input` piped to its stdin
+fn lilcc_with_stdin(args: &[&str], dir: &TempDir, input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_compiler_driver"))
+        .args(args)
+        .current_dir(&dir.0)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run the compiler driver");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .expect("failed to write the source");
+    child.wait_with_output().expect("the driver didn't finish")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn preprocess_to_stdout_expands_macros() {
+    let dir = TempDir::new("preprocess");
+    // `@` isn't a token, the source would fail to lex
+    let source = dir.write("prog.c", "#define X 2\nint main(void) { return X; } @\n");
+
+    for args in [vec!["-E", &source], vec!["-E", &source, "-o", "-"]] {
+        let output = lilcc(&args);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(stdout(&output).contains("int main(void) { return 2; } @"));
+    }
+    assert!(
+        !PathBuf::from("-").exists(),
+        "`-o -` wrote a file named `-`"
+    );
+}
+
+#[test]
+fn links_two_files() {
+    let dir = TempDir::new("link");
+    let main = dir.write(
+        "main.c",
+        "int helper(int x);\nint main(void) { return helper(4); }\n",
+    );
+    let helper = dir.write("helper.c", "int helper(int x) { return x * 3; }\n");
+    let exe = dir.path("prog");
+
+    let output = lilcc(&[&main, &helper, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 12);
+}
+
+#[test]
+fn undefined_symbol_fails_to_link() {
+    let dir = TempDir::new("undefined");
+    let main = dir.write(
+        "main.c",
+        "int helper(int x);\nint main(void) { return helper(4); }\n",
+    );
+    let exe = dir.path("prog");
+
+    let output = lilcc(&[&main, "-o", &exe]);
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("undefined reference to `helper'"));
+    assert!(!PathBuf::from(&exe).exists());
+}
+
+#[test]
+fn no_cpp_compiles_and_reports_raw_lines() {
+    let dir = TempDir::new("no-cpp");
+    let source = dir.write(
+        "prog.c",
+        "/* first\n   second */\nint main(void) { return 7; }\n",
+    );
+    let exe = dir.path("prog");
+    let output = lilcc(&["--no-cpp", &source, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 7);
+
+    let source = dir.write("bad.c", "int main(void) {\n\n    return x;\n}\n");
+    let output = lilcc(&["--no-cpp", &source, "-o", &exe]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).contains("line 3:"), "{}", stderr(&output));
+}
+
+#[test]
+fn no_cpp_rejects_directives() {
+    let dir = TempDir::new("no-cpp-directives");
+    for directive in ["#include <stdio.h>", "#define X 2", "#if 0", "#pragma once"] {
+        let source = dir.write(
+            "prog.c",
+            &format!("{directive}\nint main(void) {{ return 0; }}\n"),
+        );
+        let output = lilcc(&["--no-cpp", "-S", &source, "-o", "-"]);
+        assert_eq!(output.status.code(), Some(2), "{directive} was accepted");
+        assert!(stderr(&output).contains("need the preprocessor"));
+    }
+
+    // line markers are still read
+    let source = dir.write(
+        "marked.c",
+        "# 40 \"prog.c\"\nint main(void) { return x; }\n",
+    );
+    let output = lilcc(&["--no-cpp", "-S", &source, "-o", "-"]);
+    assert!(stderr(&output).contains("line 40:"), "{}", stderr(&output));
+}
+
+#[test]
+fn declarations_only_compile_to_an_object() {
+    let dir = TempDir::new("declarations-only");
+    for (name, contents) in [("decl.c", "extern int x;\n"), ("empty.c", "")] {
+        let source = dir.write(name, contents);
+        let object = dir.path(&name.replace(".c", ".o"));
+        let output = lilcc(&["-c", &source, "-o", &object]);
+        assert!(output.status.success(), "{name}: {}", stderr(&output));
+        let bytes = fs::read(&object).expect("no object file was written");
+        assert!(bytes.starts_with(b"\x7fELF"), "{name} isn't an ELF object");
+    }
+
+    // and links with a file defining the variable
+    let decl = dir.path("decl.c");
+    let main = dir.write("main.c", "int x = 3;\nint main(void) { return x; }\n");
+    let exe = dir.path("prog");
+    let output = lilcc(&[&decl, &main, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 3);
+}
+
+/// check that `text` is a single well-formed JSON value, panicking at the first error
+fn assert_well_formed_json(text: &str) {
+    fn value(bytes: &[u8], mut i: usize) -> usize {
+        i = skip_whitespace(bytes, i);
+        match bytes.get(i) {
+            Some(b'{') => sequence(bytes, i + 1, b'}', |bytes, i| {
+                let i = skip_whitespace(bytes, i);
+                assert_eq!(bytes.get(i), Some(&b'"'), "a key isn't a string at {i}");
+                let i = skip_whitespace(bytes, string(bytes, i));
+                assert_eq!(bytes.get(i), Some(&b':'), "no `:` after a key at {i}");
+                value(bytes, i + 1)
+            }),
+            Some(b'[') => sequence(bytes, i + 1, b']', value),
+            Some(b'"') => string(bytes, i),
+            Some(b'-' | b'0'..=b'9') => {
+                let end = bytes[i + 1..]
+                    .iter()
+                    .position(|b| !matches!(b, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-'))
+                    .map_or(bytes.len(), |len| i + 1 + len);
+                let number = std::str::from_utf8(&bytes[i..end]).unwrap();
+                assert!(
+                    number.parse::<f64>().is_ok(),
+                    "bad number {number:?} at {i}"
+                );
+                end
+            }
+            _ => ["true", "false", "null"]
+                .iter()
+                .find(|word| bytes[i..].starts_with(word.as_bytes()))
+                .map(|word| i + word.len())
+                .unwrap_or_else(|| panic!("no JSON value at {i}")),
+        }
+    }
+
+    /// the comma separated items of an object or array, up to its closing byte
+    fn sequence(bytes: &[u8], i: usize, close: u8, item: fn(&[u8], usize) -> usize) -> usize {
+        let mut i = skip_whitespace(bytes, i);
+        if bytes.get(i) == Some(&close) {
+            return i + 1;
+        }
+        loop {
+            i = skip_whitespace(bytes, item(bytes, i));
+            match bytes.get(i) {
+                Some(b',') => i += 1,
+                Some(&b) if b == close => return i + 1,
+                _ => panic!("expected `,` or `{}` at {i}", close as char),
+            }
+        }
+    }
+
+    fn string(bytes: &[u8], mut i: usize) -> usize {
+        i += 1;
+        loop {
+            match bytes.get(i) {
+                Some(b'"') => return i + 1,
+                Some(b'\\') => i += 2,
+                Some(b) if *b >= 0x20 => i += 1,
+                _ => panic!("unterminated string or control character at {i}"),
+            }
+        }
+    }
+
+    fn skip_whitespace(bytes: &[u8], i: usize) -> usize {
+        i + bytes[i..]
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count()
+    }
+
+    let end = skip_whitespace(text.as_bytes(), value(text.as_bytes(), 0));
+    assert_eq!(end, text.len(), "trailing text after the JSON value");
+}
+
+#[test]
+fn json_ast_has_the_return_and_its_constant() {
+    let dir = TempDir::new("json-ast");
+    let source = dir.write("prog.c", "int main(void) { return 2; }\n");
+    let output = lilcc(&["--parse", "--json", &source]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json = stdout(&output);
+    assert_well_formed_json(&json);
+    let ret = json
+        .split(r#"{"kind":"Return","#)
+        .nth(1)
+        .unwrap_or_else(|| panic!("no return statement: {json}"));
+    assert!(ret.starts_with(r#""span":{"#), "{json}");
+    assert!(
+        ret.contains(r#""expr":{"kind":"Constant","#)
+            && ret.contains(r#""const_type":"ConstInt","value":2}"#),
+        "the return's constant is missing: {json}"
+    );
+}
+
+#[test]
+fn validated_json_ast_has_resolved_names() {
+    let dir = TempDir::new("json-validate");
+    let source = dir.write("prog.c", "int main(void) { int x = 2; return x; }\n");
+    let parsed = stdout(&lilcc(&["--parse", "--json", &source]));
+    assert!(parsed.contains(r#""name":"x""#), "{parsed}");
+
+    let output = lilcc(&["--validate", "--json", &source]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json = stdout(&output);
+    assert_well_formed_json(&json);
+    assert!(json.contains(r#""kind":"VariableDecl""#), "{json}");
+    assert!(
+        json.contains(r#""expr":{"kind":"Var","#) && json.contains(r#""name":"x.1"}"#),
+        "the returned variable isn't resolved: {json}"
+    );
+    assert!(!json.contains(r#""name":"x""#), "{json}");
+}
+
+#[test]
+fn failed_input_removes_the_assembly_of_the_others() {
+    let dir = TempDir::new("failed-input");
+    let ok = dir.write("ok.c", "int helper(void) { return 1; }\n");
+    let bad = dir.write("bad.c", "int main(void) { return x; }\n");
+    let exe = dir.path("prog");
+
+    let output = lilcc(&[&ok, &bad, "-o", &exe]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(
+        !PathBuf::from(dir.path("ok.s")).exists(),
+        "ok.s was left behind"
+    );
+    assert!(!PathBuf::from(&exe).exists());
+}
+
+#[test]
+fn output_path_options() {
+    let dir = TempDir::new("output-path");
+    let main = dir.write("main.c", "int main(void) { return 5; }\n");
+    let other = dir.write("other.c", "int other(void) { return 1; }\n");
+
+    // without `-o` the executable is named after the first source
+    let output = lilcc(&[&main]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&dir.path("main")), 5);
+    assert!(!PathBuf::from(dir.path("main.s")).exists());
+
+    let exe = dir.path("renamed");
+    let output = lilcc(&[&main, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 5);
+
+    // each stage writes its output to `-o`, with whatever extension it's given
+    for (stage, name) in [
+        ("-S", "custom.s"),
+        ("-S", "asm.txt"),
+        ("-c", "custom.o"),
+        ("-c", "obj.txt"),
+    ] {
+        let output = lilcc(&[stage, &main, "-o", &dir.path(name)]);
+        assert!(output.status.success(), "{stage}: {}", stderr(&output));
+        let contents = fs::read(dir.path(name)).unwrap_or_else(|_| panic!("{name} wasn't written"));
+        if stage == "-c" {
+            assert!(contents.starts_with(b"\x7fELF"), "{name} isn't an object");
+        } else {
+            assert!(
+                String::from_utf8_lossy(&contents).contains("main:"),
+                "{name}"
+            );
+        }
+    }
+    // without `-o` the assembly and object are named after the source
+    for (stage, name) in [("-S", "main.s"), ("-c", "main.o")] {
+        let output = lilcc(&[stage, &main]);
+        assert!(output.status.success(), "{stage}: {}", stderr(&output));
+        assert!(
+            PathBuf::from(dir.path(name)).exists(),
+            "{name} wasn't written"
+        );
+    }
+
+    // one output can't hold the object or assembly of several files
+    for stage in ["-c", "-S", "-E"] {
+        let output = lilcc(&[stage, &main, &other, "-o", &dir.path("out")]);
+        assert_eq!(output.status.code(), Some(1), "{stage} was accepted");
+        assert!(stderr(&output).contains("cannot specify '-o'"));
+    }
+}
+
+#[test]
+fn source_read_from_stdin_links_to_a_out() {
+    let dir = TempDir::new("stdin");
+    for args in [vec!["-"], vec!["-", "--no-cpp"]] {
+        let output = lilcc_with_stdin(&args, &dir, "int main(void) { return 9; }\n");
+        assert!(output.status.success(), "{}", stderr(&output));
+
+        let exe = dir.path("a.out");
+        assert_eq!(run(&exe), 9);
+        fs::remove_file(&exe).expect("a.out was written");
+    }
+}
+
+#[test]
+fn source_read_from_stdin_compiles_to_stdout_and_reports_lines() {
+    let dir = TempDir::new("stdin-asm");
+    let output = lilcc_with_stdin(
+        &["-", "-S", "-o", "-"],
+        &dir,
+        "int main(void) {\n  return 3;\n}\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("main:"), "{}", stdout(&output));
+
+    // the source map is built from the buffered input
+    let output = lilcc_with_stdin(
+        &["-", "-S", "-o", "-"],
+        &dir,
+        "int main(void) {\n  return x;\n}\n",
+    );
+    assert_eq!(output.status.code(), Some(4));
+    let errors = stderr(&output);
+    assert!(errors.contains("<stdin> --> line 2:10"), "{errors}");
+    assert!(errors.contains("   2 |   return x;"), "{errors}");
+}
+
+#[test]
+fn out_of_range_initializer_is_diagnosed_in_c_spelling() {
+    let dir = TempDir::new("initializer-overflow");
+    let source = dir.write(
+        "prog.c",
+        "int big = 5000000000;\nint small = 5;\nint main(void) { return small; }\n",
+    );
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let warnings = stderr(&output);
+    assert_eq!(warnings.matches("warning:").count(), 1, "{warnings}");
+    assert!(
+        warnings.contains("overflow in conversion to int changes the value")
+            && warnings.contains("5000000000 doesn't fit in int"),
+        "{warnings}"
+    );
+    assert!(warnings.contains("line 1:"), "{warnings}");
+}
+
+#[test]
+fn sign_conversion_warns_only_when_the_sign_changes() {
+    let dir = TempDir::new("sign-conversion");
+    let source = dir.write(
+        "prog.c",
+        "int main(void) {\n    unsigned u = -1;\n    unsigned v = 1;\n    return (int)v;\n}\n",
+    );
+    let output = lilcc(&["-Wsign-conversion", "-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let warnings = stderr(&output);
+    assert_eq!(warnings.matches("warning:").count(), 1, "{warnings}");
+    assert!(
+        warnings.contains("conversion from int to unsigned int changes the sign of the value"),
+        "{warnings}"
+    );
+    assert!(warnings.contains("line 2:") && warnings.contains("-1 becomes 4294967295"));
+
+    // the warning is off by default
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn debug_info_has_a_loc_for_each_statement_line() {
+    let dir = TempDir::new("debug-info");
+    let source = dir.write(
+        "prog.c",
+        "int main(void) {\n    int x = 1;\n    x = x + 2;\n\n    if (x > 2)\n        x = 7;\n    return x;\n}\n",
+    );
+    let output = lilcc(&["-g", "-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let assembly = stdout(&output);
+    assert!(assembly.starts_with("\t.file 1 \"prog.c\"\n"), "{assembly}");
+    let locs: Vec<&str> = assembly
+        .lines()
+        .filter_map(|line| line.strip_prefix("\t.loc 1 "))
+        .collect();
+    assert_eq!(locs, ["2", "3", "5", "6", "7"], "{assembly}");
+
+    // the directives assemble
+    let exe = dir.path("prog");
+    let output = lilcc(&["-g", &source, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 7);
+
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(!stdout(&output).contains(".loc"));
+}
+
+#[test]
+fn asm_symbol_dump_lists_statics_and_external_functions() {
+    let dir = TempDir::new("dump-asm-symbols");
+    let source = dir.write(
+        "prog.c",
+        "static int counter = 3;\nint helper(int x);\nint main(void) { return helper(counter); }\n",
+    );
+    let output = lilcc(&[
+        "--dump-asm-symbols",
+        "-S",
+        &source,
+        "-o",
+        &dir.path("prog.s"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let dump = stdout(&output);
+    assert!(dump.starts_with("SymbolRegistery\n"), "{dump}");
+    for entry in [
+        "  Obj(name: counter, type: Int, size: 4, is_static: true)\n",
+        "  Fun(name: helper, is_def: false)\n",
+        "  Fun(name: main, is_def: true)\n",
+    ] {
+        assert!(dump.contains(entry), "{entry:?} is missing:\n{dump}");
+    }
+}
+
+#[test]
+fn symbol_table_shows_tentative_and_initialized_globals() {
+    let dir = TempDir::new("print-symbol-table");
+    let source = dir.write(
+        "prog.c",
+        "int tentative;\nint initialized = 5;\nint main(void) { int local = 1; return local; }\n",
+    );
+    let output = lilcc(&[
+        "--print-symbol-table",
+        "-S",
+        &source,
+        "-o",
+        &dir.path("prog.s"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let table = stdout(&output);
+    assert!(table.starts_with("SymbolTable\n"), "{table}");
+    for entry in [
+        "  tentative: Scalar(Int) StaticAttrs { init_value: Tentative, external: true }\n",
+        "  initialized: Scalar(Int) StaticAttrs { init_value: Initial(IntInit(5)), external: true }\n",
+        "  local.1: Scalar(Int) LocalAttrs\n",
+        "  main: Func(Int()) FunAttrs { defined: true, external: true }\n",
+    ] {
+        assert!(table.contains(entry), "{entry:?} is missing:\n{table}");
+    }
+}
+
+#[test]
+fn chained_comparison_warns_unless_parenthesized() {
+    let dir = TempDir::new("chained-comparison");
+    let source = dir.write("chained.c", "int main(void) {\n    return 1 < 2 < 3;\n}\n");
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let warnings = stderr(&output);
+    assert_eq!(warnings.matches("warning:").count(), 1, "{warnings}");
+    assert!(
+        warnings.contains("comparisons like `a < b < c` don't have their mathematical meaning"),
+        "{warnings}"
+    );
+    assert!(warnings.contains("line 2:"), "{warnings}");
+
+    let source = dir.write(
+        "parenthesized.c",
+        "int main(void) { return (1 < 2) == 0; }\n",
+    );
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn cfg_of_an_if_else_has_a_block_for_each_branch() {
+    let dir = TempDir::new("dump-cfg");
+    let source = dir.write(
+        "prog.c",
+        "int main(void) {\n    int x = 1;\n    if (x)\n        x = 2;\n    else\n        x = 3;\n    return x;\n}\n",
+    );
+    let output = lilcc(&["--dump-cfg", "-S", &source, "-o", &dir.path("prog.s")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let cfg = stdout(&output);
+    let lines: Vec<&str> = cfg.lines().collect();
+    // the condition, each branch and the join after them
+    assert_eq!(
+        lines,
+        [
+            "CFG main.0",
+            "  entry -> block0",
+            "  block0 (2 instructions) -> label.2.0, block1",
+            "  block1 (2 instructions) -> label.3.0",
+            "  label.2.0 (2 instructions) -> label.3.0",
+            "  label.3.0 (2 instructions) -> exit",
+        ],
+        "{cfg}"
+    );
+}
+
+#[test]
+fn save_temps_keeps_the_intermediate_files() {
+    let dir = TempDir::new("save-temps");
+    let source = dir.write("prog.c", "#define X 6\nint main(void) { return X; }\n");
+    let exe = dir.path("prog");
+
+    let output = lilcc(&["--save-temps", &source, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(run(&exe), 6);
+    let assembly = fs::read_to_string(dir.path("prog.s")).expect("prog.s was deleted");
+    assert!(assembly.contains("main:"), "{assembly}");
+    let preprocessed = fs::read_to_string(dir.path("prog.i")).expect("prog.i was deleted");
+    assert!(preprocessed.contains("return 6;"), "{preprocessed}");
+
+    // without the flag both are removed
+    fs::remove_file(dir.path("prog.s")).unwrap();
+    fs::remove_file(dir.path("prog.i")).unwrap();
+    let output = lilcc(&[&source, "-o", &exe]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!PathBuf::from(dir.path("prog.s")).exists());
+    assert!(!PathBuf::from(dir.path("prog.i")).exists());
+}
+
+#[test]
+fn stack_report_gives_16_aligned_frame_sizes() {
+    let dir = TempDir::new("stack-report");
+    let source = dir.write(
+        "prog.c",
+        "int small(void) { return 1; }\nint main(void) { int a = 1; int b = 2; long c = 3; int d = 4; int e = 5; return a + b + (int)c + d + e + small(); }\n",
+    );
+    let output = lilcc(&["--stack-report", "-S", &source, "-o", &dir.path("prog.s")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report = stdout(&output);
+    assert!(report.starts_with("Stack usage\n"), "{report}");
+    assert!(report.contains("  small: 0 bytes\n"), "{report}");
+
+    let main_size: usize = report
+        .lines()
+        .find_map(|line| line.strip_prefix("  main: ")?.strip_suffix(" bytes"))
+        .unwrap_or_else(|| panic!("main isn't reported: {report}"))
+        .parse()
+        .expect("the size is a number");
+    // five spilled locals, one of them a long
+    assert!(main_size >= 24 && main_size.is_multiple_of(16), "{report}");
+}
+
+#[test]
+fn listing_shows_each_source_line_above_its_instructions() {
+    let dir = TempDir::new("listing");
+    let source = dir.write(
+        "prog.c",
+        "int main(void) {\n    int x = 1;\n    return 2;\n}\n",
+    );
+    let output = lilcc(&["--listing", "-S", &source, "-o", &dir.path("prog.s")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let listing = fs::read_to_string(dir.path("prog.lst")).expect("no listing was written");
+    assert!(
+        listing
+            .contains("# 2: int x = 1;\n\tmovl\t$1, -4(%rbp)\n# 3: return 2;\n\tmovl\t$2, %eax\n"),
+        "{listing}"
+    );
+    // the assembly itself has no source lines
+    let assembly = fs::read_to_string(dir.path("prog.s")).unwrap();
+    assert!(!assembly.contains("# 3:"), "{assembly}");
+}
+
+#[test]
+fn self_assignment_warns() {
+    let dir = TempDir::new("self-assignment");
+    // the inner `x` is another variable than the outer one
+    let source = dir.write(
+        "prog.c",
+        "int main(void) {\n    int x = 1; int y = 2; int a = 3;\n    x = x;\n    x = y;\n    a = a + 1;\n    { int x = 5; y = x; }\n    return x + a;\n}\n",
+    );
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let warnings = stderr(&output);
+    assert_eq!(warnings.matches("warning:").count(), 1, "{warnings}");
+    assert!(
+        warnings.contains("a variable is assigned to itself"),
+        "{warnings}"
+    );
+    assert!(warnings.contains("line 3:5"), "{warnings}");
+}
+
+#[test]
+fn assembly_to_stdout_writes_no_file() {
+    let dir = TempDir::new("asm-stdout");
+    let source = dir.write("prog.c", "int main(void) { return 2; }\n");
+    let output = lilcc(&["-S", &source, "-o", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let assembly = stdout(&output);
+    assert!(
+        assembly.contains("main:\n") && assembly.contains("\tmovl\t$2, %eax\n"),
+        "{assembly}"
+    );
+
+    let files: Vec<_> = fs::read_dir(&dir.0)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, ["prog.c"], "files were written");
+    assert!(
+        !PathBuf::from("-").exists(),
+        "`-o -` wrote a file named `-`"
+    );
+}
+
+#[test]
+fn function_is_callable_from_a_gcc_compiled_main() {
+    let dir = TempDir::new("c-linkage");
+    let foo = dir.write(
+        "foo.c",
+        "int counter = 40;\nint foo(int x) { return x + counter; }\n",
+    );
+    let main = dir.write(
+        "main.c",
+        "extern int counter;\nint foo(int x);\nint main(void) { counter = counter + 1; return foo(1); }\n",
+    );
+    let (foo_object, main_object, exe) = (dir.path("foo.o"), dir.path("main.o"), dir.path("prog"));
+
+    let output = lilcc(&["-c", &foo, "-o", &foo_object]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    for args in [
+        vec!["-c", main.as_str(), "-o", main_object.as_str()],
+        vec![
+            main_object.as_str(),
+            foo_object.as_str(),
+            "-o",
+            exe.as_str(),
+        ],
+    ] {
+        let output = Command::new("gcc")
+            .args(&args)
+            .output()
+            .expect("failed to run gcc");
+        assert!(output.status.success(), "gcc {args:?}: {}", stderr(&output));
+    }
+    assert_eq!(run(&exe), 42);
+}
@@ -1,18 +1,36 @@
 use codegen::asm;
 use shared_context::symbol_interner::SymbolInterner;
-use shared_context::{Identifier, SymbolRegistery};
+use shared_context::{Identifier, OperandSize, SymbolRegistery};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io;
 
 mod write_instructions;
 mod write_static_variables;
 
+/// The assembly syntax the emitter writes.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum AsmSyntax {
+    #[default]
+    Att, // AT&T syntax, `movq %rsp, %rbp`
+    Intel, // Intel syntax without register prefixes, `mov rbp, rsp`
+}
+
 /// Emitter is responsible for generating assembly code from the codegen
 /// abstract representation
 pub struct Emitter<'a> {
     /// Reference to the interner, used to resolve symbols to strings.
     interner: &'a SymbolInterner<'a>,
     symbol_reg: &'a SymbolRegistery,
+
+    /// Name of the source file line number debug info refers to, if requested.
+    debug_file: Option<&'a str>,
+
+    /// Text of each source line, written as a comment above its instructions in a listing.
+    listing: Option<HashMap<usize, &'a str>>,
+
+    /// Syntax used for instructions and operands.
+    syntax: AsmSyntax,
 }
 
 impl<'a> Emitter<'a> {
@@ -22,15 +40,56 @@ impl<'a> Emitter<'a> {
         Self {
             interner,
             symbol_reg,
+            debug_file: None,
+            listing: None,
+            syntax: AsmSyntax::default(),
         }
     }
 
+    /// Select the assembly syntax to write.
+    pub fn with_syntax(mut self, syntax: AsmSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Emit line number debug info referring to the given source file.
+    ///
+    /// The assembler builds the `.debug_line` section from the `.file`
+    /// directive and the `.loc` directives written for each source line.
+    pub fn with_debug_info(mut self, source_file: &'a str) -> Self {
+        self.debug_file = Some(source_file);
+        self
+    }
+
+    /// Write each source line as a comment above the instructions generated for it,
+    /// e.g. `# 2: return 2;`, producing a listing meant to be read rather than assembled.
+    ///
+    /// `source_lines` maps a line number to its text, the instructions of a line
+    /// missing from it only get its number.
+    pub fn with_listing(mut self, source_lines: HashMap<usize, &'a str>) -> Self {
+        self.listing = Some(source_lines);
+        self
+    }
+
     /// Writes a complete asm::Program to the given file path.
     ///
     /// Returns an `io::Error` if writing to the file fails.
     pub fn write_program(&self, program: asm::Program, output_file_path: &str) -> io::Result<()> {
         // Create the file for writing
         let mut file = File::create(output_file_path)?;
+        self.write_program_to(program, &mut file)
+    }
+
+    /// Writes a complete asm::Program to any writer, e.g. a `Vec<u8>` buffer.
+    ///
+    /// Returns an `io::Error` if writing to `out` fails.
+    pub fn write_program_to(
+        &self,
+        program: asm::Program,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        // Select the syntax and declare the source file referred to by `.loc` directives
+        self.write_program_prologue(out)?;
 
         // Decompose the program into individual functions
         let items = program.into_parts();
@@ -39,14 +98,15 @@ impl<'a> Emitter<'a> {
             match item {
                 asm::TopLevel::F(fun_def) => {
                     // Write each function's definition to the output buffer
-                    self.write_function_def(fun_def, &mut file)?;
+                    self.write_function_def(fun_def, out)?;
                 }
-                asm::TopLevel::S(var_def) => self.write_static_variable(var_def, &mut file)?,
+                asm::TopLevel::S(var_def) => self.write_static_variable(var_def, out)?,
+                asm::TopLevel::C(bits) => self.write_double_constant(bits, out)?,
             }
         }
 
         // Write any necessary epilogue at the end of the program
-        self.write_program_epilogue(&mut file)?;
+        self.write_program_epilogue(out)?;
         Ok(())
     }
 
@@ -56,30 +116,91 @@ impl<'a> Emitter<'a> {
         function: asm::FunctionDef,
         out: &mut impl io::Write,
     ) -> io::Result<()> {
-        let (name, external, instructions) = function.into_parts();
-        self.write_function_def_prolouge(name, external, out)?;
+        let asm::FunctionParts {
+            name,
+            external,
+            instructions,
+            annotations,
+            callee_saved,
+            canary,
+        } = function.into_parts();
+        self.write_function_def_prolouge(name, external, &callee_saved, out)?;
+
+        // the Tacky instruction the following instructions were generated from
+        let mut annotation = None;
 
-        for instr in instructions {
-            // Write each instruction for this function
-            self.write_instruction(instr, out)?;
+        for (position, instr) in instructions.into_iter().enumerate() {
+            match (instr, annotation) {
+                (asm::Instruction::Annotation(index), _) => {
+                    annotation = Some(annotations[index].as_str())
+                }
+                (_, Some(text)) => {
+                    self.write_annotated_instruction(instr, text, &callee_saved, canary, out)?
+                }
+                // Write each instruction for this function
+                (_, None) => self.write_instruction(instr, &callee_saved, canary, out)?,
+            }
+
+            // the first instruction reserves the stack frame, the canary slot is in it
+            if position == 0
+                && let Some(slot) = canary
+            {
+                self.write_canary_store(slot, out)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Writes an instruction with its originating Tacky instruction as a trailing comment
+    /// on every line it produces, e.g. `movl $2, %eax   # Ret(ConstInt(2))`.
+    fn write_annotated_instruction(
+        &self,
+        instr: asm::Instruction,
+        text: &str,
+        callee_saved: &[asm::Register],
+        canary: Option<i64>,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        self.write_instruction(instr, callee_saved, canary, &mut buffer)?;
+
+        for line in String::from_utf8_lossy(&buffer).lines() {
+            writeln!(out, "{}   # {}", line, text)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a program-level prologue: the syntax directive for Intel syntax,
+    /// and the source file declaration when debug info is enabled.
+    fn write_program_prologue(&self, out: &mut impl io::Write) -> io::Result<()> {
+        if let AsmSyntax::Intel = self.syntax {
+            writeln!(out, "\t.intel_syntax noprefix")?;
+        }
+
+        match self.debug_file {
+            Some(source_file) => writeln!(out, "\t.file 1 \"{}\"", source_file),
+            None => Ok(()),
+        }
+    }
+
     /// Writes a program-level epilogue, e.g., section directives.
     fn write_program_epilogue(&self, out: &mut impl io::Write) -> io::Result<()> {
         writeln!(out, "\t.section .note.GNU-stack,\"\",@progbits")
     }
 
     /// Writes the prologue of a function, including label and stack setup.
+    ///
+    /// The callee-saved registers are pushed right below the saved `%rbp`,
+    /// the register allocator keeps the function's stack slots below them.
     fn write_function_def_prolouge(
         &self,
         name: Identifier,
         external: bool,
+        callee_saved: &[asm::Register],
         out: &mut impl io::Write,
     ) -> io::Result<()> {
-        let fun_name = self.format_identifier(name);
+        let fun_name = self.format_linkage_name(name);
         // Declare function as global if it has an external linkage
         if external {
             writeln!(out, "\t.globl {}", fun_name)?;
@@ -90,8 +211,16 @@ impl<'a> Emitter<'a> {
         writeln!(out, "{}:", fun_name)?;
 
         // Standard prologue instructions
-        self.format_one_operand_instruction("pushq", "%rbp", out)?;
-        self.format_two_operand_instruction("movq", "%rsp", "%rbp", out)
+        let suffix = self.convert_operand_size_to_suffix(OperandSize::QuadWord);
+        let rbp = self.convert_frame_register("rbp");
+        let rsp = self.convert_frame_register("rsp");
+        self.format_one_operand_instruction(&format!("push{}", suffix), &rbp, out)?;
+        self.format_two_operand_instruction(&format!("mov{}", suffix), &rsp, &rbp, out)?;
+
+        for register in callee_saved {
+            self.write_push_instruction(asm::Operand::Reg(*register), out)?;
+        }
+        Ok(())
     }
 
     /// Converts an Identifier to a string using the interner.
@@ -110,4 +239,20 @@ impl<'a> Emitter<'a> {
             )
         }
     }
+
+    /// Converts the name of a function or a variable with linkage to its symbol.
+    ///
+    /// C names aren't decorated, the symbol is the name as written so it matches the symbols
+    /// objects compiled by GCC define and reference. Identifier resolution never renames a name
+    /// with linkage, only the ones formatted with an id (like `count.3` for a `static` local)
+    /// are renamed, and the `.` can't appear in a C identifier, so they can't collide with it.
+    /// The compiler's own labels (`.Llabel.3`) and constants (`.Ldouble.`) also contain a `.`.
+    fn format_linkage_name(&self, identifier: Identifier) -> String {
+        assert_eq!(
+            identifier.get_id(),
+            0,
+            "a name with linkage is never renamed"
+        );
+        self.interner.lookup(identifier.get_symbol()).to_string()
+    }
 }
@@ -1,4 +1,4 @@
-use crate::Emitter;
+use crate::{AsmSyntax, Emitter};
 use codegen::asm::{self, Operand};
 use shared_context::{Identifier, OperandSize};
 use std::io;
@@ -7,18 +7,29 @@ mod to_x86_asm;
 
 impl<'a> Emitter<'a> {
     /// Writes a single `asm::Instruction` to the output buffer.
+    /// a `ret` restores `callee_saved` and checks the `canary` slot first, if there is one
     pub(crate) fn write_instruction(
         &self,
         instr: asm::Instruction,
+        callee_saved: &[asm::Register],
+        canary: Option<i64>,
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         match instr {
             asm::Instruction::Mov { size, dst, src } => self.write_mov(size, src, dst, out),
             asm::Instruction::Movsx { src, dst } => self.write_movsx(src, dst, out),
+            asm::Instruction::Movzb { size, src, dst } => self.write_movzb(size, src, dst, out),
+            asm::Instruction::Cvttsd2si { size, src, dst } => {
+                self.write_cvttsd2si(size, src, dst, out)
+            }
+            asm::Instruction::Cvtsi2sd { size, src, dst } => {
+                self.write_cvtsi2sd(size, src, dst, out)
+            }
+            asm::Instruction::Lea { src, dst } => self.write_lea(src, dst, out),
             asm::Instruction::Unary { size, op, dst } => {
                 self.write_unary_instruction(size, op, dst, out)
             }
-            asm::Instruction::Ret => self.write_return_instruction(out),
+            asm::Instruction::Ret => self.write_return_instruction(callee_saved, canary, out),
             asm::Instruction::Binary { size, op, src, dst } => {
                 self.write_binary_instruction(size, op, src, dst, out)
             }
@@ -28,13 +39,25 @@ impl<'a> Emitter<'a> {
             asm::Instruction::Cmp { size, src, dst } => {
                 self.write_cmp_instruction(size, src, dst, out)
             }
+            asm::Instruction::Test { size, src, dst } => {
+                self.write_test_instruction(size, src, dst, out)
+            }
             asm::Instruction::Jmp(label) => self.write_jmp_instruction(label, out),
             asm::Instruction::JmpCC(cond, label) => self.write_jmpcc_instruction(cond, label, out),
             asm::Instruction::SetCC(cond, dst) => self.write_setcc_instruction(cond, dst, out),
+            asm::Instruction::Cmov {
+                cond,
+                size,
+                src,
+                dst,
+            } => self.write_cmov_instruction(cond, size, src, dst, out),
             asm::Instruction::Label(label) => self.write_label(label, out),
             asm::Instruction::Call(name) => self.wrtie_call_instruction(name, out),
+            asm::Instruction::CallIndirect(target) => self.write_indirect_call(target, out),
             asm::Instruction::Push(src) => self.write_push_instruction(src, out),
             asm::Instruction::Movzx { .. } => Ok(()), // this instruction will be replaced before reaching code emission
+            asm::Instruction::SourceLine(line) => self.write_source_line(line, out),
+            asm::Instruction::Annotation(_) => Ok(()), // handled by `write_function_def`
         }
     }
 
@@ -46,8 +69,18 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
-        let opcode = format!("mov{}", suffix);
+        let suffix = self.convert_operand_size_to_suffix(size);
+        // a 64 bit immediate only has an encoding when moved into a register, fix_instructions
+        // routes memory destinations through one
+        let opcode = if src.is_large_immediate(size) {
+            debug_assert!(
+                matches!(dst, Operand::Reg(_)),
+                "64 bit immediate stored to memory"
+            );
+            format!("movabs{}", suffix)
+        } else {
+            format!("mov{}", suffix)
+        };
         let src = self.convert_operand(src, reg_size);
         let dst = self.convert_operand(dst, reg_size);
         self.format_two_operand_instruction(&opcode, &src, &dst, out)
@@ -61,7 +94,83 @@ impl<'a> Emitter<'a> {
     ) -> io::Result<()> {
         let src = self.convert_operand(src, 4);
         let dst = self.convert_operand(dst, 8);
-        self.format_two_operand_instruction("movslq", &src, &dst, out)
+        let opcode = match self.syntax {
+            AsmSyntax::Att => "movslq",
+            AsmSyntax::Intel => "movsxd",
+        };
+        self.format_two_operand_instruction(opcode, &src, &dst, out)
+    }
+
+    fn write_movzb(
+        &self,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let src = self.convert_operand(src, 1);
+        let dst = self.convert_operand(dst, Self::convert_operand_size_to_reg_size(size));
+        let opcode = match self.syntax {
+            AsmSyntax::Att => format!("movzb{}", self.convert_operand_size_to_suffix(size)),
+            AsmSyntax::Intel => "movzx".to_string(),
+        };
+        self.format_two_operand_instruction(&opcode, &src, &dst, out)
+    }
+
+    fn write_lea(
+        &self,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        // a function from another object may be out of reach of a PC relative `lea`
+        // in a position independent executable, its address is read from the GOT
+        if let Operand::Data(name) = src
+            && self.symbol_reg.is_function(&name)
+            && !self.symbol_reg.get_function(&name).is_def()
+        {
+            let name = self.format_identifier(name);
+            let dst = self.convert_operand(dst, 8);
+            let (opcode, src) = match self.syntax {
+                AsmSyntax::Att => ("movq", format!("{}@GOTPCREL(%rip)", name)),
+                AsmSyntax::Intel => ("mov", format!("QWORD PTR {}@GOTPCREL[rip]", name)),
+            };
+            return self.format_two_operand_instruction(opcode, &src, &dst, out);
+        }
+
+        let src = self.convert_operand(src, 8);
+        let dst = self.convert_operand(dst, 8);
+        let opcode = match self.syntax {
+            AsmSyntax::Att => "leaq",
+            AsmSyntax::Intel => "lea",
+        };
+        self.format_two_operand_instruction(opcode, &src, &dst, out)
+    }
+
+    fn write_cvttsd2si(
+        &self,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let opcode = format!("cvttsd2si{}", self.convert_operand_size_to_suffix(size));
+        let src = self.convert_operand(src, 8);
+        let dst = self.convert_operand(dst, Self::convert_operand_size_to_reg_size(size));
+        self.format_two_operand_instruction(&opcode, &src, &dst, out)
+    }
+
+    fn write_cvtsi2sd(
+        &self,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let opcode = format!("cvtsi2sd{}", self.convert_operand_size_to_suffix(size));
+        let src = self.convert_operand(src, Self::convert_operand_size_to_reg_size(size));
+        let dst = self.convert_operand(dst, 8);
+        self.format_two_operand_instruction(&opcode, &src, &dst, out)
     }
 
     fn write_unary_instruction(
@@ -72,7 +181,7 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
+        let suffix = self.convert_operand_size_to_suffix(size);
         let operator = Emitter::convert_unary_op(op);
         let opcode = format!("{}{}", operator, suffix);
         let dst = self.convert_operand(dst, reg_size);
@@ -88,10 +197,16 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
-        let operator = Emitter::convert_binary_op(op);
-        let opcode = format!("{}{}", operator, suffix);
-        let src = self.convert_operand(src, reg_size);
+        // flipping the sign of a double uses the packed xor, which reads 16 bytes
+        let (opcode, src_size) = match (op, size) {
+            (asm::BinaryOP::Xor, OperandSize::Double) => ("xorpd".to_string(), 16),
+            _ => {
+                let suffix = self.convert_operand_size_to_suffix(size);
+                let operator = Emitter::convert_binary_op(op, size);
+                (format!("{}{}", operator, suffix), reg_size)
+            }
+        };
+        let src = self.convert_operand(src, src_size);
         let dst = self.convert_operand(dst, reg_size);
         self.format_two_operand_instruction(&opcode, &src, &dst, out)
     }
@@ -103,7 +218,7 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
+        let suffix = self.convert_operand_size_to_suffix(size);
         let opcode = format!("idiv{}", suffix);
         let src = self.convert_operand(src, reg_size);
         self.format_one_operand_instruction(&opcode, &src, out)
@@ -116,7 +231,7 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
+        let suffix = self.convert_operand_size_to_suffix(size);
         let opcode = format!("div{}", suffix);
         let src = self.convert_operand(src, reg_size);
         self.format_one_operand_instruction(&opcode, &src, out)
@@ -126,15 +241,82 @@ impl<'a> Emitter<'a> {
         match size {
             OperandSize::LongWord => writeln!(out, "\tcdq"),
             OperandSize::QuadWord => writeln!(out, "\tcqo"),
+            OperandSize::Double => unreachable!("doubles are divided with divsd"),
+            OperandSize::Byte => unreachable!("bytes are promoted to int before dividing"),
         }
     }
 
-    fn write_return_instruction(&self, out: &mut impl io::Write) -> io::Result<()> {
-        self.format_two_operand_instruction("movq", "%rbp", "%rsp", out)?;
-        self.format_one_operand_instruction("popq", "%rbp", out)?;
+    /// Restores the callee-saved registers pushed by the prologue, in reverse order,
+    /// then the caller's frame. The canary is checked first when the function has one.
+    ///
+    /// `%rsp` is recomputed from `%rbp`, not adjusted by the frame size, and every local
+    /// shares the frame the prologue reserves, so a `return` from inside a loop or
+    /// a nested block needs no cleanup of its own.
+    fn write_return_instruction(
+        &self,
+        callee_saved: &[asm::Register],
+        canary: Option<i64>,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let suffix = self.convert_operand_size_to_suffix(OperandSize::QuadWord);
+        let rbp = self.convert_frame_register("rbp");
+        let rsp = self.convert_frame_register("rsp");
+
+        if let Some(slot) = canary {
+            self.write_canary_check(slot, out)?;
+        }
+
+        if !callee_saved.is_empty() {
+            // point %rsp back at the last register pushed, below it are the stack slots
+            let save_area = -8 * callee_saved.len() as i64;
+            self.write_lea(
+                Operand::Stack(save_area),
+                Operand::Reg(asm::Register::SP),
+                out,
+            )?;
+            for register in callee_saved.iter().rev() {
+                let register = self.convert_operand(Operand::Reg(*register), 8);
+                self.format_one_operand_instruction(&format!("pop{}", suffix), &register, out)?;
+            }
+        }
+        self.format_two_operand_instruction(&format!("mov{}", suffix), &rbp, &rsp, out)?;
+        self.format_one_operand_instruction(&format!("pop{}", suffix), &rbp, out)?;
         out.write_all(b"\tret\n")
     }
 
+    /// Copies the thread's stack guard, at `%fs:40` on x86-64 Linux, into the canary slot.
+    /// `%r11` isn't used to pass arguments, it's cleared so the guard doesn't stay in it.
+    pub(crate) fn write_canary_store(&self, slot: i64, out: &mut impl io::Write) -> io::Result<()> {
+        let suffix = self.convert_operand_size_to_suffix(OperandSize::QuadWord);
+        let guard = self.convert_stack_guard();
+        let scratch = self.convert_operand(Operand::Reg(asm::Register::R11), 8);
+        let slot = self.convert_operand(Operand::Stack(slot), 8);
+        self.format_two_operand_instruction(&format!("mov{}", suffix), &guard, &scratch, out)?;
+        self.format_two_operand_instruction(&format!("mov{}", suffix), &scratch, &slot, out)?;
+        self.write_binary_instruction(
+            OperandSize::LongWord,
+            asm::BinaryOP::Xor,
+            Operand::Reg(asm::Register::R11),
+            Operand::Reg(asm::Register::R11),
+            out,
+        )
+    }
+
+    /// Compares the canary slot with the stack guard and calls `__stack_chk_fail`, which
+    /// aborts, when a write through the frame changed it. `%r11` keeps the return value intact.
+    fn write_canary_check(&self, slot: i64, out: &mut impl io::Write) -> io::Result<()> {
+        let suffix = self.convert_operand_size_to_suffix(OperandSize::QuadWord);
+        let guard = self.convert_stack_guard();
+        let scratch = self.convert_operand(Operand::Reg(asm::Register::R11), 8);
+        let slot = self.convert_operand(Operand::Stack(slot), 8);
+        self.format_two_operand_instruction(&format!("mov{}", suffix), &slot, &scratch, out)?;
+        self.format_two_operand_instruction(&format!("sub{}", suffix), &guard, &scratch, out)?;
+        // a local numeric label, each check jumps to the next `1:`
+        self.format_one_operand_instruction("je", "1f", out)?;
+        self.format_one_operand_instruction("call", "__stack_chk_fail@PLT", out)?;
+        writeln!(out, "1:")
+    }
+
     fn write_cmp_instruction(
         &self,
         size: OperandSize,
@@ -143,13 +325,29 @@ impl<'a> Emitter<'a> {
         out: &mut impl io::Write,
     ) -> io::Result<()> {
         let reg_size = Self::convert_operand_size_to_reg_size(size);
-        let suffix = Self::convert_operand_size_to_suffix(size);
-        let opcode = format!("cmp{}", suffix);
+        let opcode = match size {
+            OperandSize::Double => "comisd".to_string(),
+            _ => format!("cmp{}", self.convert_operand_size_to_suffix(size)),
+        };
         let src1 = self.convert_operand(src1, reg_size);
         let src2 = self.convert_operand(src2, reg_size);
         self.format_two_operand_instruction(&opcode, &src1, &src2, out)
     }
 
+    fn write_test_instruction(
+        &self,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let reg_size = Self::convert_operand_size_to_reg_size(size);
+        let opcode = format!("test{}", self.convert_operand_size_to_suffix(size));
+        let src = self.convert_operand(src, reg_size);
+        let dst = self.convert_operand(dst, reg_size);
+        self.format_two_operand_instruction(&opcode, &src, &dst, out)
+    }
+
     fn write_jmp_instruction(&self, label: Identifier, out: &mut impl io::Write) -> io::Result<()> {
         let tar = format!(".L{}", self.format_identifier(label));
         self.format_one_operand_instruction("jmp", &tar, out)
@@ -177,19 +375,74 @@ impl<'a> Emitter<'a> {
         self.format_one_operand_instruction(&op, &dst, out)
     }
 
-    fn write_push_instruction(&self, src: Operand, out: &mut impl io::Write) -> io::Result<()> {
+    fn write_cmov_instruction(
+        &self,
+        cond: asm::Cond,
+        size: OperandSize,
+        src: asm::Operand,
+        dst: asm::Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let reg_size = Self::convert_operand_size_to_reg_size(size);
+        let op = format!(
+            "cmov{}{}",
+            Emitter::convert_cond(cond),
+            self.convert_operand_size_to_suffix(size)
+        );
+        let src = self.convert_operand(src, reg_size);
+        let dst = self.convert_operand(dst, reg_size);
+        self.format_two_operand_instruction(&op, &src, &dst, out)
+    }
+
+    pub(crate) fn write_push_instruction(
+        &self,
+        src: Operand,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
         let src = self.convert_operand(src, 8);
-        self.format_one_operand_instruction("pushq", &src, out)
+        let opcode = format!(
+            "push{}",
+            self.convert_operand_size_to_suffix(OperandSize::QuadWord)
+        );
+        self.format_one_operand_instruction(&opcode, &src, out)
     }
 
+    /// A function defined in this file, `static` or not, is called directly,
+    /// only functions defined elsewhere go through the PLT.
     fn wrtie_call_instruction(&self, name: Identifier, out: &mut impl io::Write) -> io::Result<()> {
-        let mut fun_name = self.format_identifier(name);
+        let mut fun_name = self.format_linkage_name(name);
         if !self.symbol_reg.get_function(&name).is_def() {
             fun_name.push_str("@PLT");
         }
         self.format_one_operand_instruction("call", &fun_name, out)
     }
 
+    /// AT&T syntax marks the operand of an indirect call with `*`
+    fn write_indirect_call(&self, target: Operand, out: &mut impl io::Write) -> io::Result<()> {
+        let target = self.convert_operand(target, 8);
+        match self.syntax {
+            AsmSyntax::Att => {
+                self.format_one_operand_instruction("call", &format!("*{}", target), out)
+            }
+            AsmSyntax::Intel => self.format_one_operand_instruction("call", &target, out),
+        }
+    }
+
+    /// Write the source line the following instructions were generated from,
+    /// a `.loc` directive for debug info and a comment with its text in a listing.
+    fn write_source_line(&self, line: usize, out: &mut impl io::Write) -> io::Result<()> {
+        if let Some(source_lines) = &self.listing {
+            match source_lines.get(&line) {
+                Some(text) => writeln!(out, "# {}: {}", line, text.trim())?,
+                None => writeln!(out, "# {}", line)?,
+            }
+        }
+        if self.debug_file.is_some() {
+            writeln!(out, "\t.loc 1 {}", line)?;
+        }
+        Ok(())
+    }
+
     fn write_label(&self, label: Identifier, out: &mut impl io::Write) -> io::Result<()> {
         let label = format!(".L{}", self.format_identifier(label));
         writeln!(out, "{label}:")
@@ -206,6 +459,7 @@ impl<'a> Emitter<'a> {
     }
 
     /// format a single operand instruction and write it into out
+    /// operands are always given in AT&T order, Intel syntax writes the destination first
     pub(crate) fn format_two_operand_instruction(
         &self,
         op: &str,
@@ -213,6 +467,9 @@ impl<'a> Emitter<'a> {
         dst: &str,
         out: &mut impl io::Write,
     ) -> io::Result<()> {
-        writeln!(out, "\t{}\t{}, {}", op, src, dst)
+        match self.syntax {
+            AsmSyntax::Att => writeln!(out, "\t{}\t{}, {}", op, src, dst),
+            AsmSyntax::Intel => writeln!(out, "\t{}\t{}, {}", op, dst, src),
+        }
     }
 }
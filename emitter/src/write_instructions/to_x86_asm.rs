@@ -1,19 +1,34 @@
 use codegen::asm;
 use shared_context::OperandSize;
 
-use crate::Emitter;
+use crate::{AsmSyntax, Emitter};
 
 impl<'a> Emitter<'a> {
     /// convert an operand to it's x86_64 form, reg_size specifiy the size of the register in bytes
     /// if the operand is a register, entering an invalid size will default to $ bytes
     pub(crate) fn convert_operand(&self, operand: asm::Operand, reg_size: usize) -> String {
+        if let AsmSyntax::Intel = self.syntax {
+            return self.convert_operand_to_intel(operand, reg_size);
+        }
+
         let x86_operand = match operand {
             asm::Operand::Immediate(int) => format!("${int}"),
             asm::Operand::Stack(int) => format!("{int}(%rbp)"),
+            asm::Operand::Memory(reg, int) => {
+                format!("{int}({})", Emitter::convert_register(reg, 8))
+            }
+            asm::Operand::Indexed(base, index, scale) => format!(
+                "({},{},{scale})",
+                Emitter::convert_register(base, 8),
+                Emitter::convert_register(index, 8)
+            ),
             asm::Operand::Reg(reg) => Emitter::convert_register(reg, reg_size),
             asm::Operand::Data(identifier) => {
                 format!("{}(%rip)", self.format_identifier(identifier))
             }
+            asm::Operand::DoubleConst(bits) => {
+                format!("{}(%rip)", Self::format_double_constant(bits))
+            }
             // becuase register allocation removes all pseudo registers
             // it will not get printed as x86 assembly
             asm::Operand::Pseudo(_) => "dummy string".to_string(),
@@ -22,21 +37,83 @@ impl<'a> Emitter<'a> {
         x86_operand
     }
 
-    pub(crate) fn convert_operand_size_to_suffix(size: OperandSize) -> &'static str {
-        match size {
-            OperandSize::LongWord => "l",
-            OperandSize::QuadWord => "q",
+    /// convert an operand to it's Intel syntax form, memory operands carry an explicit size
+    /// because the instruction mnemonics don't have a size suffix
+    fn convert_operand_to_intel(&self, operand: asm::Operand, reg_size: usize) -> String {
+        let ptr_size = match reg_size {
+            1 => "BYTE",
+            8 => "QWORD",
+            16 => "XMMWORD",
+            _ => "DWORD",
+        };
+
+        match operand {
+            asm::Operand::Immediate(int) => format!("{int}"),
+            asm::Operand::Stack(int) => format!("{ptr_size} PTR [rbp{int:+}]"),
+            asm::Operand::Memory(reg, int) => format!(
+                "{ptr_size} PTR [{}{int:+}]",
+                Emitter::convert_register(reg, 8).trim_start_matches('%')
+            ),
+            asm::Operand::Indexed(base, index, scale) => format!(
+                "{ptr_size} PTR [{}+{}*{scale}]",
+                Emitter::convert_register(base, 8).trim_start_matches('%'),
+                Emitter::convert_register(index, 8).trim_start_matches('%')
+            ),
+            asm::Operand::Reg(reg) => Emitter::convert_register(reg, reg_size)
+                .trim_start_matches('%')
+                .to_string(),
+            asm::Operand::Data(identifier) => {
+                format!("{ptr_size} PTR {}[rip]", self.format_identifier(identifier))
+            }
+            asm::Operand::DoubleConst(bits) => {
+                format!("{ptr_size} PTR {}[rip]", Self::format_double_constant(bits))
+            }
+            asm::Operand::Pseudo(_) => "dummy string".to_string(),
+        }
+    }
+
+    /// the mnemonic size suffix, Intel syntax infers the size from the operands
+    /// except for doubles, where the `sd` suffix is part of the instruction name
+    pub(crate) fn convert_operand_size_to_suffix(&self, size: OperandSize) -> &'static str {
+        match (size, self.syntax) {
+            (OperandSize::Double, _) => "sd",
+            (_, AsmSyntax::Intel) => "",
+            (OperandSize::Byte, AsmSyntax::Att) => "b",
+            (OperandSize::LongWord, AsmSyntax::Att) => "l",
+            (OperandSize::QuadWord, AsmSyntax::Att) => "q",
+        }
+    }
+
+    /// format the frame registers (`rbp`, `rsp`) that are not part of asm::Register
+    pub(crate) fn convert_frame_register(&self, name: &str) -> String {
+        match self.syntax {
+            AsmSyntax::Att => format!("%{name}"),
+            AsmSyntax::Intel => name.to_string(),
+        }
+    }
+
+    /// The thread's stack guard value, 40 bytes into the thread control block `%fs` points to
+    pub(crate) fn convert_stack_guard(&self) -> String {
+        match self.syntax {
+            AsmSyntax::Att => "%fs:40".to_string(),
+            AsmSyntax::Intel => "QWORD PTR fs:40".to_string(),
         }
     }
 
     pub(crate) fn convert_operand_size_to_reg_size(size: OperandSize) -> usize {
         match size {
+            OperandSize::Byte => 1,
             OperandSize::LongWord => 4,
-            OperandSize::QuadWord => 8,
+            OperandSize::QuadWord | OperandSize::Double => 8,
         }
     }
 
     pub(crate) fn convert_register(register: asm::Register, reg_size: usize) -> String {
+        if let Some(xmm) = Self::convert_xmm_reg(register) {
+            // XMM registers have the same name at every size
+            return xmm;
+        }
+
         match reg_size {
             1 => Self::convert_1_byte_reg(register),
             8 => Self::convert_8_byte_reg(register),
@@ -48,6 +125,7 @@ impl<'a> Emitter<'a> {
     fn convert_8_byte_reg(register: asm::Register) -> String {
         match register {
             asm::Register::AX => "%rax".to_string(),
+            asm::Register::BX => "%rbx".to_string(),
             asm::Register::CX => "%rcx".to_string(),
             asm::Register::DX => "%rdx".to_string(),
             asm::Register::SI => "%rsi".to_string(),
@@ -56,13 +134,19 @@ impl<'a> Emitter<'a> {
             asm::Register::R9 => "%r9".to_string(),
             asm::Register::R10 => "%r10".to_string(),
             asm::Register::R11 => "%r11".to_string(),
+            asm::Register::R12 => "%r12".to_string(),
+            asm::Register::R13 => "%r13".to_string(),
+            asm::Register::R14 => "%r14".to_string(),
+            asm::Register::R15 => "%r15".to_string(),
             asm::Register::SP => "%rsp".to_string(),
+            _ => Self::convert_xmm_reg(register).unwrap_or_default(),
         }
     }
 
     fn convert_4_byte_reg(register: asm::Register) -> String {
         match register {
             asm::Register::AX => "%eax".to_string(),
+            asm::Register::BX => "%ebx".to_string(),
             asm::Register::CX => "%ecx".to_string(),
             asm::Register::DX => "%edx".to_string(),
             asm::Register::SI => "%esi".to_string(),
@@ -71,13 +155,19 @@ impl<'a> Emitter<'a> {
             asm::Register::R9 => "%r9d".to_string(),
             asm::Register::R10 => "%r10d".to_string(),
             asm::Register::R11 => "%r11d".to_string(),
+            asm::Register::R12 => "%r12d".to_string(),
+            asm::Register::R13 => "%r13d".to_string(),
+            asm::Register::R14 => "%r14d".to_string(),
+            asm::Register::R15 => "%r15d".to_string(),
             asm::Register::SP => "%esp".to_string(),
+            _ => Self::convert_xmm_reg(register).unwrap_or_default(),
         }
     }
 
     fn convert_1_byte_reg(register: asm::Register) -> String {
         match register {
             asm::Register::AX => "%al".to_string(),
+            asm::Register::BX => "%bl".to_string(),
             asm::Register::CX => "%cl".to_string(),
             asm::Register::DX => "%dl".to_string(),
             asm::Register::SI => "%sil".to_string(),
@@ -86,10 +176,32 @@ impl<'a> Emitter<'a> {
             asm::Register::R9 => "%r9b".to_string(),
             asm::Register::R10 => "%r10b".to_string(),
             asm::Register::R11 => "%r11b".to_string(),
+            asm::Register::R12 => "%r12b".to_string(),
+            asm::Register::R13 => "%r13b".to_string(),
+            asm::Register::R14 => "%r14b".to_string(),
+            asm::Register::R15 => "%r15b".to_string(),
             asm::Register::SP => "%spl".to_string(),
+            _ => Self::convert_xmm_reg(register).unwrap_or_default(),
         }
     }
 
+    fn convert_xmm_reg(register: asm::Register) -> Option<String> {
+        let name = match register {
+            asm::Register::XMM0 => "%xmm0",
+            asm::Register::XMM1 => "%xmm1",
+            asm::Register::XMM2 => "%xmm2",
+            asm::Register::XMM3 => "%xmm3",
+            asm::Register::XMM4 => "%xmm4",
+            asm::Register::XMM5 => "%xmm5",
+            asm::Register::XMM6 => "%xmm6",
+            asm::Register::XMM7 => "%xmm7",
+            asm::Register::XMM14 => "%xmm14",
+            asm::Register::XMM15 => "%xmm15",
+            _ => return None,
+        };
+        Some(name.to_string())
+    }
+
     pub(crate) fn convert_cond(cond: asm::Cond) -> &'static str {
         match cond {
             asm::Cond::E => "e",
@@ -102,6 +214,8 @@ impl<'a> Emitter<'a> {
             asm::Cond::AE => "ae",
             asm::Cond::B => "b",
             asm::Cond::BE => "be",
+            asm::Cond::P => "p",
+            asm::Cond::NP => "np",
         }
     }
 
@@ -112,11 +226,20 @@ impl<'a> Emitter<'a> {
         }
     }
 
-    pub(crate) fn convert_binary_op(operator: asm::BinaryOP) -> String {
-        match operator {
-            asm::BinaryOP::Add => "add".to_string(),
-            asm::BinaryOP::Sub => "sub".to_string(),
-            asm::BinaryOP::Mul => "imul".to_string(),
+    /// convert a binary operator to its mnemonic, without the size suffix
+    pub(crate) fn convert_binary_op(operator: asm::BinaryOP, size: OperandSize) -> String {
+        match (operator, size) {
+            (asm::BinaryOP::Add, _) => "add".to_string(),
+            (asm::BinaryOP::Sub, _) => "sub".to_string(),
+            (asm::BinaryOP::Mul, OperandSize::Double) => "mul".to_string(),
+            (asm::BinaryOP::Mul, _) => "imul".to_string(),
+            (asm::BinaryOP::DivDouble, _) => "div".to_string(),
+            (asm::BinaryOP::And, _) => "and".to_string(),
+            (asm::BinaryOP::Or, _) => "or".to_string(),
+            (asm::BinaryOP::Xor, _) => "xor".to_string(),
+            (asm::BinaryOP::Shl, _) => "shl".to_string(),
+            (asm::BinaryOP::Shr, _) => "shr".to_string(),
+            (asm::BinaryOP::Sar, _) => "sar".to_string(),
         }
     }
 }
@@ -11,44 +11,71 @@ impl<'a> Emitter<'a> {
     ) -> io::Result<()> {
         let (name, external, var_type, init) = var_def.into_parts();
 
-        // Determine assembly type and alignment
-        let (asm_type, alignment) = self.static_var_properties(var_type);
+        // Statics are aligned to their own size, which is also how many bytes they take
+        let asm_type = self.static_var_directive(var_type);
+        let alignment = var_type.size();
 
-        // Convert initializer to i64
+        // Tentative definitions and all zero initializers take no space in the object file
+        let in_bss = init.is_zero();
         let static_init = self.static_init_value(init);
 
         // Emit global declaration if external
         if external {
-            writeln!(out, "\t.globl {}", self.format_identifier(name))?;
+            writeln!(out, "\t.globl {}", self.format_linkage_name(name))?;
         }
 
         // Emit either .bss (zero-initialized) or .data (non-zero)
-        if static_init == 0 {
+        if in_bss {
             self.emit_bss(out, name, alignment)
         } else {
             self.emit_data(out, name, asm_type, alignment, static_init)
         }
     }
 
-    /// Determines assembly type and alignment based on variable type
-    fn static_var_properties(&self, var_type: Type) -> (&'static str, usize) {
-        match var_type {
-            Type::Int => ("long", 4),
-            _ => ("quad", 8), // adjust as needed for other types
+    /// Determines the data directive based on variable type
+    fn static_var_directive(&self, var_type: Type) -> &'static str {
+        match var_type.size() {
+            1 => "byte", // _Bool
+            4 => "long", // int and unsigned int
+            _ => "quad", // long, unsigned long and double
         }
     }
 
-    /// Converts `StaticInit` enum to an i64 for emission
-    /// to which type we convert static init does not matter, as they have the same bit representation
+    /// Converts `StaticInit` enum to the unsigned value of its bits for emission
+    /// to which type we convert static init does not matter, as they have the same bit representation,
+    /// but a negative `int` is only reinterpreted as 32 bits so it fits in its `.long`
     fn static_init_value(&self, init: StaticInit) -> u64 {
         match init {
-            StaticInit::IntInit(i) => i as u64,
+            StaticInit::IntInit(i) => i as u32 as u64,
             StaticInit::LongInit(l) => l as u64,
             StaticInit::UintInit(ui) => ui as u64,
             StaticInit::UlongInit(ul) => ul,
+            StaticInit::DoubleInit(d) => d.to_bits(),
+            StaticInit::BoolInit(b) => b as u64,
         }
     }
 
+    /// Writes a read-only double constant, aligned to 16 bytes so `xorpd`
+    /// can use it as a memory operand
+    pub(crate) fn write_double_constant(
+        &self,
+        bits: u64,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "	.section .rodata\n\t.align 16\n{}:\n\t.quad {}",
+            Self::format_double_constant(bits),
+            bits
+        )
+    }
+
+    /// Returns the label of the double constant with the given bit pattern,
+    /// the `.` keeps it from colliding with the `.L` prefixed user labels
+    pub(crate) fn format_double_constant(bits: u64) -> String {
+        format!(".Ldouble.{:x}", bits)
+    }
+
     /// Emits a zero-initialized variable in the .bss section
     fn emit_bss(
         &self,
@@ -0,0 +1,209 @@
+// Control-flow graph of a tacky function.
+//
+// A function body is partitioned into basic blocks: straight-line runs of
+// instructions that are only entered at the top and only left at the bottom.
+// A block starts at a label (or after a jump or a return) and ends after a
+// jump or a return. Its successors are the blocks control can reach next:
+//   - `Jump` goes to the block of its target label,
+//   - `JumpIfZero`/`JumpIfNotZero`/`CompareAndJump` go to their target and fall through,
+//   - `Ret` goes to the exit node,
+//   - any other instruction falls through to the next block (the exit after the last one).
+// This is the base for the dataflow passes, `dump_cfg` prints it for debugging.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use shared_context::{Identifier, symbol_interner::SymbolInterner};
+
+use crate::print_ir::DebuggingPrinter;
+use crate::tacky::{self, Instruction};
+
+/// A successor of a block, the exit node holds no instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Node {
+    Block(usize), // index into the graph's blocks
+    Exit,
+}
+
+/// A straight-line run of instructions.
+pub struct BasicBlock {
+    label: Option<Identifier>,  // the label the block starts with, if any
+    instructions: Range<usize>, // the block's instructions, indices into the function body
+    successors: Vec<Node>,
+}
+
+impl BasicBlock {
+    /// Returns the label the block starts with, if any.
+    pub fn get_label(&self) -> Option<Identifier> {
+        self.label
+    }
+
+    /// Returns the range of the function's instructions that make up the block.
+    pub fn get_instructions(&self) -> Range<usize> {
+        self.instructions.clone()
+    }
+
+    /// Returns the nodes control can reach after the block, in instruction order.
+    pub fn get_successors(&self) -> &[Node] {
+        &self.successors
+    }
+}
+
+/// The basic blocks of a function body, in the order they appear in it.
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+}
+
+impl ControlFlowGraph {
+    /// Partitions a function body into basic blocks and links them.
+    pub fn build(instructions: &[tacky::SpannedInstruction]) -> Self {
+        let mut blocks = Self::partition(instructions);
+
+        let label_blocks: HashMap<Identifier, usize> = blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| block.label.map(|label| (label, index)))
+            .collect();
+
+        let blocks_count = blocks.len();
+        for (index, block) in blocks.iter_mut().enumerate() {
+            let fall_through = if index + 1 < blocks_count {
+                Node::Block(index + 1)
+            } else {
+                Node::Exit
+            };
+            let target = |label: &Identifier| Node::Block(label_blocks[label]);
+
+            let (last, _) = &instructions[block.instructions.end - 1];
+            block.successors = match last {
+                Instruction::Ret(_) => vec![Node::Exit],
+                Instruction::Jump(label) => vec![target(label)],
+                Instruction::JumpIfZero(_, label)
+                | Instruction::JumpIfNotZero(_, label)
+                | Instruction::CompareAndJump { target: label, .. } => {
+                    // a conditional jump to the next block only has one successor
+                    let target = target(label);
+                    if target == fall_through {
+                        vec![target]
+                    } else {
+                        vec![target, fall_through]
+                    }
+                }
+                _ => vec![fall_through],
+            };
+        }
+
+        Self { blocks }
+    }
+
+    /// Splits the instructions into blocks, without computing their successors.
+    fn partition(instructions: &[tacky::SpannedInstruction]) -> Vec<BasicBlock> {
+        let mut blocks = Vec::new();
+        let mut start = 0;
+
+        for (index, (instr, _)) in instructions.iter().enumerate() {
+            match instr {
+                // a label starts a block, closing the one before it
+                Instruction::Label(_) if index != start => {
+                    blocks.push(Self::make_block(instructions, start..index));
+                    start = index;
+                }
+                Instruction::Jump(_)
+                | Instruction::JumpIfZero(..)
+                | Instruction::JumpIfNotZero(..)
+                | Instruction::CompareAndJump { .. }
+                | Instruction::Ret(_) => {
+                    blocks.push(Self::make_block(instructions, start..index + 1));
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        if start != instructions.len() {
+            blocks.push(Self::make_block(instructions, start..instructions.len()));
+        }
+
+        blocks
+    }
+
+    fn make_block(instructions: &[tacky::SpannedInstruction], range: Range<usize>) -> BasicBlock {
+        let label = match instructions[range.start].0 {
+            Instruction::Label(label) => Some(label),
+            _ => None,
+        };
+        BasicBlock {
+            label,
+            instructions: range,
+            successors: Vec::new(),
+        }
+    }
+
+    /// Returns the node control reaches first, the first block if the body isn't empty.
+    pub fn get_entry(&self) -> Node {
+        if self.blocks.is_empty() {
+            Node::Exit
+        } else {
+            Node::Block(0)
+        }
+    }
+
+    /// Returns the blocks in the order they appear in the function body.
+    pub fn get_blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+}
+
+/// Prints the control-flow graph of every function in the program,
+/// one line per node listing its successors.
+pub fn dump_cfg(program: &tacky::Program, sy_interner: &SymbolInterner) {
+    let printer = CfgPrinter {
+        printer: DebuggingPrinter::new(sy_interner),
+    };
+
+    for item in program.get_functions() {
+        if let tacky::TopLevel::F(fun_def) = item {
+            printer.print_function(fun_def);
+        }
+    }
+}
+
+struct CfgPrinter<'src, 'ctx> {
+    printer: DebuggingPrinter<'src, 'ctx>, // used to name blocks by their label
+}
+
+impl<'src, 'ctx> CfgPrinter<'src, 'ctx> {
+    fn print_function(&self, fun_def: &tacky::FunctionDef) {
+        let cfg = ControlFlowGraph::build(fun_def.get_instructions());
+
+        println!("CFG {}", self.printer.format_identifier(fun_def.get_name()));
+        println!("  entry -> {}", self.format_node(&cfg, cfg.get_entry()));
+        for (index, block) in cfg.get_blocks().iter().enumerate() {
+            println!(
+                "  {} ({} instructions) -> {}",
+                self.format_node(&cfg, Node::Block(index)),
+                block.get_instructions().len(),
+                self.format_successors(&cfg, block.get_successors())
+            );
+        }
+    }
+
+    fn format_successors(&self, cfg: &ControlFlowGraph, successors: &[Node]) -> String {
+        successors
+            .iter()
+            .map(|node| self.format_node(cfg, *node))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// a block is named by its label, a block without one (the first block, or code
+    /// after a jump) by its position
+    fn format_node(&self, cfg: &ControlFlowGraph, node: Node) -> String {
+        match node {
+            Node::Exit => "exit".to_string(),
+            Node::Block(index) => match cfg.get_blocks()[index].get_label() {
+                Some(label) => self.printer.format_identifier(label),
+                None => format!("block{}", index),
+            },
+        }
+    }
+}
@@ -8,8 +8,8 @@
 use crate::IRgen;
 use crate::tacky;
 use parser::ast::{self, Expression};
-use shared_context::SpannedIdentifier;
 use shared_context::Type;
+use shared_context::{Const, SpannedIdentifier};
 
 mod gen_logical_expressions;
 
@@ -25,9 +25,10 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         expr: ast::Expression,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
-        let (inner, expr_type, _) = expr.into_parts();
+        let (inner, expr_type, span) = expr.into_parts();
+        let start = instructions.len();
 
-        match inner {
+        let value = match inner {
             // Integer literal constant.
             ast::InnerExpression::Constant(int) => tacky::Value::Constant(int),
 
@@ -61,13 +62,101 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
                 self.gen_function_call(name, args, expr_type, instructions)
             }
 
+            // Call through a function pointer: `fp(x, y, ...)`
+            ast::InnerExpression::IndirectCall { callee, args } => {
+                self.gen_indirect_call(*callee, args, expr_type, instructions)
+            }
+
             // Casting expression
             ast::InnerExpression::Cast { target_type, expr } => {
                 self.gen_cast_expression(*expr, target_type, instructions)
             }
+
+            // Dereference: `*ptr` reads the object the pointer points to
+            ast::InnerExpression::Dereference(inner) => {
+                let src_ptr = self.gen_expression(*inner, instructions);
+                let dst = self.make_temp_var(expr_type);
+                instructions.push(tacky::Instruction::Load { src_ptr, dst });
+                dst
+            }
+
+            // Address-of: `&x`
+            ast::InnerExpression::AddrOf(inner) => {
+                self.gen_address_of(*inner, expr_type, instructions)
+            }
+
+            // Subscript: `a[i]` reads the element at `a + i`
+            ast::InnerExpression::Subscript { array, index } => {
+                let src_ptr = self.gen_element_address(*array, *index, expr_type, instructions);
+                let dst = self.make_temp_var(expr_type);
+                instructions.push(tacky::Instruction::Load { src_ptr, dst });
+                dst
+            }
+        };
+
+        self.record_span(start, span, instructions);
+        value
+    }
+
+    /// Generates Tacky instructions for an address-of expression.
+    ///
+    /// `&*ptr` is just `ptr`, the dereference is never performed.
+    fn gen_address_of(
+        &mut self,
+        inner: ast::Expression,
+        expr_type: Type,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let (inner, inner_type, _) = inner.into_parts();
+        match inner {
+            ast::InnerExpression::Dereference(ptr) => self.gen_expression(*ptr, instructions),
+            ast::InnerExpression::Var(name) => {
+                let dst = self.make_temp_var(expr_type);
+                instructions.push(tacky::Instruction::GetAddress {
+                    src: tacky::Value::Var(name.get_identifier()),
+                    dst,
+                });
+                dst
+            }
+            ast::InnerExpression::Subscript { array, index } => {
+                self.gen_element_address(*array, *index, inner_type, instructions)
+            }
+            _ => unreachable!("identifier resolution only lets l-values have their address taken"),
         }
     }
 
+    /// Generates Tacky instructions computing the address of a subscripted element,
+    /// `array` is the pointer to the first element and `index` the `long` element count.
+    ///
+    /// A constant index is converted to `long` here, so code generation can fold it
+    /// into the address offset.
+    fn gen_element_address(
+        &mut self,
+        array: ast::Expression,
+        index: ast::Expression,
+        element: Type,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let pointer_type = array.get_type();
+        let ptr = self.gen_expression(array, instructions);
+        let constant_index = match index.get_inner_ref() {
+            ast::InnerExpression::Cast { expr, .. } => Self::as_constant(expr),
+            _ => Self::as_constant(&index),
+        };
+        let index = match constant_index {
+            Some(cons) => tacky::Value::Constant(cons.convert_to(Type::Long)),
+            None => self.gen_expression(index, instructions),
+        };
+        let dst = self.make_temp_var(pointer_type);
+        instructions.push(tacky::Instruction::AddPtr {
+            ptr,
+            index,
+            scale: element.size() as u64,
+            dst,
+        });
+        dst
+    }
+
     /// Generates Tacky instructions for a binary expression.
     ///
     /// Handles arithmetic, comparison, and logical binary operators.
@@ -85,6 +174,14 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
 
         match operator {
             // Logical short-circuiting handled in a dedicated module.
+            BinaryOP::LogicalAnd | BinaryOP::LogicalOr
+                if self.fold_constants
+                    && (Self::as_constant(&operand1).is_some()
+                        || Self::as_constant(&operand2).is_some()) =>
+            {
+                let is_and = matches!(operator, BinaryOP::LogicalAnd);
+                self.gen_folded_logical(is_and, operand1, operand2, instructions)
+            }
             BinaryOP::LogicalAnd => self.gen_logical_and(operand1, operand2, instructions),
             BinaryOP::LogicalOr => self.gen_logical_or(operand1, operand2, instructions),
 
@@ -112,6 +209,8 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     ///
     /// Evaluates the operand, applies the unary operation, and stores
     /// the result in a temporary variable.
+    /// A negated literal such as `-1` is a constant itself, so later stages see it
+    /// as one (e.g. to lower `x / -1` without `idiv`).
     fn gen_unary_expr(
         &mut self,
         operator: ast::UnaryOP,
@@ -119,6 +218,10 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         expr_type: Type,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
+        if let (ast::UnaryOP::Neg, Some(cons)) = (operator, IRgen::as_constant(&operand)) {
+            return tacky::Value::Constant(-cons);
+        }
+
         let src = self.gen_expression(operand, instructions);
         let dst = self.make_temp_var(expr_type);
 
@@ -134,19 +237,37 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     }
 
     /// Generates Tacky instructions for an assignment expression.
+    ///
+    /// The rvalue is evaluated before the lvalue target is resolved, so in `a = b = 5`
+    /// the inner assignment to `b` completes before anything is stored in `a`,
+    /// and the order stays right once lvalues can have side effects of their own.
+    /// Assigning to a dereference stores through the pointer instead of copying.
     fn gen_assignment(
         &mut self,
         lvalue: ast::Expression,
         rvalue: ast::Expression,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
-        let lval = self.gen_expression(lvalue, instructions);
         let rval = self.gen_expression(rvalue, instructions);
 
-        instructions.push(tacky::Instruction::Copy {
-            src: rval,
-            dst: lval,
-        });
+        let (lvalue, lvalue_type, _) = lvalue.into_parts();
+        match lvalue {
+            ast::InnerExpression::Dereference(ptr) => {
+                let dst_ptr = self.gen_expression(*ptr, instructions);
+                instructions.push(tacky::Instruction::Store { src: rval, dst_ptr });
+            }
+            ast::InnerExpression::Subscript { array, index } => {
+                let dst_ptr = self.gen_element_address(*array, *index, lvalue_type, instructions);
+                instructions.push(tacky::Instruction::Store { src: rval, dst_ptr });
+            }
+            ast::InnerExpression::Var(name) => {
+                instructions.push(tacky::Instruction::Copy {
+                    src: rval,
+                    dst: tacky::Value::Var(name.get_identifier()),
+                });
+            }
+            _ => unreachable!("identifier resolution only lets l-values be assigned to"),
+        }
 
         rval
     }
@@ -160,6 +281,14 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         expr_type: Type,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
+        if self.branchless_select
+            && !expr_type.is_double()
+            && Self::is_plain_value(&cons)
+            && Self::is_plain_value(&alt)
+        {
+            return self.gen_select(cond, cons, alt, expr_type, instructions);
+        }
+
         let result_var = self.make_temp_var(expr_type);
         let e2_label = self.make_label();
         let end_label = self.make_label();
@@ -189,20 +318,51 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         result_var
     }
 
+    /// Generates a conditional expression whose branches are plain values as a `Select`,
+    /// both values are read and the condition picks one.
+    fn gen_select(
+        &mut self,
+        cond: Expression,
+        cons: Expression,
+        alt: Expression,
+        expr_type: Type,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let result_var = self.make_temp_var(expr_type);
+        let cond = self.gen_expression(cond, instructions);
+        let src1 = self.gen_expression(cons, instructions);
+        let src2 = self.gen_expression(alt, instructions);
+        instructions.push(tacky::Instruction::Select {
+            cond,
+            src1,
+            src2,
+            dst: result_var,
+        });
+        result_var
+    }
+
+    /// whether an expression is a variable or a constant, lowered without any instruction
+    fn is_plain_value(expr: &Expression) -> bool {
+        matches!(
+            expr.get_inner_ref(),
+            ast::InnerExpression::Var(_) | ast::InnerExpression::Constant(_)
+        )
+    }
+
     /// Generates Tacky instructions for a function call.
     fn gen_function_call(
         &mut self,
         sp_iden: SpannedIdentifier,
-        args: Vec<Box<Expression>>,
+        args: Vec<Expression>,
         expr_type: Type,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
-        let result_var = self.make_temp_var(expr_type);
+        let result_var = self.make_result_var(expr_type);
 
         // Evaluate all arguments in order and collect their values.
         let mut tacky_args = Vec::new();
         for arg in args {
-            tacky_args.push(self.gen_expression(*arg, instructions));
+            tacky_args.push(self.gen_expression(arg, instructions));
         }
 
         // Emit the function call instruction.
@@ -212,7 +372,45 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
             dst: result_var,
         });
 
-        result_var
+        result_var.unwrap_or(Self::VOID_PLACEHOLDER)
+    }
+
+    /// Generates Tacky instructions for a call through a function pointer,
+    /// the callee is evaluated before the arguments.
+    fn gen_indirect_call(
+        &mut self,
+        callee: Expression,
+        args: Vec<Expression>,
+        expr_type: Type,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let fun_ptr = self.gen_expression(callee, instructions);
+        let result_var = self.make_result_var(expr_type);
+
+        let mut tacky_args = Vec::new();
+        for arg in args {
+            tacky_args.push(self.gen_expression(arg, instructions));
+        }
+
+        instructions.push(tacky::Instruction::IndirectCall {
+            fun_ptr,
+            args: tacky_args,
+            dst: result_var,
+        });
+
+        result_var.unwrap_or(Self::VOID_PLACEHOLDER)
+    }
+
+    /// The value of an expression of type `void`, type checking only lets one appear
+    /// where its value is discarded, so it's never read.
+    const VOID_PLACEHOLDER: tacky::Value = tacky::Value::Constant(Const::ConstInt(0));
+
+    /// Creates the temporary a call's result is stored in, a call to a `void` function has none.
+    fn make_result_var(&mut self, expr_type: Type) -> Option<tacky::Value> {
+        match expr_type {
+            Type::Void => None,
+            _ => Some(self.make_temp_var(expr_type)),
+        }
     }
 
     /// generate tacky instructions for expression casting
@@ -222,18 +420,65 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         target_type: Type,
         instructions: &mut Vec<tacky::Instruction>,
     ) -> tacky::Value {
+        // a cast to void only evaluates its operand for its side effects
+        if target_type == Type::Void {
+            self.gen_expression(inner, instructions);
+            return Self::VOID_PLACEHOLDER;
+        }
+
         let inner_ty = inner.get_type();
         let inner_ty_size = inner_ty.size();
         let target_ty_size = target_type.size();
 
-        let result = self.gen_expression(inner, instructions);
+        let mut result = self.gen_expression(inner, instructions);
+
+        // a constant is converted at compile time, so the backend sees the converted
+        // constant, e.g. the `-1L` divisor of `m / -1` on a long, which it lowers without `idiv`
+        if let tacky::Value::Constant(c) = result {
+            return tacky::Value::Constant(c.convert_to(target_type));
+        }
+
         let dst = self.make_temp_var(target_type);
 
-        if target_ty_size == inner_ty_size {
+        // a `_Bool` is 1 for any nonzero value, the conversion is a comparison with zero
+        if target_type == Type::Bool {
+            instructions.push(tacky::Instruction::Binary {
+                op: tacky::BinaryOP::NotEqual,
+                src1: result,
+                src2: tacky::Value::Constant(Const::zero(inner_ty)),
+                dst,
+            });
+            return dst;
+        }
+
+        // a double is only converted from a longword or a quadword, a `_Bool` is widened first
+        let inner_ty = if target_type.is_double() && inner_ty == Type::Bool {
+            let widened = self.make_temp_var(Type::Int);
+            instructions.push(tacky::Instruction::ZeroExtend {
+                src: result,
+                dst: widened,
+            });
+            result = widened;
+            Type::Int
+        } else {
+            inner_ty
+        };
+
+        // conversions to and from double change the representation, not just the width
+        if target_type.is_double() && inner_ty.is_signed() {
+            instructions.push(tacky::Instruction::IntToDouble { src: result, dst });
+        } else if target_type.is_double() {
+            instructions.push(tacky::Instruction::UIntToDouble { src: result, dst });
+        } else if inner_ty.is_double() && target_type.is_signed() {
+            instructions.push(tacky::Instruction::DoubleToInt { src: result, dst });
+        } else if inner_ty.is_double() {
+            instructions.push(tacky::Instruction::DoubleToUInt { src: result, dst });
+        } else if target_ty_size == inner_ty_size {
             instructions.push(tacky::Instruction::Copy { src: result, dst });
         } else if target_ty_size < inner_ty_size {
             instructions.push(tacky::Instruction::Truncate { src: result, dst });
         } else if inner_ty.is_signed() {
+            // only reached when the target is strictly wider, the backend relies on it
             instructions.push(tacky::Instruction::SignExtend { src: result, dst });
         } else {
             instructions.push(tacky::Instruction::ZeroExtend { src: result, dst });
@@ -245,7 +490,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     ///
     /// Arithmetic, comparison, and logical operators are mapped directly.
     /// Any unrecognized operator defaults to `Add` (should not occur in valid ASTs).
-    fn convert_binary_op(op: ast::BinaryOP) -> tacky::BinaryOP {
+    pub(crate) fn convert_binary_op(op: ast::BinaryOP) -> tacky::BinaryOP {
         match op {
             // Arithmetic operators
             ast::BinaryOP::Add => tacky::BinaryOP::Add,
@@ -8,6 +8,9 @@
 //
 // Each operation produces an explicit control flow with labels and conditional
 // jumps, ensuring precise runtime behavior identical to C-like semantics.
+//
+// When constant folding is enabled, an operation with a constant operand is
+// lowered without any branches instead, see `gen_folded_logical`.
 
 use crate::IRgen;
 use crate::tacky;
@@ -15,6 +18,66 @@ use parser::ast::{self};
 use shared_context::{Const, Type};
 
 impl<'a, 'b> IRgen<'a, 'b> {
+    /// Returns the value of a constant literal expression.
+    pub(super) fn as_constant(expr: &ast::Expression) -> Option<Const> {
+        match expr.get_inner_ref() {
+            ast::InnerExpression::Constant(cons) => Some(*cons),
+            _ => None,
+        }
+    }
+
+    /// Generates a logical expression that has at least one constant operand, without branches.
+    ///
+    /// A constant decides the result when it is `0` for `&&` or non-zero for `||`:
+    /// - a deciding constant on the left is the result, the right is never evaluated (`0 && x`).
+    /// - any other constant on the left leaves the truth of the right as the result (`1 && x`).
+    /// - a constant on the right is folded the same way, but the left is still evaluated
+    ///   for its side effects (`f() && 0` calls `f` and is 0).
+    pub(super) fn gen_folded_logical(
+        &mut self,
+        is_and: bool,
+        operand1: ast::Expression,
+        operand2: ast::Expression,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let decides = |cons: Const| cons.is_zero() == is_and;
+        let decided = tacky::Value::Constant(Const::ConstInt(!is_and as i32));
+
+        match (Self::as_constant(&operand1), Self::as_constant(&operand2)) {
+            (Some(cons), _) if decides(cons) => decided,
+            (Some(_), _) => self.gen_truth_value(operand2, instructions),
+            (None, Some(cons)) if decides(cons) => {
+                self.gen_expression(operand1, instructions);
+                decided
+            }
+            (None, Some(_)) => self.gen_truth_value(operand1, instructions),
+            (None, None) => unreachable!("folded logical expressions have a constant operand"),
+        }
+    }
+
+    /// Generates an expression converted to a truth value, `1` if it's non-zero and `0` otherwise.
+    fn gen_truth_value(
+        &mut self,
+        expr: ast::Expression,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) -> tacky::Value {
+        let expr_type = expr.get_type();
+        let value = self.gen_expression(expr, instructions);
+
+        if let tacky::Value::Constant(cons) = value {
+            return tacky::Value::Constant(Const::ConstInt(!cons.is_zero() as i32));
+        }
+
+        let dst = self.make_temp_var(Type::Int);
+        instructions.push(tacky::Instruction::Binary {
+            op: tacky::BinaryOP::NotEqual,
+            src1: value,
+            src2: tacky::Value::Constant(Const::zero(expr_type)),
+            dst,
+        });
+        dst
+    }
+
     /// Generates short-circuiting logic for a logical AND expression.
     pub(super) fn gen_logical_and(
         &mut self,
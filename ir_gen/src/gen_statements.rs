@@ -22,12 +22,18 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         stmt: ast::Statement,
         instructions: &mut Vec<tacky::Instruction>,
     ) {
-        let (stmt_type, _) = stmt.into_parts();
+        let (stmt_type, span) = stmt.into_parts();
+        let start = instructions.len();
+
+        // blocks don't generate code themselves, their items are marked instead
+        if !matches!(stmt_type, ast::StatementType::Compound(_)) {
+            self.gen_source_line(span, instructions);
+        }
 
         match stmt_type {
             // Return statement: evaluate expression and emit `Ret`.
             ast::StatementType::Return(expr) => {
-                let val = self.gen_expression(expr, instructions);
+                let val = expr.map(|expr| self.gen_expression(expr, instructions));
                 instructions.push(tacky::Instruction::Ret(val));
             }
 
@@ -37,7 +43,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
             }
 
             // Empty / null statement: ignore.
-            ast::StatementType::Null => return,
+            ast::StatementType::Null => (),
 
             // Compound statement (block): recursively lower contained items.
             ast::StatementType::Compound(block) => self.gen_block(block, instructions),
@@ -74,6 +80,8 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
                 label,
             } => self.gen_for_statement(init, condition, post, *body, label, instructions),
         }
+
+        self.record_span(start, span, instructions);
     }
 
     /// Lowers an `if` or `if-else` statement.
@@ -96,6 +104,10 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     }
 
     /// Lowers an `if-else` statement into IR.
+    ///
+    /// An `if` clause that is only a `return` never reaches the end of the statement,
+    /// so there is no jump over the `else` clause and no end label for it to target:
+    /// `if (c) return 1; else ...` is `JumpIfZero(c, else)`, `Ret(1)`, then the `else` clause.
     fn gen_if_statement_with_else_clause(
         &mut self,
         condition: Expression,
@@ -104,12 +116,18 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         instructions: &mut Vec<tacky::Instruction>,
     ) {
         let else_label = self.make_label();
-        let end_label = self.make_label();
+        let returns = Self::is_return(&if_clause);
 
-        let cond_result = self.gen_expression(condition, instructions);
-        instructions.push(tacky::Instruction::JumpIfZero(cond_result, else_label));
+        self.gen_conditional_jump(condition, false, else_label, instructions);
 
         self.gen_statements(if_clause, instructions);
+        if returns {
+            instructions.push(tacky::Instruction::Label(else_label));
+            self.gen_statements(else_clause, instructions);
+            return;
+        }
+
+        let end_label = self.make_label();
         instructions.push(tacky::Instruction::Jump(end_label));
 
         instructions.push(tacky::Instruction::Label(else_label));
@@ -117,7 +135,73 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         instructions.push(tacky::Instruction::Label(end_label));
     }
 
+    /// Lowers the condition of a statement and jumps to `target` when it's `jump_if`,
+    /// true for a non-zero value.
+    ///
+    /// A comparison jumps on the comparison itself, with a
+    /// `CompareAndJump`, so `if (a < b)` is a `cmp` and a `jge` without computing the
+    /// 0 or 1 result of `a < b`. Any other condition is computed, then tested with a
+    /// `JumpIfZero` or a `JumpIfNotZero`.
+    fn gen_conditional_jump(
+        &mut self,
+        condition: Expression,
+        jump_if: bool,
+        target: Identifier,
+        instructions: &mut Vec<tacky::Instruction>,
+    ) {
+        let span = condition.get_span();
+        let start = instructions.len();
+
+        match condition.into_parts() {
+            (
+                ast::InnerExpression::Binary {
+                    operator,
+                    operand1,
+                    operand2,
+                },
+                _,
+                _,
+            ) if operator.is_comparison() => {
+                let src1 = self.gen_expression(*operand1, instructions);
+                let src2 = self.gen_expression(*operand2, instructions);
+                instructions.push(tacky::Instruction::CompareAndJump {
+                    op: Self::convert_binary_op(operator),
+                    src1,
+                    src2,
+                    jump_if,
+                    target,
+                });
+            }
+            (inner, expr_type, _) => {
+                let value =
+                    self.gen_expression(Expression::new(inner, expr_type, span), instructions);
+                instructions.push(match jump_if {
+                    true => tacky::Instruction::JumpIfNotZero(value, target),
+                    false => tacky::Instruction::JumpIfZero(value, target),
+                });
+            }
+        }
+
+        self.record_span(start, span, instructions);
+    }
+
+    /// Whether a statement is only a `return`, alone or as the single item of a block.
+    fn is_return(stmt: &Statement) -> bool {
+        match stmt.get_type_ref() {
+            ast::StatementType::Return(_) => true,
+            ast::StatementType::Compound(block) => matches!(
+                block.get_items_ref(),
+                [ast::BlockItem::S(stmt)] if Self::is_return(stmt)
+            ),
+            _ => false,
+        }
+    }
+
     /// Lowers an `if` statement without an `else` clause.
+    ///
+    /// The end label is the target of the conditional jump over the clause, so an
+    /// early `if (c) return 1;` is exactly `JumpIfZero(c, end)`, `Ret(1)`, `Label(end)`,
+    /// the guarded return needs no other jump or label.
     fn gen_if_statement_without_else_clause(
         &mut self,
         condition: Expression,
@@ -125,8 +209,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         instructions: &mut Vec<tacky::Instruction>,
     ) {
         let end_label = self.make_label();
-        let cond_result = self.gen_expression(condition, instructions);
-        instructions.push(tacky::Instruction::JumpIfZero(cond_result, end_label));
+        self.gen_conditional_jump(condition, false, end_label, instructions);
 
         self.gen_statements(if_clause, instructions);
         instructions.push(tacky::Instruction::Label(end_label));
@@ -153,6 +236,10 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     }
 
     /// Lowers a `do-while` loop.
+    ///
+    /// A constant condition is left to the optimizer: constant folding turns the backward
+    /// `JumpIfNotZero` into nothing (`while (0)`) or a `Jump` (`while (1)`), and dead code
+    /// elimination drops the labels nothing jumps to, so `do { body } while (0)` is just `body`.
     fn gen_do_while_statement(
         &mut self,
         condition: Expression,
@@ -168,8 +255,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         self.gen_statements(body, instructions);
 
         instructions.push(tacky::Instruction::Label(continue_label));
-        let value = self.gen_expression(condition, instructions);
-        instructions.push(tacky::Instruction::JumpIfNotZero(value, start_label));
+        self.gen_conditional_jump(condition, true, start_label, instructions);
 
         instructions.push(tacky::Instruction::Label(break_label));
     }
@@ -186,8 +272,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         let break_label = self.convert_to_break_label(label);
 
         instructions.push(tacky::Instruction::Label(continue_label));
-        let value = self.gen_expression(condition, instructions);
-        instructions.push(tacky::Instruction::JumpIfZero(value, break_label));
+        self.gen_conditional_jump(condition, false, break_label, instructions);
 
         self.gen_statements(body, instructions);
         instructions.push(tacky::Instruction::Jump(continue_label));
@@ -195,6 +280,10 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     }
 
     /// Lowers a `for` loop.
+    ///
+    /// Every clause may be omitted: `for (;;)` still gets its start, continue and
+    /// break labels, only the condition's `JumpIfZero` is missing, so the loop
+    /// is left through a `break` alone.
     fn gen_for_statement(
         &mut self,
         init: ForInit,
@@ -214,6 +303,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         self.gen_for_statement_condition(condition, instructions, break_label);
         self.gen_statements(body, instructions);
 
+        // `continue` jumps to the post expression, a loop that continues still steps
         instructions.push(tacky::Instruction::Label(continue_label));
         self.gen_for_statement_post(post, instructions);
 
@@ -224,7 +314,11 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     /// Generates the initialization part of a `for` loop.
     fn gen_for_init(&mut self, init: ForInit, instructions: &mut Vec<tacky::Instruction>) {
         match init {
-            ForInit::D(var_decl) => self.gen_variable_declaration(var_decl, instructions),
+            ForInit::D(var_decls) => {
+                for var_decl in var_decls {
+                    self.gen_variable_declaration(var_decl, instructions);
+                }
+            }
             ForInit::E(optional_expr) => {
                 if let Some(expr) = optional_expr {
                     self.gen_expression(expr, instructions);
@@ -234,6 +328,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     }
 
     /// Generates the conditional check for a `for` loop.
+    /// A missing condition is always true, so nothing is emitted for it.
     fn gen_for_statement_condition(
         &mut self,
         optional_expr: Option<Expression>,
@@ -241,8 +336,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         break_label: Identifier,
     ) {
         if let Some(expr) = optional_expr {
-            let value = self.gen_expression(expr, instructions);
-            instructions.push(tacky::Instruction::JumpIfZero(value, break_label));
+            self.gen_conditional_jump(expr, false, break_label, instructions);
         }
     }
 
@@ -0,0 +1,264 @@
+// Function inlining.
+//
+// Replaces the calls to small functions with a copy of their body. A function
+// is inlined when:
+//   - its body is a single basic block, no labels or jumps, up to its first `Ret`,
+//   - the block has at most `INLINE_LIMIT` instructions,
+//   - it isn't recursive, it can't reach itself through the direct calls of the call graph.
+// At a call site the arguments are copied into fresh copies of the parameters,
+// the callee's local variables are renamed to fresh variables of the caller,
+// and the returned value is copied into the call's destination. Static variables
+// keep their names, every copy of the body shares them.
+//
+// Calls are inlined with the bodies the functions had before the pass, so a call
+// inside an inlined body stays a call. The function definitions are kept, they can
+// still be called from other files or through a pointer.
+
+use crate::IRgen;
+use crate::tacky::{self, Instruction, Value};
+use shared_context::{Identifier, Span, symbol_table::IdenAttrs};
+use std::collections::{HashMap, HashSet};
+
+/// The largest body inlined, not counting its `Ret` and source line markers.
+const INLINE_LIMIT: usize = 8;
+
+/// What a call to an inlined function is replaced with.
+struct InlineCandidate {
+    params: Vec<Identifier>,
+    body: Vec<Instruction>, // the instructions before the `Ret`
+    ret: Option<Value>,     // the value the function returns
+}
+
+impl<'src, 'ctx> IRgen<'src, 'ctx> {
+    /// Inline the calls to small, non-recursive, single-block functions in every function.
+    pub(crate) fn inline_functions(&mut self, program: &mut tacky::Program) {
+        let candidates = Self::collect_candidates(program);
+        if candidates.is_empty() {
+            return;
+        }
+
+        for item in program.get_mut_functions() {
+            if let tacky::TopLevel::F(fun_def) = item {
+                self.inline_calls(&candidates, fun_def.get_mut_instructions());
+            }
+        }
+    }
+
+    /// Finds the functions whose calls are inlined.
+    fn collect_candidates(program: &tacky::Program) -> HashMap<Identifier, InlineCandidate> {
+        let functions: Vec<&tacky::FunctionDef> = program
+            .get_functions()
+            .iter()
+            .filter_map(|item| match item {
+                tacky::TopLevel::F(fun_def) => Some(fun_def),
+                tacky::TopLevel::S(_) => None,
+            })
+            .collect();
+
+        // the functions each function calls directly
+        let call_graph: HashMap<Identifier, Vec<Identifier>> = functions
+            .iter()
+            .map(|fun_def| {
+                let callees = fun_def
+                    .get_instructions()
+                    .iter()
+                    .filter_map(|(instr, _)| match instr {
+                        Instruction::FunCall { name, .. } => Some(*name),
+                        _ => None,
+                    })
+                    .collect();
+                (fun_def.get_name(), callees)
+            })
+            .collect();
+
+        functions
+            .into_iter()
+            .filter(|fun_def| !Self::is_recursive(fun_def.get_name(), &call_graph))
+            .filter_map(|fun_def| {
+                let candidate = Self::make_candidate(fun_def)?;
+                Some((fun_def.get_name(), candidate))
+            })
+            .collect()
+    }
+
+    /// whether a function can reach itself through the calls of the call graph,
+    /// functions that aren't defined in the file make no calls
+    fn is_recursive(
+        function: Identifier,
+        call_graph: &HashMap<Identifier, Vec<Identifier>>,
+    ) -> bool {
+        let mut visited = HashSet::new();
+        let mut worklist = call_graph.get(&function).cloned().unwrap_or_default();
+
+        while let Some(callee) = worklist.pop() {
+            if callee == function {
+                return true;
+            }
+            if visited.insert(callee)
+                && let Some(callees) = call_graph.get(&callee)
+            {
+                worklist.extend(callees);
+            }
+        }
+        false
+    }
+
+    /// The body a call to the function is replaced with,
+    /// `None` if it has more than one block or is too big
+    fn make_candidate(fun_def: &tacky::FunctionDef) -> Option<InlineCandidate> {
+        let mut body = Vec::new();
+
+        for (instr, _) in fun_def.get_instructions() {
+            match instr {
+                Instruction::Ret(ret) => {
+                    return Some(InlineCandidate {
+                        params: fun_def.get_params().to_vec(),
+                        body,
+                        ret: *ret,
+                    });
+                }
+                Instruction::Label(_)
+                | Instruction::Jump(_)
+                | Instruction::JumpIfZero(..)
+                | Instruction::JumpIfNotZero(..)
+                | Instruction::CompareAndJump { .. } => return None,
+                // the inlined instructions are attributed to the call's line
+                Instruction::SourceLine(_) => continue,
+                _ if body.len() == INLINE_LIMIT => return None,
+                _ => body.push(instr.clone()),
+            }
+        }
+
+        None
+    }
+
+    /// Replace each call to a candidate in a function body with the candidate's body,
+    /// the inlined instructions get the span of the call.
+    fn inline_calls(
+        &mut self,
+        candidates: &HashMap<Identifier, InlineCandidate>,
+        instructions: &mut Vec<tacky::SpannedInstruction>,
+    ) {
+        let mut new_instructions = Vec::with_capacity(instructions.len());
+
+        for (instr, span) in instructions.drain(..) {
+            let candidate = match &instr {
+                Instruction::FunCall { name, args, .. } => candidates
+                    .get(name)
+                    .filter(|candidate| candidate.params.len() == args.len()),
+                _ => None,
+            };
+
+            match (candidate, instr) {
+                (Some(candidate), Instruction::FunCall { args, dst, .. }) => {
+                    let inlined = self.inline_call(candidate, &args, dst);
+                    new_instructions.extend(inlined.into_iter().map(|instr| (instr, span)));
+                }
+                (_, instr) => new_instructions.push((instr, span)),
+            }
+        }
+
+        *instructions = new_instructions;
+    }
+
+    /// The instructions a call is replaced with: copy the arguments to the parameters,
+    /// run the body on renamed locals, then copy the returned value to `dst`.
+    fn inline_call(
+        &mut self,
+        candidate: &InlineCandidate,
+        args: &[Value],
+        dst: Option<Value>,
+    ) -> Vec<Instruction> {
+        let mut renamed = HashMap::new();
+        let mut inlined = Vec::with_capacity(args.len() + candidate.body.len() + 1);
+
+        for (param, arg) in candidate.params.iter().zip(args) {
+            let param = self.rename_local(Value::Var(*param), &mut renamed);
+            inlined.push(Instruction::Copy {
+                src: *arg,
+                dst: param,
+            });
+        }
+
+        for instr in &candidate.body {
+            let mut instr = instr.clone();
+            for value in Self::get_values_mut(&mut instr) {
+                *value = self.rename_local(*value, &mut renamed);
+            }
+            inlined.push(instr);
+        }
+
+        if let (Some(ret), Some(dst)) = (candidate.ret, dst) {
+            let src = self.rename_local(ret, &mut renamed);
+            inlined.push(Instruction::Copy { src, dst });
+        }
+
+        inlined
+    }
+
+    /// A local variable of the callee is replaced with the same fresh variable of the
+    /// caller everywhere in one inlined body, static variables and constants are kept.
+    fn rename_local(
+        &mut self,
+        value: Value,
+        renamed: &mut HashMap<Identifier, Identifier>,
+    ) -> Value {
+        let Value::Var(iden) = value else {
+            return value;
+        };
+        let entry = self.symbol_table.unsafe_lookup(iden);
+        if entry.attributes != IdenAttrs::LocalAttrs {
+            return value;
+        }
+        let entry_type = entry.entry_type;
+
+        let fresh = *renamed.entry(iden).or_insert_with(|| {
+            let s = format!("inl.{}", self.var_counter);
+            self.var_counter += 1;
+            let fresh = Identifier::new(self.sy_interner.intern(&s), 0);
+            self.symbol_table
+                .add(fresh, entry_type, IdenAttrs::LocalAttrs, Span::default());
+            fresh
+        });
+        Value::Var(fresh)
+    }
+
+    /// returns every value an instruction reads or writes, to be renamed
+    fn get_values_mut(instr: &mut Instruction) -> Vec<&mut Value> {
+        match instr {
+            Instruction::Binary {
+                src1, src2, dst, ..
+            } => vec![src1, src2, dst],
+            Instruction::Unary { src, dst, .. }
+            | Instruction::Copy { src, dst }
+            | Instruction::SignExtend { src, dst }
+            | Instruction::ZeroExtend { src, dst }
+            | Instruction::Truncate { src, dst }
+            | Instruction::DoubleToInt { src, dst }
+            | Instruction::DoubleToUInt { src, dst }
+            | Instruction::IntToDouble { src, dst }
+            | Instruction::UIntToDouble { src, dst }
+            | Instruction::GetAddress { src, dst }
+            | Instruction::Load { src_ptr: src, dst }
+            | Instruction::Store { src, dst_ptr: dst } => vec![src, dst],
+            Instruction::Select {
+                cond,
+                src1,
+                src2,
+                dst,
+            } => vec![cond, src1, src2, dst],
+            Instruction::AddPtr {
+                ptr, index, dst, ..
+            } => vec![ptr, index, dst],
+            Instruction::FunCall { args, dst, .. } => args.iter_mut().chain(dst).collect(),
+            Instruction::IndirectCall { fun_ptr, args, dst } => std::iter::once(fun_ptr)
+                .chain(args.iter_mut())
+                .chain(dst)
+                .collect(),
+            Instruction::JumpIfZero(val, _) | Instruction::JumpIfNotZero(val, _) => vec![val],
+            Instruction::CompareAndJump { src1, src2, .. } => vec![src1, src2],
+            Instruction::Ret(val) => val.iter_mut().collect(),
+            Instruction::Jump(_) | Instruction::Label(_) | Instruction::SourceLine(_) => Vec::new(),
+        }
+    }
+}
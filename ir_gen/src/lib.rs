@@ -7,28 +7,52 @@
 
 use parser::ast::{self, StorageClass};
 use shared_context::{
-    Const, Identifier, Span, StaticVariable, Type, get_tentative_init,
+    Const, Identifier, OptPasses, Span, StaticVariable, Type, get_tentative_init,
     symbol_interner::SymbolInterner,
     symbol_table::{EntryType, IdenAttrs, InitValue, SymbolTable},
+    type_interner::TypeInterner,
 };
 
 use crate::tacky::TopLevel;
 
+pub mod cfg;
 mod gen_expressions;
 mod gen_statements;
+mod inlining;
+mod optimize;
 pub mod print_ir;
 pub mod tacky;
+mod verify;
+
+pub use verify::verify_tacky;
 
 /// the enrty point for IR generation
 /// Consumes an AST and produce a tacky program
-pub fn lower_to_tacky(
+/// then runs the IR optimization passes selected in `passes`,
+/// inlining runs first so the other passes clean up the inlined code
+pub fn lower_to_tacky<'src>(
     program: ast::Program,
-    sy_interner: &mut SymbolInterner,
+    ty_interner: &TypeInterner<'src>,
+    sy_interner: &mut SymbolInterner<'src>,
     symbol_table: &mut SymbolTable,
     var_counter: usize,
+    passes: &OptPasses,
+    debug_info: bool,
 ) -> tacky::Program {
-    let mut ir_gen = IRgen::new(var_counter, sy_interner, symbol_table);
-    let program_tacky = ir_gen.gen_tacky(program);
+    let mut ir_gen = IRgen::new(
+        var_counter,
+        ty_interner,
+        sy_interner,
+        symbol_table,
+        debug_info,
+    )
+    .with_constant_folding(passes.constant_folding)
+    .with_branchless_select(passes.branchless_select);
+    let mut program_tacky = ir_gen.gen_tacky(program);
+    if passes.inlining {
+        ir_gen.inline_functions(&mut program_tacky);
+    }
+    optimize::Optimizer::new(symbol_table, passes).optimize(&mut program_tacky);
     program_tacky
 }
 
@@ -42,28 +66,96 @@ pub struct IRgen<'src, 'ctx> {
     /// Counter used to generate unique temporary variables and labels.
     var_counter: usize,
 
+    /// Used to get the return types of functions.
+    ty_interner: &'ctx TypeInterner<'src>,
+
     /// Reference to the symbol interner used to manage symbol deduplication.
     sy_interner: &'ctx mut SymbolInterner<'src>,
     symbol_table: &'ctx mut SymbolTable,
+
+    /// Whether to mark statement boundaries with their source line.
+    debug_info: bool,
+
+    /// Whether to fold logical expressions with a constant operand while lowering them.
+    fold_constants: bool,
+
+    /// Whether to lower conditional expressions on plain values to a `Select`.
+    branchless_select: bool,
+
+    /// The source span of each instruction of the function being lowered.
+    spans: Vec<Option<Span>>,
 }
 
 impl<'src, 'ctx> IRgen<'src, 'ctx> {
     /// Creates a new IR generator instance.
     pub fn new(
         var_counter: usize,
+        ty_interner: &'ctx TypeInterner<'src>,
         sy_interner: &'ctx mut SymbolInterner<'src>,
         symbol_table: &'ctx mut SymbolTable,
+        debug_info: bool,
     ) -> Self {
         Self {
             var_counter,
+            ty_interner,
             sy_interner,
             symbol_table,
+            debug_info,
+            fold_constants: false,
+            branchless_select: false,
+            spans: Vec::new(),
+        }
+    }
+
+    /// Folds `&&` and `||` with a constant operand as they are lowered.
+    ///
+    /// The Tacky folding pass only sees the jumps these operators are lowered to,
+    /// folding them here removes the branches, and the skipped operand is never lowered.
+    pub fn with_constant_folding(mut self, enabled: bool) -> Self {
+        self.fold_constants = enabled;
+        self
+    }
+
+    /// Lowers `c ? a : b` to a `Select` instead of jumps when both `a` and `b` are
+    /// variables or constants, computing both has no side effects and costs less than a branch.
+    pub fn with_branchless_select(mut self, enabled: bool) -> Self {
+        self.branchless_select = enabled;
+        self
+    }
+
+    /// Marks the start of a statement's instructions with its source line,
+    /// used by the emitter to produce line number debug info.
+    fn gen_source_line(&self, span: Span, instructions: &mut Vec<tacky::Instruction>) {
+        if !self.debug_info {
+            return;
+        }
+
+        // statements nested on the same line, like a for loop and its initializer, share a marker
+        if matches!(instructions.last(), Some(tacky::Instruction::SourceLine(line)) if *line == span.line)
+        {
+            return;
+        }
+
+        instructions.push(tacky::Instruction::SourceLine(span.line));
+    }
+
+    /// Attaches `span` to the instructions pushed since `start` that don't have a span yet.
+    ///
+    /// Nested nodes are lowered first, so each instruction keeps the span of the
+    /// innermost expression or statement it was lowered from.
+    fn record_span(&mut self, start: usize, span: Span, instructions: &[tacky::Instruction]) {
+        self.spans.resize(instructions.len(), None);
+        for entry in &mut self.spans[start..] {
+            entry.get_or_insert(span);
         }
     }
 
     /// Creates a new temporary variable (e.g., `tmp.0`, `tmp.1`, …)
     /// it stores the variable in the symbol table and returns it as a tacky::Value::Var.
     ///
+    /// A `.` can't appear in a C identifier, so a temporary never shares its symbol
+    /// with a user variable, even one named `tmp`.
+    ///
     /// Used to hold intermediate computation results during expression lowering.
     fn make_temp_var(&mut self, var_type: Type) -> tacky::Value {
         let s = format!("tmp.{}", self.var_counter);
@@ -79,11 +171,12 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         tacky::Value::Var(temp_id)
     }
 
-    /// Generates a unique label identifier (e.g., `label_0`, `label_1`, …).
+    /// Generates a unique label identifier (e.g., `label.0`, `label.1`, …).
     ///
     /// Used for control flow constructs such as loops and conditionals.
+    /// As with temporaries, the `.` keeps labels apart from user identifiers like `label_0`.
     fn make_label(&mut self) -> Identifier {
-        let s = format!("label_{}", self.var_counter);
+        let s = format!("label.{}", self.var_counter);
         self.var_counter += 1;
         let symbol = self.sy_interner.intern(&s);
         Identifier::new(symbol, 0)
@@ -91,7 +184,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
 
     /// Converts a label into a corresponding “break” label.
     ///
-    /// For example, `label_3` becomes `label_3_break`.
+    /// For example, `label.3` becomes `label.3_break`.
     fn convert_to_break_label(&mut self, label: Identifier) -> Identifier {
         let symbol = label.get_symbol();
         let s = format!("{}_break", self.sy_interner.lookup(symbol));
@@ -100,7 +193,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
 
     /// Converts a label into a corresponding “continue” label.
     ///
-    /// For example, `label_3` becomes `label_3_continue`.
+    /// For example, `label.3` becomes `label.3_continue`.
     fn convert_to_continue_label(&mut self, label: Identifier) -> Identifier {
         let symbol = label.get_symbol();
         let s = format!("{}_continue", self.sy_interner.lookup(symbol));
@@ -123,8 +216,8 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
                         tacky_items.push(tacky::TopLevel::F(tacky_function));
                     }
                 }
-                // skip file scope variable declarations
-                ast::Declaration::VarDecl(_) => continue,
+                // skip file scope variable and enum declarations
+                ast::Declaration::VarDecl(_) | ast::Declaration::EnumDecl(_) => continue,
             }
         }
 
@@ -171,25 +264,31 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
     ///
     /// Returns `None` if the given AST node represents only a function declaration.
     fn gen_function_def(&mut self, function: ast::FunctionDecl) -> Option<tacky::FunctionDef> {
-        let (name, _, params, body, _, _) = function.into_parts();
+        let (name, type_id, params, body, _, _) = function.into_parts();
 
         match body {
             Some(block) => {
-                // Convert parameter identifiers
+                // Convert parameter identifiers, a definition names all of them
                 let tacky_params = params
                     .iter()
+                    .flatten()
                     .map(|sp_iden| sp_iden.get_identifier())
                     .collect();
 
                 let mut instructions = Vec::new();
-                self.gen_function_block(block, &mut instructions);
+                let ret_type = self.ty_interner.get(type_id).ret;
+                self.gen_function_block(block, ret_type, &mut instructions);
                 let identifier = name.get_identifier();
 
+                // the implicit return has no source
+                let mut spans = std::mem::take(&mut self.spans);
+                spans.resize(instructions.len(), None);
+
                 Some(tacky::FunctionDef::new(
                     identifier,
                     self.get_function_linkage(identifier),
                     tacky_params,
-                    instructions,
+                    instructions.into_iter().zip(spans).collect(),
                 ))
             }
             None => None, // Skip pure declarations
@@ -207,11 +306,15 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
 
     /// Generates a full function body block.
     ///
-    /// Automatically appends a `return 0` instruction at the end if
-    /// no explicit return statement is encountered.
+    /// Appends a `return 0` if the body doesn't end in a `return`, that's the implicit
+    /// return of `main`, or a `return;` in a `void` function. Semantic analysis rejects
+    /// any other function whose end is reachable, so for those it's unreachable, and only
+    /// keeps every function ending in a `Ret` so code generation never falls through
+    /// into the next one.
     fn gen_function_block(
         &mut self,
         block: ast::Block,
+        ret_type: Type,
         instructions: &mut Vec<tacky::Instruction>,
     ) {
         let (block_items, _) = block.into_parts();
@@ -219,10 +322,13 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
             self.gen_block_item(item, instructions);
         }
 
-        // Default return for functions without explicit `return` statements
-        instructions.push(tacky::Instruction::Ret(tacky::Value::Constant(
-            Const::ConstInt(0),
-        )));
+        if !matches!(instructions.last(), Some(tacky::Instruction::Ret(_))) {
+            let val = match ret_type {
+                Type::Void => None,
+                _ => Some(tacky::Value::Constant(Const::ConstInt(0))),
+            };
+            instructions.push(tacky::Instruction::Ret(val));
+        }
     }
 
     /// Generates IR for a standard block (without adding implicit returns).
@@ -251,7 +357,8 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         instructions: &mut Vec<tacky::Instruction>,
     ) {
         match decl {
-            ast::Declaration::FunDecl(_) => return, // Skip pure declarations
+            // Skip pure declarations, enumerators were replaced by their values
+            ast::Declaration::FunDecl(_) | ast::Declaration::EnumDecl(_) => return,
             ast::Declaration::VarDecl(var_decl) => {
                 self.gen_variable_declaration(var_decl, instructions)
             }
@@ -267,7 +374,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
         var_decl: ast::VariableDecl,
         instructions: &mut Vec<tacky::Instruction>,
     ) {
-        let (name, _, init, storage_class, _) = var_decl.into_parts();
+        let (name, _, init, storage_class, span) = var_decl.into_parts();
 
         // if a variable is declared with static keyword, skip it
         // static variable definitions will be handled separatly
@@ -277,6 +384,9 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
 
         match init {
             Some(init) => {
+                let start = instructions.len();
+                self.gen_source_line(span, instructions);
+
                 // Lower the initializer expression
                 let value = self.gen_expression(init, instructions);
 
@@ -286,6 +396,7 @@ impl<'src, 'ctx> IRgen<'src, 'ctx> {
                     dst: tacky::Value::Var(name.get_identifier()),
                 };
                 instructions.push(instr);
+                self.record_span(start, span, instructions);
             }
             None => return, // No initializer: skip emission
         }
@@ -0,0 +1,56 @@
+// IR optimization passes.
+//
+// This module runs the optimizations selected by `OptPasses` over every
+// function of a lowered tacky program. The passes are repeated until the
+// function stops changing, since folding a constant condition can make code
+// unreachable, and removing code can expose new constants to fold.
+
+use crate::tacky;
+use shared_context::{OptPasses, SymbolTable};
+
+mod constant_folding;
+mod dead_code;
+
+/// Runs the IR optimization passes over a tacky program.
+pub(crate) struct Optimizer<'ctx> {
+    /// used to look up the type of cast destinations while folding
+    symbol_table: &'ctx SymbolTable,
+    passes: &'ctx OptPasses,
+}
+
+impl<'ctx> Optimizer<'ctx> {
+    pub(crate) fn new(symbol_table: &'ctx SymbolTable, passes: &'ctx OptPasses) -> Self {
+        Self {
+            symbol_table,
+            passes,
+        }
+    }
+
+    /// Optimize every function definition in the program.
+    pub(crate) fn optimize(&self, program: &mut tacky::Program) {
+        for item in program.get_mut_functions() {
+            if let tacky::TopLevel::F(fun_def) = item {
+                self.optimize_function(fun_def.get_mut_instructions());
+            }
+        }
+    }
+
+    /// Run the selected passes on a function body until none of them makes progress.
+    fn optimize_function(&self, instructions: &mut Vec<tacky::SpannedInstruction>) {
+        loop {
+            let mut changed = false;
+
+            if self.passes.constant_folding {
+                changed |= self.fold_constants(instructions);
+            }
+
+            if self.passes.dead_code_elimination {
+                changed |= Self::eliminate_dead_code(instructions);
+            }
+
+            if !changed {
+                return;
+            }
+        }
+    }
+}
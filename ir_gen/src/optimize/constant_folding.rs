@@ -0,0 +1,216 @@
+// Constant folding.
+//
+// Replaces operations whose operands are all constants with a `Copy` of the
+// computed result, and turns conditional jumps on constants, or on a comparison
+// of constants, into either an unconditional jump or nothing at all. A `Select` on a constant becomes a `Copy`.
+
+use super::Optimizer;
+use crate::tacky::{self, BinaryOP, Instruction, UnaryOP, Value};
+use shared_context::{Const, Type, symbol_table::EntryType};
+
+impl<'ctx> Optimizer<'ctx> {
+    /// Fold every instruction with constant operands.
+    /// Returns true if any instruction was changed or removed.
+    ///
+    /// A replacement keeps the span of the instruction it replaces.
+    pub(super) fn fold_constants(&self, instructions: &mut Vec<tacky::SpannedInstruction>) -> bool {
+        let mut changed = false;
+        let mut new_instructions = Vec::with_capacity(instructions.len());
+
+        for (instr, span) in instructions.drain(..) {
+            match self.fold_instruction(&instr) {
+                // the instruction is replaced or dropped
+                Some(folded) => {
+                    changed = true;
+                    new_instructions.extend(folded.map(|folded| (folded, span)));
+                }
+                None => new_instructions.push((instr, span)),
+            }
+        }
+
+        *instructions = new_instructions;
+        changed
+    }
+
+    /// Try to fold a single instruction.
+    ///
+    /// Returns `None` if the instruction can't be folded, otherwise
+    /// returns the instruction that replaces it (which may be nothing).
+    fn fold_instruction(&self, instr: &Instruction) -> Option<Option<Instruction>> {
+        let folded = match *instr {
+            Instruction::Binary {
+                ref op,
+                src1: Value::Constant(c1),
+                src2: Value::Constant(c2),
+                dst,
+            } => Self::fold_binary(op, c1, c2).map(|result| Instruction::Copy {
+                src: Value::Constant(result),
+                dst,
+            }),
+
+            Instruction::Unary {
+                ref op,
+                src: Value::Constant(c),
+                dst,
+            } => Some(Instruction::Copy {
+                src: Value::Constant(Self::fold_unary(op, c)),
+                dst,
+            }),
+
+            Instruction::SignExtend {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::ZeroExtend {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::Truncate {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::DoubleToInt {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::DoubleToUInt {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::IntToDouble {
+                src: Value::Constant(c),
+                dst,
+            }
+            | Instruction::UIntToDouble {
+                src: Value::Constant(c),
+                dst,
+            } => Some(Instruction::Copy {
+                src: Value::Constant(c.convert_to(self.get_value_type(dst))),
+                dst,
+            }),
+
+            // a constant condition picks its value at compile time
+            Instruction::Select {
+                cond: Value::Constant(c),
+                src1,
+                src2,
+                dst,
+            } => Some(Instruction::Copy {
+                src: if c.is_zero() { src2 } else { src1 },
+                dst,
+            }),
+
+            Instruction::JumpIfZero(Value::Constant(c), target) => {
+                return Some(c.is_zero().then_some(Instruction::Jump(target)));
+            }
+
+            Instruction::JumpIfNotZero(Value::Constant(c), target) => {
+                return Some((!c.is_zero()).then_some(Instruction::Jump(target)));
+            }
+
+            Instruction::CompareAndJump {
+                ref op,
+                src1: Value::Constant(c1),
+                src2: Value::Constant(c2),
+                jump_if,
+                target,
+            } => {
+                let holds = Self::fold_comparison(op, c1, c2).to_bool();
+                return Some((holds == jump_if).then_some(Instruction::Jump(target)));
+            }
+
+            _ => None,
+        };
+
+        folded.map(Some)
+    }
+
+    /// Evaluate a binary operation on two constants of the same type.
+    ///
+    /// Returns `None` when the result is not defined at compile time
+    /// (division by zero or signed division overflow), so the operation
+    /// is left for the program to perform at run time.
+    fn fold_binary(op: &BinaryOP, c1: Const, c2: Const) -> Option<Const> {
+        let result = match op {
+            BinaryOP::Add => c1 + c2,
+            BinaryOP::Sub => c1 - c2,
+            BinaryOP::Mul => c1 * c2,
+            BinaryOP::Div => return c1.checked_div(c2),
+            BinaryOP::Mod => return c1.checked_rem(c2),
+
+            // comparisons and logical operators always produce an int
+            BinaryOP::LogicalAnd => Self::from_bool(c1.to_bool() && c2.to_bool()),
+            BinaryOP::LogicalOr => Self::from_bool(c1.to_bool() || c2.to_bool()),
+            _ => return Some(Self::fold_comparison(op, c1, c2)),
+        };
+
+        Some(result)
+    }
+
+    /// Evaluate a comparison of two constants of the same type.
+    fn fold_comparison(op: &BinaryOP, c1: Const, c2: Const) -> Const {
+        let ordering = match (c1, c2) {
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => a.partial_cmp(&b),
+            // integers are compared by their value, signed or unsigned according to their type
+            _ => Some(Self::const_value(c1).cmp(&Self::const_value(c2))),
+        };
+
+        // a NaN operand is unordered, only `!=` is true
+        Self::from_bool(match (op, ordering) {
+            (BinaryOP::NotEqual, None) => true,
+            (_, None) => false,
+            (BinaryOP::Equal, Some(ord)) => ord.is_eq(),
+            (BinaryOP::NotEqual, Some(ord)) => ord.is_ne(),
+            (BinaryOP::LessThan, Some(ord)) => ord.is_lt(),
+            (BinaryOP::GreaterThan, Some(ord)) => ord.is_gt(),
+            (BinaryOP::LessThanOrEq, Some(ord)) => ord.is_le(),
+            (BinaryOP::GreaterThanOrEq, Some(ord)) => ord.is_ge(),
+            (_, Some(_)) => unreachable!("{:?} isn't a comparison", op),
+        })
+    }
+
+    /// Evaluate a unary operation on a constant.
+    fn fold_unary(op: &UnaryOP, c: Const) -> Const {
+        match op {
+            UnaryOP::Neg => -c,
+            UnaryOP::LogicalNot => Self::from_bool(!c.to_bool()),
+            UnaryOP::Not => match c {
+                Const::ConstInt(v) => Const::ConstInt(!v),
+                Const::ConstLong(v) => Const::ConstLong(!v),
+                Const::ConstUint(v) => Const::ConstUint(!v),
+                Const::ConstUlong(v) => Const::ConstUlong(!v),
+                // the type checker rejects `~` on a double, and promotes a `_Bool` to `int`
+                Const::ConstDouble(_) => unreachable!("bitwise not on a double"),
+                Const::ConstBool(_) => unreachable!("bitwise not on a _Bool"),
+            },
+        }
+    }
+
+    /// The mathematical value of an integer constant, signed or unsigned according to its type.
+    fn const_value(c: Const) -> i128 {
+        match c {
+            Const::ConstInt(v) => v as i128,
+            Const::ConstLong(v) => v as i128,
+            Const::ConstUint(v) => v as i128,
+            Const::ConstUlong(v) => v as i128,
+            Const::ConstBool(v) => v as i128,
+            Const::ConstDouble(_) => unreachable!("doubles are compared as doubles"),
+        }
+    }
+
+    fn from_bool(value: bool) -> Const {
+        Const::ConstInt(value as i32)
+    }
+
+    /// Get the type of a value, variables are looked up in the symbol table.
+    fn get_value_type(&self, value: Value) -> Type {
+        match value {
+            Value::Constant(c) => c.get_type(),
+            Value::Var(iden) => match self.symbol_table.unsafe_lookup(iden).entry_type {
+                EntryType::Scalar(ty) => ty,
+                EntryType::Func(_) => unreachable!("a function can't be used as a value"),
+                EntryType::Array { .. } => unreachable!("an array is only used by its address"),
+            },
+        }
+    }
+}
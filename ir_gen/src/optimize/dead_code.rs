@@ -0,0 +1,81 @@
+// Dead code elimination.
+//
+// Removes instructions that can never execute: everything that follows an
+// unconditional jump or a return up to the next label that is actually
+// jumped to. Labels that nothing jumps to are removed as well, along with
+// jumps to the label that immediately follows them.
+
+use super::Optimizer;
+use crate::tacky::{self, Instruction};
+use shared_context::Identifier;
+use std::collections::HashSet;
+
+impl<'ctx> Optimizer<'ctx> {
+    /// Remove unreachable instructions and unused labels from a function body.
+    /// Returns true if any instruction was removed.
+    pub(super) fn eliminate_dead_code(instructions: &mut Vec<tacky::SpannedInstruction>) -> bool {
+        let old_len = instructions.len();
+
+        Self::remove_redundant_jumps(instructions);
+        Self::remove_unreachable_instructions(instructions);
+
+        instructions.len() != old_len
+    }
+
+    /// Remove jumps whose target is the very next instruction.
+    fn remove_redundant_jumps(instructions: &mut Vec<tacky::SpannedInstruction>) {
+        let mut new_instructions: Vec<tacky::SpannedInstruction> =
+            Vec::with_capacity(instructions.len());
+
+        for (instr, span) in instructions.drain(..) {
+            if let Instruction::Label(label) = instr {
+                // falling through reaches the label anyway
+                while let Some((Instruction::Jump(target), _)) = new_instructions.last() {
+                    if *target != label {
+                        break;
+                    }
+                    new_instructions.pop();
+                }
+            }
+            new_instructions.push((instr, span));
+        }
+
+        *instructions = new_instructions;
+    }
+
+    /// Drop labels that are never jumped to, and the instructions that
+    /// can't be reached because control never flows into them.
+    fn remove_unreachable_instructions(instructions: &mut Vec<tacky::SpannedInstruction>) {
+        let jump_targets = Self::collect_jump_targets(instructions);
+        let mut reachable = true;
+
+        instructions.retain(|(instr, _)| match instr {
+            Instruction::Label(label) => {
+                // a jump target is reachable even if the code before it isn't
+                let is_target = jump_targets.contains(label);
+                reachable |= is_target;
+                is_target
+            }
+            _ if !reachable => false,
+            Instruction::Jump(_) | Instruction::Ret(_) => {
+                reachable = false;
+                true
+            }
+            _ => true,
+        });
+    }
+
+    /// Collect every label used as the target of a jump.
+    fn collect_jump_targets(instructions: &[tacky::SpannedInstruction]) -> HashSet<Identifier> {
+        instructions
+            .iter()
+            .filter_map(|(instr, _)| match instr {
+                Instruction::Jump(target)
+                | Instruction::JumpIfZero(_, target)
+                | Instruction::JumpIfNotZero(_, target)
+                | Instruction::CompareAndJump { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect()
+    }
+}
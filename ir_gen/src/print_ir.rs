@@ -1,4 +1,4 @@
-use shared_context::{Identifier, StaticVariable, symbol_interner::SymbolInterner};
+use shared_context::{Identifier, Span, StaticVariable, symbol_interner::SymbolInterner};
 
 use crate::tacky::{self, Value};
 
@@ -25,7 +25,7 @@ impl<'src, 'ctx> DebuggingPrinter<'src, 'ctx> {
         }
     }
 
-    fn format_identifier(&self, identifier: Identifier) -> String {
+    pub fn format_identifier(&self, identifier: Identifier) -> String {
         let (symbol, id) = identifier.into_parts();
         format!("{}.{}", self.sy_interner.lookup(symbol), id)
     }
@@ -67,8 +67,8 @@ impl<'src, 'ctx> DebuggingPrinter<'src, 'ctx> {
         );
         self.print_params(params);
 
-        for instr in instructions {
-            self.print_instruction(instr);
+        for (instr, span) in instructions {
+            self.print_instruction(instr, span);
         }
     }
 
@@ -83,145 +83,208 @@ impl<'src, 'ctx> DebuggingPrinter<'src, 'ctx> {
         println!(")");
     }
 
-    fn print_instruction(&self, instr: tacky::Instruction) {
+    /// prints an instruction followed by the line it was lowered from, when it has one
+    fn print_instruction(&self, instr: tacky::Instruction, span: Option<Span>) {
         let indent = " ".repeat(4);
+        match span {
+            Some(span) => println!(
+                "{}{}  (line {})",
+                indent,
+                self.format_instruction(&instr),
+                span.line
+            ),
+            None => println!("{}{}", indent, self.format_instruction(&instr)),
+        }
+    }
+
+    /// Formats a single instruction on one line, as printed by `print`.
+    /// Also used by the code generator to annotate the emitted assembly.
+    pub fn format_instruction(&self, instr: &tacky::Instruction) -> String {
         match instr {
-            tacky::Instruction::Ret(val) => {
-                println!("{}Ret({})", indent, self.format_value(val));
-            }
+            tacky::Instruction::Ret(val) => format!("Ret({})", self.format_optional_value(*val)),
 
             tacky::Instruction::Unary { .. } | tacky::Instruction::Binary { .. } => {
-                self.print_operator(instr, indent);
+                self.format_operator(instr)
             }
 
             tacky::Instruction::Label(_)
             | tacky::Instruction::Copy { .. }
             | tacky::Instruction::Jump(_)
             | tacky::Instruction::JumpIfZero(_, _)
-            | tacky::Instruction::JumpIfNotZero(_, _) => {
-                self.print_control_flow(instr, indent);
-            }
+            | tacky::Instruction::JumpIfNotZero(_, _)
+            | tacky::Instruction::CompareAndJump { .. } => self.format_control_flow(instr),
+
+            tacky::Instruction::Select {
+                cond,
+                src1,
+                src2,
+                dst,
+            } => format!(
+                "Select(cond: {}, src1: {}, src2: {}, dst: {})",
+                self.format_value(*cond),
+                self.format_value(*src1),
+                self.format_value(*src2),
+                self.format_value(*dst)
+            ),
 
             tacky::Instruction::FunCall { name, args, dst } => {
-                self.print_function_call(name, args, dst, indent);
+                self.format_function_call(*name, args, *dst)
+            }
+            tacky::Instruction::IndirectCall { fun_ptr, args, dst } => {
+                let mut formated = format!(
+                    "IndirectCall(fun_ptr: {}, args: ",
+                    self.format_value(*fun_ptr)
+                );
+                for arg in args {
+                    formated.push_str(&self.format_value(*arg));
+                }
+                formated.push_str(&format!(", dst: {})", self.format_optional_value(*dst)));
+                formated
             }
 
-            tacky::Instruction::SignExtend { src, dst } => self.print_sign_extend(src, dst, indent),
-            tacky::Instruction::Truncate { src, dst } => self.print_truncate(src, dst, indent),
-            tacky::Instruction::ZeroExtend { src, dst } => self.print_zero_extend(src, dst, indent),
+            tacky::Instruction::SignExtend { src, dst } => {
+                self.format_cast("SignExtend", *src, *dst)
+            }
+            tacky::Instruction::Truncate { src, dst } => self.format_cast("Truncate", *src, *dst),
+            tacky::Instruction::ZeroExtend { src, dst } => {
+                self.format_cast("ZeroExtend", *src, *dst)
+            }
+            tacky::Instruction::DoubleToInt { src, dst } => {
+                self.format_cast("DoubleToInt", *src, *dst)
+            }
+            tacky::Instruction::DoubleToUInt { src, dst } => {
+                self.format_cast("DoubleToUInt", *src, *dst)
+            }
+            tacky::Instruction::IntToDouble { src, dst } => {
+                self.format_cast("IntToDouble", *src, *dst)
+            }
+            tacky::Instruction::UIntToDouble { src, dst } => {
+                self.format_cast("UIntToDouble", *src, *dst)
+            }
+            tacky::Instruction::GetAddress { src, dst } => {
+                self.format_cast("GetAddress", *src, *dst)
+            }
+            tacky::Instruction::Load { src_ptr, dst } => format!(
+                "Load(src_ptr: {}, dst: {})",
+                self.format_value(*src_ptr),
+                self.format_value(*dst)
+            ),
+            tacky::Instruction::Store { src, dst_ptr } => format!(
+                "Store(src: {}, dst_ptr: {})",
+                self.format_value(*src),
+                self.format_value(*dst_ptr)
+            ),
+            tacky::Instruction::AddPtr {
+                ptr,
+                index,
+                scale,
+                dst,
+            } => format!(
+                "AddPtr(ptr: {}, index: {}, scale: {}, dst: {})",
+                self.format_value(*ptr),
+                self.format_value(*index),
+                scale,
+                self.format_value(*dst)
+            ),
+            tacky::Instruction::SourceLine(line) => format!("SourceLine({})", line),
         }
     }
 
-    fn print_sign_extend(&self, src: Value, dst: Value, indent: String) {
-        println!(
-            "{}SignExtend(src: {}, dst: {})",
-            indent,
-            self.format_value(src),
-            self.format_value(dst)
-        )
-    }
-
-    fn print_zero_extend(&self, src: Value, dst: Value, indent: String) {
-        println!(
-            "{}ZeroExtend(src: {}, dst: {})",
-            indent,
+    fn format_cast(&self, name: &str, src: Value, dst: Value) -> String {
+        format!(
+            "{}(src: {}, dst: {})",
+            name,
             self.format_value(src),
             self.format_value(dst)
         )
     }
 
-    fn print_truncate(&self, src: Value, dst: Value, indent: String) {
-        println!(
-            "{}Truncate(src: {}, dst: {})",
-            indent,
-            self.format_value(src),
-            self.format_value(dst)
-        )
+    fn format_function_call(&self, name: Identifier, args: &[Value], dst: Option<Value>) -> String {
+        let mut formated = format!("FunCall(name: {}, args: ", self.format_identifier(name));
+        for arg in args {
+            formated.push_str(&self.format_value(*arg));
+        }
+        formated.push_str(&format!(", dst: {})", self.format_optional_value(dst)));
+        formated
     }
 
-    fn print_function_call(&self, name: Identifier, args: Vec<Value>, dst: Value, indent: String) {
-        print!(
-            "{}FunCall(name: {}, args: ",
-            indent,
-            self.format_identifier(name)
-        );
-        for arg in args {
-            print!("{}", self.format_value(arg));
+    /// the missing value of a `void` function's return or call is written `void`
+    fn format_optional_value(&self, val: Option<Value>) -> String {
+        match val {
+            Some(val) => self.format_value(val),
+            None => "void".to_string(),
         }
-        println!(", dst: {})", self.format_value(dst));
     }
 
-    fn print_operator(&self, instr: tacky::Instruction, indent: String) {
+    fn format_operator(&self, instr: &tacky::Instruction) -> String {
         match instr {
-            tacky::Instruction::Unary { op, src, dst } => {
-                println!(
-                    "{}Unary({:?} ,src: {}, dst: {})",
-                    indent,
-                    op,
-                    self.format_value(src),
-                    self.format_value(dst),
-                )
-            }
+            tacky::Instruction::Unary { op, src, dst } => format!(
+                "Unary({:?} ,src: {}, dst: {})",
+                op,
+                self.format_value(*src),
+                self.format_value(*dst),
+            ),
 
             tacky::Instruction::Binary {
                 op,
                 src1,
                 src2,
                 dst,
-            } => {
-                println!(
-                    "{}Binary({:?} ,src1: {}, src2: {}, dst: {})",
-                    indent,
-                    op,
-                    self.format_value(src1),
-                    self.format_value(src2),
-                    self.format_value(dst)
-                );
-            }
-            _ => unreachable!("Only Binary and Unary expr will reach print_operator"),
+            } => format!(
+                "Binary({:?} ,src1: {}, src2: {}, dst: {})",
+                op,
+                self.format_value(*src1),
+                self.format_value(*src2),
+                self.format_value(*dst)
+            ),
+            _ => unreachable!("Only Binary and Unary expr will reach format_operator"),
         }
     }
 
-    fn print_control_flow(&self, instr: tacky::Instruction, indent: String) {
+    fn format_control_flow(&self, instr: &tacky::Instruction) -> String {
         match instr {
             tacky::Instruction::Label(label) => {
-                println!("{}Label({})", indent, self.format_identifier(label));
+                format!("Label({})", self.format_identifier(*label))
             }
 
-            tacky::Instruction::Copy { src, dst } => {
-                println!(
-                    "{}Copy(src: {}, dst: {})",
-                    indent,
-                    self.format_value(src),
-                    self.format_value(dst)
-                );
-            }
+            tacky::Instruction::Copy { src, dst } => format!(
+                "Copy(src: {}, dst: {})",
+                self.format_value(*src),
+                self.format_value(*dst)
+            ),
 
             tacky::Instruction::Jump(target) => {
-                println!("{}Jump(tar: {})", indent, self.format_identifier(target));
+                format!("Jump(tar: {})", self.format_identifier(*target))
             }
 
-            tacky::Instruction::JumpIfZero(condition, target) => {
-                println!(
-                    "{}JumpIfZero(cond: {}, tar: {})",
-                    indent,
-                    self.format_value(condition),
-                    self.format_identifier(target)
-                );
-            }
+            tacky::Instruction::JumpIfZero(condition, target) => format!(
+                "JumpIfZero(cond: {}, tar: {})",
+                self.format_value(*condition),
+                self.format_identifier(*target)
+            ),
 
-            tacky::Instruction::JumpIfNotZero(condition, target) => {
-                println!(
-                    "{}JumpIfNotZero(cond: {}, tar: {})",
-                    indent,
-                    self.format_value(condition),
-                    self.format_identifier(target)
-                );
-            }
+            tacky::Instruction::JumpIfNotZero(condition, target) => format!(
+                "JumpIfNotZero(cond: {}, tar: {})",
+                self.format_value(*condition),
+                self.format_identifier(*target)
+            ),
 
-            _ => {
-                println!("{}Invalid control-flow instruction", indent);
-            }
+            tacky::Instruction::CompareAndJump {
+                op,
+                src1,
+                src2,
+                jump_if,
+                target,
+            } => format!(
+                "CompareAndJump({:?}, src1: {}, src2: {}, if: {}, tar: {})",
+                op,
+                self.format_value(*src1),
+                self.format_value(*src2),
+                jump_if,
+                self.format_identifier(*target)
+            ),
+
+            _ => "Invalid control-flow instruction".to_string(),
         }
     }
 }
@@ -4,7 +4,7 @@
 // called TAC (Three Adress Code) used by the compiler backend. It represents the lowered form of the source
 // program after semantic analysis, suitable for optimization and code generation.
 
-use shared_context::{Const, Identifier, StaticVariable};
+use shared_context::{Const, Identifier, Span, StaticVariable};
 
 /// Represents a compiled program at the IR level.
 ///
@@ -24,6 +24,16 @@ impl Program {
     pub fn into_parts(self) -> Vec<TopLevel> {
         self.items
     }
+
+    /// Returns a mutable reference to the underlying vector of top level items.
+    pub fn get_mut_functions(&mut self) -> &mut Vec<TopLevel> {
+        &mut self.items
+    }
+
+    /// Returns the top level items.
+    pub fn get_functions(&self) -> &[TopLevel] {
+        &self.items
+    }
 }
 
 /// represent a global object in the IR
@@ -38,11 +48,12 @@ pub enum TopLevel {
 ///
 /// Each FunctionDef contains the function's name, its parameters,
 /// and the list of IR Instructions that make up its body.
+/// Each instruction is paired with the span of the source it was lowered from, if any.
 pub struct FunctionDef {
     name: Identifier,
     external: bool,
     params: Vec<Identifier>,
-    instructions: Vec<Instruction>,
+    instructions: Vec<SpannedInstruction>,
 }
 
 impl FunctionDef {
@@ -51,7 +62,7 @@ impl FunctionDef {
         name: Identifier,
         external: bool,
         params: Vec<Identifier>,
-        instructions: Vec<Instruction>,
+        instructions: Vec<SpannedInstruction>,
     ) -> Self {
         Self {
             name,
@@ -63,13 +74,38 @@ impl FunctionDef {
 
     /// Consumes the FunctionDef and returns its components.
     ///
-    /// Returns a tuple of `(name, params, instructions)`.
-    pub fn into_parts(self) -> (Identifier, bool, Vec<Identifier>, Vec<Instruction>) {
+    /// Returns a tuple of `(name, external, params, instructions)`.
+    pub fn into_parts(self) -> (Identifier, bool, Vec<Identifier>, Vec<SpannedInstruction>) {
         (self.name, self.external, self.params, self.instructions)
     }
+
+    /// Returns a mutable reference to the function’s instruction list.
+    pub fn get_mut_instructions(&mut self) -> &mut Vec<SpannedInstruction> {
+        &mut self.instructions
+    }
+
+    /// Returns the function's name.
+    pub fn get_name(&self) -> Identifier {
+        self.name
+    }
+
+    /// Returns the function's parameters.
+    pub fn get_params(&self) -> &[Identifier] {
+        &self.params
+    }
+
+    /// Returns the function’s instruction list, each instruction with its source span.
+    pub fn get_instructions(&self) -> &[SpannedInstruction] {
+        &self.instructions
+    }
 }
 
+/// An instruction and the span of the source it was lowered from, `None` for the ones
+/// with no source. A pass that replaces an instruction keeps its span.
+pub type SpannedInstruction = (Instruction, Option<Span>);
+
 /// A single instruction in the intermediate representation.
+#[derive(Clone)]
 pub enum Instruction {
     /// A binary operation: `dst = src1 op src2`.
     Binary {
@@ -85,11 +121,28 @@ pub enum Instruction {
     /// Copies a value from one variable to another: `dst = src`.
     Copy { src: Value, dst: Value },
 
-    /// Function call: `dst = name(args...)`.
+    /// Copies one of two values without branching: `dst = cond ? src1 : src2`,
+    /// both values are already computed. Only used for integer and pointer values.
+    Select {
+        cond: Value,
+        src1: Value,
+        src2: Value,
+        dst: Value,
+    },
+
+    /// Function call: `dst = name(args...)`, without a `dst` for a `void` function.
     FunCall {
         name: Identifier,
         args: Vec<Value>,
-        dst: Value,
+        dst: Option<Value>,
+    },
+
+    /// Call through a function pointer: `dst = (*fun_ptr)(args...)`,
+    /// without a `dst` for a `void` function.
+    IndirectCall {
+        fun_ptr: Value,
+        args: Vec<Value>,
+        dst: Option<Value>,
     },
 
     /// Unconditional jump to the given label.
@@ -101,6 +154,17 @@ pub enum Instruction {
     /// Conditional jump if the given value is non-zero.
     JumpIfNotZero(Value, Identifier),
 
+    /// Compares two values of the same type and jumps to the label when the comparison
+    /// `src1 op src2` is `jump_if`, a conditional jump on a comparison that doesn't
+    /// compute its 0 or 1 result.
+    CompareAndJump {
+        op: BinaryOP,
+        src1: Value,
+        src2: Value,
+        jump_if: bool,
+        target: Identifier,
+    },
+
     /// A label marking a jump target.
     Label(Identifier),
 
@@ -113,8 +177,42 @@ pub enum Instruction {
     /// used to cast a long to int
     Truncate { src: Value, dst: Value },
 
-    /// Function return with the given value.
-    Ret(Value),
+    /// used to cast a double to a signed integer, truncating toward zero
+    DoubleToInt { src: Value, dst: Value },
+
+    /// used to cast a double to an unsigned integer, truncating toward zero
+    DoubleToUInt { src: Value, dst: Value },
+
+    /// used to cast a signed integer to a double
+    IntToDouble { src: Value, dst: Value },
+
+    /// used to cast an unsigned integer to a double
+    UIntToDouble { src: Value, dst: Value },
+
+    /// Takes the address of a variable: `dst = &src`.
+    GetAddress { src: Value, dst: Value },
+
+    /// Reads the object a pointer points to: `dst = *src_ptr`.
+    Load { src_ptr: Value, dst: Value },
+
+    /// Writes to the object a pointer points to: `*dst_ptr = src`.
+    Store { src: Value, dst_ptr: Value },
+
+    /// Advances a pointer by `index` elements of `scale` bytes: `dst = ptr + index * scale`,
+    /// the index is a `long`.
+    AddPtr {
+        ptr: Value,
+        index: Value,
+        scale: u64,
+        dst: Value,
+    },
+
+    /// Function return with the given value, or without one in a `void` function.
+    Ret(Option<Value>),
+
+    /// Marks the start of the code generated for a source line.
+    /// Only emitted when debug info is requested.
+    SourceLine(usize),
 }
 
 /// Represents a source or destination operand in an instruction.
@@ -133,7 +231,7 @@ pub enum Value {
 ///
 /// These include arithmetic, logical, and comparison operators.
 /// The semantics are defined according to the source language’s specification.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum BinaryOP {
     // Arithmetic
     Add,
@@ -159,7 +257,7 @@ pub enum BinaryOP {
 ///
 /// These represent single-operand transformations such as negation
 /// or logical inversion.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum UnaryOP {
     /// Bitwise or logical NOT.
     Not,
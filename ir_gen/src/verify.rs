@@ -0,0 +1,259 @@
+// Tacky IR verifier.
+//
+// A developer aid that checks the invariants the backend relies on,
+// so lowering bugs are reported where they happen instead of as broken assembly:
+//   - every jump targets a label defined in the same function,
+//   - no label is defined twice in a function,
+//   - every function ends in a `Ret`,
+//   - every variable operand has a symbol table entry.
+// A violation panics with the offending instruction and the source line it was lowered from.
+
+use std::collections::HashSet;
+
+use shared_context::{Identifier, Span, SymbolTable, symbol_interner::SymbolInterner};
+
+use crate::print_ir::DebuggingPrinter;
+use crate::tacky::{self, Instruction, Value};
+
+/// Verifies a tacky program, panicking on the first broken invariant.
+pub fn verify_tacky(
+    program: &tacky::Program,
+    symbol_table: &SymbolTable,
+    sy_interner: &SymbolInterner,
+) {
+    let verifier = Verifier {
+        symbol_table,
+        printer: DebuggingPrinter::new(sy_interner),
+    };
+
+    for item in program.get_functions() {
+        if let tacky::TopLevel::F(fun_def) = item {
+            verifier.verify_function(fun_def);
+        }
+    }
+}
+
+struct Verifier<'src, 'ctx> {
+    symbol_table: &'ctx SymbolTable,
+    printer: DebuggingPrinter<'src, 'ctx>, // used to show the offending instruction
+}
+
+impl<'src, 'ctx> Verifier<'src, 'ctx> {
+    fn verify_function(&self, fun_def: &tacky::FunctionDef) {
+        let name = self.printer.format_identifier(fun_def.get_name());
+        let instructions = fun_def.get_instructions();
+
+        for param in fun_def.get_params() {
+            if !self.has_entry(*param) {
+                panic!(
+                    "IR verification failed in {}: parameter {} has no symbol table entry",
+                    name,
+                    self.printer.format_identifier(*param)
+                );
+            }
+        }
+
+        // labels are collected first, a jump can target a label defined after it
+        let mut labels = HashSet::new();
+        for (instr, span) in instructions {
+            if matches!(instr, Instruction::Label(label) if !labels.insert(*label)) {
+                self.fail(&name, "label defined twice", instr, *span);
+            }
+        }
+
+        for (instr, span) in instructions {
+            match instr {
+                Instruction::Jump(target)
+                | Instruction::JumpIfZero(_, target)
+                | Instruction::JumpIfNotZero(_, target)
+                | Instruction::CompareAndJump { target, .. }
+                    if !labels.contains(target) =>
+                {
+                    self.fail(&name, "jump target has no matching label", instr, *span)
+                }
+                _ => (),
+            }
+
+            let operands = Self::get_operands(instr);
+            if operands
+                .iter()
+                .any(|val| matches!(val, Value::Var(iden) if !self.has_entry(*iden)))
+            {
+                self.fail(&name, "variable has no symbol table entry", instr, *span);
+            }
+        }
+
+        if !matches!(instructions.last(), Some((Instruction::Ret(_), _))) {
+            panic!(
+                "IR verification failed in {}: function doesn't end in a Ret",
+                name
+            );
+        }
+    }
+
+    fn has_entry(&self, iden: Identifier) -> bool {
+        self.symbol_table.lookup(iden).is_some()
+    }
+
+    /// returns every value an instruction reads or writes
+    fn get_operands(instr: &Instruction) -> Vec<Value> {
+        match instr {
+            Instruction::Binary {
+                src1, src2, dst, ..
+            } => vec![*src1, *src2, *dst],
+            Instruction::Unary { src, dst, .. }
+            | Instruction::Copy { src, dst }
+            | Instruction::SignExtend { src, dst }
+            | Instruction::ZeroExtend { src, dst }
+            | Instruction::Truncate { src, dst }
+            | Instruction::DoubleToInt { src, dst }
+            | Instruction::DoubleToUInt { src, dst }
+            | Instruction::IntToDouble { src, dst }
+            | Instruction::UIntToDouble { src, dst }
+            | Instruction::GetAddress { src, dst }
+            | Instruction::Load { src_ptr: src, dst }
+            | Instruction::Store { src, dst_ptr: dst } => vec![*src, *dst],
+            Instruction::Select {
+                cond,
+                src1,
+                src2,
+                dst,
+            } => vec![*cond, *src1, *src2, *dst],
+            Instruction::AddPtr {
+                ptr, index, dst, ..
+            } => vec![*ptr, *index, *dst],
+            Instruction::FunCall { args, dst, .. } => {
+                let mut operands = args.clone();
+                operands.extend(*dst);
+                operands
+            }
+            Instruction::IndirectCall { fun_ptr, args, dst } => {
+                let mut operands = vec![*fun_ptr];
+                operands.extend(args.iter().copied());
+                operands.extend(*dst);
+                operands
+            }
+            Instruction::JumpIfZero(val, _) | Instruction::JumpIfNotZero(val, _) => vec![*val],
+            Instruction::CompareAndJump { src1, src2, .. } => vec![*src1, *src2],
+            Instruction::Ret(val) => val.iter().copied().collect(),
+            Instruction::Jump(_) | Instruction::Label(_) | Instruction::SourceLine(_) => Vec::new(),
+        }
+    }
+
+    fn fail(&self, function: &str, reason: &str, instr: &Instruction, span: Option<Span>) -> ! {
+        let location = match span {
+            Some(span) => format!(" (lowered from line {})", span.line),
+            None => String::new(),
+        };
+        panic!(
+            "IR verification failed in {}: {}\n    {}{}",
+            function,
+            reason,
+            self.printer.format_instruction(instr),
+            location
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_context::{
+        Bump, Const, Type,
+        symbol_table::{EntryType, IdenAttrs},
+    };
+
+    /// verifies a program of one function `f` with the given body, the names in
+    /// `locals` have symbol table entries
+    fn verify_body(locals: &[&str], body: impl FnOnce(&mut SymbolInterner) -> Vec<Instruction>) {
+        let arena = Bump::new();
+        let mut sy_interner = SymbolInterner::new(&arena);
+        let mut symbol_table = SymbolTable::new();
+        for (id, local) in locals.iter().enumerate() {
+            symbol_table.add(
+                Identifier::new(sy_interner.intern(local), id + 1),
+                EntryType::Scalar(Type::Int),
+                IdenAttrs::LocalAttrs,
+                Span::new(0, 0, 1),
+            );
+        }
+        let name = Identifier::new(sy_interner.intern("f"), 0);
+        let instructions = body(&mut sy_interner);
+        let program = tacky::Program::new(vec![tacky::TopLevel::F(tacky::FunctionDef::new(
+            name,
+            true,
+            Vec::new(),
+            instructions
+                .into_iter()
+                .map(|instr| (instr, None))
+                .collect(),
+        ))]);
+        verify_tacky(&program, &symbol_table, &sy_interner);
+    }
+
+    fn ret_zero() -> Instruction {
+        Instruction::Ret(Some(Value::Constant(Const::ConstInt(0))))
+    }
+
+    #[test]
+    fn well_formed_function_passes() {
+        verify_body(&["x"], |sy| {
+            let (x, label) = (
+                Identifier::new(sy.intern("x"), 1),
+                Identifier::new(sy.intern("end"), 0),
+            );
+            vec![
+                Instruction::Copy {
+                    src: Value::Constant(Const::ConstInt(1)),
+                    dst: Value::Var(x),
+                },
+                Instruction::JumpIfZero(Value::Var(x), label),
+                Instruction::Label(label),
+                Instruction::Ret(Some(Value::Var(x))),
+            ]
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "jump target has no matching label")]
+    fn jump_to_a_missing_label_fails() {
+        verify_body(&[], |sy| {
+            vec![
+                Instruction::Jump(Identifier::new(sy.intern("nowhere"), 0)),
+                ret_zero(),
+            ]
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "label defined twice")]
+    fn duplicate_label_fails() {
+        verify_body(&[], |sy| {
+            let label = Identifier::new(sy.intern("twice"), 0);
+            vec![
+                Instruction::Label(label),
+                Instruction::Label(label),
+                ret_zero(),
+            ]
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "variable has no symbol table entry")]
+    fn unknown_variable_fails() {
+        verify_body(&[], |sy| {
+            vec![Instruction::Ret(Some(Value::Var(Identifier::new(
+                sy.intern("y"),
+                7,
+            ))))]
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "function doesn't end in a Ret")]
+    fn missing_ret_fails() {
+        verify_body(&[], |sy| {
+            vec![Instruction::Label(Identifier::new(sy.intern("end"), 0))]
+        });
+    }
+}
@@ -67,16 +67,30 @@ pub struct Lexer<'a> {
 
 impl<'a> Lexer<'a> {
     /// Creates a new [`Lexer`] for the given source code.
+    ///
+    /// Lines are counted from 1, preprocessed sources also reset the count
+    /// with their line directives.
     pub fn new(source_code: &'a str) -> Self {
+        let extras = token::LexerExtras {
+            line: 1,
+            raw_source: false,
+        };
         Self {
-            lex: Token::lexer(source_code),
+            lex: Token::lexer_with_extras(source_code, extras),
         }
     }
 
+    /// Lexes a source that wasn't preprocessed, any directive other than a line marker
+    /// is a `LineDirective` token instead of being skipped.
+    pub fn with_raw_source(mut self, raw_source: bool) -> Self {
+        self.lex.extras.raw_source = raw_source;
+        self
+    }
+
     /// Retrieves the next token from the input stream, if available.
     ///
     /// This wraps `logos`'s `next()` function, mapping any lexing errors
-    /// to the Token::Error variant.
+    /// to the Token::Error variant, its lexeme is the text that wasn't recognized.
     pub fn next(&mut self) -> Option<SpannedToken<'a>> {
         let token = match self.lex.next()? {
             Ok(tok) => tok,
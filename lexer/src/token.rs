@@ -1,13 +1,14 @@
-use logos::Logos;
-use logos::Skip;
+use logos::{FilterResult, Logos, Skip};
 mod token_impl;
 
 /// Additional state information maintained by the `logos` lexer.
 ///
-/// Currently, this tracks the current line number in the source file.
+/// This tracks the current line number in the source file, and whether the source
+/// went through the preprocessor.
 #[derive(Default, Debug)]
 pub struct LexerExtras {
     pub line: usize,
+    pub raw_source: bool, // not preprocessed, only line markers can be skipped
 }
 
 /// Called when a newline is encountered.
@@ -18,19 +19,32 @@ fn logos_newline(lexer: &mut logos::Lexer<Token>) -> Skip {
     Skip
 }
 
-/// Handles `#line`-style preprocessor directives that preserve the original
-/// source line numbering in preprocessed files.
+/// Called when a block comment is encountered.
 ///
-/// Example directive: `# 42 "source.c"`
-fn logos_line_directive(lexer: &mut logos::Lexer<Token>) -> Skip {
-    let slice = lexer.slice();
-    let parts: Vec<&str> = slice.split_whitespace().collect();
-    if parts.len() >= 2 {
-        if let Ok(line) = parts[1].parse::<usize>() {
+/// Counts the newlines inside it, so the lines after a multi-line comment stay correct.
+fn logos_block_comment(lexer: &mut logos::Lexer<Token>) -> Skip {
+    lexer.extras.line += lexer.slice().matches('\n').count();
+    Skip
+}
+
+/// Handles the preprocessor directives left in a preprocessed file.
+///
+/// Line markers restart the line count, so spans keep the original source line numbers,
+/// both the `# 42 "source.c"` form gcc writes and the `#line 42 "source.c"` form.
+/// Any other directive (e.g. `#pragma once`) is skipped in a preprocessed source, in a
+/// source that wasn't preprocessed it's emitted as a `LineDirective` token the parser rejects.
+fn logos_line_directive(lexer: &mut logos::Lexer<Token>) -> FilterResult<(), ()> {
+    let mut parts = lexer.slice()[1..].split_whitespace().peekable();
+    parts.next_if_eq(&"line");
+    match parts.next().map(str::parse::<usize>) {
+        Some(Ok(line)) => {
+            // the directive's own newline brings the count to `line`
             lexer.extras.line = line.saturating_sub(1);
+            FilterResult::Skip
         }
+        _ if lexer.extras.raw_source => FilterResult::Emit(()),
+        _ => FilterResult::Skip,
     }
-    Skip
 }
 
 /// All possible token kinds recognized by the lexer.
@@ -47,15 +61,19 @@ pub enum Token {
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", priority = 0)]
     Identifier,
 
-    /// Integer constatns
-    #[regex(r"[0-9]+", priority = 0)]
+    /// Integer constatns, decimal or hexadecimal (`0x1F`)
+    /// The suffix is a `u`/`U` and a `l`/`L`/`ll`/`LL` in either order
+    #[regex(r"[0-9]+|0[xX][0-9a-fA-F]+", priority = 0)]
     ConstantInt,
-    #[regex(r"[0-9]+[lL]", priority = 1)]
+    #[regex(r"([0-9]+|0[xX][0-9a-fA-F]+)(l|L|ll|LL)", priority = 1)]
     ConstantLong,
-    #[regex(r"[0-9]+[uU]")]
+    #[regex(r"([0-9]+|0[xX][0-9a-fA-F]+)[uU]")]
     ConstantUint,
-    #[regex(r"[0-9]+([lL][uU]|[uU][lL])")]
+    #[regex(r"([0-9]+|0[xX][0-9a-fA-F]+)([uU](l|L|ll|LL)|(l|L|ll|LL)[uU])")]
     ConstantUlong,
+    /// Floating point constants, e.g. `1.5`, `.5`, `1.`, `15e-1`
+    #[regex(r"([0-9]*\.[0-9]+|[0-9]+\.?)[eE][+-]?[0-9]+|[0-9]*\.[0-9]+|[0-9]+\.")]
+    ConstantDouble,
     //
     // Keywords
     //
@@ -69,8 +87,14 @@ pub enum Token {
     Unsigned,
     #[token("signed")]
     Signed,
+    #[token("double")]
+    Double,
     #[token("void")]
     Void,
+    /// `bool` is the C23 spelling of `_Bool`
+    #[token("_Bool")]
+    #[token("bool")]
+    Bool,
     #[token("else")]
     Else,
     #[token("if")]
@@ -89,6 +113,25 @@ pub enum Token {
     Static,
     #[token("extern")]
     Extern,
+    #[token("register")]
+    Register,
+    #[token("typedef")]
+    Typedef,
+    #[token("const")]
+    Const,
+    #[token("volatile")]
+    Volatile,
+    /// `__restrict` is the spelling GCC keeps in preprocessed system headers
+    #[token("restrict")]
+    #[token("__restrict")]
+    Restrict,
+    /// `__inline` and `__inline__` are the spellings GCC keeps in system headers
+    #[token("inline")]
+    #[token("__inline")]
+    #[token("__inline__")]
+    Inline,
+    #[token("enum")]
+    Enum,
 
     //
     // Operators
@@ -135,6 +178,9 @@ pub enum Token {
     // Bitwise operator
     #[token("~")]
     BitwiseNot,
+    /// `&` only takes the address of its operand so far, `&&` is lexed as `LogicalAnd`
+    #[token("&")]
+    BitwiseAnd,
 
     //
     // Symbols and punctuation
@@ -147,6 +193,10 @@ pub enum Token {
     LeftCurlyBracket,
     #[token("}")]
     RightCurlyBracket,
+    #[token("[")]
+    LeftSquareBracket,
+    #[token("]")]
+    RightSquareBracket,
     #[token(";")]
     Semicolon,
     #[token(":")]
@@ -155,12 +205,15 @@ pub enum Token {
     QuestionMark,
     #[token(",")]
     Comma,
+    #[token("...")]
+    Ellipsis,
 
     //
     // Skipped patterns (whitespace, comments, etc.)
     //
-    /// Preprocessor line directive (`# ...`).
-    #[regex(r"# [^\n]*", logos_line_directive)]
+    /// Preprocessor directive (`# 42 "source.c"`, `#line 42`, `#pragma once`),
+    /// only emitted for a directive other than a line marker in a source that wasn't preprocessed.
+    #[regex(r"#[^\n]*", logos_line_directive)]
     LineDirective,
 
     /// Newlines increment the line counter.
@@ -169,7 +222,7 @@ pub enum Token {
     /// Whitespace and comments are skipped entirely.
     #[regex(r"[ \t\f]+", logos::skip)]
     #[regex(r"//[^\n]*", logos::skip)]
-    #[regex(r"/\*[^*]*\*+([^/*][^*]*\*+)*/", logos::skip)]
+    #[regex(r"/\*[^*]*\*+([^/*][^*]*\*+)*/", logos_block_comment)]
     Skip,
 
     //
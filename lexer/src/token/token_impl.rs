@@ -45,18 +45,38 @@ impl Token {
             self,
             Token::Int
                 | Token::Long
+                | Token::Double
+                | Token::Void
+                | Token::Bool
                 | Token::Static
                 | Token::Extern
                 | Token::Unsigned
                 | Token::Signed
+                | Token::Register
+                | Token::Typedef
+                | Token::Const
+                | Token::Volatile
+                | Token::Restrict
+                | Token::Inline
         )
     }
 
+    /// Returns `true` if this token repressent a type qualifier
+    pub fn is_qualifier(&self) -> bool {
+        matches!(self, Token::Const | Token::Volatile | Token::Restrict)
+    }
+
     /// Returns `true` if this token repressent a type
     pub fn is_type(&self) -> bool {
         matches!(
             self,
-            Token::Int | Token::Long | Token::Unsigned | Token::Signed
+            Token::Int
+                | Token::Long
+                | Token::Unsigned
+                | Token::Signed
+                | Token::Double
+                | Token::Void
+                | Token::Bool
         )
     }
 
@@ -90,6 +110,7 @@ impl fmt::Display for Token {
             Token::ConstantLong => write!(f, "long int contant"),
             Token::ConstantUint => write!(f, "unsigned int contant"),
             Token::ConstantUlong => write!(f, "unsigned long int constant"),
+            Token::ConstantDouble => write!(f, "double constant"),
 
             // Keywords
             Token::Return => write!(f, "return"),
@@ -97,7 +118,9 @@ impl fmt::Display for Token {
             Token::Long => write!(f, "long"),
             Token::Signed => write!(f, "signed"),
             Token::Unsigned => write!(f, "unsigned"),
+            Token::Double => write!(f, "double"),
             Token::Void => write!(f, "void"),
+            Token::Bool => write!(f, "_Bool"),
             Token::Else => write!(f, "else"),
             Token::If => write!(f, "if"),
             Token::While => write!(f, "while"),
@@ -107,6 +130,13 @@ impl fmt::Display for Token {
             Token::Continue => write!(f, "continue"),
             Token::Static => write!(f, "static"),
             Token::Extern => write!(f, "extern"),
+            Token::Register => write!(f, "register"),
+            Token::Typedef => write!(f, "typedef"),
+            Token::Const => write!(f, "const"),
+            Token::Volatile => write!(f, "volatile"),
+            Token::Restrict => write!(f, "restrict"),
+            Token::Inline => write!(f, "inline"),
+            Token::Enum => write!(f, "enum"),
 
             // Operators
             Token::Assignment => write!(f, "="),
@@ -132,16 +162,20 @@ impl fmt::Display for Token {
 
             // Bitwise
             Token::BitwiseNot => write!(f, "~"),
+            Token::BitwiseAnd => write!(f, "&"),
 
             // Symbols
             Token::LeftParenthesis => write!(f, "("),
             Token::RightParenthesis => write!(f, ")"),
             Token::LeftCurlyBracket => write!(f, "{{"),
             Token::RightCurlyBracket => write!(f, "}}"),
+            Token::LeftSquareBracket => write!(f, "["),
+            Token::RightSquareBracket => write!(f, "]"),
             Token::Semicolon => write!(f, ";"),
             Token::Colon => write!(f, ":"),
             Token::QuestionMark => write!(f, "?"),
             Token::Comma => write!(f, ","),
+            Token::Ellipsis => write!(f, "..."),
 
             // Skipped, line directive, or invalid
             Token::Skip => write!(f, "<skip>"),
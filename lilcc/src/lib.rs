@@ -0,0 +1,402 @@
+use codegen::codegen;
+use emitter::{AsmSyntax, Emitter};
+use ir_gen::{cfg::dump_cfg, lower_to_tacky, tacky, verify_tacky};
+use parser::{ParseErr, ast, parse};
+pub use semantic_analysis::SemanticWarning;
+use semantic_analysis::{SemanticErr, analize, print_symbol_table::SymbolTablePrinter};
+use shared_context::{
+    Bump, Interner, OptPasses, SymbolRegistery, SymbolTable, source_map::SourceMap,
+};
+use std::{error::Error, fmt, io};
+
+/// Name the source is referred to by in error messages
+const SOURCE_NAME: &str = "<string>";
+
+/// The stage of compilation an error comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompileStage {
+    Lex,      // a character sequence that isn't a token
+    Parse,    // a syntax error
+    Semantic, // identifier resolution, loop labeling or type checking rejected the program
+    Io,       // reading the source or writing the output failed
+    Assemble, // gcc couldn't assemble the emitted assembly into an object file
+    Link,     // gcc couldn't link the object files, e.g. for an undefined symbol
+}
+
+/// An error from any stage of compiling a source, holding the stage's own error.
+///
+/// Parse and semantic errors display the same formatted message the compiler
+/// driver prints, including the source context.
+#[derive(Debug)]
+pub enum CompileError {
+    Lex(ParseErr),
+    Parse(ParseErr),
+    Semantic(SemanticErr),
+    Io(io::Error),
+    Assemble(String), // gcc's errors
+    Link(String),     // gcc's errors
+}
+
+impl CompileError {
+    /// The stage compilation failed in.
+    pub fn stage(&self) -> CompileStage {
+        match self {
+            CompileError::Lex(_) => CompileStage::Lex,
+            CompileError::Parse(_) => CompileStage::Parse,
+            CompileError::Semantic(_) => CompileStage::Semantic,
+            CompileError::Io(_) => CompileStage::Io,
+            CompileError::Assemble(_) => CompileStage::Assemble,
+            CompileError::Link(_) => CompileStage::Link,
+        }
+    }
+
+    /// The exit code the driver uses for this error, distinct for each stage.
+    pub fn exit_code(&self) -> i32 {
+        match self.stage() {
+            CompileStage::Io => 1,
+            CompileStage::Lex => 2,
+            CompileStage::Parse => 3,
+            CompileStage::Semantic => 4,
+            CompileStage::Assemble => 5,
+            CompileStage::Link => 6,
+        }
+    }
+}
+
+impl From<ParseErr> for CompileError {
+    /// Unknown tokens are only found while parsing, but are lexing errors.
+    fn from(err: ParseErr) -> Self {
+        if err.is_lexical() {
+            CompileError::Lex(err)
+        } else {
+            CompileError::Parse(err)
+        }
+    }
+}
+
+impl From<SemanticErr> for CompileError {
+    fn from(err: SemanticErr) -> Self {
+        CompileError::Semantic(err)
+    }
+}
+
+impl From<io::Error> for CompileError {
+    fn from(err: io::Error) -> Self {
+        CompileError::Io(err)
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Lex(err) | CompileError::Parse(err) => write!(f, "{}", err),
+            CompileError::Semantic(err) => write!(f, "{}", err),
+            CompileError::Io(err) => write!(f, "{}", err),
+            CompileError::Assemble(stderr) => write!(f, "assembling failed:\n{}", stderr),
+            CompileError::Link(stderr) => write!(f, "linking failed:\n{}", stderr),
+        }
+    }
+}
+
+impl Error for CompileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CompileError::Lex(err) | CompileError::Parse(err) => Some(err),
+            CompileError::Semantic(err) => Some(err),
+            CompileError::Io(err) => Some(err),
+            CompileError::Assemble(_) | CompileError::Link(_) => None,
+        }
+    }
+}
+
+/// Options that change how a source file is lowered to IR and assembly.
+///
+/// The default runs no optimization passes and no debugging output, and writes AT&T syntax.
+#[derive(Default)]
+pub struct CompileOptions {
+    pub passes: OptPasses,
+    pub debug_info: bool,           // emit line number debug info
+    pub annotate: bool,             // comment each assembly instruction with its Tacky instruction
+    pub listing: bool,              // write a listing of the assembly interleaved with the source
+    pub verify_ir: bool,            // check the IR invariants before code generation
+    pub dump_cfg: bool,             // print the control-flow graph of each function's IR
+    pub dump_symbols: bool,         // print the symbol registry used by code generation
+    pub stack_report: bool,         // print the stack frame size of each function
+    pub print_symbol_table: bool,   // print the symbol table built by type checking
+    pub warn_sign_conversion: bool, // warn about implicit conversions that can change a value's sign
+    pub keep_going: bool,           // report every semantic error instead of stopping at the first
+    pub stack_protector: bool,      // check a stack canary before each function returns
+    pub no_cpp: bool,               // the source wasn't preprocessed, directives are errors
+    pub syntax: AsmSyntax,
+}
+
+/// Parses a source and runs semantic analysis on it, returning the analyzed AST,
+/// the counter of the variables identifier resolution renamed and the warnings found.
+///
+/// The symbol table is printed after type checking when `print_symbol_table` is set.
+pub fn analyze<'src>(
+    source: &'src str,
+    smap: &SourceMap<'src>,
+    interner: &mut Interner<'src>,
+    sytab: &mut SymbolTable,
+    options: &CompileOptions,
+) -> Result<(ast::Program, usize, Vec<SemanticWarning>), CompileError> {
+    let lexer = lexer::Lexer::new(source).with_raw_source(options.no_cpp);
+    let program_ast = parse(lexer, &mut interner.ty, &mut interner.sy, smap)?;
+
+    let analized = analize(
+        &mut interner.ty,
+        &mut interner.sy,
+        sytab,
+        smap,
+        program_ast,
+        options.warn_sign_conversion,
+        options.keep_going,
+    )?;
+
+    if options.print_symbol_table {
+        SymbolTablePrinter::new(&interner.ty, &interner.sy).print(sytab);
+    }
+
+    Ok(analized)
+}
+
+/// Runs every stage up to the Tacky IR on a source: parsing, semantic analysis,
+/// lowering and the IR optimization passes. The semantic warnings are returned with the IR.
+///
+/// Source line markers are kept in the IR for `-g` and for listings, the IR is checked
+/// with `verify_ir` and its control-flow graph printed with `dump_cfg`.
+pub fn front_end<'src>(
+    source: &'src str,
+    smap: &SourceMap<'src>,
+    interner: &mut Interner<'src>,
+    sytab: &mut SymbolTable,
+    options: &CompileOptions,
+) -> Result<(tacky::Program, Vec<SemanticWarning>), CompileError> {
+    let (analized_program, counter, warnings) = analyze(source, smap, interner, sytab, options)?;
+
+    let program_tacky = lower_to_tacky(
+        analized_program,
+        &interner.ty,
+        &mut interner.sy,
+        sytab,
+        counter,
+        &options.passes,
+        options.debug_info || options.listing,
+    );
+
+    if options.verify_ir {
+        verify_tacky(&program_tacky, sytab, &interner.sy);
+    }
+    if options.dump_cfg {
+        dump_cfg(&program_tacky, &interner.sy);
+    }
+
+    Ok((program_tacky, warnings))
+}
+
+/// Compiles already preprocessed C source to assembly in memory.
+///
+/// Runs every stage the driver runs for `--asm` (lex, parse, semantic analysis,
+/// Tacky generation, code generation and emission) with no optimization passes
+/// and AT&T syntax, and returns the assembly text instead of writing a file.
+pub fn compile_str(source: &str) -> Result<String, CompileError> {
+    compile_str_with(source, &CompileOptions::default()).map(|compiled| compiled.assembly)
+}
+
+/// The output of [`compile_str_with`].
+#[derive(Debug)]
+pub struct Compiled {
+    pub assembly: String,               // the emitted assembly text
+    pub warnings: Vec<SemanticWarning>, // the semantic warnings, in the order they were found
+}
+
+/// Compiles already preprocessed C source to assembly in memory with the given options,
+/// like [`compile_str`]. Line markers and listings are ignored, there's no file to refer to.
+///
+/// The semantic warnings are returned instead of printed, it's up to the caller to report them.
+pub fn compile_str_with(source: &str, options: &CompileOptions) -> Result<Compiled, CompileError> {
+    let arena = Bump::new();
+    let mut interner = Interner::new(&arena);
+    let smap = SourceMap::new(SOURCE_NAME, source);
+    let mut sytab = SymbolTable::new();
+
+    let (program_tacky, warnings) = front_end(source, &smap, &mut interner, &mut sytab, options)?;
+
+    let syreg = SymbolRegistery::build(sytab);
+    let annotate = options.annotate.then_some(&interner.sy);
+    let program_asm = codegen(
+        program_tacky,
+        &interner.ty,
+        &syreg,
+        &options.passes,
+        annotate,
+        options.stack_protector,
+    );
+
+    let mut assembly = Vec::new();
+    Emitter::new(&interner.sy, &syreg)
+        .with_syntax(options.syntax)
+        .write_program_to(program_asm, &mut assembly)?;
+
+    // the emitter only writes ASCII text
+    let assembly = String::from_utf8(assembly)
+        .map_err(|err| CompileError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+
+    Ok(Compiled { assembly, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_error(source: &str) -> CompileError {
+        compile_str(source).expect_err("the source doesn't compile")
+    }
+
+    #[test]
+    fn lexer_error_is_lex() {
+        let err = compile_error("int main(void) { return 1 @ 2; }");
+        assert!(matches!(err, CompileError::Lex(_)));
+        assert_eq!(err.stage(), CompileStage::Lex);
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn unknown_character_is_named_at_its_span() {
+        let message = compile_error("int main(void) {\n    return 2 @ 3;\n}\n").to_string();
+        assert!(message.contains("unexpected character '@'"), "{message}");
+        assert!(message.contains("line 2:14"), "{message}");
+        // the caret is under the `@`
+        assert!(
+            message.contains("\n   2 |     return 2 @ 3;\n     |              ^ "),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn lines_after_a_block_comment_are_counted() {
+        let source = "/* one\n   two\n   three */\nint main(void) { return x; }\n";
+        let message = compile_error(source).to_string();
+        assert!(message.contains("line 4:"), "{message}");
+
+        // and inside a line, with a line comment
+        let source = "int main(void) { /* a\n b */ int y = 1; // c\n\n  return x; }\n";
+        let message = compile_error(source).to_string();
+        assert!(message.contains("line 4:"), "{message}");
+    }
+
+    #[test]
+    fn line_marker_sets_the_next_line_number() {
+        let message =
+            compile_error("int a;\n#line 100\nint main(void) { return x; }\n").to_string();
+        assert!(message.contains("line 100:"), "{message}");
+
+        // the counting goes on from the marker, the preprocessor's form is read too
+        let source = "# 40 \"prog.c\"\nint main(void) {\n  return x;\n}\n";
+        let message = compile_error(source).to_string();
+        assert!(message.contains("line 41:"), "{message}");
+    }
+
+    #[test]
+    fn parse_error_is_parse() {
+        let err = compile_error("int main(void) { return 1 }");
+        assert!(matches!(err, CompileError::Parse(_)));
+        assert_eq!(err.stage(), CompileStage::Parse);
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    #[test]
+    fn semantic_error_is_semantic() {
+        let err = compile_error("int main(void) { return x; }");
+        assert!(matches!(err, CompileError::Semantic(_)));
+        assert_eq!(err.stage(), CompileStage::Semantic);
+        assert_eq!(err.exit_code(), 4);
+    }
+
+    #[test]
+    fn lowered_return_carries_its_line() {
+        let source = "int main(void) {\n    int x = 2;\n    return x + 1;\n}\n";
+        let arena = Bump::new();
+        let mut interner = Interner::new(&arena);
+        let smap = SourceMap::new(SOURCE_NAME, source);
+        let mut sytab = SymbolTable::new();
+        let options = CompileOptions::default();
+        let (program, _) = front_end(source, &smap, &mut interner, &mut sytab, &options)
+            .unwrap_or_else(|err| panic!("the source doesn't compile:\n{err}"));
+
+        let Some(tacky::TopLevel::F(main)) = program.get_functions().first() else {
+            panic!("main isn't lowered to a function");
+        };
+        let returns: Vec<Option<usize>> = main
+            .get_instructions()
+            .iter()
+            .filter(|(instr, _)| matches!(instr, tacky::Instruction::Ret(_)))
+            .map(|(_, span)| span.map(|span| span.line))
+            .collect();
+        assert_eq!(returns, [Some(3)]);
+    }
+
+    #[test]
+    fn empty_return_lowers_to_a_return_of_no_value() {
+        let source = "int g;\nvoid set(int v) { if (v) { g = v; return; } g = 1; return; }\n";
+        let arena = Bump::new();
+        let mut interner = Interner::new(&arena);
+        let smap = SourceMap::new(SOURCE_NAME, source);
+        let mut sytab = SymbolTable::new();
+        let options = CompileOptions::default();
+        let (program, _) = front_end(source, &smap, &mut interner, &mut sytab, &options)
+            .unwrap_or_else(|err| panic!("the source doesn't compile:\n{err}"));
+
+        let Some(tacky::TopLevel::F(set)) = program.get_functions().first() else {
+            panic!("set isn't lowered to a function");
+        };
+        let returns: Vec<&tacky::Instruction> = set
+            .get_instructions()
+            .iter()
+            .map(|(instr, _)| instr)
+            .filter(|instr| matches!(instr, tacky::Instruction::Ret(_)))
+            .collect();
+        assert_eq!(returns.len(), 2);
+        assert!(
+            returns
+                .iter()
+                .all(|ret| matches!(ret, tacky::Instruction::Ret(None)))
+        );
+
+        // nothing is moved into the return register
+        let assembly = compile_str(source).expect("the source compiles");
+        assert!(!assembly.contains("%eax"), "{assembly}");
+    }
+
+    #[test]
+    fn warnings_are_returned_with_the_assembly() {
+        let source = "int main(void) {\n    int x = 1;\n    x = x;\n    return 1 < 2 < 3;\n}\n";
+        let compiled = compile_str_with(source, &CompileOptions::default())
+            .unwrap_or_else(|err| panic!("the source doesn't compile:\n{err}"));
+        let warnings: Vec<String> = compiled.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(
+            warnings[0].contains("a variable is assigned to itself"),
+            "{warnings:?}"
+        );
+        assert!(warnings[0].contains("line 3:"), "{warnings:?}");
+        assert!(warnings[1].contains("line 4:"), "{warnings:?}");
+        assert!(compiled.assembly.contains("main:"), "{}", compiled.assembly);
+
+        // a program without warnings has none
+        let compiled = compile_str_with("int main(void) { return 0; }", &CompileOptions::default())
+            .expect("the source compiles");
+        assert!(compiled.warnings.is_empty(), "{:?}", compiled.warnings);
+    }
+
+    #[test]
+    fn gcc_errors_have_their_own_exit_codes() {
+        let assemble = CompileError::Assemble("bad instruction".to_string());
+        let link = CompileError::Link("undefined reference to `f'".to_string());
+        assert_eq!(assemble.stage(), CompileStage::Assemble);
+        assert_eq!(link.stage(), CompileStage::Link);
+        assert_eq!((assemble.exit_code(), link.exit_code()), (5, 6));
+        assert!(link.to_string().contains("undefined reference"));
+    }
+}
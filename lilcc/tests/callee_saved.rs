@@ -0,0 +1,88 @@
+mod common;
+use common::{assembly_at, run};
+
+/// the registers a group of `push`es or `pop`s after a line saves or restores
+fn registers_after<'a>(
+    lines: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+) -> Vec<&'a str> {
+    let mut registers = Vec::new();
+    while let Some(register) = lines
+        .peek()
+        .and_then(|line| line.strip_prefix("pushq\t").or(line.strip_prefix("popq\t")))
+        .filter(|&register| register != "%rbp")
+    {
+        registers.push(register);
+        lines.next();
+    }
+    registers
+}
+
+/// checks each `ret` pops the registers its function's prologue pushed, in reverse order,
+/// returning the number of registers saved and of `ret`s checked
+fn check_saves_are_balanced(assembly: &str) -> (usize, usize) {
+    let (mut saved, mut rets) = (0, 0);
+    let mut pushed = Vec::new();
+    let mut lines = assembly.lines().map(str::trim).peekable();
+    while let Some(line) = lines.next() {
+        if line == "movq\t%rsp, %rbp" {
+            pushed = registers_after(&mut lines);
+            saved += pushed.len();
+        } else if line.starts_with("leaq\t") && line.ends_with(", %rsp") {
+            let mut popped = registers_after(&mut lines);
+            popped.reverse();
+            assert_eq!(
+                pushed, popped,
+                "the pops don't undo the pushes:\n{assembly}"
+            );
+            rets += 1;
+        }
+    }
+    (saved, rets)
+}
+
+#[test]
+fn callee_saved_registers_are_popped_in_reverse_order() {
+    let source = "int f(int x) {
+        int a = x + 1; int b = a * 2; int c = b - x;
+        if (x > 3)
+            return a + b + c;
+        return a * b * c;
+    }
+    int main(void) { return f(5) == 6 + 12 + 7; }";
+    let assembly = assembly_at(source, 2).expect("the source compiles");
+    let (saved, rets) = check_saves_are_balanced(&assembly);
+    assert!(saved > 0, "no register is saved:\n{assembly}");
+    // f has two `ret`s, each restores what the prologue saved
+    assert!(
+        rets >= 3,
+        "a `ret` doesn't restore the registers:\n{assembly}"
+    );
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn callee_saved_registers_survive_calls() {
+    // both functions keep their variables in `%rbx` and `%r12` at -O2, the caller's
+    // ones still hold their values after the call; the branch keeps it from being inlined
+    let source = "int clobber(int x) {
+        if (x < 0)
+            return 0;
+        int a = x * 3; int b = a + x;
+        return a - b;
+    }
+    int main(void) {
+        int a = 10; int b = 20;
+        int c = clobber(a);
+        return a + b + c;
+    }";
+    let assembly = assembly_at(source, 2).expect("the source compiles");
+    assert!(
+        assembly.contains("call\tclobber"),
+        "the call is inlined:\n{assembly}"
+    );
+    assert!(
+        check_saves_are_balanced(&assembly).0 > 0,
+        "no register is saved:\n{assembly}"
+    );
+    assert_eq!(run(source), 20);
+}
@@ -0,0 +1,73 @@
+mod common;
+use common::{assembly, compile_error, run};
+use lilcc::CompileError;
+
+#[test]
+fn stack_parameters_are_read_at_their_offsets() {
+    let source = "long f(long a, long b, long c, long d, long e, long g, long h, long i) {
+        return i;
+    }
+    long seventh(long a, long b, long c, long d, long e, long g, long h, long i) {
+        return h;
+    }
+    int main(void) {
+        long big = 8l * 4294967296l + 42l;
+        return (f(1, 2, 3, 4, 5, 6, 7, big) == big) + (seventh(1, 2, 3, 4, 5, 6, 7, big) == 7) * 2;
+    }";
+    assert_eq!(run(source), 3);
+    assert_eq!(
+        run(
+            "long f(long a, long b, long c, long d, long e, long g, long h, long i) { return i; }
+        int main(void) { return f(1, 2, 3, 4, 5, 6, 7, 8); }"
+        ),
+        8
+    );
+}
+
+#[test]
+fn int_returned_from_a_long_function_is_extended() {
+    let source = "long f(void) { int x = 5; return x; }
+    long g(int x) { return x; }
+    int main(void) {
+        long a = f();
+        long b = g(-5);
+        return (a == 5l) + (b == -5l) * 2 + (a / 4294967296l == 0l) * 4;
+    }";
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn variadic_call_passes_the_argument_count_check() {
+    // `char *` is stubbed as `long`
+    let source = "int printf(long fmt, ...);
+    int main(void) { int fmt = 0; printf(fmt, 1, 2l, 3.0); return printf(fmt); }";
+    let assembly = assembly(source);
+    let calls: Vec<&str> = assembly.split("\tcall\tprintf@PLT\n").collect();
+    assert_eq!(calls.len(), 3, "{assembly}");
+    // `%al` holds the number of vector registers the arguments are passed in
+    assert!(calls[0].ends_with("\tmovl\t$1, %eax\n"), "{}", calls[0]);
+    assert!(calls[1].ends_with("\tmovl\t$0, %eax\n"), "{}", calls[1]);
+    // the fixed parameter is still converted to its type
+    assert!(calls[0].contains("\tmovslq\t"), "{}", calls[0]);
+
+    let err = compile_error("int printf(long fmt, ...); int main(void) { return printf(); }");
+    assert!(matches!(err, CompileError::Semantic(_)));
+}
+
+#[test]
+fn trailing_comma_in_arguments_is_ignored() {
+    let call = |args| {
+        format!("int f(int a, int b) {{ return a - b; }} int main(void) {{ return f({args}); }}")
+    };
+    assert_eq!(assembly(&call("5, 2,")), assembly(&call("5, 2")));
+    assert_eq!(run(&call("5, 2,")), 3);
+
+    // only one, and only after an argument
+    for args in ["5, 2,,", ","] {
+        let err = compile_error(&call(args));
+        assert!(
+            matches!(err, CompileError::Parse(_)),
+            "f({args}) was accepted"
+        );
+    }
+}
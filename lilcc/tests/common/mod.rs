@@ -0,0 +1,93 @@
+// Helpers shared by the integration tests: compile a source with `compile_str_with`,
+// assemble and link it with gcc and run it.
+
+#![allow(dead_code)] // each test file uses its own subset of the helpers
+
+use lilcc::{CompileError, CompileOptions};
+use shared_context::OptPasses;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// the optimization levels every program is run at
+pub const OPT_LEVELS: [u8; 3] = [0, 1, 2];
+
+/// compile a source at an optimization level, returning the assembly text
+pub fn assembly_at(source: &str, level: u8) -> Result<String, CompileError> {
+    let options = CompileOptions {
+        passes: OptPasses::from_level(level),
+        ..CompileOptions::default()
+    };
+    lilcc::compile_str_with(source, &options).map(|compiled| compiled.assembly)
+}
+
+/// compile a source with no optimization passes, panicking if it doesn't compile
+pub fn assembly(source: &str) -> String {
+    assembly_at(source, 0).unwrap_or_else(|err| panic!("the source doesn't compile:\n{err}"))
+}
+
+/// a fresh path under the system temp dir, unique across the tests of the process
+fn temp_path(extension: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!(
+        "lilcc-test-{}-{}.{}",
+        std::process::id(),
+        n,
+        extension
+    ))
+}
+
+/// assemble and link an assembly text with gcc and run the program, returning its exit code
+pub fn run_assembly(assembly: &str) -> i32 {
+    let (asm_path, exe_path) = (temp_path("s"), temp_path("out"));
+    fs::write(&asm_path, assembly).expect("failed to write the assembly");
+
+    let linked = Command::new("gcc")
+        .arg(&asm_path)
+        .arg("-o")
+        .arg(&exe_path)
+        .output()
+        .expect("failed to run gcc");
+    let _ = fs::remove_file(&asm_path);
+    assert!(
+        linked.status.success(),
+        "gcc rejected the assembly:\n{}\n{assembly}",
+        String::from_utf8_lossy(&linked.stderr)
+    );
+
+    let status = Command::new(&exe_path)
+        .status()
+        .expect("failed to run the program");
+    let _ = fs::remove_file(&exe_path);
+    status
+        .code()
+        .unwrap_or_else(|| panic!("the program was killed by a signal: {status:?}"))
+}
+
+/// compile and run a program at every optimization level, asserting they all exit
+/// with the same code, which is returned
+pub fn run(source: &str) -> i32 {
+    let codes: Vec<i32> = OPT_LEVELS
+        .iter()
+        .map(|&level| {
+            let assembly = assembly_at(source, level)
+                .unwrap_or_else(|err| panic!("the source doesn't compile at -O{level}:\n{err}"));
+            run_assembly(&assembly)
+        })
+        .collect();
+    assert!(
+        codes.iter().all(|&code| code == codes[0]),
+        "the optimization levels disagree: {codes:?}"
+    );
+    codes[0]
+}
+
+/// the semantic or parse error a source is rejected with
+pub fn compile_error(source: &str) -> CompileError {
+    match lilcc::compile_str(source) {
+        Ok(_) => panic!("the source compiled"),
+        Err(err) => err,
+    }
+}
@@ -0,0 +1,74 @@
+mod common;
+use common::{compile_error, run};
+use lilcc::CompileError;
+
+#[test]
+fn array_length_is_a_constant_expression() {
+    let source = "int main(void) {
+        int a[2 + 1];
+        for (int i = 0; i < 3; i = i + 1)
+            a[i] = i * 10;
+        return a[0] + a[1] + a[2];
+    }";
+    assert_eq!(run(source), 30);
+}
+
+#[test]
+fn array_length_can_use_an_enumerator() {
+    let source = "enum { N = 4 };
+    int main(void) {
+        long a[N * 2 - 1];
+        a[0] = 1;
+        a[6] = 6;
+        return a[0] + a[6];
+    }";
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn enumerator_value_can_use_earlier_enumerators() {
+    let source = "enum E { A = 2, B = A * 3, C, D = -(B - 1) };
+    int main(void) {
+        return A + B * 10 + C * 100 + (D == -5) * 1000 == 2 + 60 + 700 + 1000;
+    }";
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn array_length_must_be_constant() {
+    let err = compile_error("int main(void) { int n = 3; int a[n]; return 0; }");
+    assert!(matches!(err, CompileError::Semantic(_)));
+}
+
+#[test]
+fn array_length_must_be_positive() {
+    let err = compile_error("int main(void) { int a[1 - 1]; return 0; }");
+    assert!(matches!(err, CompileError::Semantic(_)));
+}
+
+#[test]
+fn enumerator_value_must_be_constant() {
+    let err = compile_error("int x; enum E { A = x }; int main(void) { return A; }");
+    assert!(matches!(err, CompileError::Semantic(_)));
+    let err = compile_error("enum E { A = 1.5 }; int main(void) { return A; }");
+    assert!(matches!(err, CompileError::Semantic(_)));
+}
+
+#[test]
+fn non_constant_file_scope_initializer_says_why() {
+    let global =
+        compile_error("int f(void); int g = f(); int main(void) { return g; }").to_string();
+    assert!(global.contains("expression is not a constant"), "{global}");
+    assert!(global.contains("evaluated at run time"), "{global}");
+
+    // a local `extern` can't have an initializer at all
+    let local = compile_error("int main(void) { extern int x = 1; return x; }").to_string();
+    assert!(local.contains("invalid initializer"), "{local}");
+    assert_ne!(global.lines().next(), local.lines().next());
+
+    // a local may be initialized with anything
+    assert_eq!(
+        run("int f(void) { return 4; } int main(void) { int g = f(); return g; }"),
+        4
+    );
+}
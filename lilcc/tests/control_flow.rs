@@ -0,0 +1,177 @@
+mod common;
+use common::{OPT_LEVELS, assembly_at, compile_error, run};
+
+#[test]
+fn empty_for_loop_exits_with_break() {
+    assert_eq!(run("int main(void) { for (;;) { break; } return 4; }"), 4);
+
+    let source = "int main(void) {
+        int n = 0;
+        for (;;) {
+            n = n + 1;
+            if (n == 5)
+                break;
+        }
+        return n;
+    }";
+    assert_eq!(run(source), 5);
+}
+
+#[test]
+fn do_while_zero_has_no_conditional_jump() {
+    let source = "int main(void) { int x = 0; do { x = 1; } while (0); return x; }";
+    let jumps = |level| {
+        let assembly = assembly_at(source, level).expect("the source compiles");
+        assembly
+            .lines()
+            .filter(|line| line.starts_with("\tj") && !line.starts_with("\tjmp"))
+            .count()
+    };
+    assert_eq!(jumps(0), 1, "-O0 keeps the loop's jump");
+    assert_eq!(
+        jumps(1),
+        0,
+        "-O1 drops the jump on a constant false condition"
+    );
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn continue_in_a_for_loop_runs_the_post_expression() {
+    let source = "int main(void) {
+        int iterations = 0;
+        int odd = 0;
+        for (int i = 0; i < 10; i = i + 1) {
+            iterations = iterations + 1;
+            if (i % 2 == 0)
+                continue;
+            odd = odd + 1;
+        }
+        return iterations * 10 + odd;
+    }";
+    assert_eq!(run(source), 105);
+}
+
+#[test]
+fn return_from_a_loop_inside_an_if_restores_the_stack() {
+    // called in a loop, a wrong `%rsp` after any of the returns would crash the caller
+    let source = "int find(int limit) {
+        int big[8];
+        big[0] = limit;
+        if (limit > 0) {
+            int i = 0;
+            while (1) {
+                int square = i * i;
+                if (square >= big[0])
+                    return i;
+                i = i + 1;
+            }
+        }
+        return -1;
+    }
+    int main(void) {
+        int total = 0;
+        for (int n = -2; n < 50; n = n + 1)
+            total = total + find(n);
+        return total % 256;
+    }";
+    // the smallest `i` with `i * i >= n` for n in 1..50, and -1 for n in -2..=0
+    let expected: i32 = (1..50)
+        .map(|n: i32| (1..).find(|i| i * i >= n).unwrap())
+        .sum::<i32>()
+        - 3;
+    assert_eq!(run(source), expected % 256);
+}
+
+#[test]
+fn nested_conditionals_are_right_associative() {
+    // `(a ? b : c) ? d : e` would give 3
+    let source = "int main(void) {
+        int a = 1, b = 2, c = 0, d = 3, e = 4;
+        return a ? b : c ? d : e;
+    }";
+    assert_eq!(run(source), 2);
+
+    let source = "int main(void) {
+        int a = 0, b = 2, c = 1, d = 3, e = 4;
+        return a ? b : c ? d : e;
+    }";
+    assert_eq!(run(source), 3);
+
+    // the middle operand is a whole conditional
+    let source = "int main(void) {
+        int a = 1, b = 0, c = 2, d = 3, e = 4;
+        return a ? b ? c : d : e;
+    }";
+    assert_eq!(run(source), 3);
+    assert_eq!(run("int main(void) { return 1 ? 1 ? 2 : 3 : 4; }"), 2);
+    assert_eq!(run("int main(void) { return 0 ? 1 ? 2 : 3 : 4; }"), 4);
+}
+
+#[test]
+fn only_main_returns_zero_when_it_falls_off_the_end() {
+    assert_eq!(run("int main(void) {}"), 0);
+    assert_eq!(run("int main(void) { int x = 3; if (x) x = 4; }"), 0);
+
+    for source in [
+        "int f(void) { int x = 1; } int main(void) { return f(); }",
+        "int f(int x) { if (x) return 1; } int main(void) { return f(1); }",
+    ] {
+        let err = compile_error(source);
+        assert!(
+            err.to_string().contains("missing return statement"),
+            "{err}"
+        );
+    }
+    // returning on every path, or returning nothing, needs no return at the end
+    let source = "void g(void) {}
+    int f(int x) { if (x) return 1; else return 2; }
+    int main(void) { g(); return f(0); }";
+    assert_eq!(run(source), 2);
+}
+
+#[test]
+fn early_return_is_one_conditional_jump_over_the_return() {
+    let source = "int f(int c) { if (c) return 1; return 2; }
+    int main(void) { return f(0) * 3 + f(5); }";
+    let assembly = assembly_at(source, 0).unwrap();
+    let f = &assembly[assembly.find("\nf:\n").unwrap()..assembly.find("\nmain:\n").unwrap()];
+    let jumps: Vec<&str> = f.lines().filter(|line| line.starts_with("\tj")).collect();
+    let labels: Vec<&str> = f
+        .lines()
+        .filter_map(|line| line.strip_suffix(':'))
+        .collect();
+    assert_eq!(jumps.len(), 1, "{f}");
+    // the one label is the jump's target, right after the early return
+    assert_eq!(labels, ["f", jumps[0].rsplit('\t').next().unwrap()], "{f}");
+    assert!(
+        f.contains("\tmovl\t$1, %eax\n\tmovq\t%rbp, %rsp\n\tpopq\t%rbp\n\tret\n.L"),
+        "{f}"
+    );
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn relational_condition_jumps_without_a_setcc() {
+    let source = "int f(int a, int b) { if (a < b) return 1; return 2; }
+    int main(void) {
+        int i = 0; int n = 0;
+        while (i < 3) { n = n + f(i, 2); i = i + 1; }
+        return n;
+    }";
+    for level in OPT_LEVELS {
+        let assembly = assembly_at(source, level).unwrap();
+        assert!(!assembly.contains("\tset"), "-O{level}:\n{assembly}");
+        // the `if` and the loop condition are each a `cmp` and the inverted jump
+        let pairs: Vec<(&str, &str)> = assembly
+            .lines()
+            .zip(assembly.lines().skip(1))
+            .filter(|(line, _)| line.starts_with("\tcmpl\t"))
+            .collect();
+        assert_eq!(pairs.len(), 2, "-O{level}:\n{assembly}");
+        for (_, jump) in pairs {
+            assert!(jump.starts_with("\tjge\t"), "-O{level}: {jump}");
+        }
+    }
+    assert_eq!(run(source), 4);
+}
@@ -0,0 +1,126 @@
+mod common;
+use common::{assembly, run};
+
+#[test]
+fn comparison_result_widens_to_long() {
+    assert_eq!(
+        run("int main(void) { long y = (1 < 2); return (int)y; }"),
+        1
+    );
+
+    // on variables, so the comparisons aren't folded; the upper half is clear
+    let source = "int main(void) {
+        int a = 1; int b = 2;
+        long y = a < b;
+        long z = !a;
+        return (y == 1l) + (z == 0l) * 2 + (y * 4294967296l == 4294967296l) * 4;
+    }";
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn unary_operations_on_unsigned_are_32_bit() {
+    assert_eq!(
+        run("int main(void) { return -(unsigned)1 == 4294967295u; }"),
+        1
+    );
+    assert_eq!(run("int main(void) { return ~0u == 4294967295u; }"), 1);
+
+    // on variables, so the operations aren't folded; widened, the upper half is clear
+    let source = "int main(void) {
+        unsigned one = 1; unsigned zero = 0;
+        unsigned negated = -one;
+        unsigned inverted = ~zero;
+        unsigned long wide = negated;
+        return (negated == 4294967295u) + (inverted == 4294967295u) * 2
+            + (wide == 4294967295ul) * 4 + (-one + one == 0u) * 8;
+    }";
+    assert_eq!(run(source), 15);
+
+    let assembly = assembly("int main(void) { unsigned x = 1; x = -x; x = ~x; return x; }");
+    assert!(assembly.contains("\tnegl\t"), "{assembly}");
+    assert!(assembly.contains("\tnotl\t"), "{assembly}");
+}
+
+#[test]
+fn unary_operations_on_the_low_half_leave_the_upper_half() {
+    // the upper half of `slot` would change with a 64-bit `not` or `neg`
+    let source = "int main(void) {
+        long slot = 0;
+        unsigned *low = (unsigned *)&slot;
+        *low = ~*low;
+        long inverted = slot;
+        *low = 1;
+        *low = -*low;
+        return (inverted == 4294967295l) + (slot == 4294967295l) * 2;
+    }";
+    assert_eq!(run(source), 3);
+}
+
+#[test]
+fn bool_operands_are_promoted_to_int() {
+    // the sum is computed in `int`, not wrapped to `_Bool`
+    let source = "int main(void) { _Bool a = 1; _Bool b = 1; int c = a + b; return c; }";
+    assert_eq!(run(source), 2);
+    assert_eq!(
+        run("int main(void) { _Bool a = 1; int c = -a; return c == -1; }"),
+        1
+    );
+}
+
+#[test]
+fn comparison_into_a_stale_long_slot_is_zero_or_one() {
+    // `dirty` leaves all ones in the stack slots `check` gets next
+    let source = "long dirty(void) {
+        long a = -1; long b = -1; long c = -1; long d = -1;
+        long e = -1; long f = -1; long g = -1; long h = -1;
+        return a + b + c + d + e + f + g + h;
+    }
+    int check(int x, int y) {
+        long lt = x < y; long ge = x >= y; long not = !x; long eq = x == y;
+        return (lt == 1l) + (ge == 0l) * 2 + (not == 0l) * 4 + (eq == 0l) * 8;
+    }
+    int main(void) { dirty(); return check(1, 2); }";
+    assert_eq!(run(source), 15);
+}
+
+#[test]
+fn only_widening_an_int_sign_extends() {
+    let source =
+        "int main(void) { int some_int = -7; long y = (long)some_int; return (int)(y + 10l); }";
+    assert_eq!(assembly(source).matches("movslq").count(), 1);
+    assert_eq!(run(source), 3);
+
+    // casts that don't widen a signed operand
+    let source = "int main(void) {
+        long y = 5; unsigned u = 4;
+        long same = (long)y; int narrow = (int)y; unsigned long zero_extended = (unsigned long)u;
+        return (int)same + narrow + (int)zero_extended;
+    }";
+    assert!(!assembly(source).contains("movslq"));
+    assert_eq!(run(source), 14);
+}
+
+#[test]
+fn redundant_casts_fold_but_narrowing_then_widening_is_kept() {
+    let source = "int main(void) { int x = -3; long y = (long)(long)x; return (int)(y + 10l); }";
+    assert_eq!(assembly(source).matches("movslq").count(), 1);
+    assert_eq!(run(source), 7);
+
+    // truncating to `int` loses the upper half, the extension sees the low half only
+    let source = "int main(void) { long x = 4294967295l; long y = (long)(int)x; return y == -1l; }";
+    assert_eq!(assembly(source).matches("movslq").count(), 1);
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn conversion_to_bool_is_zero_or_one() {
+    assert_eq!(run("int main(void) { _Bool b = 5; return (int)b; }"), 1);
+
+    // from every other type, with set bits only outside the low byte or the low half
+    let source = "int main(void) {
+        _Bool c = 0.5; _Bool d = -256; long l = 4294967296; _Bool e = l; _Bool f = 0;
+        return (int)c + d * 2 + e * 4 + (f == 0) * 8;
+    }";
+    assert_eq!(run(source), 15);
+}
@@ -0,0 +1,240 @@
+mod common;
+use common::{assembly, compile_error, run};
+use lilcc::{CompileError, CompileOptions};
+
+#[test]
+fn static_locals_with_the_same_name_are_distinct_symbols() {
+    let source = "int f(void) { static int count = 1; count = count + 1; return count; }
+    int g(void) { static int count; count = count + 10; return count; }
+    int main(void) { f(); g(); return f() + g(); }";
+    let assembly = assembly(source);
+    let labels: Vec<&str> = assembly
+        .lines()
+        .filter(|line| line.starts_with("count"))
+        .collect();
+    assert_eq!(labels.len(), 2, "expected two `count` symbols:\n{assembly}");
+    assert_ne!(labels[0], labels[1]);
+    assert_eq!(run(source), 3 + 20);
+}
+
+#[test]
+fn redeclaring_a_function_with_other_parameters_is_an_error() {
+    for source in [
+        "int f(int a); int f(int a, int b); int main(void) { return 0; }",
+        "int f(int a); int f(long a); int main(void) { return 0; }",
+        "int f(int a); long f(int a); int main(void) { return 0; }",
+    ] {
+        let err = compile_error(source);
+        assert!(
+            err.to_string().contains("incompatible declartions"),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn redeclaring_a_function_with_the_same_type_is_accepted() {
+    let source = "int f(int a, long b);
+    int f(int x, long y);
+    int main(void) { return f(1, 2); }
+    int f(int a, long b) { return a + b; }";
+    assert_eq!(run(source), 3);
+}
+
+#[test]
+fn extern_declaration_before_or_after_the_definition() {
+    let definition_first = "int g = 5;
+    extern int g;
+    int main(void) { extern int g; return g; }";
+    assert_eq!(run(definition_first), 5);
+
+    let extern_first = "extern int g;
+    int main(void) { return g; }
+    int g = 6;";
+    assert_eq!(run(extern_first), 6);
+}
+
+#[test]
+fn two_definitions_of_a_variable_are_an_error() {
+    let err = compile_error("int g = 5; int g = 6; int main(void) { return g; }");
+    assert!(err.to_string().contains("duplicate declartion"), "{err}");
+}
+
+#[test]
+fn variable_used_before_its_declaration_is_an_error() {
+    let err = compile_error("int main(void) { x = 1; int x; return x; }");
+    assert!(err.to_string().contains("undeclared"), "{err}");
+    // the inner `x` isn't in scope yet, the outer one is
+    assert_eq!(
+        run("int main(void) { int x = 2; { int y = x; int x = 5; return y + x; } }"),
+        7
+    );
+}
+
+#[test]
+fn function_declared_later_at_file_scope_can_be_called() {
+    let source = "int main(void) { return twice(4) + helper(); }
+    int twice(int x) { return 2 * x; }
+    static int helper(void) { return 1; }";
+    assert_eq!(run(source), 9);
+
+    // the call is checked against the later declaration
+    let err = compile_error("int main(void) { return f(1, 2); } int f(int x);");
+    assert!(matches!(err, lilcc::CompileError::Semantic(_)));
+}
+
+#[test]
+fn user_names_never_collide_with_generated_ones() {
+    let source = "int tmp = 5;
+    int label_0(void) { return 1; }
+    int main(void) {
+        int tmp_0 = tmp * 2 + label_0();
+        for (int i = 0; i < 3; i = i + 1) {
+            int tmp = i * 3;
+            tmp_0 = tmp_0 + (tmp > 2 ? tmp : 1);
+        }
+        { int tmp = 100; tmp_0 = tmp_0 + tmp; }
+        return tmp_0 + tmp;
+    }";
+    assert_eq!(run(source), 126);
+
+    // only the user's names are symbols, the generated labels are local `.L` labels
+    let assembly = assembly(source);
+    let symbols: Vec<&str> = assembly
+        .lines()
+        .filter_map(|line| line.strip_suffix(':'))
+        .filter(|label| !label.starts_with(".L"))
+        .collect();
+    assert_eq!(symbols, ["label_0", "main", "tmp"], "{assembly}");
+}
+
+#[test]
+fn assigning_to_a_const_variable_is_an_error() {
+    for source in [
+        "int main(void) { const int x = 1; x = 2; return x; }",
+        "const int g = 1; int main(void) { g = 2; return g; }",
+    ] {
+        let err = compile_error(source);
+        assert!(
+            err.to_string().contains("assignment to a const variable"),
+            "{err}"
+        );
+    }
+    // initializing it is allowed
+    assert_eq!(run("int main(void) { const int y = 3; return y; }"), 3);
+}
+
+#[test]
+fn enumerators_are_int_constants() {
+    assert_eq!(run("int main(void) { enum {A, B=5, C}; return C; }"), 6);
+    assert_eq!(
+        run("enum {A, B=5, C}; int main(void) { return A + B * 2 + C * 4; }"),
+        34
+    );
+
+    let err = compile_error("enum {A}; enum {A}; int main(void) { return 0; }");
+    assert!(err.to_string().contains("duplicate declartion"), "{err}");
+    let err = compile_error("enum {A}; int main(void) { A = 2; return A; }");
+    assert!(err.to_string().contains("invalid left-hand side"), "{err}");
+}
+
+#[test]
+fn defining_a_function_twice_is_an_error() {
+    let err = compile_error(
+        "int f(void) { return 1; } int f(void) { return 1; } int main(void) { return f(); }",
+    );
+    let message = err.to_string();
+    assert!(message.contains("duplicate declartion"), "{message}");
+    assert!(
+        message.contains("first declaration:") && message.contains("second declaration:"),
+        "{message}"
+    );
+
+    // prototypes before and after the one definition are fine
+    let source = "int f(void); int f(void) { return 1; } int f(void);
+    int main(void) { return f(); }";
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn volatile_and_restrict_are_accepted_and_ignored() {
+    let source = "volatile int x = 1;
+    int main(void) { volatile long y = 2; int * restrict p = &x; return x + (int)y + *p; }";
+    assert_eq!(run(source), 4);
+    let source = "int f(int * restrict a, volatile int b) { return *a + b; }
+    int main(void) { int v = 3; return f(&v, 4); }";
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn one_declaration_can_declare_several_variables() {
+    assert_eq!(run("int main(void) { int a=1, b=2; return a+b; }"), 3);
+
+    // each declarator has its own pointer level and initializer, at both scopes
+    let source = "long g = 4, h;
+    int main(void) {
+        int a = 1, *q = &a, c;
+        c = 5;
+        h = 2;
+        return a + *q + c + (int)(g + h);
+    }";
+    assert_eq!(run(source), 13);
+}
+
+#[test]
+fn typedef_names_alias_their_type() {
+    assert_eq!(
+        run("int main(void) { typedef long big; big x = 5; return (int)x; }"),
+        5
+    );
+    // the alias has the full width and signedness of its type
+    let source = "typedef long big;
+    typedef unsigned int u;
+    typedef u v;
+    v f(v a) { return a - 1; }
+    int main(void) { big y = 4294967296; return (y == 4294967296l) + (f(0) == 4294967295u) * 2; }";
+    assert_eq!(run(source), 3);
+}
+
+#[test]
+fn keep_going_reports_every_undeclared_variable() {
+    let source = "int main(void) {\n    int a = x;\n    return a + y;\n}\n";
+    let options = CompileOptions {
+        keep_going: true,
+        ..CompileOptions::default()
+    };
+    let message = match lilcc::compile_str_with(source, &options) {
+        Err(err @ CompileError::Semantic(_)) => err.to_string(),
+        other => panic!("the source wasn't rejected by semantic analysis: {other:?}"),
+    };
+    assert_eq!(
+        message.matches("use of undeclared identifier").count(),
+        2,
+        "{message}"
+    );
+    assert!(
+        message.contains("line 2:13") && message.contains("line 3:16"),
+        "{message}"
+    );
+
+    // by default only the first is
+    let message = compile_error(source).to_string();
+    assert_eq!(
+        message.matches("use of undeclared identifier").count(),
+        1,
+        "{message}"
+    );
+}
+
+#[test]
+fn inline_specifier_is_accepted_and_ignored() {
+    let source = "inline int f(void){return 1;} int main(void) { return f(); }";
+    assert_eq!(run(source), 1);
+    // the function is still defined as usual
+    assert!(assembly(source).contains("\t.globl f\n"));
+
+    let source = "static __inline int g(int x) { return x * 2; }
+    int inline h(void);
+    int main(void) { return g(3); }";
+    assert_eq!(run(source), 6);
+}
@@ -0,0 +1,33 @@
+mod common;
+use common::run;
+
+#[test]
+fn int_min_divided_by_minus_one_wraps() {
+    let source = "int main(void) {
+        int m = -2147483647 - 1;
+        return (m / -1 == m) + (m % -1 == 0) * 2;
+    }";
+    assert_eq!(run(source), 3);
+}
+
+#[test]
+fn long_min_divided_by_minus_one_wraps() {
+    // the int `-1` is converted to a long divisor
+    let source = "int main(void) {
+        long m = -9223372036854775807l - 1;
+        long q = m / -1;
+        long r = m % -1;
+        return (q == m) + (r == 0) * 2 + (m / -1l == m) * 4 + (m % -1l == 0) * 8;
+    }";
+    assert_eq!(run(source), 15);
+}
+
+#[test]
+fn unsigned_division_by_converted_minus_one() {
+    // `-1` converted to unsigned is the largest value, not a negation
+    let source = "int main(void) {
+        unsigned int x = 7;
+        return (x / -1) + (x % -1) + 4294967295u / -1;
+    }";
+    assert_eq!(run(source), 8);
+}
@@ -0,0 +1,49 @@
+mod common;
+use common::{assembly, run};
+
+#[test]
+fn double_cast_to_int() {
+    assert_eq!(
+        run("int main(void) { double x = 1.5; return (int)(x + x); }"),
+        3
+    );
+}
+
+/// a NaN compares unordered: every comparison with it is false, except `!=`
+#[test]
+fn comparisons_with_nan_are_false() {
+    let source = "int main(void) {
+        double zero = 0.0;
+        double y = zero / zero;
+        double one = 1.0;
+        return (y == y) + (y != y) * 2 + (y < one) * 4 + (y <= one) * 8
+            + (y > one) * 16 + (y >= one) * 32 + (one < y) * 64;
+    }";
+    assert_eq!(run(source), 2);
+}
+
+#[test]
+fn conditions_on_nan() {
+    // a NaN is non-zero, so it's true, and the comparisons jumped on are false
+    let source = "int main(void) {
+        double zero = 0.0;
+        double y = zero / zero;
+        int r = 0;
+        if (y) r = r + 1;
+        if (!y) r = r + 2;
+        if (y == y) r = r + 4;
+        if (y != y) r = r + 8;
+        if (y < 1.0) r = r + 16;
+        if (!(y <= 1.0)) r = r + 32;
+        while (y >= 1.0) { r = 100; break; }
+        return r + (y ? 64 : 0);
+    }";
+    assert_eq!(run(source), 1 + 8 + 32 + 64);
+}
+
+#[test]
+fn double_equality_checks_parity() {
+    let asm = assembly("int eq(double a, double b) { return a == b; }");
+    assert!(asm.contains("comisd"));
+    assert!(asm.contains("setp"), "{asm}");
+}
@@ -0,0 +1,316 @@
+mod common;
+use common::{OPT_LEVELS, assembly, assembly_at, run, run_assembly};
+use emitter::AsmSyntax;
+use lilcc::CompileOptions;
+use shared_context::OptPasses;
+
+#[test]
+fn address_of_a_local_is_a_leaq_of_its_slot() {
+    let source = "int main(void) { int x = 4; int *p = &x; *p = *p + 1; return x; }";
+    let assembly = assembly(source);
+    // the slot `x` is initialized in
+    let slot = assembly
+        .lines()
+        .find_map(|line| line.strip_prefix("\tmovl\t$4, "))
+        .unwrap_or_else(|| panic!("`x` isn't stored in a slot:\n{assembly}"));
+    assert!(slot.ends_with("(%rbp)"), "{slot} isn't a frame slot");
+    assert!(
+        assembly.contains(&format!("\tleaq\t{slot}, ")),
+        "`&x` isn't a leaq of {slot}:\n{assembly}"
+    );
+    assert_eq!(run(source), 5);
+}
+
+#[test]
+fn static_function_is_local_and_called_directly() {
+    let source = "static int helper(int x) { if (x) return 2; return 1; }
+    int other(void);
+    int main(void) { return helper(3); }
+    int call_other(void) { return other(); }";
+    let assembly = assembly(source);
+    assert!(!assembly.contains(".globl\thelper") && !assembly.contains(".globl helper"));
+    assert!(assembly.contains(".globl"), "main is global:\n{assembly}");
+    assert!(
+        assembly.contains("\tcall\thelper\n"),
+        "helper isn't called directly:\n{assembly}"
+    );
+    // a function defined elsewhere is called through the PLT
+    assert!(assembly.contains("\tcall\tother@PLT\n"), "{assembly}");
+    assert_eq!(
+        run("static int helper(int x) { if (x) return 2; return 1; }
+        int main(void) { return helper(3); }"),
+        2
+    );
+}
+
+#[test]
+fn global_updated_from_itself_has_no_memory_to_memory_instruction() {
+    let source = "int g = 41;
+    int main(void) { g = g + 1; g = g; return g; }";
+    for level in OPT_LEVELS {
+        let assembly = assembly_at(source, level).expect("the source compiles");
+        let both_in_memory = assembly
+            .lines()
+            .find(|line| line.matches("(%rip)").count() > 1);
+        assert!(
+            both_in_memory.is_none(),
+            "{both_in_memory:?} has two memory operands at -O{level}:\n{assembly}"
+        );
+    }
+    assert_eq!(run(source), 42);
+}
+
+#[test]
+fn first_stack_slot_is_right_below_the_base_pointer() {
+    let source = "int main(void) { long x = 5; return (int)x; }";
+    let assembly = assembly(source);
+    assert!(
+        assembly.contains("\tmovq\t$5, -8(%rbp)\n"),
+        "`x` isn't in the slot at -8(%rbp):\n{assembly}"
+    );
+    assert_eq!(run(source), 5);
+
+    // a stack parameter is read above the return address
+    let source = "long last(long a, long b, long c, long d, long e, long f, long g) { return g; }
+    int main(void) { return (int)last(1, 2, 3, 4, 5, 6, 7); }";
+    assert!(
+        assembly_at(source, 0)
+            .expect("the source compiles")
+            .contains("\t16(%rbp), ")
+    );
+    assert_eq!(run(source), 7);
+}
+
+#[test]
+fn intel_syntax_output_assembles() {
+    let source = "long twice(long x) { return x * 2; }
+    int main(void) {
+        long a = 20; int b = 1; double d = 2.5;
+        if (a > b) a = twice(a) + (long)d;
+        return (int)a;
+    }";
+    for level in OPT_LEVELS {
+        let options = CompileOptions {
+            passes: OptPasses::from_level(level),
+            syntax: AsmSyntax::Intel,
+            ..CompileOptions::default()
+        };
+        let intel = lilcc::compile_str_with(source, &options)
+            .expect("the source compiles")
+            .assembly;
+        assert!(intel.starts_with("\t.intel_syntax noprefix\n"), "{intel}");
+        assert!(intel.contains("\tmov\trbp, rsp\n"), "{intel}");
+        assert!(!intel.contains('%'), "a register has a prefix:\n{intel}");
+        assert_eq!(run_assembly(&intel), 42, "-O{level}");
+    }
+    // memory operands are bracketed
+    let intel = lilcc::compile_str_with(
+        "int main(void) { long x = 5; return (int)x; }",
+        &CompileOptions {
+            syntax: AsmSyntax::Intel,
+            ..CompileOptions::default()
+        },
+    )
+    .unwrap()
+    .assembly;
+    assert!(intel.contains("\tmov\tQWORD PTR [rbp-8], 5\n"), "{intel}");
+}
+
+#[test]
+fn annotations_name_the_tacky_instruction() {
+    let annotated = |source| {
+        let options = CompileOptions {
+            annotate: true,
+            ..CompileOptions::default()
+        };
+        lilcc::compile_str_with(source, &options)
+            .expect("the source compiles")
+            .assembly
+    };
+    let assembly = annotated("int main(void) { return 2; }");
+    assert!(
+        assembly.contains("\tmovl\t$2, %eax   # Ret(ConstInt(2))\n")
+            && assembly.contains("\tret   # Ret(ConstInt(2))\n"),
+        "{assembly}"
+    );
+    // the prologue isn't generated from an instruction
+    assert!(assembly.contains("\tpushq\t%rbp\n"), "{assembly}");
+    assert_eq!(run_assembly(&assembly), 2);
+
+    let assembly = annotated("int main(void) { int x = 3; return x; }");
+    assert!(
+        assembly.contains("\tmovl\t$3, -4(%rbp)   # Copy(src: ConstInt(3), dst: x.1)\n"),
+        "{assembly}"
+    );
+    assert!(
+        !assembly_at("int main(void) { return 2; }", 0)
+            .unwrap()
+            .contains('#')
+    );
+}
+
+/// the section and alignment directives above a static variable's label and the data below it
+fn static_directives<'a>(assembly: &'a str, name: &str) -> [&'a str; 3] {
+    let lines: Vec<&str> = assembly.lines().collect();
+    let label = lines
+        .iter()
+        .position(|line| line.strip_suffix(':') == Some(name))
+        .unwrap_or_else(|| panic!("{name} isn't defined:\n{assembly}"));
+    [lines[label - 2], lines[label - 1], lines[label + 1]]
+}
+
+#[test]
+fn statics_are_aligned_to_their_size() {
+    let source = "long big = 5; int small = 3; double d = 1.5;
+    int main(void) { static long counter = 2; return small; }";
+    let assembly = assembly(source);
+    assert_eq!(
+        static_directives(&assembly, "big"),
+        ["\t.data", "\t.align 8", "\t.quad 5"]
+    );
+    assert_eq!(
+        static_directives(&assembly, "small"),
+        ["\t.data", "\t.align 4", "\t.long 3"]
+    );
+    assert_eq!(static_directives(&assembly, "d")[1], "\t.align 8");
+    assert_eq!(static_directives(&assembly, "counter.1")[1], "\t.align 8");
+}
+
+#[test]
+fn zero_initialized_statics_are_in_bss() {
+    let source = "int g; int h = 5; long z = 0; static double d;
+    int main(void) { static int calls; g = h; return g + calls + (int)z; }";
+    let assembly = assembly(source);
+    assert_eq!(
+        static_directives(&assembly, "g"),
+        ["\t.bss", "\t.align 4", "\t.zero 4"]
+    );
+    assert_eq!(
+        static_directives(&assembly, "h"),
+        ["\t.data", "\t.align 4", "\t.long 5"]
+    );
+    assert_eq!(
+        static_directives(&assembly, "z"),
+        ["\t.bss", "\t.align 8", "\t.zero 8"]
+    );
+    assert_eq!(static_directives(&assembly, "d")[0], "\t.bss");
+    assert_eq!(static_directives(&assembly, "calls.1")[0], "\t.bss");
+    assert_eq!(run(source), 5);
+}
+
+#[test]
+fn negative_static_initializers_have_their_bit_pattern() {
+    let source = "int g = -1; long h = -1; unsigned int u = -1; unsigned long ul = -1;
+    long small = -2147483649;
+    int main(void) {
+        return (g == -1) + (h == -1l) * 2 + (u == 4294967295u) * 4 + (ul + 1ul == 0ul) * 8
+            + (small + 2147483648l == -1l) * 16;
+    }";
+    let assembly = assembly(source);
+    assert_eq!(static_directives(&assembly, "g")[2], "\t.long 4294967295");
+    assert_eq!(static_directives(&assembly, "u")[2], "\t.long 4294967295");
+    for name in ["h", "ul"] {
+        assert_eq!(
+            static_directives(&assembly, name)[2],
+            "\t.quad 18446744073709551615"
+        );
+    }
+    assert_eq!(
+        static_directives(&assembly, "small")[2],
+        "\t.quad 18446744071562067967"
+    );
+    assert_eq!(run(source), 31);
+}
+
+#[test]
+fn large_immediate_is_loaded_with_movabsq() {
+    let source = "long f(long a) { return a + 0x100000000; }
+    int main(void) {
+        long x = 0x100000000;
+        long y = f(1);
+        return (int)((x + y) / 0x100000000);
+    }";
+    for level in OPT_LEVELS {
+        let assembly = assembly_at(source, level).unwrap();
+        let loads: Vec<&str> = assembly
+            .lines()
+            .filter(|line| line.contains("$4294967296"))
+            .collect();
+        assert!(!loads.is_empty(), "{assembly}");
+        // only into a register, memory goes through one first
+        for load in loads {
+            assert!(
+                load.starts_with("\tmovabsq\t$4294967296, %"),
+                "-O{level}: {load}"
+            );
+        }
+    }
+    assert_eq!(run(source), 2);
+}
+
+#[test]
+fn stack_protector_stores_and_checks_a_canary() {
+    let source = "int twice(int x) { if (x > 5) return x; return x * 2; }
+    int main(void) { int x = 3; return twice(x); }";
+    let options = CompileOptions {
+        stack_protector: true,
+        ..CompileOptions::default()
+    };
+    let assembly = lilcc::compile_str_with(source, &options)
+        .expect("the source compiles")
+        .assembly;
+    let load = "\tmovq\t%fs:40, %r11\n\tmovq\t%r11, -8(%rbp)\n";
+    let check = "\tmovq\t-8(%rbp), %r11\n\tsubq\t%fs:40, %r11\n\tje\t1f\n\tcall\t__stack_chk_fail@PLT\n1:\n\tmovq\t%rbp, %rsp\n\tpopq\t%rbp\n\tret\n";
+    // one load per function, one check per return
+    assert_eq!(assembly.matches(load).count(), 2, "{assembly}");
+    assert_eq!(assembly.matches(check).count(), 3, "{assembly}");
+    assert_eq!(assembly.matches("\tret\n").count(), 3, "{assembly}");
+    assert_eq!(run_assembly(&assembly), 6);
+
+    assert!(!assembly_at(source, 0).unwrap().contains("%fs:40"));
+}
+
+#[test]
+fn int_constant_copied_to_a_long_is_a_quadword_move() {
+    // `dirty` leaves all ones in the slot `x` gets
+    let source = "long dirty(void) { long a = -1; long b = -1; return a + b; }
+    long f(void) { return 5; }
+    long check(void) { long x = 5; x = 7; return x; }
+    int main(void) { dirty(); return (int)(check() + f()); }";
+    let assembly = assembly(source);
+    for mov in [
+        "\tmovq\t$5, %rax\n",
+        "\tmovq\t$5, -8(%rbp)\n",
+        "\tmovq\t$7, -8(%rbp)\n",
+    ] {
+        assert!(assembly.contains(mov), "{mov:?} is missing:\n{assembly}");
+    }
+    assert!(!assembly.contains("\tmovl\t$5, ") && !assembly.contains("\tmovl\t$7, "));
+    assert_eq!(run(source), 12);
+}
+
+#[test]
+fn constant_on_the_left_of_a_comparison_is_swapped() {
+    let source = "int gt(int x) { return 5 > x; }
+    int main(void) { int x = 3; if (5 > x) return gt(7) + gt(2) * 2; return 9; }";
+    let assembly = assembly(source);
+    // `5 > x` is `x < 5`, with the immediate as the source
+    assert_eq!(
+        assembly.matches("\tcmpl\t$5, -4(%rbp)\n").count(),
+        2,
+        "{assembly}"
+    );
+    assert!(
+        assembly.contains("\tcmpl\t$5, -4(%rbp)\n\tmovl\t$0, -8(%rbp)\n\tsetl\t-8(%rbp)\n"),
+        "{assembly}"
+    );
+    assert!(
+        assembly.contains("\tcmpl\t$5, -4(%rbp)\n\tjge\t"),
+        "{assembly}"
+    );
+    assert!(
+        !assembly.contains("%r11d\n\tcmpl"),
+        "the immediate is loaded:\n{assembly}"
+    );
+    assert_eq!(run(source), 2);
+}
@@ -0,0 +1,243 @@
+mod common;
+use common::{assembly_at, run};
+use lilcc::CompileOptions;
+use shared_context::OptPasses;
+
+fn assembly_at_level(source: &str, level: u8) -> String {
+    assembly_at(source, level).unwrap_or_else(|err| panic!("the source doesn't compile:\n{err}"))
+}
+
+#[test]
+fn constant_folding_runs_at_o1() {
+    let source = "int main(void) { return 2 + 3; }";
+    let (o0, o1) = (assembly_at_level(source, 0), assembly_at_level(source, 1));
+    assert_ne!(o0, o1);
+    assert!(o0.contains("addl"), "-O0 computes the sum:\n{o0}");
+    assert!(!o1.contains("addl"), "-O1 folds the sum:\n{o1}");
+    assert!(o1.contains("$5"), "-O1 returns the folded constant:\n{o1}");
+    assert_eq!(run(source), 5);
+}
+
+#[test]
+fn linear_scan_keeps_variables_in_registers_at_o2() {
+    let source = "int main(void) {
+        int a = 3;
+        int b = a * 4;
+        return a + b;
+    }";
+    let (o1, o2) = (assembly_at_level(source, 1), assembly_at_level(source, 2));
+    assert!(!o1.contains("%rbx"), "-O1 only uses stack slots:\n{o1}");
+    assert!(
+        o2.contains("%ebx"),
+        "-O2 keeps a variable in a register:\n{o2}"
+    );
+    assert!(!o2.contains("(%rbp)\n"), "-O2 needs no stack slot:\n{o2}");
+    assert_eq!(run(source), 15);
+}
+
+#[test]
+fn linear_scan_keeps_loop_variables_live_across_iterations() {
+    // more variables than registers, some are spilled to the stack
+    let source = "int add(int x, int y) { return x + y; }
+    int main(void) {
+        int a = 1; int b = 2; int c = 3; int d = 4; int e = 5; int f = 6; int g = 7;
+        int total = 0;
+        for (int i = 0; i < 5; i = i + 1) {
+            total = add(total, a + b + c + d + e + f + g);
+            a = a + 1;
+            g = g - 1;
+        }
+        return total;
+    }";
+    assert_eq!(run(source), 140);
+}
+
+#[test]
+fn linear_scan_leaves_address_taken_variables_in_memory() {
+    let source = "int main(void) {
+        int x = 1;
+        int *p = &x;
+        *p = 9;
+        return x;
+    }";
+    let o2 = assembly_at_level(source, 2);
+    assert!(
+        o2.contains("leaq\t-"),
+        "`&x` is the address of a stack slot:\n{o2}"
+    );
+    assert_eq!(run(source), 9);
+}
+
+#[test]
+fn peephole_matches_across_annotations() {
+    // the `continue` ends the body with a jump to the post-expression right after it
+    let source = "int main(void) {
+        int steps = 0;
+        for (int i = 0; i < 3; i = i + 1) {
+            steps = steps + 1;
+            continue;
+        }
+        return steps;
+    }";
+    let compile = |peephole, annotate| {
+        let options = CompileOptions {
+            passes: OptPasses {
+                peephole,
+                ..OptPasses::default()
+            },
+            annotate,
+            ..CompileOptions::default()
+        };
+        lilcc::compile_str_with(source, &options)
+            .expect("the source compiles")
+            .assembly
+    };
+    let jumps = |assembly: &str| assembly.matches("\tjmp\t").count();
+
+    let plain = compile(true, false);
+    assert!(
+        jumps(&plain) < jumps(&compile(false, false)),
+        "the peephole pass removes the jump to the next label:\n{plain}"
+    );
+
+    // the annotated output is the same code with a comment on each line
+    let annotated = compile(true, true);
+    let stripped: Vec<&str> = annotated
+        .lines()
+        .map(|line| line.split("   # ").next().unwrap_or(line))
+        .collect();
+    assert_eq!(stripped, plain.lines().collect::<Vec<_>>());
+    assert_eq!(run(source), 3);
+}
+
+#[test]
+fn small_function_is_inlined_at_o2() {
+    let source = "int add(int a, int b) { return a + b; }
+    int main(void) { int x = 3; return add(x, 4); }";
+    let (o1, o2) = (assembly_at_level(source, 1), assembly_at_level(source, 2));
+    assert!(o1.contains("\tcall\tadd"), "-O1 doesn't inline:\n{o1}");
+    assert!(
+        !o2.contains("\tcall\tadd"),
+        "the call to add isn't inlined:\n{o2}"
+    );
+    // the function is still emitted, other files can call it
+    assert!(o2.contains("\nadd:\n"), "{o2}");
+    assert_eq!(run(source), 7);
+
+    // a recursive function is never inlined, even with a single block
+    let source = "int forever(int x) { return forever(x); }
+    int main(void) { return 0; }";
+    assert!(assembly_at_level(source, 2).contains("\tcall\tforever"));
+}
+
+#[test]
+fn multiplication_by_a_power_of_two_is_a_shift() {
+    let source = "int main(void) { int x = 3; return x * 16; }";
+    let (o0, o1) = (assembly_at_level(source, 0), assembly_at_level(source, 1));
+    assert!(o0.contains("\timull\t"), "-O0 multiplies:\n{o0}");
+    assert!(o1.contains("\tshll\t$4, "), "x * 16 isn't a shift:\n{o1}");
+    assert!(!o1.contains("imul"), "{o1}");
+    assert_eq!(run(source), 48);
+
+    // `16 * x` and a long operand too
+    let source = "int main(void) { long y = 5; int x = 2; return (int)(y * 4) + 16 * x; }";
+    let o1 = assembly_at_level(source, 1);
+    assert!(
+        o1.contains("\tshlq\t$2, ") && o1.contains("\tshll\t$4, "),
+        "{o1}"
+    );
+    assert!(!o1.contains("imul"), "{o1}");
+    assert_eq!(run(source), 52);
+
+    // a negative or non power of two multiplier keeps its multiplication
+    for (multiplier, expected) in [("-8", 76), ("6", 118), ("1", 103)] {
+        let source = format!("int main(void) {{ int x = 3; return x * {multiplier} + 100; }}");
+        let o1 = assembly_at_level(&source, 1);
+        assert!(
+            o1.contains("imul") && !o1.contains("\tshl"),
+            "x * {multiplier}:\n{o1}"
+        );
+        assert_eq!(run(&source), expected, "x * {multiplier}");
+    }
+}
+
+#[test]
+fn constant_operand_of_a_logical_operator_removes_the_branch() {
+    let source = "int calls = 0;
+    int side_effecting(void) { calls = calls + 1; return 1; }
+    int main(void) {
+        int a = 0 && side_effecting();
+        int b = 1 || side_effecting();
+        int c = 1 && side_effecting();
+        int d = 0 || side_effecting();
+        return a + b * 2 + c * 4 + d * 8 + calls * 16;
+    }";
+    let calls = |level| {
+        assembly_at_level(source, level)
+            .matches("\tcall\tside_effecting")
+            .count()
+    };
+    assert_eq!(calls(0), 4);
+    // only the right operands of `1 &&` and `0 ||` are evaluated
+    assert_eq!(calls(1), 2);
+    assert_eq!(run(source), 46);
+
+    let o1 = assembly_at_level("int f(void); int main(void) { return 0 && f(); }", 1);
+    assert!(!o1.contains("call"), "{o1}");
+}
+
+#[test]
+fn comparison_with_zero_in_a_register_is_a_test() {
+    let source = "int f(int x) { return !x; }
+    long g(long y) { if (y) return 1; return 2; }
+    int main(void) { return f(0) + g(5) * 2; }";
+    let o2 = assembly_at_level(source, 2);
+    assert!(
+        o2.contains("\ttestl\t%ebx, %ebx\n"),
+        "`!x` isn't a test:\n{o2}"
+    );
+    assert!(
+        o2.contains("\ttestq\t%rbx, %rbx\n"),
+        "`if (y)` isn't a test:\n{o2}"
+    );
+    assert!(
+        !o2.contains("\tcmpl\t$0, ") && !o2.contains("\tcmpq\t$0, "),
+        "{o2}"
+    );
+
+    // a value on the stack is compared with an immediate, `test` can't take two memory operands
+    let o0 = assembly_at_level(source, 0);
+    assert!(
+        o0.contains("\tcmpl\t$0, ") && !o0.contains("\ttest"),
+        "{o0}"
+    );
+    assert_eq!(run(source), 3);
+}
+
+/// the lines of a function's assembly, from its label to its first `ret`
+fn function_body<'a>(assembly: &'a str, name: &str) -> &'a str {
+    let start = assembly
+        .find(&format!("\n{name}:\n"))
+        .unwrap_or_else(|| panic!("{name} isn't defined:\n{assembly}"));
+    let end = start
+        + assembly[start..]
+            .find("\tret\n")
+            .expect("the function returns");
+    &assembly[start..end]
+}
+
+#[test]
+fn simple_conditional_is_a_cmov_at_o2() {
+    let source = "int pick(int x) { return x ? 1 : 2; }
+    long pickl(long x, long a, long b) { return x < 3 ? a : b; }
+    int main(void) { return pick(0) * 10 + pick(7) + (int)(pickl(1, 4, 5) * 2 + pickl(9, 4, 5)); }";
+    let o2 = assembly_at_level(source, 2);
+    for (name, cmov) in [("pick", "\tcmovnel\t"), ("pickl", "\tcmovneq\t")] {
+        let body = function_body(&o2, name);
+        assert!(body.contains(cmov), "{name} has no {cmov:?}:\n{body}");
+        assert!(!body.contains("\tj"), "{name} still branches:\n{body}");
+    }
+    let o1 = assembly_at_level(source, 1);
+    assert!(!o1.contains("cmov"), "-O1 branches:\n{o1}");
+    assert_eq!(run(source), 34);
+}
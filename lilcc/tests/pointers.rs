@@ -0,0 +1,78 @@
+mod common;
+use common::{assembly, compile_error, run};
+use lilcc::CompileError;
+
+#[test]
+fn dereferencing_the_address_of_a_local_reads_it() {
+    assert_eq!(
+        run("int main(void) { int x = 5; int *p = &x; return *p; }"),
+        5
+    );
+}
+
+#[test]
+fn assignment_through_a_pointer_stores_to_the_object() {
+    let source = "long g = 1;
+    int main(void) {
+        int x = 5;
+        int *p = &x;
+        long *q = &g;
+        *p = 7;
+        *q = *q + 40;
+        return x + (int)g;
+    }";
+    assert_eq!(run(source), 48);
+
+    // a pointer to a pointer
+    let source =
+        "int main(void) { int x = 1; int *p = &x; int **pp = &p; **pp = 3; return *p + x; }";
+    assert_eq!(run(source), 6);
+}
+
+#[test]
+fn address_of_and_dereference_need_the_right_operand() {
+    for source in [
+        "int main(void) { int x = 5; return *x; }",
+        "int main(void) { int x = 5; int *p = &(x + 1); return 0; }",
+    ] {
+        let err = compile_error(source);
+        assert!(matches!(err, CompileError::Semantic(_)), "{err}");
+    }
+}
+
+#[test]
+fn calling_through_a_function_pointer_matches_a_direct_call() {
+    let source = "int twice(int x) { return x * 2; }
+    int apply(int (*f)(int), int v) { return f(v); }
+    int main(void) {
+        int (*fp)(int) = &twice;
+        int (*gp)(int) = twice;
+        return (fp(5) == twice(5)) + (gp(3) == 6) * 2 + apply(twice, 4) * 4;
+    }";
+    assert_eq!(run(source), 35);
+    let assembly = assembly(source);
+    assert!(
+        assembly.contains("\tcall\t*%"),
+        "no indirect call:\n{assembly}"
+    );
+    assert!(assembly.contains("\tcall\ttwice\n"), "{assembly}");
+}
+
+#[test]
+fn local_array_elements_are_subscripted() {
+    assert_eq!(
+        run("int main(void) { int a[3]; a[0]=1; a[1]=2; return a[0]+a[1]; }"),
+        3
+    );
+
+    // long elements are 8 bytes apart, the neighbouring locals are untouched
+    let source = "int main(void) {
+        int before = 7;
+        long a[4];
+        int after = 9;
+        for (int i = 0; i < 4; i = i + 1)
+            a[i] = i * 4294967296l + i;
+        return (int)(a[3] / 4294967296l) + (int)a[2] + before + after;
+    }";
+    assert_eq!(run(source), 21);
+}
@@ -28,6 +28,11 @@ impl Block {
         Self { items, span }
     }
 
+    /// Returns a shared reference to the items of the block.
+    pub fn get_items_ref(&self) -> &[BlockItem] {
+        &self.items
+    }
+
     /// Deconstructs the block into its items and span.
     pub fn into_parts(self) -> (Vec<BlockItem>, Span) {
         (self.items, self.span)
@@ -36,10 +41,11 @@ impl Block {
 
 /// Represents a declaration within a block or at the global level.
 ///
-/// Can be either a variable declaration or a function declaration.
+/// Can be a variable declaration, a function declaration or an enum declaration.
 pub enum Declaration {
     VarDecl(VariableDecl),
     FunDecl(FunctionDecl),
+    EnumDecl(EnumDecl),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,7 +62,7 @@ pub enum StorageClass {
 pub struct FunctionDecl {
     name: SpannedIdentifier,
     type_id: TypeID,
-    params: Vec<SpannedIdentifier>,
+    params: Vec<Option<SpannedIdentifier>>, // a prototype can leave its parameters unnamed
     body: Option<Block>,
     storage: StorageClass,
     span: Span,
@@ -67,7 +73,7 @@ impl FunctionDecl {
     pub fn new(
         name: SpannedIdentifier,
         type_id: TypeID,
-        params: Vec<SpannedIdentifier>,
+        params: Vec<Option<SpannedIdentifier>>,
         body: Option<Block>,
         storage: StorageClass,
         span: Span,
@@ -96,13 +102,27 @@ impl FunctionDecl {
         self.storage
     }
 
+    pub fn get_sp_identifier(&self) -> SpannedIdentifier {
+        self.name
+    }
+
+    /// Returns the id of the function's type in the type interner.
+    pub fn get_type_id(&self) -> TypeID {
+        self.type_id
+    }
+
+    /// Returns a shared reference to the body, if the function has one.
+    pub fn get_body_ref(&self) -> Option<&Block> {
+        self.body.as_ref()
+    }
+
     /// Deconstructs the function declaration into its components.
     pub fn into_parts(
         self,
     ) -> (
         SpannedIdentifier,
         TypeID,
-        Vec<SpannedIdentifier>,
+        Vec<Option<SpannedIdentifier>>,
         Option<Block>,
         StorageClass,
         Span,
@@ -127,6 +147,8 @@ pub struct VariableDecl {
     init: Option<Expression>,
     storage: StorageClass,
     span: Span,
+    is_const: bool,                     // declared with the `const` qualifier
+    array_len: Option<Box<Expression>>, // the length of an array of `var_type` (`int a[3]`)
 }
 
 impl VariableDecl {
@@ -144,13 +166,43 @@ impl VariableDecl {
             init,
             storage,
             span,
+            is_const: false,
+            array_len: None,
         }
     }
 
+    /// Records whether the variable was declared with the `const` qualifier.
+    pub fn with_const(mut self, is_const: bool) -> Self {
+        self.is_const = is_const;
+        self
+    }
+
+    /// Records the variable is an array, its type is the element type and `array_len`
+    /// is the constant expression giving its number of elements.
+    pub fn with_array_len(mut self, array_len: Option<Expression>) -> Self {
+        self.array_len = array_len.map(Box::new);
+        self
+    }
+
+    /// return the expression of the number of elements if the variable is an array
+    pub fn get_array_len_ref(&self) -> Option<&Expression> {
+        self.array_len.as_deref()
+    }
+
+    /// Removes the length expression, so it can be resolved and put back with [`Self::with_array_len`].
+    pub fn take_array_len(&mut self) -> Option<Expression> {
+        self.array_len.take().map(|len| *len)
+    }
+
     pub fn get_storage_class(&self) -> StorageClass {
         self.storage
     }
 
+    /// return whether the variable was declared `const`
+    pub fn is_const(&self) -> bool {
+        self.is_const
+    }
+
     /// return spanned identifier
     pub fn get_sp_identifier(&self) -> SpannedIdentifier {
         self.name
@@ -174,6 +226,37 @@ impl VariableDecl {
     }
 }
 
+/// Represents an enum declaration, `enum tag { A, B = 5, C };`.
+///
+/// The tag is dropped by the parser, only the enumerators and their optional value
+/// expressions are kept. Each enumerator is an `int` constant, the type checker evaluates
+/// the values, there are no variables of an enum type.
+pub struct EnumDecl {
+    enumerators: Vec<(SpannedIdentifier, Option<Expression>)>,
+    span: Span,
+}
+
+impl EnumDecl {
+    /// Creates a new [`EnumDecl`].
+    pub fn new(enumerators: Vec<(SpannedIdentifier, Option<Expression>)>, span: Span) -> Self {
+        Self { enumerators, span }
+    }
+
+    /// Returns a shared reference to the enumerators and their value expressions.
+    pub fn get_enumerators_ref(&self) -> &[(SpannedIdentifier, Option<Expression>)] {
+        &self.enumerators
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    /// Deconstructs the enum declaration into its enumerators and span.
+    pub fn into_parts(self) -> (Vec<(SpannedIdentifier, Option<Expression>)>, Span) {
+        (self.enumerators, self.span)
+    }
+}
+
 /// Represents a single statement node in the AST.
 ///
 /// The statement carries its variant and its associated Span.
@@ -184,8 +267,8 @@ pub struct Statement {
 
 /// Enumerates the various types of statements supported by the language.
 pub enum StatementType {
-    /// A `return` statement with an expression.
-    Return(Expression),
+    /// A `return` statement, without an expression in a `void` function (`return;`).
+    Return(Option<Expression>),
 
     /// A standalone expression as a statement (e.g. `x++;`).
     ExprStatement(Expression),
@@ -235,9 +318,10 @@ pub enum StatementType {
 
 /// Represents the initialization clause of a `for` loop.
 ///
-/// It can either be a declaration (`int i = 0;`) or an optional expression.
+/// It can either be a declaration (`int i = 0, j = 10;`), one `VariableDecl` for each
+/// declarator, or an optional expression.
 pub enum ForInit {
-    D(VariableDecl),
+    D(Vec<VariableDecl>),
     E(Option<Expression>),
 }
 
@@ -247,6 +331,11 @@ impl Statement {
         Self { stmt, span }
     }
 
+    /// Returns a shared reference to the statement's variant.
+    pub fn get_type_ref(&self) -> &StatementType {
+        &self.stmt
+    }
+
     /// Deconstructs the statement into its variant and span.
     pub fn into_parts(self) -> (StatementType, Span) {
         (self.stmt, self.span)
@@ -269,6 +358,11 @@ impl Program {
         Self { declarations }
     }
 
+    /// Returns a shared reference to the program's declarations.
+    pub fn get_declarations_ref(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
     /// Deconstructs the program into its function list.
     pub fn into_parts(self) -> Vec<Declaration> {
         self.declarations
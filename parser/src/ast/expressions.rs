@@ -11,6 +11,7 @@ pub struct Expression {
     inner: InnerExpression,
     expr_type: Type,
     span: Span,
+    parenthesized: bool, // written inside `()`, the span doesn't include them
 }
 
 /// Enumerates all supported expression variants.
@@ -60,7 +61,27 @@ pub enum InnerExpression {
     /// A function call expression, e.g. `foo(x, y)`.
     FunctionCall {
         name: SpannedIdentifier,
-        args: Vec<Box<Expression>>,
+        args: Vec<Expression>,
+    },
+
+    /// A call through a function pointer, e.g. `(*fp)(x)`, or `fp(x)` once type checking
+    /// found `fp` is a variable.
+    IndirectCall {
+        callee: Box<Expression>,
+        args: Vec<Expression>,
+    },
+
+    /// A pointer dereference (`*p`), it designates the object `p` points to.
+    Dereference(Box<Expression>),
+
+    /// Taking the address of an lvalue (`&x`).
+    AddrOf(Box<Expression>),
+
+    /// A subscript (`a[i]`), it designates the element `index` elements past
+    /// the one `array` points to.
+    Subscript {
+        array: Box<Expression>,
+        index: Box<Expression>,
     },
 }
 
@@ -71,9 +92,21 @@ impl Expression {
             inner,
             expr_type,
             span,
+            parenthesized: false,
         }
     }
 
+    /// Records whether the expression was written inside parentheses.
+    pub fn with_parentheses(mut self, parenthesized: bool) -> Self {
+        self.parenthesized = parenthesized;
+        self
+    }
+
+    /// return whether the expression was written inside parentheses
+    pub fn is_parenthesized(&self) -> bool {
+        self.parenthesized
+    }
+
     pub fn set_type(&mut self, new_type: Type) {
         self.expr_type = new_type
     }
@@ -91,6 +124,17 @@ impl Expression {
         self.span
     }
 
+    /// Whether the expression designates an object, which can be assigned to
+    /// and have its address taken: a variable, a dereferenced pointer or a subscript.
+    pub fn is_lvalue(&self) -> bool {
+        matches!(
+            self.inner,
+            InnerExpression::Var(_)
+                | InnerExpression::Dereference(_)
+                | InnerExpression::Subscript { .. }
+        )
+    }
+
     /// Deconstructs the expression into its variant and span.
     pub fn into_parts(self) -> (InnerExpression, Type, Span) {
         (self.inner, self.expr_type, self.span)
@@ -146,4 +190,16 @@ impl BinaryOP {
             Self::Add | Self::Sub | Self::Mul | Self::Div | Self::Mod
         )
     }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Equal
+                | Self::NotEqual
+                | Self::LessThan
+                | Self::GreaterThan
+                | Self::LessThanOrEq
+                | Self::GreaterThanOrEq
+        )
+    }
 }
@@ -1,9 +1,7 @@
 use lexer::{SpannedToken, token::Token};
-use parse_err::ParseErr;
 use shared_context::{
     Span, source_map::SourceMap, symbol_interner::SymbolInterner, type_interner::TypeInterner,
 };
-use std::error::Error;
 
 use crate::ast::*;
 
@@ -11,14 +9,25 @@ mod parse_declarations;
 mod parse_err;
 mod parse_expressions;
 mod parse_statement;
+mod parse_typedefs;
 
 pub mod ast;
 pub mod print_ast;
+pub mod print_json_ast;
+
+pub use parse_err::ParseErr;
+
+/// Default limit of nesting levels in the tree being parsed: nested `parse_expression`,
+/// `parse_factor` and `parse_statement` calls, the operators of a chain like `1 + 2 + 3`
+/// and the `*`s of a declarator. A parenthesized expression takes two levels.
+pub const MAX_NESTING_DEPTH: usize = 1000;
 
 /// Top-level entry point for parsing a program.
 ///
 /// Consumes the lexer and takes a mutable ref to compiler context, producing a parsed Program AST node
-/// or an error if the input is invalid.
+/// or an error if the input is invalid. Any input gives one or the other, malformed or truncated
+/// input is an error and nesting past `MAX_NESTING_DEPTH` is rejected before it can overflow
+/// the stack, here or in the later passes that recurse on the tree.
 ///
 /// The CompilerContext provides access to source
 /// maps and the string interner.
@@ -27,10 +36,9 @@ pub fn parse<'src, 'ctx>(
     ty_interner: &'ctx mut TypeInterner<'src>,
     interner: &'ctx mut SymbolInterner<'src>,
     source_map: &'ctx SourceMap<'src>,
-) -> Result<Program, Box<dyn Error>> {
+) -> Result<Program, ParseErr> {
     let mut parser = Parser::new(lexer, ty_interner, interner, source_map)?;
-    let program = parser.parse_program()?;
-    Ok(program)
+    parser.parse_program()
 }
 
 /// The main parser structure.
@@ -52,6 +60,15 @@ pub struct Parser<'src, 'ctx> {
     first_peeked_token: Option<SpannedToken<'src>>,
     second_peeked_token: Option<SpannedToken<'src>>,
     third_peeked_token: Option<SpannedToken<'src>>,
+
+    /// How deeply the tree being parsed is nested, and the limit past which
+    /// parsing stops with an error instead of overflowing the stack.
+    depth: usize,
+    max_depth: usize,
+
+    /// The names declared in each enclosing scope that tell typedef names apart
+    /// from other identifiers, the file scope first.
+    typedef_scopes: Vec<parse_typedefs::TypedefScope>,
 }
 
 impl<'src, 'ctx> Parser<'src, 'ctx> {
@@ -71,9 +88,48 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             first_peeked_token: None,
             second_peeked_token: None,
             third_peeked_token: None,
+            depth: 0,
+            max_depth: MAX_NESTING_DEPTH,
+            typedef_scopes: vec![parse_typedefs::TypedefScope::new()],
         })
     }
 
+    /// Sets how deeply the tree can nest, `MAX_NESTING_DEPTH` by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Runs `parse` one level of nesting deeper.
+    ///
+    /// Returns an error on the next token once `max_depth` levels are entered,
+    /// recursing on pathologically nested input would overflow the stack.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseErr>,
+    ) -> Result<T, ParseErr> {
+        let depth = self.depth;
+        let result = self.enter_nesting().and_then(|()| parse(self));
+        self.depth = depth;
+        result
+    }
+
+    /// Enters one level of nesting without leaving it, the caller restores `depth`.
+    ///
+    /// For nesting built in a loop instead of by recursion, like the left operand of each
+    /// operator in `1 + 2 + 3` or each `*` of `int ***p`, which later passes recurse on.
+    fn enter_nesting(&mut self) -> Result<(), ParseErr> {
+        if self.depth >= self.max_depth {
+            return Err(ParseErr::new(
+                "nesting too deep",
+                self.peek()?.get_span(),
+                self.source_map,
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
     /// Advances to the next token and returns it.
     ///
     /// If a peeked token exists, it is consumed first; otherwise, the lexer
@@ -85,7 +141,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 self.current_token = token;
                 self.first_peeked_token = self.second_peeked_token.take();
                 self.second_peeked_token = self.third_peeked_token.take();
-                Ok(token)
+                self.check_lexed(token)
             }
             None => {
                 // No lookahead available, fetch from lexer
@@ -97,7 +153,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                     )
                 })?;
                 self.current_token = token;
-                Ok(token)
+                self.check_lexed(token)
             }
         }
     }
@@ -105,17 +161,18 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     /// Returns the next token without consuming it.
     fn peek(&mut self) -> Result<SpannedToken<'src>, ParseErr> {
         match self.first_peeked_token {
-            Some(token) => Ok(token),
+            Some(token) => self.check_lexed(token),
             None => {
                 // Fetch a new token from the lexer and cache it
                 self.first_peeked_token = self.lexer.next();
-                Ok(self.first_peeked_token.ok_or_else(|| {
+                let token = self.first_peeked_token.ok_or_else(|| {
                     ParseErr::new(
                         "end of input",
                         self.current_token.get_span(),
                         &self.source_map,
                     )
-                })?)
+                })?;
+                self.check_lexed(token)
             }
         }
     }
@@ -128,16 +185,17 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         }
 
         match self.second_peeked_token {
-            Some(token) => Ok(token),
+            Some(token) => self.check_lexed(token),
             None => {
                 self.second_peeked_token = self.lexer.next();
-                Ok(self.second_peeked_token.ok_or_else(|| {
+                let token = self.second_peeked_token.ok_or_else(|| {
                     ParseErr::new(
                         "end of input (peek_two)",
                         self.current_token.get_span(),
                         &self.source_map,
                     )
-                })?)
+                })?;
+                self.check_lexed(token)
             }
         }
     }
@@ -154,20 +212,31 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         }
 
         match self.third_peeked_token {
-            Some(token) => Ok(token),
+            Some(token) => self.check_lexed(token),
             None => {
                 self.third_peeked_token = self.lexer.next();
-                Ok(self.third_peeked_token.ok_or_else(|| {
+                let token = self.third_peeked_token.ok_or_else(|| {
                     ParseErr::new(
                         "end of input (peek_three)",
                         self.current_token.get_span(),
                         &self.source_map,
                     )
-                })?)
+                })?;
+                self.check_lexed(token)
             }
         }
     }
 
+    /// Reports a token the lexer couldn't recognize, or a directive left in a source that
+    /// wasn't preprocessed, as a lexical error.
+    fn check_lexed(&self, token: SpannedToken<'src>) -> Result<SpannedToken<'src>, ParseErr> {
+        match token.get_token() {
+            Token::Error => Err(ParseErr::unknown_token(&token, self.source_map)),
+            Token::LineDirective => Err(ParseErr::unsupported_directive(&token, self.source_map)),
+            _ => Ok(token),
+        }
+    }
+
     /// Ensures the next token matches the expected kind.
     ///
     /// If it matches, the token is consumed. Otherwise, a descriptive
@@ -184,12 +253,17 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
     /// Parses the root of the program.
     ///
-    /// A program is a list of function declarations. This method loops
-    /// until EOF, repeatedly parsing top-level functions.
+    /// A program is a list of function, variable and enum declarations. This method loops
+    /// until EOF, repeatedly parsing top-level declarations. The list can be empty, an empty
+    /// file or one with only declarations (`extern int x;`) compiles to an object with no code.
     pub fn parse_program(&mut self) -> Result<Program, ParseErr> {
         let mut declarations = Vec::new();
-        while self.peek().is_ok() {
-            declarations.push(self.parse_declaration()?);
+        loop {
+            match self.peek() {
+                Ok(_) => declarations.extend(self.parse_declaration()?),
+                Err(err) if err.is_lexical() => return Err(err),
+                Err(_) => break, // end of input
+            }
         }
         Ok(Program::new(declarations))
     }
@@ -200,12 +274,13 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
         self.expect_token(Token::LeftCurlyBracket)?;
 
+        self.enter_scope();
         let mut block_items = Vec::new();
         while self.peek()?.get_token() != Token::RightCurlyBracket {
-            let block_item = self.parse_block_item()?;
-            block_items.push(block_item);
+            block_items.extend(self.parse_block_item()?);
         }
         self.advance()?; // consume '}'
+        self.leave_scope();
 
         let end = self.current_token.get_span().end;
         let span = Span::new(start, end, line);
@@ -214,11 +289,214 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     }
 
     /// Parses a block item, which may be either a declaration or a statement.
-    fn parse_block_item(&mut self) -> Result<BlockItem, ParseErr> {
-        if self.peek()?.get_token().is_specifier() {
-            Ok(BlockItem::D(self.parse_declaration()?))
+    /// A declaration with several declarators is one item for each of them.
+    fn parse_block_item(&mut self) -> Result<Vec<BlockItem>, ParseErr> {
+        let token = self.peek()?;
+        if self.starts_declaration(token) || token.get_token() == Token::Enum {
+            let declarations = self.parse_declaration()?;
+            Ok(declarations.into_iter().map(BlockItem::D).collect())
         } else {
-            Ok(BlockItem::S(self.parse_statement()?))
+            Ok(vec![BlockItem::S(self.parse_statement()?)])
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_context::{Bump, Const};
+
+    /// programs exercising every kind of declaration, statement and expression
+    const CORPUS: &[&str] = &[
+        "int main(void) { return 2 + 3 * (4 - 1); }",
+        "static long g = 5; extern int h; int f(int a, long *b, ...);",
+        "int main(void) { int x = 1, *p = &x; if (x) x = *p ? x : -x; else return ~x; }",
+        "int main(void) { for (int i = 0; i < 3; i = i + 1) { continue; } while (1) break; do ; while (0); }",
+        "typedef unsigned int u; enum e { A, B = 2, }; u f(u x) { return (u)x << A; }",
+        "double d(double x) { int a[4]; a[0] = 1; return x / 2.5e1 + a[0]; }",
+        "int (*fp)(int); int g(void) { return fp(1, 2,) && !fp || fp != 0; }",
+    ];
+
+    /// parses a source, panicking only if the parser does, returns whether it parsed
+    fn parse_source(source: &str) -> bool {
+        let arena = Bump::new();
+        let mut ty_interner = TypeInterner::new(&arena);
+        let mut sy_interner = SymbolInterner::new(&arena);
+        let smap = SourceMap::new("test.c", source);
+        parse(
+            lexer::Lexer::new(source),
+            &mut ty_interner,
+            &mut sy_interner,
+            &smap,
+        )
+        .is_ok()
+    }
+
+    /// maps each variable declared at file scope by a source, the source must parse
+    fn map_var_decls<T>(source: &str, f: impl Fn(VariableDecl) -> T) -> Vec<T> {
+        let arena = Bump::new();
+        let mut ty_interner = TypeInterner::new(&arena);
+        let mut sy_interner = SymbolInterner::new(&arena);
+        let smap = SourceMap::new("test.c", source);
+        let program = parse(
+            lexer::Lexer::new(source),
+            &mut ty_interner,
+            &mut sy_interner,
+            &smap,
+        )
+        .unwrap_or_else(|err| panic!("the source doesn't parse:\n{err}"));
+        program
+            .into_parts()
+            .into_iter()
+            .filter_map(|decl| match decl {
+                Declaration::VarDecl(var_decl) => Some(f(var_decl)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// the types of the variables a source declares at file scope
+    fn declared_types(source: &str) -> Vec<shared_context::Type> {
+        map_var_decls(source, |var_decl| var_decl.into_parts().1)
+    }
+
+    /// the type and value of each file scope variable's constant initializer
+    fn initializer_constants(source: &str) -> Vec<(shared_context::Type, Const)> {
+        map_var_decls(source, |var_decl| {
+            match var_decl.into_parts().2.map(Expression::into_parts) {
+                Some((InnerExpression::Constant(constant), ty, _)) => (ty, constant),
+                _ => panic!("the initializer isn't a constant"),
+            }
+        })
+    }
+
+    #[test]
+    fn integer_suffixes_in_any_order_and_case() {
+        use shared_context::Type;
+        let source = "unsigned a = 42u; unsigned b = 42U; unsigned long c = 42lu;
+            unsigned long d = 42ul; unsigned long e = 42LLU; unsigned long f = 0x1FFuLL;
+            long g = 42LL; long h = 42l;";
+        let constants = initializer_constants(source);
+        assert_eq!(constants.len(), 8);
+        for (ty, constant) in &constants[..2] {
+            assert_eq!(*ty, Type::Uint);
+            assert!(matches!(constant, Const::ConstUint(42)), "{constant:?}");
+        }
+        for (ty, constant) in &constants[2..5] {
+            assert_eq!(*ty, Type::Ulong);
+            assert!(matches!(constant, Const::ConstUlong(42)), "{constant:?}");
+        }
+        assert!(matches!(
+            constants[5],
+            (Type::Ulong, Const::ConstUlong(0x1FF))
+        ));
+        for (ty, constant) in &constants[6..] {
+            assert_eq!(*ty, Type::Long);
+            assert!(matches!(constant, Const::ConstLong(42)), "{constant:?}");
+        }
+
+        // an unsigned constant too large for `unsigned int` is `unsigned long`
+        assert!(matches!(
+            initializer_constants("unsigned long x = 4294967296u;")[0],
+            (Type::Ulong, Const::ConstUlong(4294967296))
+        ));
+    }
+
+    #[test]
+    fn spelled_out_integer_types() {
+        use shared_context::Type;
+        assert_eq!(declared_types("unsigned long z = 5;"), vec![Type::Ulong]);
+        let source =
+            "unsigned int a; long unsigned int b; signed long c; int signed d; unsigned e;";
+        assert_eq!(
+            declared_types(source),
+            vec![Type::Uint, Type::Ulong, Type::Long, Type::Int, Type::Uint]
+        );
+    }
+
+    #[test]
+    fn long_long_is_long() {
+        use shared_context::Type;
+        assert_eq!(declared_types("long long x = 5;"), vec![Type::Long]);
+        assert_eq!(
+            declared_types("unsigned long long y = 6;"),
+            vec![Type::Ulong]
+        );
+        let source = "long long int a; int long long b; long unsigned long c; signed long long d;";
+        assert_eq!(
+            declared_types(source),
+            vec![Type::Long, Type::Long, Type::Ulong, Type::Long]
+        );
+    }
+
+    #[test]
+    fn prototype_parameters_can_be_unnamed() {
+        assert!(parse_source(
+            "int f(int, long); int f(int a, long b) { return a; }"
+        ));
+        assert!(parse_source("int g(int *, long, ...);"));
+        // a definition uses its parameters, they must be named
+        assert!(!parse_source("int f(int, long) { return 1; }"));
+        assert!(!parse_source("int f(int a, long) { return a; }"));
+    }
+
+    #[test]
+    fn conflicting_specifiers_are_an_error() {
+        for source in [
+            "signed unsigned x;",
+            "int int x;",
+            "unsigned double x;",
+            "long long long x;",
+            "long double long x;",
+        ] {
+            assert!(!parse_source(source), "`{source}` was accepted");
+        }
+    }
+
+    #[test]
+    fn truncated_input_never_panics() {
+        for source in CORPUS {
+            for end in 0..=source.len() {
+                parse_source(&source[..end]);
+            }
+        }
+    }
+
+    #[test]
+    fn input_with_a_dropped_byte_never_panics() {
+        for source in CORPUS {
+            for index in 0..source.len() {
+                let mutated = format!("{}{}", &source[..index], &source[index + 1..]);
+                parse_source(&mutated);
+            }
+        }
+    }
+
+    /// parses `return` of a constant in `depth` parentheses with a nesting limit
+    fn parse_nested(depth: usize, max_depth: usize) -> Result<Program, ParseErr> {
+        let source = format!(
+            "int main(void) {{ return {}1{}; }}",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        );
+        let arena = Bump::new();
+        let mut ty_interner = TypeInterner::new(&arena);
+        let mut sy_interner = SymbolInterner::new(&arena);
+        let smap = SourceMap::new("test.c", &source);
+        Parser::new(
+            lexer::Lexer::new(&source),
+            &mut ty_interner,
+            &mut sy_interner,
+            &smap,
+        )?
+        .with_max_depth(max_depth)
+        .parse_program()
+    }
+
+    #[test]
+    fn nesting_past_the_limit_is_an_error() {
+        // a parenthesized expression takes two levels
+        assert!(parse_nested(10, 50).is_ok());
+        assert!(parse_nested(100, 50).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::Parser;
-use crate::ast::{Block, Declaration, FunctionDecl, StorageClass, VariableDecl};
+use crate::ast::{
+    Block, Declaration, EnumDecl, Expression, FunctionDecl, StorageClass, VariableDecl,
+};
 use crate::parse_err::ParseErr;
 use lexer::SpannedToken;
 use lexer::token::Token;
@@ -7,46 +9,146 @@ use shared_context::Type;
 use shared_context::{Identifier, Span, SpannedIdentifier};
 use std::collections::HashSet;
 
+/// The specifiers shared by the declarators of a declaration
+pub(crate) struct DeclSpecifiers {
+    pub(crate) base_type: Type,
+    pub(crate) storage_class: StorageClass,
+    pub(crate) is_const: bool,
+    pub(crate) is_typedef: bool, // the declarators name types instead of declaring objects
+}
+
+/// The parameters of a function declarator, in order
+struct ParamsList {
+    types: Vec<Type>,
+    names: Vec<Option<SpannedIdentifier>>, // `None` for an unnamed parameter
+    is_variadic: bool,                     // the list ends in `...`
+}
+
 impl<'src, 'ctx> Parser<'src, 'ctx> {
     /// Parses a declaration, determining whether it is a function or variable declaration.
-    pub(crate) fn parse_declaration(&mut self) -> Result<Declaration, ParseErr> {
+    ///
+    /// Variables can share their specifiers in a list of declarators (`int a = 1, *p, b;`),
+    /// each one is returned as its own declaration, in order.
+    /// A function is declared on its own, `int f(void), g(void);` isn't supported.
+    /// A `typedef` only names types for the rest of the scope, it gives no declaration.
+    pub(crate) fn parse_declaration(&mut self) -> Result<Vec<Declaration>, ParseErr> {
         let (start, line) = self.peek()?.get_span().get_start_and_line();
-        let (decl_type, storage_class) = self.parse_type_and_storage_class_list()?;
+        if self.peek()?.get_token() == Token::Enum {
+            return Ok(vec![Declaration::EnumDecl(
+                self.parse_enum_declaration(start, line)?,
+            )]);
+        }
 
-        let token = self.peek_two()?.get_token();
-        match token {
-            Token::LeftParenthesis => Ok(Declaration::FunDecl(self.parse_function_decl(
-                decl_type,
-                storage_class,
-                start,
-                line,
-            )?)),
-            _ => Ok(Declaration::VarDecl(self.parse_variable_declaration(
+        // qualifiers on a function's return type have no effect
+        let DeclSpecifiers {
+            base_type,
+            storage_class,
+            is_const,
+            is_typedef,
+        } = self.parse_type_and_storage_class_list()?;
+        if is_typedef {
+            self.parse_typedef_declarators(base_type, is_const)?;
+            return Ok(Vec::new());
+        }
+        let (decl_type, decl_const) = self.parse_pointer_declarator(base_type, is_const)?;
+
+        if !self.is_function_pointer_declarator()?
+            && self.peek_two()?.get_token() == Token::LeftParenthesis
+        {
+            return Ok(vec![Declaration::FunDecl(self.parse_function_decl(
                 decl_type,
                 storage_class,
                 start,
                 line,
-            )?)),
+            )?)]);
         }
+
+        let declarations = self.parse_variable_declarators(
+            (base_type, is_const),
+            (decl_type, decl_const),
+            storage_class,
+            start,
+            line,
+        )?;
+        Ok(declarations.into_iter().map(Declaration::VarDecl).collect())
     }
 
-    /// parse a specifier list to determine the type and storage class of a declaration
-    pub(crate) fn parse_type_and_storage_class_list(
+    /// Parses a list of variable declarators up to the `;` ending the declaration,
+    /// the `*`s of the first one are already parsed into `first`.
+    ///
+    /// Every declarator applies its own `*`s to the specifiers' `base` type and `const`,
+    /// so `int *p, x` declares a pointer and an `int`. The first declaration's span starts
+    /// at the specifiers, the others' at their own declarator.
+    pub(crate) fn parse_variable_declarators(
         &mut self,
-    ) -> Result<(Type, StorageClass), ParseErr> {
+        base: (Type, bool),
+        first: (Type, bool),
+        storage_class: StorageClass,
+        start: usize,
+        line: usize,
+    ) -> Result<Vec<VariableDecl>, ParseErr> {
+        let mut declarations = Vec::new();
+        let (mut decl_type, mut is_const) = first;
+        let (mut start, mut line) = (start, line);
+
+        loop {
+            let (var_type, name, var_const) = if self.is_function_pointer_declarator()? {
+                let (var_type, name, is_const) =
+                    self.parse_function_pointer_declarator(decl_type)?;
+                (var_type, self.expect_declarator_name(name)?, is_const)
+            } else {
+                (decl_type, self.parse_identifier()?, is_const)
+            };
+            self.declare_ordinary_identifier(name)?;
+            if self.peek()?.get_token() == Token::LeftParenthesis {
+                return Err(ParseErr::new(
+                    "a function must be declared on its own, not in a list of declarators",
+                    self.peek()?.get_span(),
+                    self.source_map,
+                ));
+            }
+            declarations.push(
+                self.parse_variable_declaration(name, var_type, storage_class, start, line)?
+                    .with_const(var_const),
+            );
+
+            if self.peek()?.get_token() != Token::Comma {
+                break;
+            }
+            self.advance()?; // consume ','
+            (start, line) = self.peek()?.get_span().get_start_and_line();
+            (decl_type, is_const) = self.parse_pointer_declarator(base.0, base.1)?;
+        }
+
+        self.expect_token(Token::Semicolon)?;
+        Ok(declarations)
+    }
+
+    /// parse a specifier list to determine the type and storage class of a declaration,
+    /// whether it's qualified with `const` and whether it's a `typedef`
+    ///
+    /// `typedef` is a storage class specifier, it can't be combined with another one.
+    /// `inline` is accepted and dropped, functions are never inlined.
+    pub(crate) fn parse_type_and_storage_class_list(&mut self) -> Result<DeclSpecifiers, ParseErr> {
         let (list, span) = self.collect_declaration_specifiers()?;
         let mut type_list = Vec::new();
         let mut storage_class_list = Vec::new();
+        let mut is_const = false;
 
         for specifier in list {
-            if specifier.get_token().is_type() {
+            if specifier.get_token().is_type() || specifier.get_token() == Token::Identifier {
                 type_list.push(specifier);
+            } else if specifier.get_token().is_qualifier() {
+                // repeating a qualifier is allowed, `volatile` and `restrict` are accepted and ignored
+                is_const |= specifier.get_token() == Token::Const;
+            } else if specifier.get_token() == Token::Inline {
+                continue;
             } else {
                 storage_class_list.push(specifier);
             }
         }
 
-        let decl_type = self.parse_type(type_list, span)?;
+        let (base_type, typedef_const) = self.parse_type_specifiers(type_list, span)?;
 
         if storage_class_list.len() > 1 {
             return Err(ParseErr::new(
@@ -56,35 +158,155 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             ));
         }
 
-        let storage_class = match storage_class_list.get(0) {
-            Some(class) => self.parse_storage_class(*class)?,
-            None => StorageClass::None,
+        let is_typedef = matches!(storage_class_list.first(), Some(class) if class.get_token() == Token::Typedef);
+        let storage_class = match storage_class_list.first() {
+            Some(class) if !is_typedef => self.parse_storage_class(*class)?,
+            _ => StorageClass::None,
         };
 
-        Ok((decl_type, storage_class))
+        Ok(DeclSpecifiers {
+            base_type,
+            storage_class,
+            is_const: is_const || typedef_const,
+            is_typedef,
+        })
     }
 
     /// collect all the tokens that make up a declaration specifier into one vector,
     /// return the vector and span of the list of specifiers,
     /// this list will be used to parse the types and storage class of the declaration
+    ///
+    /// a typedef name is a type specifier when no other type specifier came before it,
+    /// after one it's the declarator's name (`typedef int t; long t;` declares a `long t`)
     fn collect_declaration_specifiers(
         &mut self,
     ) -> Result<(Vec<SpannedToken<'src>>, Span), ParseErr> {
         let (start, line) = self.peek()?.get_span().get_start_and_line();
 
         let mut specifier_list = Vec::new();
-        while self.peek()?.get_token().is_specifier() {
+        let mut seen_type = false;
+        loop {
+            let token = self.peek()?;
+            let is_typedef_name = !seen_type && self.lookup_typedef(token).is_some();
+            if !token.get_token().is_specifier() && !is_typedef_name {
+                break;
+            }
+            seen_type |= token.get_token().is_type() || is_typedef_name;
             self.advance()?; // consume the token
             specifier_list.push(self.current_token);
         }
 
-        let end = self.current_token.get_span().end;
-        let span = Span::new(start, end, line);
+        // an empty list is reported on the token found instead,
+        // the previous token ends before the list would start
+        let span = if specifier_list.is_empty() {
+            self.peek()?.get_span()
+        } else {
+            Span::new(start, self.current_token.get_span().end, line)
+        };
 
         Ok((specifier_list, span))
     }
 
+    /// parse the `*`s in front of a declarator's name, each one wraps `base_type` in a pointer
+    ///
+    /// `is_const` is whether the specifiers had a `const`, it applies to what the outermost pointer
+    /// points to, so the declared object is only `const` when a `const` follows the last `*`
+    /// (`int *const p`). Qualifiers of the pointed to types aren't tracked.
+    /// Pointers to `void` aren't supported.
+    ///
+    /// return the declared type and whether the declared object is `const`
+    pub(crate) fn parse_pointer_declarator(
+        &mut self,
+        base_type: Type,
+        is_const: bool,
+    ) -> Result<(Type, bool), ParseErr> {
+        let mut decl_type = base_type;
+        let mut is_const = is_const;
+
+        let depth = self.depth;
+        while self.peek()?.get_token() == Token::Mul {
+            self.enter_nesting()?;
+            self.advance()?; // consume '*'
+            if decl_type == Type::Void {
+                return Err(ParseErr::new(
+                    "pointers to void aren't supported",
+                    self.current_token.get_span(),
+                    self.source_map,
+                ));
+            }
+            decl_type = self.ty_interner.intern_pointer(decl_type);
+            is_const = false;
+
+            while self.peek()?.get_token().is_qualifier() {
+                self.advance()?; // consume the qualifier
+                is_const |= self.current_token.get_token() == Token::Const;
+            }
+        }
+        self.depth = depth;
+
+        Ok((decl_type, is_const))
+    }
+
+    /// whether the next tokens start a function pointer declarator, `(*`
+    pub(crate) fn is_function_pointer_declarator(&mut self) -> Result<bool, ParseErr> {
+        Ok(self.peek()?.get_token() == Token::LeftParenthesis
+            && self.peek_two()?.get_token() == Token::Mul)
+    }
+
+    /// parse a function pointer declarator, `(*name)(params)`, the function returns `ret_type`
+    ///
+    /// The name can be omitted, like in a cast (`(int (*)(int))`) or an unnamed parameter,
+    /// qualifiers after the `*` make the declared pointer itself `const`.
+    /// Only a single `*` is accepted inside the parentheses.
+    ///
+    /// return the pointer type, the name and whether the declared object is `const`
+    pub(crate) fn parse_function_pointer_declarator(
+        &mut self,
+        ret_type: Type,
+    ) -> Result<(Type, Option<SpannedIdentifier>, bool), ParseErr> {
+        self.expect_token(Token::LeftParenthesis)?;
+        self.expect_token(Token::Mul)?;
+
+        let mut is_const = false;
+        while self.peek()?.get_token().is_qualifier() {
+            self.advance()?; // consume the qualifier
+            is_const |= self.current_token.get_token() == Token::Const;
+        }
+
+        let name = match self.peek()?.get_token() {
+            Token::RightParenthesis => None,
+            _ => Some(self.parse_identifier()?),
+        };
+        self.expect_token(Token::RightParenthesis)?;
+
+        // the parameters' names only document them
+        self.expect_token(Token::LeftParenthesis)?;
+        let ParamsList {
+            types, is_variadic, ..
+        } = self.parse_params_list()?;
+        self.expect_token(Token::RightParenthesis)?;
+
+        let type_id = self.ty_interner.intern(ret_type, &types, is_variadic);
+        let pointer_type = self.ty_interner.intern_pointer(Type::Function(type_id));
+        Ok((pointer_type, name, is_const))
+    }
+
+    /// a declaration's function pointer declarator must have a name
+    pub(crate) fn expect_declarator_name(
+        &self,
+        name: Option<SpannedIdentifier>,
+    ) -> Result<SpannedIdentifier, ParseErr> {
+        name.ok_or_else(|| {
+            ParseErr::new(
+                "expected a name in the declarator",
+                self.current_token.get_span(),
+                self.source_map,
+            )
+        })
+    }
+
     /// parse a specifier list that doesn't contain storage class specifiers
+    /// used for parameters and casts, where qualifiers and `register` are accepted and ignored
     ///
     /// return an err if a storage class specifier is found
     pub(crate) fn parse_type_list(&mut self) -> Result<Type, ParseErr> {
@@ -92,8 +314,11 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         let mut type_list = Vec::new();
 
         for specifier in list {
-            if specifier.get_token().is_type() {
+            let token = specifier.get_token();
+            if token.is_type() || token == Token::Identifier {
                 type_list.push(specifier);
+            } else if token.is_qualifier() || token == Token::Register {
+                continue;
             } else {
                 return Err(ParseErr::new(
                     "Invalid use of storage class specifier",
@@ -103,22 +328,84 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             }
         }
 
-        self.parse_type(type_list, span)
+        // a parameter or a cast being `const` has no effect
+        Ok(self.parse_type_specifiers(type_list, span)?.0)
+    }
+
+    /// parse the type specifiers of a specifier list, either a typedef name on its own
+    /// or the type keywords `parse_type` combines
+    ///
+    /// return the type and whether a typedef name makes it `const`
+    fn parse_type_specifiers(
+        &mut self,
+        token_list: Vec<SpannedToken>,
+        span: Span,
+    ) -> Result<(Type, bool), ParseErr> {
+        match token_list.as_slice() {
+            [token] if token.get_token() == Token::Identifier => {
+                // only a typedef name is collected as a specifier
+                self.lookup_typedef(*token).ok_or_else(|| {
+                    ParseErr::new("unknown type name", token.get_span(), self.source_map)
+                })
+            }
+            _ if token_list
+                .iter()
+                .any(|t| t.get_token() == Token::Identifier) =>
+            {
+                Err(ParseErr::new(
+                    "invalid type specifier",
+                    span,
+                    self.source_map,
+                ))
+            }
+            _ => Ok((self.parse_type(token_list, span)?, false)),
+        }
     }
 
     /// parse types annotations in a specifier list
+    ///
+    /// the specifiers can appear in any order, e.g. `long unsigned int` is `Type::Ulong`
+    /// - `unsigned` with `long` gives `Type::Ulong`, without it `Type::Uint`
+    /// - `long` gives `Type::Long`, `signed` on its own or with `int` gives `Type::Int`
+    /// - `long long` is the same 64 bit type as `long`
+    /// - `double` and `void` can't be combined with any other type specifier
+    ///
+    /// an empty list, a repeated specifier (other than a second `long`), or `signed` with `unsigned`
+    /// is an error reported on the span of the whole list
     fn parse_type(&mut self, token_list: Vec<SpannedToken>, span: Span) -> Result<Type, ParseErr> {
-        let type_list: Vec<&str> = token_list.iter().map(|st| st.get_lexeme()).collect();
+        // `bool` and `_Bool` name the same type
+        let mut type_list: Vec<&str> = token_list
+            .iter()
+            .map(|st| match st.get_token() {
+                Token::Bool => "_Bool",
+                _ => st.get_lexeme(),
+            })
+            .collect();
+
+        // `long long` is an alias of `long`, drop the second one so only a third `long` is a duplicate
+        if type_list.iter().filter(|t| **t == "long").count() == 2 {
+            let second_long = type_list.iter().rposition(|t| *t == "long").unwrap();
+            type_list.remove(second_long);
+        }
 
         if token_list.is_empty()
             || Self::has_duplicates(&type_list)
             || Self::contains_all(&type_list, &["signed", "unsigned"])
+            || (type_list.contains(&"double") && type_list.len() > 1)
+            || (type_list.contains(&"void") && type_list.len() > 1)
+            || (type_list.contains(&"_Bool") && type_list.len() > 1)
         {
             Err(ParseErr::new(
                 "invalid type specifier",
                 span,
                 &self.source_map,
             ))
+        } else if type_list.contains(&"double") {
+            Ok(Type::Double)
+        } else if type_list.contains(&"void") {
+            Ok(Type::Void)
+        } else if type_list.contains(&"_Bool") {
+            Ok(Type::Bool)
         } else if Self::contains_all(&type_list, &["unsigned", "long"]) {
             Ok(Type::Ulong)
         } else if type_list.contains(&"unsigned") {
@@ -151,6 +438,8 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         match token.get_token() {
             Token::Static => Ok(StorageClass::Static),
             Token::Extern => Ok(StorageClass::Extern),
+            // `register` is only a hint, the variable gets automatic storage
+            Token::Register => Ok(StorageClass::None),
             _ => Err(ParseErr::expected(
                 "storage specifier",
                 &token,
@@ -159,15 +448,29 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         }
     }
 
-    /// Parses a variable declaration:
-    pub(crate) fn parse_variable_declaration(
+    /// Parses the rest of a variable declarator, after its name: its optional array length
+    /// and its optional initializer
+    fn parse_variable_declaration(
         &mut self,
+        name: SpannedIdentifier,
         var_type: Type,
         storage_class: StorageClass,
         start: usize,
         line: usize,
     ) -> Result<VariableDecl, ParseErr> {
-        let name = self.parse_identifier()?;
+        if var_type == Type::Void {
+            return Err(ParseErr::new(
+                "variable declared void",
+                name.get_span(),
+                self.source_map,
+            ));
+        }
+
+        let array_len = match self.peek()?.get_token() {
+            Token::LeftSquareBracket => Some(self.parse_array_length()?),
+            _ => None,
+        };
+
         let init = match self.peek()?.get_token() {
             Token::Assignment => {
                 self.advance()?; // consume '='
@@ -176,11 +479,61 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             _ => None,
         };
 
+        let end = self.current_token.get_span().end;
+        let span = Span::new(start, end, line);
+        Ok(VariableDecl::new(name, var_type, init, storage_class, span).with_array_len(array_len))
+    }
+
+    /// Parses the length of an array declarator: `[N]`, where `N` is an expression
+    /// the type checker evaluates to a positive integer constant.
+    /// There are only arrays of one dimension.
+    fn parse_array_length(&mut self) -> Result<Expression, ParseErr> {
+        self.advance()?; // consume '['
+        let length = self.parse_expression(0)?;
+        self.expect_token(Token::RightSquareBracket)?;
+        Ok(length)
+    }
+
+    /// Parses an enum declaration:
+    ///
+    /// `enum [tag] { name [= value], ... [,] };`, the tag is accepted and ignored.
+    /// The values are evaluated by the type checker, an enumerator without a value is
+    /// one more than the previous one, the first one is 0.
+    fn parse_enum_declaration(&mut self, start: usize, line: usize) -> Result<EnumDecl, ParseErr> {
+        self.expect_token(Token::Enum)?;
+        if self.peek()?.get_token() == Token::Identifier {
+            self.advance()?; // consume the tag
+        }
+        self.expect_token(Token::LeftCurlyBracket)?;
+
+        let mut enumerators = Vec::new();
+        loop {
+            let name = self.parse_identifier()?;
+            self.declare_ordinary_identifier(name)?;
+            let value = match self.peek()?.get_token() {
+                Token::Assignment => {
+                    self.advance()?; // consume '='
+                    Some(self.parse_expression(0)?)
+                }
+                _ => None,
+            };
+            enumerators.push((name, value));
+
+            if self.peek()?.get_token() != Token::Comma {
+                break;
+            }
+            self.advance()?; // consume ','
+            // a trailing comma may end the list
+            if self.peek()?.get_token() == Token::RightCurlyBracket {
+                break;
+            }
+        }
+
+        self.expect_token(Token::RightCurlyBracket)?;
         self.expect_token(Token::Semicolon)?;
 
         let end = self.current_token.get_span().end;
-        let span = Span::new(start, end, line);
-        Ok(VariableDecl::new(name, var_type, init, storage_class, span))
+        Ok(EnumDecl::new(enumerators, Span::new(start, end, line)))
     }
 
     /// Parses a function declaration:
@@ -192,16 +545,40 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         line: usize,
     ) -> Result<FunctionDecl, ParseErr> {
         let name = self.parse_identifier()?;
+        self.declare_ordinary_identifier(name)?;
 
+        let (params_start, params_line) = self.peek()?.get_span().get_start_and_line();
         self.expect_token(Token::LeftParenthesis)?;
-        let (params_types, params_iden) = self.parse_params_list()?;
+        let ParamsList {
+            types: params_types,
+            names: params_iden,
+            is_variadic,
+        } = self.parse_params_list()?;
         self.expect_token(Token::RightParenthesis)?;
+        let params_end = self.current_token.get_span().end;
 
+        // the parameters are in scope in the body, where they hide typedefs of the same name
+        self.enter_scope();
+        for param in params_iden.iter().flatten() {
+            self.declare_ordinary_identifier(*param)?;
+        }
         let body = self.parse_optional_block()?;
+        self.leave_scope();
+
+        // only a prototype's parameters can be unnamed, a definition uses them
+        if body.is_some() && params_iden.iter().any(Option::is_none) {
+            return Err(ParseErr::new(
+                "parameter name omitted in a function definition",
+                Span::new(params_start, params_end, params_line),
+                self.source_map,
+            ));
+        }
 
         let end = self.current_token.get_span().end;
         let span = Span::new(start, end, line);
-        let type_id = self.ty_interner.intern(ret_type, &params_types);
+        let type_id = self
+            .ty_interner
+            .intern(ret_type, &params_types, is_variadic);
 
         Ok(FunctionDecl::new(
             name,
@@ -230,32 +607,79 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     /// Parses a function parameter list.
     ///
     /// Accepts either:
-    /// - `void` (no parameters), or
-    /// - one or more `int <identifier>` pairs separated by commas.
-    ///
-    /// Returns two vectors
-    /// - vector of params identifiers
-    /// - vector for params types
-    fn parse_params_list(&mut self) -> Result<(Vec<Type>, Vec<SpannedIdentifier>), ParseErr> {
-        let mut params_iden = Vec::new();
-        let mut params_type = Vec::new();
-
-        if self.peek()?.get_token() == Token::Void {
+    /// - `void` (no parameters), a `void` that doesn't end the list is a parameter's type, or
+    /// - one or more `int <identifier>` pairs separated by commas, where the name
+    ///   can follow `*`s (`int *p`) or be a function pointer declarator (`int (*f)(int)`),
+    ///   optionally followed by `, ...` for a variadic function.
+    ///   The name can be omitted (`int f(int, long);`), `parse_function_decl` only
+    ///   accepts that for prototypes.
+    fn parse_params_list(&mut self) -> Result<ParamsList, ParseErr> {
+        let mut params = ParamsList {
+            types: Vec::new(),
+            names: Vec::new(),
+            is_variadic: false,
+        };
+
+        if self.peek()?.get_token() == Token::Void
+            && self.peek_two()?.get_token() == Token::RightParenthesis
+        {
             self.advance()?; // consume 'void'
-            return Ok((params_type, params_iden));
+            return Ok(params);
         }
 
-        let param_type = self.parse_type_list()?;
-        params_iden.push(self.parse_identifier()?);
-        params_type.push(param_type);
+        let (param_type, param_name) = self.parse_param()?;
+        params.types.push(param_type);
+        params.names.push(param_name);
 
         while self.peek()?.get_token() != Token::RightParenthesis {
             self.expect_token(Token::Comma)?;
-            let param_type = self.parse_type_list()?;
-            params_iden.push(self.parse_identifier()?);
-            params_type.push(param_type);
+
+            // `...` must be the last entry of the list
+            if self.peek()?.get_token() == Token::Ellipsis {
+                self.advance()?; // consume '...'
+                params.is_variadic = true;
+                return Ok(params);
+            }
+
+            let (param_type, param_name) = self.parse_param()?;
+            params.types.push(param_type);
+            params.names.push(param_name);
+        }
+        Ok(params)
+    }
+
+    /// Parses one parameter, its type and its name if it has one.
+    /// only a function pointer parameter can have `void` in its type, as the return type
+    fn parse_param(&mut self) -> Result<(Type, Option<SpannedIdentifier>), ParseErr> {
+        let param_type = self.parse_param_type()?;
+        if self.is_function_pointer_declarator()? {
+            let (param_type, name, _) = self.parse_function_pointer_declarator(param_type)?;
+            return Ok((param_type, name));
+        }
+        if param_type == Type::Void {
+            return Err(ParseErr::new(
+                "parameter declared void",
+                self.current_token.get_span(),
+                self.source_map,
+            ));
+        }
+        Ok((param_type, self.parse_param_name()?))
+    }
+
+    /// Parses the type of a parameter, its specifiers and the `*`s before its name.
+    /// a `const` parameter can still be assigned to, qualifiers are accepted and ignored
+    fn parse_param_type(&mut self) -> Result<Type, ParseErr> {
+        let base_type = self.parse_type_list()?;
+        let (param_type, _) = self.parse_pointer_declarator(base_type, false)?;
+        Ok(param_type)
+    }
+
+    /// Parses the name of a parameter, `None` when the parameter ends without one.
+    fn parse_param_name(&mut self) -> Result<Option<SpannedIdentifier>, ParseErr> {
+        match self.peek()?.get_token() {
+            Token::Comma | Token::RightParenthesis => Ok(None),
+            _ => Ok(Some(self.parse_identifier()?)),
         }
-        Ok((params_type, params_iden))
     }
 
     /// Parses an identifier and returns it as a SpannedToken.
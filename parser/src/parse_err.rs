@@ -12,6 +12,7 @@ use std::fmt;
 #[derive(Debug)]
 pub struct ParseErr {
     formated_error: String,
+    lexical: bool, // the lexer didn't recognize a token, rather than the parser rejecting one
 }
 
 impl ParseErr {
@@ -22,14 +23,47 @@ impl ParseErr {
     pub fn new(message: &str, span: Span, source_map: &SourceMap) -> Self {
         Self {
             formated_error: source_map.format_message(message, span),
+            lexical: false,
         }
     }
 
+    /// Constructs the error for a token the lexer couldn't recognize,
+    /// naming the offending text, e.g. `unexpected character '@'`.
+    pub fn unknown_token(found: &SpannedToken, source_map: &SourceMap) -> Self {
+        Self {
+            lexical: true,
+            ..ParseErr::new(
+                &format!("unexpected character '{}'", found.get_lexeme()),
+                found.get_span(),
+                source_map,
+            )
+        }
+    }
+
+    /// Constructs the error for a preprocessor directive in a source compiled without
+    /// the preprocessor, only line markers can be lexed.
+    pub fn unsupported_directive(found: &SpannedToken, source_map: &SourceMap) -> Self {
+        Self {
+            lexical: true,
+            ..ParseErr::new(
+                "preprocessor directives other than line markers need the preprocessor, \
+                 compile without `--no-cpp`",
+                found.get_span(),
+                source_map,
+            )
+        }
+    }
+
+    /// Whether the error comes from lexing rather than parsing.
+    pub fn is_lexical(&self) -> bool {
+        self.lexical
+    }
+
     /// Constructs a standardized “expected …” parse error message.
     ///
     /// Typically used when the parser encounters an unexpected token.
     /// For example:
-    /// ```
+    /// ```text
     /// expected: 'identifier'
     /// ```
     pub fn expected(expected: impl ToString, found: &SpannedToken, source_map: &SourceMap) -> Self {
@@ -10,6 +10,10 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     /// Parses an expression with a minimum precedence, supporting
     /// binary, unary, assignment, and conditional (`?:`) operators.
     pub(crate) fn parse_expression(&mut self, min_prec: usize) -> Result<Expression, ParseErr> {
+        self.nested(|parser| parser.parse_expression_body(min_prec))
+    }
+
+    fn parse_expression_body(&mut self, min_prec: usize) -> Result<Expression, ParseErr> {
         // Capture the start span for this expression
         let line = self.peek()?.get_span().line;
         let mut span_start = self.peek()?.get_span().start;
@@ -18,11 +22,14 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         let mut left = self.parse_factor()?;
 
         // Continue parsing while the next token is a binary operator of sufficient precedence
+        let depth = self.depth;
         while self.peek()?.get_token().is_binary() {
             let next_token = self.peek()?.get_token();
             if next_token.precedence() < min_prec {
                 break;
             }
+            // the expression parsed so far becomes an operand, one level deeper
+            self.enter_nesting()?;
 
             left = match next_token {
                 Token::Assignment => {
@@ -37,6 +44,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             // Update the start position for the next iteration
             span_start = self.peek()?.get_span().start;
         }
+        self.depth = depth;
 
         Ok(left)
     }
@@ -64,6 +72,11 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     }
 
     /// Handles ternary conditional expressions (`cond ? cons : alt`)
+    ///
+    /// The middle is parsed like a parenthesized expression, so `a ? b ? c : d : e`
+    /// nests in the middle. The else branch is parsed at the `?`'s own precedence,
+    /// not one above it, which makes the operator right associative:
+    /// `a ? b : c ? d : e` is `a ? b : (c ? d : e)`.
     fn handle_conditional(
         &mut self,
         left: Expression,
@@ -6,22 +6,40 @@ use shared_context::{Const, Span, Type};
 impl<'a, 'b> Parser<'a, 'b> {
     /// Parses a "factor" in an expression.
     /// A factor can be:
-    /// - an integer constant
+    /// - an integer or floating point constant
     /// - a unary expression
+    /// - a dereference (`*p`) or an address-of (`&x`) expression
     /// - a parenthesized expression
     /// - a type cast
     /// - an identifier (variable or function call)
+    ///
+    /// an integer constant, a parenthesized expression or an identifier can be followed
+    /// by subscripts (`a[i]`, `i[a]`)
     pub(crate) fn parse_factor(&mut self) -> Result<Expression, ParseErr> {
+        self.nested(Self::parse_factor_body)
+    }
+
+    fn parse_factor_body(&mut self) -> Result<Expression, ParseErr> {
         let token = self.peek()?;
 
         match token.get_token() {
-            tok if tok.is_int_constant() => self.handle_constant_int(),
+            tok if tok.is_int_constant() => {
+                let expr = self.parse_constant_integer()?;
+                self.parse_subscripts(expr)
+            }
+            Token::ConstantDouble => self.parse_constant_double(),
             tok if tok.is_unary() => self.handle_unary_expression(),
-            Token::LeftParenthesis if self.peek_two()?.get_token().is_specifier() => {
-                self.handle_type_cast()
+            Token::Mul => self.handle_dereference_expression(),
+            Token::BitwiseAnd => self.handle_address_of_expression(),
+            Token::LeftParenthesis if self.is_type_cast()? => self.handle_type_cast(),
+            Token::LeftParenthesis => {
+                let expr = self.handle_parenthesized_expression()?;
+                self.parse_subscripts(expr)
+            }
+            Token::Identifier => {
+                let expr = self.handle_identifier_expression()?;
+                self.parse_subscripts(expr)
             }
-            Token::LeftParenthesis => self.handle_parenthesized_expression(),
-            Token::Identifier => self.handle_identifier_expression(),
             _ => Err(ParseErr::new(
                 "invalid expression",
                 token.get_span(),
@@ -30,15 +48,23 @@ impl<'a, 'b> Parser<'a, 'b> {
         }
     }
 
-    /// Handles parsing of integer constants
-    fn handle_constant_int(&mut self) -> Result<Expression, ParseErr> {
-        match self.peek()?.get_token() {
-            Token::ConstantInt => self.parse_constant_int(),
-            Token::ConstantLong => self.parse_constant_long(),
-            Token::ConstantUint => self.parse_constant_uint(),
-            Token::ConstantUlong => self.parse_constant_ulong(),
-            _ => self.parse_constant_int(),
+    /// Parses the subscripts following an expression: `<expr>[<index>]...`,
+    /// they bind tighter than the unary operators, `*a[1]` is `*(a[1])`
+    fn parse_subscripts(&mut self, mut expr: Expression) -> Result<Expression, ParseErr> {
+        let (start, line) = expr.get_span().get_start_and_line();
+        while self.peek()?.get_token() == Token::LeftSquareBracket {
+            self.advance()?; // consume '['
+            let index = self.parse_expression(0)?;
+            self.expect_token(Token::RightSquareBracket)?;
+
+            let end = self.current_token.get_span().end;
+            let inner = InnerExpression::Subscript {
+                array: Box::new(expr),
+                index: Box::new(index),
+            };
+            expr = Expression::new(inner, Type::default(), Span::new(start, end, line));
         }
+        Ok(expr)
     }
 
     /// Handles parsing of unary expressions, e.g., `-x` or `!flag`
@@ -57,20 +83,80 @@ impl<'a, 'b> Parser<'a, 'b> {
         Ok(Expression::new(expr_type, Type::default(), span))
     }
 
-    /// Handles parenthesized expressions: `(expr)`
+    /// Handles a pointer dereference: `*<factor>`
+    fn handle_dereference_expression(&mut self) -> Result<Expression, ParseErr> {
+        let (start, line) = self.peek()?.get_span().get_start_and_line();
+
+        self.advance()?; // consume '*'
+        let inner_exp = self.parse_factor()?;
+        let end = self.current_token.get_span().end;
+
+        let expr_type = InnerExpression::Dereference(Box::new(inner_exp));
+        let span = Span::new(start, end, line);
+        Ok(Expression::new(expr_type, Type::default(), span))
+    }
+
+    /// Handles taking an address: `&<factor>`
+    fn handle_address_of_expression(&mut self) -> Result<Expression, ParseErr> {
+        let (start, line) = self.peek()?.get_span().get_start_and_line();
+
+        self.advance()?; // consume '&'
+        let inner_exp = self.parse_factor()?;
+        let end = self.current_token.get_span().end;
+
+        let expr_type = InnerExpression::AddrOf(Box::new(inner_exp));
+        let span = Span::new(start, end, line);
+        Ok(Expression::new(expr_type, Type::default(), span))
+    }
+
+    /// Handles parenthesized expressions: `(expr)`, and calls through them: `(*fp)(args)`
     fn handle_parenthesized_expression(&mut self) -> Result<Expression, ParseErr> {
+        let (start, line) = self.peek()?.get_span().get_start_and_line();
+
         self.advance()?; // consume '('
-        let inner_exp = self.parse_expression(0)?;
+        let mut expr = self.parse_expression(0)?.with_parentheses(true);
         self.expect_token(Token::RightParenthesis)?;
-        Ok(inner_exp)
+
+        while self.peek()?.get_token() == Token::LeftParenthesis {
+            self.advance()?; // consume '('
+            let args = self.parse_function_args()?;
+            self.expect_token(Token::RightParenthesis)?;
+
+            let end = self.current_token.get_span().end;
+            let inner = InnerExpression::IndirectCall {
+                callee: Box::new(expr),
+                args,
+            };
+            expr = Expression::new(inner, Type::default(), Span::new(start, end, line));
+        }
+        Ok(expr)
     }
 
-    /// handle type casting: (<type>) <factor>
+    /// whether the `(` ahead starts a type cast, it's followed by a type keyword or a typedef name
+    fn is_type_cast(&mut self) -> Result<bool, ParseErr> {
+        let next = self.peek_two()?;
+        Ok(self.starts_declaration(next))
+    }
+
+    /// handle type casting: (<type>) <factor>, where the type can end in `*`s (`(long *)`)
+    /// or be a function pointer (`(int (*)(int))`)
     fn handle_type_cast(&mut self) -> Result<Expression, ParseErr> {
         let (start, line) = self.peek()?.get_span().get_start_and_line();
 
         self.advance()?; // consume '('
-        let target_type = self.parse_type_list()?;
+        let base_type = self.parse_type_list()?;
+        let (mut target_type, _) = self.parse_pointer_declarator(base_type, false)?;
+        if self.is_function_pointer_declarator()? {
+            let (pointer_type, name, _) = self.parse_function_pointer_declarator(target_type)?;
+            if let Some(name) = name {
+                return Err(ParseErr::new(
+                    "a type name can't declare a name",
+                    name.get_span(),
+                    self.source_map,
+                ));
+            }
+            target_type = pointer_type;
+        }
         self.expect_token(Token::RightParenthesis)?;
         let expr_type = InnerExpression::Cast {
             target_type,
@@ -106,16 +192,20 @@ impl<'a, 'b> Parser<'a, 'b> {
         Ok(Expression::new(expr_type, Type::default(), span))
     }
 
-    /// Parses the argument list of a function call
-    fn parse_function_args(&mut self) -> Result<Vec<Box<Expression>>, ParseErr> {
+    /// Parses the argument list of a function call,
+    /// a trailing comma after the last argument is accepted (`f(1, 2,)`)
+    fn parse_function_args(&mut self) -> Result<Vec<Expression>, ParseErr> {
         let mut args = Vec::new();
         match self.peek()?.get_token() {
             Token::RightParenthesis => Ok(args), // no arguments
             _ => {
-                args.push(Box::new(self.parse_expression(0)?));
+                args.push(self.parse_expression(0)?);
                 while self.peek()?.get_token() != Token::RightParenthesis {
                     self.expect_token(Token::Comma)?;
-                    args.push(Box::new(self.parse_expression(0)?));
+                    if self.peek()?.get_token() == Token::RightParenthesis {
+                        break;
+                    }
+                    args.push(self.parse_expression(0)?);
                 }
                 Ok(args)
             }
@@ -134,87 +224,29 @@ impl<'a, 'b> Parser<'a, 'b> {
         Ok(Expression::new(expr_type, Type::default(), span))
     }
 
-    /// Parses an integer constant
+    /// Parses an integer constant with an optional suffix (e.g., `42`, `0x1F`, `42LLU`)
     ///
-    /// Turn it into a long integer constant if the value does not fit
-    fn parse_constant_int(&mut self) -> Result<Expression, ParseErr> {
+    /// The trailing `u`/`l` characters are scanned off and classify the constant,
+    /// its type is the first of the candidates for that classification the value fits in.
+    /// Like C, hexadecimal constants may also be unsigned when they don't fit a signed type.
+    fn parse_constant_integer(&mut self) -> Result<Expression, ParseErr> {
         let (start, line) = self.peek()?.get_span().get_start_and_line();
         let token = self.advance()?;
+        let lexeme = token.get_lexeme();
 
-        // parse the number literal into 128 bit signed integer
-        let value = token.get_lexeme().parse::<i128>().map_err(|_| {
-            ParseErr::new(
-                "failed to parse integer constant",
-                token.get_span(),
-                &self.source_map,
-            )
-        })?;
-
-        let end = self.current_token.get_span().end;
-        let span = Span::new(start, end, line);
+        // split off the suffix, the lexer already checked its form
+        let digits = lexeme.trim_end_matches(['u', 'U', 'l', 'L']);
+        let suffix = &lexeme[digits.len()..];
+        let is_unsigned = suffix.contains(['u', 'U']);
+        let is_long = suffix.contains(['l', 'L']);
 
-        // check if the number literal can fit to any of the supported types
-        if let Ok(int) = i32::try_from(value) {
-            let constant = Const::ConstInt(int);
-            let expr_type = InnerExpression::Constant(constant);
-            Ok(Expression::new(expr_type, Type::Int, span))
-        } else if let Ok(long) = i64::try_from(value) {
-            let contant = Const::ConstLong(long);
-            let expr_type = InnerExpression::Constant(contant);
-            Ok(Expression::new(expr_type, Type::Long, span))
-        } else {
-            return Err(ParseErr::new(
-                "integer value too large to represent",
-                token.get_span(),
-                &self.source_map,
-            ));
-        }
-    }
-
-    /// parse a long integer constant (e.g., `123l` or `123L`)
-    fn parse_constant_long(&mut self) -> Result<Expression, ParseErr> {
-        let (start, line) = self.peek()?.get_span().get_start_and_line();
-        let token = self.advance()?;
-
-        // remove the suffix
-        let lexeme = &token.get_lexeme()[..token.get_lexeme().len() - 1];
-
-        let value = lexeme.parse::<i128>().map_err(|_| {
-            ParseErr::new(
-                "failed to parse integer constant",
-                token.get_span(),
-                &self.source_map,
-            )
-        })?;
-
-        let end = self.current_token.get_span().end;
-        let span = Span::new(start, end, line);
-
-        if let Ok(long) = i64::try_from(value) {
-            let contant = Const::ConstLong(long);
-            let expr_type = InnerExpression::Constant(contant);
-            Ok(Expression::new(expr_type, Type::Long, span))
-        } else {
-            return Err(ParseErr::new(
-                "integer value too large to represent",
-                token.get_span(),
-                &self.source_map,
-            ));
-        }
-    }
-
-    /// Parses an unsigned integer constant
-    ///
-    /// Turn it into an unsignd long integer constant if the value does not fit
-    fn parse_constant_uint(&mut self) -> Result<Expression, ParseErr> {
-        let (start, line) = self.peek()?.get_span().get_start_and_line();
-        let token = self.advance()?;
-
-        // remove the suffix
-        let lexeme = &token.get_lexeme()[..token.get_lexeme().len() - 1];
+        let (digits, is_hex) = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+            Some(hex_digits) => (hex_digits, true),
+            None => (digits, false),
+        };
 
         // parse the number literal into 128 bit unsigned integer
-        let value = lexeme.parse::<u128>().map_err(|_| {
+        let value = u128::from_str_radix(digits, if is_hex { 16 } else { 10 }).map_err(|_| {
             ParseErr::new(
                 "failed to parse integer constant",
                 token.get_span(),
@@ -222,38 +254,48 @@ impl<'a, 'b> Parser<'a, 'b> {
             )
         })?;
 
+        let candidates: &[Type] = match (is_unsigned, is_long, is_hex) {
+            (false, false, false) => &[Type::Int, Type::Long],
+            (false, false, true) => &[Type::Int, Type::Uint, Type::Long, Type::Ulong],
+            (false, true, false) => &[Type::Long],
+            (false, true, true) => &[Type::Long, Type::Ulong],
+            (true, false, _) => &[Type::Uint, Type::Ulong],
+            (true, true, _) => &[Type::Ulong],
+        };
+
         let end = self.current_token.get_span().end;
         let span = Span::new(start, end, line);
 
-        // check if the number literal can fit to any of the supported types
-        if let Ok(uint) = u32::try_from(value) {
-            let constant = Const::ConstUint(uint);
-            let expr_type = InnerExpression::Constant(constant);
-            Ok(Expression::new(expr_type, Type::Uint, span))
-        } else if let Ok(ulong) = u64::try_from(value) {
-            let contant = Const::ConstUlong(ulong);
-            let expr_type = InnerExpression::Constant(contant);
-            Ok(Expression::new(expr_type, Type::Ulong, span))
-        } else {
-            return Err(ParseErr::new(
-                "integer value too large to represent",
-                token.get_span(),
-                &self.source_map,
-            ));
+        // check if the number literal can fit to any of the candidate types
+        for &ty in candidates {
+            let constant = match ty {
+                Type::Int => i32::try_from(value).ok().map(Const::ConstInt),
+                Type::Long => i64::try_from(value).ok().map(Const::ConstLong),
+                Type::Uint => u32::try_from(value).ok().map(Const::ConstUint),
+                _ => u64::try_from(value).ok().map(Const::ConstUlong),
+            };
+            if let Some(constant) = constant {
+                let expr_type = InnerExpression::Constant(constant);
+                return Ok(Expression::new(expr_type, ty, span));
+            }
         }
+
+        Err(ParseErr::new(
+            "integer value too large to represent",
+            token.get_span(),
+            &self.source_map,
+        ))
     }
 
-    /// parse an unsigned long integer constant (e.g., `123lU` or `123uL`)
-    fn parse_constant_ulong(&mut self) -> Result<Expression, ParseErr> {
+    /// parse a floating point constant (e.g., `1.5`, `.5` or `1e10`)
+    fn parse_constant_double(&mut self) -> Result<Expression, ParseErr> {
         let (start, line) = self.peek()?.get_span().get_start_and_line();
         let token = self.advance()?;
 
-        // remove the suffix
-        let lexeme = &token.get_lexeme()[..token.get_lexeme().len() - 2];
-
-        let value = lexeme.parse::<u128>().map_err(|_| {
+        // Rust's parser rounds to the nearest double, as C requires
+        let value = token.get_lexeme().parse::<f64>().map_err(|_| {
             ParseErr::new(
-                "failed to parse integer constant",
+                "failed to parse floating point constant",
                 token.get_span(),
                 &self.source_map,
             )
@@ -262,16 +304,8 @@ impl<'a, 'b> Parser<'a, 'b> {
         let end = self.current_token.get_span().end;
         let span = Span::new(start, end, line);
 
-        if let Ok(ulong) = u64::try_from(value) {
-            let contant = Const::ConstUlong(ulong);
-            let expr_type = InnerExpression::Constant(contant);
-            Ok(Expression::new(expr_type, Type::Ulong, span))
-        } else {
-            return Err(ParseErr::new(
-                "integer value too large to represent",
-                token.get_span(),
-                &self.source_map,
-            ));
-        }
+        let constant = Const::ConstDouble(value);
+        let expr_type = InnerExpression::Constant(constant);
+        Ok(Expression::new(expr_type, Type::Double, span))
     }
 }
@@ -1,6 +1,7 @@
 use crate::ParseErr;
 use crate::Parser;
 use crate::ast::{Expression, ForInit, Statement, StatementType};
+use crate::parse_declarations::DeclSpecifiers;
 use lexer::token::Token;
 use shared_context::{Identifier, Span};
 
@@ -8,6 +9,10 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     /// Parses a statement and returns a `Statement` AST node.
     /// Handles all types of statements: return, if, loops, break/continue, compound blocks, or expressions.
     pub(crate) fn parse_statement(&mut self) -> Result<Statement, ParseErr> {
+        self.nested(Self::parse_statement_body)
+    }
+
+    fn parse_statement_body(&mut self) -> Result<Statement, ParseErr> {
         // Get the current token's line and start position for the statement's span.
         let (start, line) = self.peek()?.get_span().get_start_and_line();
 
@@ -41,10 +46,13 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         Ok(Statement::new(stmt_type, span))
     }
 
-    /// Parses a `return` statement
+    /// Parses a `return` statement, the expression is omitted in a `void` function
     fn parse_return_statement(&mut self) -> Result<StatementType, ParseErr> {
         self.advance()?; // consume the 'return' token
-        let exp = self.parse_expression(0)?;
+        let exp = match self.peek()?.get_token() {
+            Token::Semicolon => None,
+            _ => Some(self.parse_expression(0)?),
+        };
         self.expect_token(Token::Semicolon)?;
         Ok(StatementType::Return(exp))
     }
@@ -129,7 +137,17 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     }
 
     /// Parses a `for` loop: `for (init; condition; post) stmt`
+    ///
+    /// the variables declared in `init` are in a scope of their own, they hide typedefs
+    /// in the rest of the loop
     fn parse_for_statement(&mut self) -> Result<StatementType, ParseErr> {
+        self.enter_scope();
+        let for_stmt = self.parse_for_statement_body();
+        self.leave_scope();
+        for_stmt
+    }
+
+    fn parse_for_statement_body(&mut self) -> Result<StatementType, ParseErr> {
         self.advance()?; // consume 'for'
         self.expect_token(Token::LeftParenthesis)?;
 
@@ -152,12 +170,26 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
     /// Parses the initialization part of a `for` loop
     fn parse_for_init(&mut self) -> Result<ForInit, ParseErr> {
-        let next_token = self.peek()?.get_token();
-        if next_token.is_specifier() {
-            let (start, line) = self.peek()?.get_span().get_start_and_line();
-            let (var_type, storage_class) = self.parse_type_and_storage_class_list()?;
-            Ok(ForInit::D(self.parse_variable_declaration(
-                var_type,
+        let next_token = self.peek()?;
+        if self.starts_declaration(next_token) {
+            let (start, line) = next_token.get_span().get_start_and_line();
+            let DeclSpecifiers {
+                base_type,
+                storage_class,
+                is_const,
+                is_typedef,
+            } = self.parse_type_and_storage_class_list()?;
+            if is_typedef {
+                return Err(ParseErr::new(
+                    "a typedef can't be declared in a for loop initializer",
+                    next_token.get_span(),
+                    self.source_map,
+                ));
+            }
+            let first = self.parse_pointer_declarator(base_type, is_const)?;
+            Ok(ForInit::D(self.parse_variable_declarators(
+                (base_type, is_const),
+                first,
                 storage_class,
                 start,
                 line,
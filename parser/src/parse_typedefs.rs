@@ -0,0 +1,125 @@
+use crate::ParseErr;
+use crate::Parser;
+use lexer::{SpannedToken, token::Token};
+use shared_context::{SpannedIdentifier, Type, symbol_interner::Symbol};
+use std::collections::HashMap;
+
+// This file contains the typedef names table and the parsing of `typedef` declarations.
+//
+// The lexer can't tell a typedef name from any other identifier, so the parser keeps the
+// names declared in each scope: a typedef maps to the type it names, an ordinary identifier
+// (a variable, function, parameter or enumerator) to `None`, so it hides a typedef of the
+// same name declared in an enclosing scope. A typedef is only a name for its type, it's
+// resolved while parsing and never reaches the AST.
+
+/// The names declared in a scope that decide whether an identifier names a type,
+/// a typedef maps to its type and whether it's `const`
+pub(crate) type TypedefScope = HashMap<Symbol, Option<(Type, bool)>>;
+
+impl<'src, 'ctx> Parser<'src, 'ctx> {
+    /// Starts a scope for the names declared in a block, a `for` loop or a function body
+    pub(crate) fn enter_scope(&mut self) {
+        self.typedef_scopes.push(TypedefScope::new());
+    }
+
+    pub(crate) fn leave_scope(&mut self) {
+        self.typedef_scopes.pop();
+    }
+
+    /// Records an ordinary identifier in the current scope, hiding any typedef of the same name
+    /// from enclosing scopes. A typedef of the same name in the same scope is an error.
+    pub(crate) fn declare_ordinary_identifier(
+        &mut self,
+        name: SpannedIdentifier,
+    ) -> Result<(), ParseErr> {
+        let symbol = name.get_identifier().get_symbol();
+        let scope = self
+            .typedef_scopes
+            .last_mut()
+            .expect("the file scope is never left");
+        if let Some(Some(_)) = scope.insert(symbol, None) {
+            return Err(ParseErr::new(
+                "redeclared as a different kind of symbol, the name is a typedef",
+                name.get_span(),
+                self.source_map,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Records a typedef in the current scope. Repeating a typedef of the same type is allowed,
+    /// a different type or an ordinary identifier of the same name in the scope is an error.
+    fn declare_typedef(
+        &mut self,
+        name: SpannedIdentifier,
+        typedef: (Type, bool),
+    ) -> Result<(), ParseErr> {
+        let symbol = name.get_identifier().get_symbol();
+        let scope = self
+            .typedef_scopes
+            .last_mut()
+            .expect("the file scope is never left");
+        let message = match scope.insert(symbol, Some(typedef)) {
+            None => return Ok(()),
+            Some(Some(prev)) if prev == typedef => return Ok(()),
+            Some(Some(_)) => "conflicting types for typedef",
+            Some(None) => "redeclared as a different kind of symbol, the name isn't a typedef",
+        };
+        Err(ParseErr::new(message, name.get_span(), self.source_map))
+    }
+
+    /// The type a typedef name stands for and whether it's `const`,
+    /// `None` if the innermost declaration of the name isn't a typedef
+    pub(crate) fn lookup_typedef(&mut self, token: SpannedToken) -> Option<(Type, bool)> {
+        if token.get_token() != Token::Identifier {
+            return None;
+        }
+        let symbol = self.sy_interner.intern(token.get_lexeme());
+        self.typedef_scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&symbol).copied())
+            .flatten()
+    }
+
+    /// whether a token starts a declaration, a declaration specifier or a typedef name
+    pub(crate) fn starts_declaration(&mut self, token: SpannedToken) -> bool {
+        token.get_token().is_specifier() || self.lookup_typedef(token).is_some()
+    }
+
+    /// Parses the declarators of a `typedef`, after its specifiers, up to the `;` ending it.
+    ///
+    /// Each declarator names `base_type` with its own `*`s applied, or a function pointer type.
+    /// A typedef of a function type (`typedef int f(int);`) isn't supported.
+    pub(crate) fn parse_typedef_declarators(
+        &mut self,
+        base_type: Type,
+        is_const: bool,
+    ) -> Result<(), ParseErr> {
+        loop {
+            let (decl_type, decl_const) = self.parse_pointer_declarator(base_type, is_const)?;
+            let (decl_type, name, decl_const) = if self.is_function_pointer_declarator()? {
+                let (ptr_type, name, is_const) =
+                    self.parse_function_pointer_declarator(decl_type)?;
+                (ptr_type, self.expect_declarator_name(name)?, is_const)
+            } else {
+                (decl_type, self.parse_identifier()?, decl_const)
+            };
+            if self.peek()?.get_token() == Token::LeftParenthesis {
+                return Err(ParseErr::new(
+                    "a typedef of a function type isn't supported",
+                    self.peek()?.get_span(),
+                    self.source_map,
+                ));
+            }
+            self.declare_typedef(name, (decl_type, decl_const))?;
+
+            if self.peek()?.get_token() != Token::Comma {
+                break;
+            }
+            self.advance()?; // consume ','
+        }
+
+        self.expect_token(Token::Semicolon)
+    }
+}
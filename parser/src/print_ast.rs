@@ -37,9 +37,9 @@ impl<'a> DebugTreePrinter<'a> {
         let (name, type_id, params, body, storage_class, _) = function.into_parts();
         let fun_type = self.ty_interner.get(type_id);
         println!(
-            "{}{:?} FunctionDecl {:?} \"{}\"",
+            "{}{} FunctionDecl {:?} \"{}\"",
             self.indent(level),
-            fun_type.ret,
+            self.ty_interner.format_type(fun_type.ret),
             storage_class,
             self.format_spanned_identifier(name)
         );
@@ -47,12 +47,22 @@ impl<'a> DebugTreePrinter<'a> {
         // Print function parameters
         println!("{}Params", self.indent(level + 1));
         for (param, param_type) in params.iter().zip(fun_type.params) {
-            println!(
-                "{}{:?} \"{}\"",
-                self.indent(level + 2),
-                param_type,
-                self.format_spanned_identifier(*param)
-            );
+            match param {
+                Some(param) => println!(
+                    "{}{} \"{}\"",
+                    self.indent(level + 2),
+                    self.ty_interner.format_type(*param_type),
+                    self.format_spanned_identifier(*param)
+                ),
+                None => println!(
+                    "{}{}",
+                    self.indent(level + 2),
+                    self.ty_interner.format_type(*param_type)
+                ),
+            }
+        }
+        if fun_type.is_variadic {
+            println!("{}...", self.indent(level + 2));
         }
 
         // Print function body if present
@@ -80,24 +90,46 @@ impl<'a> DebugTreePrinter<'a> {
         }
     }
 
-    /// Prints a declaration (variable or function)
+    /// Prints a declaration (variable, function or enum)
     fn print_declaration(&self, decl: Declaration, level: usize) {
         match decl {
             Declaration::VarDecl(v) => self.print_variable_decl(v, level),
             Declaration::FunDecl(f) => self.print_function(f, level),
+            Declaration::EnumDecl(e) => self.print_enum_decl(e, level),
+        }
+    }
+
+    /// Prints an enum declaration, one line per enumerator followed by its value (if present)
+    fn print_enum_decl(&self, decl: EnumDecl, level: usize) {
+        println!("{}EnumDecl", self.indent(level));
+        for (name, value) in decl.into_parts().0 {
+            println!(
+                "{}Enumerator \"{}\"",
+                self.indent(level + 1),
+                self.format_spanned_identifier(name)
+            );
+            if let Some(expr) = value {
+                println!("{}Value", self.indent(level + 2));
+                self.print_expression(expr, level + 3);
+            }
         }
     }
 
-    /// Prints a variable declaration and its initializer (if present)
-    fn print_variable_decl(&self, decl: VariableDecl, level: usize) {
+    /// Prints a variable declaration, its array length and its initializer (if present)
+    fn print_variable_decl(&self, mut decl: VariableDecl, level: usize) {
+        let array_len = decl.take_array_len();
         let (name, var_type, init, storage_class, _) = decl.into_parts();
         println!(
-            "{}{:?} VariableDecl {:?} \"{}\"",
+            "{}{} VariableDecl {:?} \"{}\"",
             self.indent(level),
-            var_type,
+            self.ty_interner.format_type(var_type),
             storage_class,
             self.format_spanned_identifier(name)
         );
+        if let Some(expr) = array_len {
+            println!("{}ArrayLength", self.indent(level + 1));
+            self.print_expression(expr, level + 2);
+        }
         if let Some(expr) = init {
             println!("{}Init", self.indent(level + 1));
             self.print_expression(expr, level + 2);
@@ -140,9 +172,11 @@ impl<'a> DebugTreePrinter<'a> {
     }
 
     /// Prints a `return` statement
-    fn print_return_stmt(&self, expr: Expression, level: usize) {
+    fn print_return_stmt(&self, expr: Option<Expression>, level: usize) {
         println!("{}Return", self.indent(level));
-        self.print_expression(expr, level + 1);
+        if let Some(expr) = expr {
+            self.print_expression(expr, level + 1);
+        }
     }
 
     /// Prints an expression statement
@@ -244,7 +278,11 @@ impl<'a> DebugTreePrinter<'a> {
         // Print initializer
         println!("{}Init", self.indent(level + 1));
         match init {
-            ForInit::D(decl) => self.print_variable_decl(decl, level + 2),
+            ForInit::D(decls) => {
+                for decl in decls {
+                    self.print_variable_decl(decl, level + 2);
+                }
+            }
             ForInit::E(Some(expr)) => self.print_expression(expr, level + 2),
             ForInit::E(None) => println!("{}None", self.indent(level + 2)),
         }
@@ -273,7 +311,11 @@ impl<'a> DebugTreePrinter<'a> {
     /// Prints an expression
     fn print_expression(&self, expr: Expression, level: usize) {
         let (expr_type, var_type, _) = expr.into_parts();
-        println!("{}{:?}", self.indent(level), var_type);
+        println!(
+            "{}{}",
+            self.indent(level),
+            self.ty_interner.format_type(var_type)
+        );
         match expr_type {
             InnerExpression::Constant(cons) => self.print_constant_expr(cons, level),
             InnerExpression::Unary { operator, operand } => {
@@ -292,9 +334,17 @@ impl<'a> DebugTreePrinter<'a> {
                 self.print_conditional_expr(*cond, *cons, *alt, level)
             }
             InnerExpression::FunctionCall { name, args } => self.print_call_expr(name, args, level),
+            InnerExpression::IndirectCall { callee, args } => {
+                self.print_indirect_call_expr(*callee, args, level)
+            }
             InnerExpression::Cast { target_type, expr } => {
                 self.print_type_cast(target_type, *expr, level)
             }
+            InnerExpression::Dereference(inner) => self.print_dereference_expr(*inner, level),
+            InnerExpression::AddrOf(inner) => self.print_address_of_expr(*inner, level),
+            InnerExpression::Subscript { array, index } => {
+                self.print_subscript_expr(*array, *index, level)
+            }
         }
     }
 
@@ -307,12 +357,30 @@ impl<'a> DebugTreePrinter<'a> {
         self.print_expression(expr, level + 2);
     }
 
-    fn format_type(&self, tar_type: Type) -> &'static str {
+    fn format_type(&self, tar_type: Type) -> String {
         match tar_type {
-            Type::Int => "int",
-            Type::Uint => "uint",
-            Type::Ulong => "ulong",
-            Type::Long => "long",
+            Type::Int => "int".to_string(),
+            Type::Uint => "uint".to_string(),
+            Type::Ulong => "ulong".to_string(),
+            Type::Long => "long".to_string(),
+            Type::Double => "double".to_string(),
+            Type::Bool => "_Bool".to_string(),
+            Type::Void => "void".to_string(),
+            Type::Pointer(id) => {
+                format!("{}*", self.format_type(self.ty_interner.get_referenced(id)))
+            }
+            Type::Function(id) => {
+                let fun_type = self.ty_interner.get(id);
+                let mut params: Vec<String> = fun_type
+                    .params
+                    .iter()
+                    .map(|t| self.format_type(*t))
+                    .collect();
+                if fun_type.is_variadic {
+                    params.push("...".to_string());
+                }
+                format!("{}({})", self.format_type(fun_type.ret), params.join(", "))
+            }
         }
     }
 
@@ -331,6 +399,12 @@ impl<'a> DebugTreePrinter<'a> {
             Const::ConstUlong(ulong) => {
                 println!("{}ConstUlong {}", self.indent(level), ulong)
             }
+            Const::ConstDouble(double) => {
+                println!("{}ConstDouble {}", self.indent(level), double)
+            }
+            Const::ConstBool(bool) => {
+                println!("{}ConstBool {}", self.indent(level), bool)
+            }
         }
     }
 
@@ -347,6 +421,26 @@ impl<'a> DebugTreePrinter<'a> {
         self.print_expression(right, level + 1);
     }
 
+    /// Prints a pointer dereference
+    fn print_dereference_expr(&self, inner: Expression, level: usize) {
+        println!("{}Dereference", self.indent(level));
+        self.print_expression(inner, level + 1);
+    }
+
+    /// Prints an address-of expression
+    fn print_address_of_expr(&self, inner: Expression, level: usize) {
+        println!("{}AddrOf", self.indent(level));
+        self.print_expression(inner, level + 1);
+    }
+
+    /// Prints a subscript expression
+    fn print_subscript_expr(&self, array: Expression, index: Expression, level: usize) {
+        println!("{}Subscript", self.indent(level));
+        self.print_expression(array, level + 1);
+        println!("{}Index", self.indent(level + 1));
+        self.print_expression(index, level + 2);
+    }
+
     /// Prints a variable reference
     fn print_var_expr(&self, id: SpannedIdentifier, level: usize) {
         println!(
@@ -383,7 +477,7 @@ impl<'a> DebugTreePrinter<'a> {
     }
 
     /// Prints a function call with arguments
-    fn print_call_expr(&self, name: SpannedIdentifier, args: Vec<Box<Expression>>, level: usize) {
+    fn print_call_expr(&self, name: SpannedIdentifier, args: Vec<Expression>, level: usize) {
         println!(
             "{}FunctionCall \"{}\"",
             self.indent(level),
@@ -391,7 +485,18 @@ impl<'a> DebugTreePrinter<'a> {
         );
         for (i, arg) in args.into_iter().enumerate() {
             println!("{}Arg {}", self.indent(level + 1), i);
-            self.print_expression(*arg, level + 2);
+            self.print_expression(arg, level + 2);
+        }
+    }
+
+    /// Prints a call through a function pointer, the callee and then the arguments
+    fn print_indirect_call_expr(&self, callee: Expression, args: Vec<Expression>, level: usize) {
+        println!("{}IndirectCall", self.indent(level));
+        println!("{}Callee", self.indent(level + 1));
+        self.print_expression(callee, level + 2);
+        for (i, arg) in args.into_iter().enumerate() {
+            println!("{}Arg {}", self.indent(level + 1), i);
+            self.print_expression(arg, level + 2);
         }
     }
 
@@ -0,0 +1,409 @@
+use crate::ast::*;
+use shared_context::symbol_interner::SymbolInterner;
+use shared_context::type_interner::TypeInterner;
+use shared_context::{Const, Identifier, Span, SpannedIdentifier, Type};
+
+/// JsonTreePrinter traverses the same structures as `DebugTreePrinter`,
+/// but serializes the AST as JSON for external tools (AST viewers, golden files).
+///
+/// Every node is an object with a `"kind"` and a `"span"` (`start`, `end` and `line`),
+/// identifiers are written as `"name.id"` like the text tree, the id only appears
+/// once identifier resolution has renamed them (`"x.3"`).
+pub struct JsonTreePrinter<'a> {
+    ty_interner: &'a TypeInterner<'a>, // used to get functions types
+    interner: &'a SymbolInterner<'a>,  // used to resolve identifiers to their string names
+}
+
+impl<'a> JsonTreePrinter<'a> {
+    /// Creates a new JsonTreePrinter with a reference to an Interner.
+    pub fn new(ty_interner: &'a TypeInterner<'a>, interner: &'a SymbolInterner) -> Self {
+        Self {
+            ty_interner,
+            interner,
+        }
+    }
+
+    /// Prints the entire program as a single JSON object
+    pub fn print(&self, program: Program) {
+        println!("{}", self.to_json(program));
+    }
+
+    /// Serializes the entire program, `{"kind":"Program","declarations":[...]}`
+    pub fn to_json(&self, program: Program) -> String {
+        let declarations: Vec<String> = program
+            .into_parts()
+            .into_iter()
+            .map(|decl| self.format_declaration(decl))
+            .collect();
+        format!(
+            "{{\"kind\":\"Program\",\"declarations\":{}}}",
+            Self::format_array(declarations)
+        )
+    }
+
+    /// Formats a declaration (variable, function or enum)
+    fn format_declaration(&self, decl: Declaration) -> String {
+        match decl {
+            Declaration::VarDecl(v) => self.format_variable_decl(v),
+            Declaration::FunDecl(f) => self.format_function(f),
+            Declaration::EnumDecl(e) => self.format_enum_decl(e),
+        }
+    }
+
+    /// Formats an enum declaration, each enumerator with its value expression (`null` if absent)
+    fn format_enum_decl(&self, decl: EnumDecl) -> String {
+        let (enumerators, span) = decl.into_parts();
+        let enumerators: Vec<String> = enumerators
+            .into_iter()
+            .map(|(name, value)| {
+                format!(
+                    "{{\"name\":{},\"value\":{}}}",
+                    self.format_spanned_identifier(name),
+                    self.format_optional_expression(value)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"kind\":\"EnumDecl\",\"span\":{},\"enumerators\":{}}}",
+            Self::format_span(span),
+            Self::format_array(enumerators)
+        )
+    }
+
+    /// Formats a function declaration with its parameters and body
+    fn format_function(&self, function: FunctionDecl) -> String {
+        let (name, type_id, params, body, storage_class, span) = function.into_parts();
+        let fun_type = self.ty_interner.get(type_id);
+
+        let params: Vec<String> = params
+            .iter()
+            .zip(fun_type.params)
+            .map(|(param, param_type)| {
+                format!(
+                    "{{\"name\":{},\"type\":{}}}",
+                    param.map_or("null".to_string(), |param| self
+                        .format_spanned_identifier(param)),
+                    self.format_type(*param_type)
+                )
+            })
+            .collect();
+        let body = match body {
+            Some(block) => self.format_block(block),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"kind\":\"FunctionDecl\",\"span\":{},\"name\":{},\"storage_class\":\"{:?}\",\
+             \"return_type\":{},\"params\":{},\"variadic\":{},\"body\":{}}}",
+            Self::format_span(span),
+            self.format_spanned_identifier(name),
+            storage_class,
+            self.format_type(fun_type.ret),
+            Self::format_array(params),
+            fun_type.is_variadic,
+            body
+        )
+    }
+
+    /// Formats a variable declaration, its array length and its initializer (`null` if absent)
+    fn format_variable_decl(&self, mut decl: VariableDecl) -> String {
+        let array_len = decl.take_array_len();
+        let (name, var_type, init, storage_class, span) = decl.into_parts();
+        format!(
+            "{{\"kind\":\"VariableDecl\",\"span\":{},\"name\":{},\"storage_class\":\"{:?}\",\
+             \"type\":{},\"array_length\":{},\"init\":{}}}",
+            Self::format_span(span),
+            self.format_spanned_identifier(name),
+            storage_class,
+            self.format_type(var_type),
+            self.format_optional_expression(array_len),
+            self.format_optional_expression(init)
+        )
+    }
+
+    /// Formats a block of statements or declarations
+    fn format_block(&self, block: Block) -> String {
+        let (items, span) = block.into_parts();
+        let items: Vec<String> = items
+            .into_iter()
+            .map(|item| match item {
+                BlockItem::D(decl) => self.format_declaration(decl),
+                BlockItem::S(stmt) => self.format_statement(stmt),
+            })
+            .collect();
+        format!(
+            "{{\"kind\":\"Block\",\"span\":{},\"items\":{}}}",
+            Self::format_span(span),
+            Self::format_array(items)
+        )
+    }
+
+    /// Formats a statement, the fields after the span depend on its kind
+    fn format_statement(&self, stmt: Statement) -> String {
+        let (stmt_type, span) = stmt.into_parts();
+        let (kind, fields) = match stmt_type {
+            StatementType::Return(expr) => (
+                "Return",
+                format!(",\"expr\":{}", self.format_optional_expression(expr)),
+            ),
+            StatementType::ExprStatement(expr) => (
+                "ExprStatement",
+                format!(",\"expr\":{}", self.format_expression(expr)),
+            ),
+            StatementType::Null => ("NullStatement", String::new()),
+            StatementType::Compound(block) => (
+                "Compound",
+                format!(",\"block\":{}", self.format_block(block)),
+            ),
+            StatementType::IfStatement {
+                condition,
+                if_clause,
+                else_clause,
+            } => (
+                "If",
+                format!(
+                    ",\"condition\":{},\"then\":{},\"else\":{}",
+                    self.format_expression(condition),
+                    self.format_statement(*if_clause),
+                    else_clause.map_or("null".to_string(), |stmt| self.format_statement(*stmt))
+                ),
+            ),
+            StatementType::Break(label) => (
+                "Break",
+                format!(",\"label\":{}", self.format_identifier(label)),
+            ),
+            StatementType::Continue(label) => (
+                "Continue",
+                format!(",\"label\":{}", self.format_identifier(label)),
+            ),
+            StatementType::While {
+                condition,
+                body,
+                label,
+            } => (
+                "While",
+                format!(
+                    ",\"label\":{},\"condition\":{},\"body\":{}",
+                    self.format_identifier(label),
+                    self.format_expression(condition),
+                    self.format_statement(*body)
+                ),
+            ),
+            StatementType::DoWhile {
+                condition,
+                body,
+                label,
+            } => (
+                "DoWhile",
+                format!(
+                    ",\"label\":{},\"body\":{},\"condition\":{}",
+                    self.format_identifier(label),
+                    self.format_statement(*body),
+                    self.format_expression(condition)
+                ),
+            ),
+            StatementType::For {
+                init,
+                condition,
+                post,
+                body,
+                label,
+            } => {
+                let init = match init {
+                    // a declaration is a list of them, one for each declarator
+                    ForInit::D(decls) => Self::format_array(
+                        decls
+                            .into_iter()
+                            .map(|decl| self.format_variable_decl(decl))
+                            .collect(),
+                    ),
+                    ForInit::E(expr) => self.format_optional_expression(expr),
+                };
+                (
+                    "For",
+                    format!(
+                        ",\"label\":{},\"init\":{},\"condition\":{},\"post\":{},\"body\":{}",
+                        self.format_identifier(label),
+                        init,
+                        self.format_optional_expression(condition),
+                        self.format_optional_expression(post),
+                        self.format_statement(*body)
+                    ),
+                )
+            }
+        };
+
+        format!(
+            "{{\"kind\":\"{}\",\"span\":{}{}}}",
+            kind,
+            Self::format_span(span),
+            fields
+        )
+    }
+
+    /// Formats an expression, its type is only meaningful after type checking
+    fn format_expression(&self, expr: Expression) -> String {
+        let (inner, expr_type, span) = expr.into_parts();
+        let (kind, fields) = match inner {
+            InnerExpression::Constant(cons) => ("Constant", Self::format_constant(cons)),
+            InnerExpression::Unary { operator, operand } => (
+                "Unary",
+                format!(
+                    ",\"operator\":\"{:?}\",\"operand\":{}",
+                    operator,
+                    self.format_expression(*operand)
+                ),
+            ),
+            InnerExpression::Binary {
+                operator,
+                operand1,
+                operand2,
+            } => (
+                "Binary",
+                format!(
+                    ",\"operator\":\"{:?}\",\"left\":{},\"right\":{}",
+                    operator,
+                    self.format_expression(*operand1),
+                    self.format_expression(*operand2)
+                ),
+            ),
+            InnerExpression::Var(id) => (
+                "Var",
+                format!(",\"name\":{}", self.format_spanned_identifier(id)),
+            ),
+            InnerExpression::Assignment { lvalue, rvalue } => (
+                "Assignment",
+                format!(
+                    ",\"lvalue\":{},\"rvalue\":{}",
+                    self.format_expression(*lvalue),
+                    self.format_expression(*rvalue)
+                ),
+            ),
+            InnerExpression::Conditional { cond, cons, alt } => (
+                "Conditional",
+                format!(
+                    ",\"condition\":{},\"then\":{},\"else\":{}",
+                    self.format_expression(*cond),
+                    self.format_expression(*cons),
+                    self.format_expression(*alt)
+                ),
+            ),
+            InnerExpression::FunctionCall { name, args } => {
+                let args: Vec<String> = args
+                    .into_iter()
+                    .map(|arg| self.format_expression(arg))
+                    .collect();
+                (
+                    "FunctionCall",
+                    format!(
+                        ",\"name\":{},\"args\":{}",
+                        self.format_spanned_identifier(name),
+                        Self::format_array(args)
+                    ),
+                )
+            }
+            InnerExpression::IndirectCall { callee, args } => {
+                let args: Vec<String> = args
+                    .into_iter()
+                    .map(|arg| self.format_expression(arg))
+                    .collect();
+                (
+                    "IndirectCall",
+                    format!(
+                        ",\"callee\":{},\"args\":{}",
+                        self.format_expression(*callee),
+                        Self::format_array(args)
+                    ),
+                )
+            }
+            InnerExpression::Cast { target_type, expr } => (
+                "Cast",
+                format!(
+                    ",\"target_type\":{},\"expr\":{}",
+                    self.format_type(target_type),
+                    self.format_expression(*expr)
+                ),
+            ),
+            InnerExpression::Dereference(inner) => (
+                "Dereference",
+                format!(",\"expr\":{}", self.format_expression(*inner)),
+            ),
+            InnerExpression::AddrOf(inner) => (
+                "AddrOf",
+                format!(",\"expr\":{}", self.format_expression(*inner)),
+            ),
+            InnerExpression::Subscript { array, index } => (
+                "Subscript",
+                format!(
+                    ",\"array\":{},\"index\":{}",
+                    self.format_expression(*array),
+                    self.format_expression(*index)
+                ),
+            ),
+        };
+
+        format!(
+            "{{\"kind\":\"{}\",\"span\":{},\"type\":{}{}}}",
+            kind,
+            Self::format_span(span),
+            self.format_type(expr_type),
+            fields
+        )
+    }
+
+    fn format_optional_expression(&self, expr: Option<Expression>) -> String {
+        match expr {
+            Some(expr) => self.format_expression(expr),
+            None => "null".to_string(),
+        }
+    }
+
+    /// Formats the fields of a constant: its type and value
+    ///
+    /// JSON numbers can't be infinite, an overflowing double literal is written as a string
+    fn format_constant(value: Const) -> String {
+        let (const_type, value) = match value {
+            Const::ConstInt(int) => ("ConstInt", int.to_string()),
+            Const::ConstUint(uint) => ("ConstUint", uint.to_string()),
+            Const::ConstLong(long) => ("ConstLong", long.to_string()),
+            Const::ConstUlong(ulong) => ("ConstUlong", ulong.to_string()),
+            Const::ConstDouble(double) if double.is_finite() => {
+                ("ConstDouble", format!("{:?}", double))
+            }
+            Const::ConstDouble(double) => ("ConstDouble", format!("\"{}\"", double)),
+            Const::ConstBool(bool) => ("ConstBool", bool.to_string()),
+        };
+        format!(",\"const_type\":\"{}\",\"value\":{}", const_type, value)
+    }
+
+    fn format_type(&self, ty: Type) -> String {
+        format!("\"{}\"", self.ty_interner.format_type(ty))
+    }
+
+    fn format_span(span: Span) -> String {
+        format!(
+            "{{\"start\":{},\"end\":{},\"line\":{}}}",
+            span.start, span.end, span.line
+        )
+    }
+
+    fn format_array(elements: Vec<String>) -> String {
+        format!("[{}]", elements.join(","))
+    }
+
+    /// Formats a simple identifier as the JSON string "name.id"
+    fn format_identifier(&self, identifier: Identifier) -> String {
+        let (symbol, id) = identifier.into_parts();
+        if id == 0 {
+            format!("\"{}\"", self.interner.lookup(symbol))
+        } else {
+            format!("\"{}.{}\"", self.interner.lookup(symbol), id)
+        }
+    }
+
+    /// Formats a spanned identifier by delegating to format_identifier
+    fn format_spanned_identifier(&self, identifier: SpannedIdentifier) -> String {
+        let (ident, _) = identifier.into_parts();
+        self.format_identifier(ident)
+    }
+}
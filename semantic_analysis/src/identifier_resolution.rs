@@ -1,4 +1,4 @@
-use crate::semantic_error::{ErrorType, SemanticErr};
+use crate::semantic_error::{ErrorRecovery, ErrorType, SemanticErr};
 use parser::ast::*;
 use shared_context::{SpannedIdentifier, source_map::SourceMap, symbol_interner::Symbol};
 use std::collections::{HashMap, VecDeque};
@@ -12,6 +12,7 @@ mod resolve_statements;
 pub(crate) struct IdentifierResolver<'src, 'ctx> {
     source_map: &'ctx SourceMap<'src>,
     variable_counter: usize, // Counter for auto-generated variables
+    errors: ErrorRecovery,   // the errors recovered from in `--keep-going` mode
 }
 
 /// Represents an entry in the identifier resolver.
@@ -105,10 +106,14 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
     /// This struct handles the first pass of semantic analysis:
     /// 1. resolving identifiers (variables and functions) and detecting duplicate declarations.
     /// 2. assign all identifiers with no linkage a unqiue identifier
-    pub fn new(source_map: &'ctx SourceMap<'src>) -> Self {
+    ///
+    /// With `keep_going` the pass continues after an error: an undeclared name is kept as it is
+    /// and a declaration or statement that fails is dropped, all the errors are reported at the end.
+    pub fn new(source_map: &'ctx SourceMap<'src>, keep_going: bool) -> Self {
         Self {
             source_map,
             variable_counter: 1, // auto-generated variable counter starts at 1
+            errors: ErrorRecovery::new(keep_going),
         }
     }
 
@@ -128,21 +133,39 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
 
     /// Resolves all functions in a `Program`, creating a global scope for declarations.
     ///
+    /// The global scope is seeded with the functions declared at file scope, so a function
+    /// can be called before its declaration; variables still have to be declared first.
+    ///
     /// This is the entry point for identifier resolution.
     pub fn resolve_program(&mut self, program: Program) -> Result<Program, SemanticErr> {
         let declarations = program.into_parts();
         let mut resolver_ctx = ResolverContext::new();
         resolver_ctx.create_scope(); // Create global scope
 
+        for decl in &declarations {
+            if let Declaration::FunDecl(fun_decl) = decl {
+                let name = fun_decl.get_sp_identifier();
+                let symbol = name.get_identifier().get_symbol();
+                if resolver_ctx.search_current_scope(&symbol).is_none() {
+                    resolver_ctx.insert_entry(symbol, ResolverEntry::new(name, true));
+                }
+            }
+        }
+
         let mut resolved_declarations = Vec::new();
         for decl in declarations {
-            resolved_declarations.push(
-                self.resolve_global_declaration(decl, &mut resolver_ctx)
-                    .map_err(|err| SemanticErr::new(err, &self.source_map))?,
-            );
+            let resolved = self.resolve_global_declaration(decl, &mut resolver_ctx);
+            if let Some(resolved_decl) = self
+                .errors
+                .recover(resolved.map(Some), || None)
+                .map_err(|err| SemanticErr::new(err, &self.source_map))?
+            {
+                resolved_declarations.push(resolved_decl);
+            }
         }
 
         resolver_ctx.delete_scope(); // Clean up global scope
+        self.errors.take_errors(self.source_map)?;
         Ok(Program::new(resolved_declarations))
     }
 
@@ -155,18 +178,35 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         resolver_ctx.create_scope(); // Create a new nested scope
 
         let (block_items, span) = block.into_parts();
-        let mut resolved_block = Vec::new();
-        for item in block_items {
-            let resolved_item = self.resolve_block_item(item, resolver_ctx)?;
-            resolved_block.push(resolved_item);
-        }
+        let resolved_block = self.resolve_block_items(block_items, resolver_ctx)?;
 
         resolver_ctx.delete_scope(); // Exit nested scope
 
         Ok(Block::new(resolved_block, span))
     }
 
+    /// Resolves the items of a block in the current scope,
+    /// with `--keep-going` an item that fails is dropped.
+    fn resolve_block_items(
+        &mut self,
+        block_items: Vec<BlockItem>,
+        resolver_ctx: &mut ResolverContext,
+    ) -> Result<Vec<BlockItem>, ErrorType> {
+        let mut resolved_items = Vec::new();
+        for item in block_items {
+            let resolved_item = self.resolve_block_item(item, resolver_ctx);
+            if let Some(resolved_item) = self.errors.recover(resolved_item.map(Some), || None)? {
+                resolved_items.push(resolved_item);
+            }
+        }
+        Ok(resolved_items)
+    }
+
     /// Resolves a single block item (either a declaration or a statement).
+    ///
+    /// Items are resolved in source order and a declaration enters the scope as it's
+    /// resolved, so a use before the declaration is a `UseOfUndeclared` error,
+    /// except for a call to a function declared later at file scope.
     fn resolve_block_item(
         &mut self,
         item: BlockItem,
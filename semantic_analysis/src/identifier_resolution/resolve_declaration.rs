@@ -1,7 +1,7 @@
 use crate::IdentifierResolver;
 use crate::identifier_resolution::{ResolverContext, ResolverEntry};
 use crate::semantic_error::ErrorType;
-use parser::ast::{Block, Declaration, FunctionDecl, StorageClass, VariableDecl};
+use parser::ast::{Block, Declaration, EnumDecl, FunctionDecl, StorageClass, VariableDecl};
 use shared_context::{Identifier, SpannedIdentifier};
 
 impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
@@ -18,6 +18,9 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
             Declaration::VarDecl(var_decl) => Ok(Declaration::VarDecl(
                 self.resolve_global_variable_declaration(var_decl, resolver_ctx)?,
             )),
+            Declaration::EnumDecl(enum_decl) => Ok(Declaration::EnumDecl(
+                self.resolve_enum_declaration(enum_decl, resolver_ctx)?,
+            )),
         }
     }
 
@@ -47,40 +50,106 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
             Declaration::VarDecl(var_decl) => Ok(Declaration::VarDecl(
                 self.resolve_local_variable_declaration(var_decl, resolver_ctx)?,
             )),
+            Declaration::EnumDecl(enum_decl) => Ok(Declaration::EnumDecl(
+                self.resolve_enum_declaration(enum_decl, resolver_ctx)?,
+            )),
         }
     }
 
     /// Resolve a global variable declaration
     fn resolve_global_variable_declaration(
         &mut self,
-        var_decl: VariableDecl,
+        mut var_decl: VariableDecl,
         resolver_ctx: &mut ResolverContext,
     ) -> Result<VariableDecl, ErrorType> {
         let sp_iden = var_decl.get_sp_identifier();
         let symbol = sp_iden.get_identifier().get_symbol();
+
+        // an enumerator is the only file scope identifier without linkage
+        if let Some(prev_entry) = resolver_ctx.search_current_scope(&symbol)
+            && !prev_entry.has_linkage()
+        {
+            return Err(ErrorType::DuplicateDefintion {
+                first: prev_entry.get_sp_identifier().get_span(),
+                second: sp_iden.get_span(),
+            });
+        }
+
+        // the array length is resolved before the name is in scope
+        let array_len = var_decl
+            .take_array_len()
+            .map(|len| self.resolve_expression(len, resolver_ctx))
+            .transpose()?;
         resolver_ctx.insert_entry(symbol, ResolverEntry::new(sp_iden, true));
-        return Ok(var_decl);
+
+        // the initializer is a constant expression, which can refer to enumerators
+        let is_const = var_decl.is_const();
+        let (name, var_type, mut init, storage_class, span) = var_decl.into_parts();
+        if let Some(expr) = init {
+            init = Some(self.resolve_expression(expr, resolver_ctx)?);
+        }
+        Ok(VariableDecl::new(name, var_type, init, storage_class, span)
+            .with_const(is_const)
+            .with_array_len(array_len))
+    }
+
+    /// Resolves the enumerators of an enum declaration.
+    ///
+    /// Each enumerator gets a unique id like a local variable, in any scope,
+    /// and can't share its name with anything else declared in the same scope.
+    /// A value is resolved before its enumerator is in scope, so it can refer to earlier ones.
+    fn resolve_enum_declaration(
+        &mut self,
+        enum_decl: EnumDecl,
+        resolver_ctx: &mut ResolverContext,
+    ) -> Result<EnumDecl, ErrorType> {
+        let (enumerators, span) = enum_decl.into_parts();
+        let mut resolved_enumerators = Vec::new();
+        for (name, value) in enumerators {
+            let (identifier, name_span) = name.into_parts();
+            let symbol = identifier.get_symbol();
+
+            if let Some(prev_entry) = resolver_ctx.search_current_scope(&symbol) {
+                return Err(ErrorType::DuplicateDefintion {
+                    first: prev_entry.get_sp_identifier().get_span(),
+                    second: name_span,
+                });
+            }
+
+            let value = value
+                .map(|expr| self.resolve_expression(expr, resolver_ctx))
+                .transpose()?;
+            let count = self.get_var_count_and_increment();
+            let resolved_name = SpannedIdentifier::new(Identifier::new(symbol, count), name_span);
+            resolver_ctx.insert_entry(symbol, ResolverEntry::new(resolved_name, false));
+            resolved_enumerators.push((resolved_name, value));
+        }
+        Ok(EnumDecl::new(resolved_enumerators, span))
     }
 
     /// Resolves a local variable declaration by resolving its identifier and initialization expression.
     pub(super) fn resolve_local_variable_declaration(
         &mut self,
-        var_decl: VariableDecl,
+        mut var_decl: VariableDecl,
         resolver_ctx: &mut ResolverContext,
     ) -> Result<VariableDecl, ErrorType> {
+        let is_const = var_decl.is_const();
+        // the array length is resolved before the name is in scope
+        let array_len = var_decl
+            .take_array_len()
+            .map(|len| self.resolve_expression(len, resolver_ctx))
+            .transpose()?;
         let (name, var_type, mut init, storage_class, span) = var_decl.into_parts();
         let resolved_name =
             self.resolve_variable_declaration_identifier(name, storage_class, resolver_ctx)?;
         if let Some(expr) = init {
             init = Some(self.resolve_expression(expr, resolver_ctx)?);
         }
-        Ok(VariableDecl::new(
-            resolved_name,
-            var_type,
-            init,
-            storage_class,
-            span,
-        ))
+        Ok(
+            VariableDecl::new(resolved_name, var_type, init, storage_class, span)
+                .with_const(is_const)
+                .with_array_len(array_len),
+        )
     }
 
     /// Resolves a variable declaration's identifier.
@@ -112,7 +181,10 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
             return Ok(name);
         }
 
-        // Assign a unique compiler-generated ID to this variable
+        // Assign a unique compiler-generated ID to this variable.
+        // The counter is shared by the whole program and never reset between functions,
+        // so `static` locals with the same name get distinct ids, and thus distinct labels
+        // (e.g. `count.1` and `count.2`) once the emitter appends the id to the name
         let count = self.get_var_count_and_increment();
         let resolved_identifier = Identifier::new(symbol, count);
         let resolved_name = SpannedIdentifier::new(resolved_identifier, name_span);
@@ -168,19 +240,24 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
 
     /// Resolves function parameters.
     ///
-    /// Each parameter is treated as a variable declaration within the function scope.
+    /// Each parameter is treated as a variable declaration within the function scope,
+    /// an unnamed parameter of a prototype declares nothing.
     fn resolve_params(
         &mut self,
-        params: Vec<SpannedIdentifier>,
+        params: Vec<Option<SpannedIdentifier>>,
         resolver_ctx: &mut ResolverContext,
-    ) -> Result<Vec<SpannedIdentifier>, ErrorType> {
+    ) -> Result<Vec<Option<SpannedIdentifier>>, ErrorType> {
         let mut resolved_params = Vec::new();
         for param in params {
-            resolved_params.push(self.resolve_variable_declaration_identifier(
-                param,
-                StorageClass::None,
-                resolver_ctx,
-            )?);
+            let resolved_param = match param {
+                Some(param) => Some(self.resolve_variable_declaration_identifier(
+                    param,
+                    StorageClass::None,
+                    resolver_ctx,
+                )?),
+                None => None,
+            };
+            resolved_params.push(resolved_param);
         }
         Ok(resolved_params)
     }
@@ -192,11 +269,7 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         resolver_ctx: &mut ResolverContext,
     ) -> Result<Block, ErrorType> {
         let (block_items, span) = block.into_parts();
-        let mut resolved_block = Vec::new();
-        for item in block_items {
-            let resolved_item = self.resolve_block_item(item, resolver_ctx)?;
-            resolved_block.push(resolved_item);
-        }
+        let resolved_block = self.resolve_block_items(block_items, resolver_ctx)?;
         Ok(Block::new(resolved_block, span))
     }
 }
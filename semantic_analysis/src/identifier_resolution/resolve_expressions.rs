@@ -15,6 +15,8 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         expr: Expression,
         resolver_ctx: &mut ResolverContext,
     ) -> Result<Expression, ErrorType> {
+        // type checking warns about comparisons written without parentheses
+        let parenthesized = expr.is_parenthesized();
         let (inner, expr_type, span) = expr.into_parts();
 
         let resolved_inner = match inner {
@@ -37,12 +39,23 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
             InnerExpression::FunctionCall { name, args } => {
                 self.resolve_function_call(name, args, resolver_ctx)?
             }
+            InnerExpression::IndirectCall { callee, args } => {
+                self.resolve_indirect_call(*callee, args, resolver_ctx)?
+            }
             InnerExpression::Cast { target_type, expr } => {
                 self.resolve_cast_expression(*expr, target_type, resolver_ctx)?
             }
+            InnerExpression::Dereference(inner) => InnerExpression::Dereference(Box::new(
+                self.resolve_expression(*inner, resolver_ctx)?,
+            )),
+            InnerExpression::AddrOf(inner) => self.resolve_address_of(*inner, resolver_ctx)?,
+            InnerExpression::Subscript { array, index } => InnerExpression::Subscript {
+                array: Box::new(self.resolve_expression(*array, resolver_ctx)?),
+                index: Box::new(self.resolve_expression(*index, resolver_ctx)?),
+            },
         };
 
-        Ok(Expression::new(resolved_inner, expr_type, span))
+        Ok(Expression::new(resolved_inner, expr_type, span).with_parentheses(parenthesized))
     }
 
     /// Resolves an optional expression (may be `None`), returning a resolved `Option`.
@@ -73,31 +86,46 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
 
     /// Resolves an assignment expression.
     ///
-    /// Ensures that the left-hand side is a valid l-value (currently only variables).
+    /// Ensures that the left-hand side is a valid l-value (a variable or a dereference).
     fn resolve_assignment(
         &mut self,
         lvalue: Expression,
         rvalue: Expression,
         resolver_ctx: &mut ResolverContext,
     ) -> Result<InnerExpression, ErrorType> {
-        let lexpr_type = lvalue.get_inner_ref();
-
-        // Only variables can be assigned to
-        match lexpr_type {
-            InnerExpression::Var(_) => Ok(InnerExpression::Assignment {
-                lvalue: Box::new(self.resolve_expression(lvalue, resolver_ctx)?),
-                rvalue: Box::new(self.resolve_expression(rvalue, resolver_ctx)?),
-            }),
-            _ => {
-                let lspan = lvalue.get_span();
-                Err(ErrorType::InvalidLeftValue(lspan))
-            }
+        if !lvalue.is_lvalue() {
+            return Err(ErrorType::InvalidLeftValue(lvalue.get_span()));
         }
+
+        Ok(InnerExpression::Assignment {
+            lvalue: Box::new(self.resolve_expression(lvalue, resolver_ctx)?),
+            rvalue: Box::new(self.resolve_expression(rvalue, resolver_ctx)?),
+        })
+    }
+
+    /// Resolves an address-of expression (`&x`).
+    ///
+    /// Only l-values have an address.
+    fn resolve_address_of(
+        &mut self,
+        inner: Expression,
+        resolver_ctx: &mut ResolverContext,
+    ) -> Result<InnerExpression, ErrorType> {
+        if !inner.is_lvalue() {
+            return Err(ErrorType::InvalidOperand(
+                inner.get_span(),
+                "can't take the address of a value that isn't an l-value",
+            ));
+        }
+
+        Ok(InnerExpression::AddrOf(Box::new(
+            self.resolve_expression(inner, resolver_ctx)?,
+        )))
     }
 
     /// Resolves a variable by checking if it exists in any accessible scope.
     ///
-    /// Returns an error if the variable is undeclared.
+    /// Returns an error if the variable is undeclared, with `--keep-going` it's kept unresolved.
     fn resolve_variable(
         &mut self,
         name: SpannedIdentifier,
@@ -106,11 +134,13 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         let (identifier, span) = name.into_parts();
         let symbol = identifier.get_symbol();
 
-        if let Some(prev_entry) = resolver_ctx.search_scope(&symbol) {
-            Ok(InnerExpression::Var(prev_entry.get_sp_identifier()))
-        } else {
-            Err(ErrorType::UseOfUndeclared(span))
-        }
+        let resolved_name = match resolver_ctx.search_scope(&symbol) {
+            Some(prev_entry) => prev_entry.get_sp_identifier(),
+            None => self
+                .errors
+                .recover(Err(ErrorType::UseOfUndeclared(span)), || name)?,
+        };
+        Ok(InnerExpression::Var(resolved_name))
     }
 
     /// Resolves a binary operation expression.
@@ -165,28 +195,52 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
     /// Resolves a function call.
     ///
     /// Checks that the function is declared, then recursively resolves all argument expressions.
+    /// Functions follow the same rule as variables, C99 removed implicit declarations,
+    /// so calling a function defined later in the file needs a prototype before the call.
     fn resolve_function_call(
         &mut self,
         name: SpannedIdentifier,
-        args: Vec<Box<Expression>>,
+        args: Vec<Expression>,
         resolver_ctx: &mut ResolverContext,
     ) -> Result<InnerExpression, ErrorType> {
         let symbol = name.get_identifier().get_symbol();
 
-        if let Some(prev_entry) = resolver_ctx.search_scope(&symbol) {
-            let mut resolved_args = Vec::new();
-            for arg in args {
-                resolved_args.push(Box::new(self.resolve_expression(*arg, resolver_ctx)?));
-            }
+        // use the name of the previous entry, this is a delibertate design choice
+        // this will help the typechecker catch errors like using a variable as a function
+        // with `--keep-going` an undeclared function is kept unresolved to resolve the arguments
+        let resolved_name = match resolver_ctx.search_scope(&symbol) {
+            Some(prev_entry) => prev_entry.get_sp_identifier(),
+            None => self
+                .errors
+                .recover(Err(ErrorType::UseOfUndeclared(name.get_span())), || name)?,
+        };
+
+        let mut resolved_args = Vec::new();
+        for arg in args {
+            resolved_args.push(self.resolve_expression(arg, resolver_ctx)?);
+        }
+
+        Ok(InnerExpression::FunctionCall {
+            name: resolved_name,
+            args: resolved_args,
+        })
+    }
 
-            Ok(InnerExpression::FunctionCall {
-                // use the name of the previous entry, this is a delibertate design choice
-                // this will help the typechecker catch errors like using a variable as a function
-                name: prev_entry.get_sp_identifier(),
-                args: resolved_args,
-            })
-        } else {
-            Err(ErrorType::UseOfUndeclared(name.get_span()))
+    /// Resolves a call through a function pointer, the callee and then the arguments.
+    fn resolve_indirect_call(
+        &mut self,
+        callee: Expression,
+        args: Vec<Expression>,
+        resolver_ctx: &mut ResolverContext,
+    ) -> Result<InnerExpression, ErrorType> {
+        let callee = Box::new(self.resolve_expression(callee, resolver_ctx)?);
+        let mut resolved_args = Vec::new();
+        for arg in args {
+            resolved_args.push(self.resolve_expression(arg, resolver_ctx)?);
         }
+        Ok(InnerExpression::IndirectCall {
+            callee,
+            args: resolved_args,
+        })
     }
 }
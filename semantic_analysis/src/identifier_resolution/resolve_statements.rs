@@ -21,7 +21,7 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         // Resolve the inner statement type
         let resolved_stmt_type = match stmt_type {
             StatementType::Return(expr) => {
-                let expr = self.resolve_expression(expr, resolver_ctx)?;
+                let expr = self.resolve_optional_expr(expr, resolver_ctx)?;
                 StatementType::Return(expr)
             }
             StatementType::ExprStatement(expr) => {
@@ -158,18 +158,18 @@ impl<'src, 'ctx> IdentifierResolver<'src, 'ctx> {
         resolver_ctx: &mut ResolverContext,
     ) -> Result<ForInit, ErrorType> {
         match init {
-            ForInit::D(var_decl) => {
-                if var_decl.get_storage_class() != StorageClass::None {
-                    Err(ErrorType::InvalidStaticDecl(
-                        var_decl.get_span(),
-                        "can't declare a static variable inside a for loop header",
-                    ))
-                } else {
-                    Ok(ForInit::D(self.resolve_local_variable_declaration(
-                        var_decl,
-                        resolver_ctx,
-                    )?))
+            ForInit::D(var_decls) => {
+                let mut resolved = Vec::with_capacity(var_decls.len());
+                for var_decl in var_decls {
+                    if var_decl.get_storage_class() != StorageClass::None {
+                        return Err(ErrorType::InvalidStaticDecl(
+                            var_decl.get_span(),
+                            "can't declare a static variable inside a for loop header",
+                        ));
+                    }
+                    resolved.push(self.resolve_local_variable_declaration(var_decl, resolver_ctx)?);
                 }
+                Ok(ForInit::D(resolved))
             }
             ForInit::E(optional_expr) => Ok(ForInit::E(
                 self.resolve_optional_expr(optional_expr, resolver_ctx)?,
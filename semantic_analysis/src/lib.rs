@@ -1,32 +1,44 @@
 use identifier_resolution::IdentifierResolver;
 use loop_labeling::LoopLabeling;
 use parser::ast::Program;
+use return_check::ReturnCheck;
 use shared_context::{
     source_map::SourceMap, symbol_interner::SymbolInterner, symbol_table::SymbolTable,
     type_interner::TypeInterner,
 };
 use type_checker::TypeChecker;
 
-use crate::semantic_error::SemanticErr;
+pub use crate::semantic_error::SemanticErr;
+pub use crate::semantic_warning::SemanticWarning;
 
 // Modules for specific semantic passes
 mod identifier_resolution;
 mod loop_labeling;
+pub mod print_symbol_table;
+mod return_check;
 mod semantic_error;
+mod semantic_warning;
 mod type_checker;
 
 /// Run all semantic analysis passes on the AST.
-/// Returns the transformed AST and the final auto-variable counter.
+/// Returns the transformed AST, the final auto-variable counter and the warnings found.
 /// - The counter ensures that auto-generated variables in code generation won't collide.
+/// - The warnings are left to the caller to report, they never stop compilation.
+/// - `warn_sign_conversion` makes type checking warn about implicit conversions
+///   that can change a value's sign, like `-Wsign-conversion`.
+/// - `keep_going` makes identifier resolution and type checking continue past errors,
+///   the returned error then reports every error the failing pass found.
 pub fn analize<'src, 'ctx>(
-    ty_interner: &'ctx TypeInterner<'src>,
+    ty_interner: &'ctx mut TypeInterner<'src>,
     sy_interner: &'ctx mut SymbolInterner<'src>,
     symbol_table: &'ctx mut SymbolTable,
     source_map: &'ctx SourceMap<'src>,
     program: Program,
-) -> Result<(Program, usize), SemanticErr> {
+    warn_sign_conversion: bool,
+    keep_going: bool,
+) -> Result<(Program, usize, Vec<SemanticWarning>), SemanticErr> {
     // Identifier Resolution Pass
-    let mut id_resolver = IdentifierResolver::new(source_map);
+    let mut id_resolver = IdentifierResolver::new(source_map, keep_going);
     let resolved_program = id_resolver.resolve_program(program)?;
 
     // Loop Labeling Pass
@@ -35,9 +47,19 @@ pub fn analize<'src, 'ctx>(
     let counter = loop_labeling.get_label_count();
 
     // Type Checking Pass
-    let mut type_checker = TypeChecker::new(symbol_table, ty_interner, source_map);
+    let mut type_checker = TypeChecker::new(
+        symbol_table,
+        ty_interner,
+        source_map,
+        warn_sign_conversion,
+        keep_going,
+    );
     let checked_program = type_checker.typecheck_program(labeled_program)?;
+    let warnings = type_checker.take_warnings();
 
-    // Return fully processed AST and auto-variable counter
-    Ok((checked_program, counter))
+    // Return Check Pass
+    ReturnCheck::new(sy_interner, ty_interner, source_map).check_program(&checked_program)?;
+
+    // Return fully processed AST, auto-variable counter and warnings
+    Ok((checked_program, counter, warnings))
 }
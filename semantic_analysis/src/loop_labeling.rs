@@ -2,6 +2,27 @@ use crate::semantic_error::{ErrorType, SemanticErr};
 use parser::ast::*;
 use shared_context::{Identifier, Span, source_map::SourceMap, symbol_interner::SymbolInterner};
 
+/// The statements `break` and `continue` can jump to from the current position.
+///
+/// The two targets are tracked separately: a loop provides both, while a construct
+/// that is only a `break` target (such as a `switch`) would replace `break_target`
+/// and carry the enclosing `continue_target` forward unchanged.
+#[derive(Clone, Copy, Default)]
+struct JumpTargets {
+    break_target: Option<Identifier>,
+    continue_target: Option<Identifier>,
+}
+
+impl JumpTargets {
+    /// Targets inside the body of the loop labeled `label`.
+    fn for_loop(label: Identifier) -> Self {
+        Self {
+            break_target: Some(label),
+            continue_target: Some(label),
+        }
+    }
+}
+
 /// Second pass: labels each loop to support `break` and `continue`
 /// Ensures break/continue are used only inside loops
 pub(crate) struct LoopLabeling<'src, 'ctx> {
@@ -33,8 +54,10 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     ///
     /// This label will later serve as the target for `break` and `continue`
     /// jumps during code generation.
+    /// Like the labels made during IR generation, the name contains a `.`, so it can't
+    /// be produced by the lexer and never collides with a user identifier.
     fn make_label(&mut self) -> Identifier {
-        let s = format!("label_{}", self.label_counter);
+        let s = format!("label.{}", self.label_counter);
         self.label_counter += 1;
         let symbol = self.sy_interner.intern(&s);
         Identifier::new(symbol, 0)
@@ -54,10 +77,12 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
         let declarations = program.into_parts();
         let mut labeled_declarations = Vec::new();
         for decl in declarations {
-            // if it is avariable delcaration skip it
+            // if it is avariable or enum delcaration skip it
             // else label function declarations
             match decl {
-                Declaration::VarDecl(_) => labeled_declarations.push(decl),
+                Declaration::VarDecl(_) | Declaration::EnumDecl(_) => {
+                    labeled_declarations.push(decl)
+                }
                 Declaration::FunDecl(fun_decl) => labeled_declarations
                     .push(Declaration::FunDecl(self.label_function_decl(fun_decl)?)),
             }
@@ -72,7 +97,7 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     fn label_function_decl(&mut self, function: FunctionDecl) -> Result<FunctionDecl, SemanticErr> {
         let (name, type_id, params, body, storage_class, span) = function.into_parts();
         let labeled_body = if let Some(block) = body {
-            match self.label_block(block, None) {
+            match self.label_block(block, JumpTargets::default()) {
                 Ok(block) => Some(block),
                 Err(err) => return Err(SemanticErr::new(err, &self.source_map)),
             }
@@ -92,18 +117,13 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     /// Labels all loops and statements inside a block.
     ///
     /// # Parameters
-    /// - `curr_loop`: The label of the nearest enclosing loop, if any.
-    ///   Used to link `break` and `continue` to their correct targets.
-    fn label_block(
-        &mut self,
-        block: Block,
-        curr_loop: Option<Identifier>,
-    ) -> Result<Block, ErrorType> {
+    /// - `targets`: The labels `break` and `continue` currently jump to, if any.
+    fn label_block(&mut self, block: Block, targets: JumpTargets) -> Result<Block, ErrorType> {
         let (block_items, span) = block.into_parts();
         let mut labeled_block = Vec::new();
 
         for item in block_items {
-            let labeled_item = self.label_block_item(item, curr_loop)?;
+            let labeled_item = self.label_block_item(item, targets)?;
             labeled_block.push(labeled_item);
         }
 
@@ -115,10 +135,10 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     fn label_block_item(
         &mut self,
         item: BlockItem,
-        curr_loop: Option<Identifier>,
+        targets: JumpTargets,
     ) -> Result<BlockItem, ErrorType> {
         if let BlockItem::S(stmt) = item {
-            Ok(BlockItem::S(self.label_statement(stmt, curr_loop)?))
+            Ok(BlockItem::S(self.label_statement(stmt, targets)?))
         } else {
             Ok(item)
         }
@@ -128,19 +148,19 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     fn label_statement(
         &mut self,
         stmt: Statement,
-        curr_loop: Option<Identifier>,
+        targets: JumpTargets,
     ) -> Result<Statement, ErrorType> {
         let (stmt_type, span) = stmt.into_parts();
         let labeled_stmt_type = match stmt_type {
             // Validate that `break` appears inside a loop
-            StatementType::Break(_) => self.label_break_statement(curr_loop, span)?,
+            StatementType::Break(_) => self.label_break_statement(targets, span)?,
 
             // Validate that `continue` appears inside a loop
-            StatementType::Continue(_) => self.label_continue_statement(curr_loop, span)?,
+            StatementType::Continue(_) => self.label_continue_statement(targets, span)?,
 
             // Recurse into nested blocks
             StatementType::Compound(block) => {
-                StatementType::Compound(self.label_block(block, curr_loop)?)
+                StatementType::Compound(self.label_block(block, targets)?)
             }
 
             // Recurse into if/else structures
@@ -148,9 +168,9 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
                 condition,
                 if_clause,
                 else_clause,
-            } => self.label_if_statement(condition, *if_clause, else_clause, curr_loop)?,
+            } => self.label_if_statement(condition, *if_clause, else_clause, targets)?,
 
-            // Label loops and create a new `curr_loop` label for each one
+            // Label loops and make each one the target of its body's breaks/continues
             StatementType::While {
                 condition,
                 body,
@@ -177,18 +197,18 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     }
 
     /// Recursively labels `if` statements and their branches.
-    /// Carries `curr_loop` forward so inner breaks/continues remain valid.
+    /// Carries `targets` forward so inner breaks/continues remain valid.
     fn label_if_statement(
         &mut self,
         condition: Expression,
         if_clause: Statement,
         else_clause: Option<Box<Statement>>,
-        curr_loop: Option<Identifier>,
+        targets: JumpTargets,
     ) -> Result<StatementType, ErrorType> {
-        let if_clause = Box::new(self.label_statement(if_clause, curr_loop)?);
+        let if_clause = Box::new(self.label_statement(if_clause, targets)?);
 
         let else_clause = if let Some(stmt) = else_clause {
-            Some(Box::new(self.label_statement(*stmt, curr_loop)?))
+            Some(Box::new(self.label_statement(*stmt, targets)?))
         } else {
             None
         };
@@ -207,8 +227,7 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
         body: Statement,
     ) -> Result<StatementType, ErrorType> {
         let label = self.make_label();
-        let curr_loop = Some(label);
-        let body = Box::new(self.label_statement(body, curr_loop)?);
+        let body = Box::new(self.label_statement(body, JumpTargets::for_loop(label))?);
         Ok(StatementType::While {
             condition,
             body,
@@ -223,8 +242,7 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
         body: Statement,
     ) -> Result<StatementType, ErrorType> {
         let label = self.make_label();
-        let curr_loop = Some(label);
-        let body = Box::new(self.label_statement(body, curr_loop)?);
+        let body = Box::new(self.label_statement(body, JumpTargets::for_loop(label))?);
         Ok(StatementType::DoWhile {
             condition,
             body,
@@ -241,8 +259,7 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
         body: Statement,
     ) -> Result<StatementType, ErrorType> {
         let label = self.make_label();
-        let curr_loop = Some(label);
-        let body = Box::new(self.label_statement(body, curr_loop)?);
+        let body = Box::new(self.label_statement(body, JumpTargets::for_loop(label))?);
         Ok(StatementType::For {
             init,
             condition,
@@ -254,14 +271,14 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
 
     /// Handles and validates `break` statements.
     ///
-    /// - If inside a loop, attaches the enclosing loop's label.
+    /// - If there is a break target, attaches its label.
     /// - Otherwise, emits a `BreakErr`.
     fn label_break_statement(
         &mut self,
-        curr_loop: Option<Identifier>,
+        targets: JumpTargets,
         span: Span,
     ) -> Result<StatementType, ErrorType> {
-        if let Some(label) = curr_loop {
+        if let Some(label) = targets.break_target {
             Ok(StatementType::Break(label))
         } else {
             Err(ErrorType::BreakErr(span))
@@ -271,13 +288,14 @@ impl<'src, 'ctx> LoopLabeling<'src, 'ctx> {
     /// Handles and validates `continue` statements.
     ///
     /// - If inside a loop, attaches the enclosing loop's label.
+    ///   Only loops are `continue` targets.
     /// - Otherwise, emits a `ContinueErr`.
     fn label_continue_statement(
         &mut self,
-        curr_loop: Option<Identifier>,
+        targets: JumpTargets,
         span: Span,
     ) -> Result<StatementType, ErrorType> {
-        if let Some(label) = curr_loop {
+        if let Some(label) = targets.continue_target {
             Ok(StatementType::Continue(label))
         } else {
             Err(ErrorType::ContinueErr(span))
@@ -0,0 +1,66 @@
+use shared_context::symbol_interner::SymbolInterner;
+use shared_context::symbol_table::{EntryType, SymbolTable};
+use shared_context::type_interner::TypeInterner;
+use shared_context::{Identifier, Type};
+
+/// SymbolTablePrinter prints every entry of the symbol table built by type checking,
+/// with its type, storage attributes and initializer.
+pub struct SymbolTablePrinter<'a> {
+    ty_interner: &'a TypeInterner<'a>,   // used to get functions types
+    sy_interner: &'a SymbolInterner<'a>, // used to resolve identifiers to their string names
+}
+
+impl<'a> SymbolTablePrinter<'a> {
+    pub fn new(ty_interner: &'a TypeInterner<'a>, sy_interner: &'a SymbolInterner<'a>) -> Self {
+        Self {
+            ty_interner,
+            sy_interner,
+        }
+    }
+
+    /// Prints the entries sorted by name, so the output is stable between runs
+    pub fn print(&self, symbol_table: &SymbolTable) {
+        println!("SymbolTable");
+
+        let mut entries: Vec<_> = symbol_table
+            .get_table_ref()
+            .iter()
+            .map(|(iden, entry)| (self.format_unique_identifier(*iden), entry))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, entry) in entries {
+            println!(
+                "  {}: {} {:?}",
+                name,
+                self.format_entry_type(entry.entry_type),
+                entry.attributes
+            );
+        }
+    }
+
+    fn format_entry_type(&self, entry_type: EntryType) -> String {
+        match entry_type {
+            EntryType::Scalar(var_type) => {
+                format!("Scalar({})", self.ty_interner.format_type(var_type))
+            }
+            EntryType::Func(type_id) => self.ty_interner.format_type(Type::Function(type_id)),
+            EntryType::Array { element, count } => {
+                format!(
+                    "Array({}[{}])",
+                    self.ty_interner.format_type(element),
+                    count
+                )
+            }
+        }
+    }
+
+    // locals with the same name are told apart by their id
+    fn format_unique_identifier(&self, identifier: Identifier) -> String {
+        let name = self.sy_interner.lookup(identifier.get_symbol());
+        match identifier.get_id() {
+            0 => name.to_string(),
+            id => format!("{}.{}", name, id),
+        }
+    }
+}
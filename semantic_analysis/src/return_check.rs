@@ -0,0 +1,167 @@
+use crate::semantic_error::{ErrorType, SemanticErr};
+use parser::ast::*;
+use shared_context::type_interner::TypeInterner;
+use shared_context::{Identifier, Type, source_map::SourceMap, symbol_interner::SymbolInterner};
+
+/// Fourth pass: rejects functions where control can reach the end of the body
+/// without a `return`.
+///
+/// `main` is an exception, it returns 0 when control reaches its end, like C99 requires.
+/// So is a `void` function, reaching its end returns like `return;`.
+/// The analysis is structural and conservative: a loop only runs forever when its
+/// condition is missing or a non-zero constant and it has no `break` of its own,
+/// and calls are assumed to return.
+pub(crate) struct ReturnCheck<'src, 'ctx> {
+    sy_interner: &'ctx SymbolInterner<'src>,
+    ty_interner: &'ctx TypeInterner<'src>, // used to get functions return types
+    source_map: &'ctx SourceMap<'src>,
+}
+
+impl<'src, 'ctx> ReturnCheck<'src, 'ctx> {
+    pub fn new(
+        sy_interner: &'ctx SymbolInterner<'src>,
+        ty_interner: &'ctx TypeInterner<'src>,
+        source_map: &'ctx SourceMap<'src>,
+    ) -> Self {
+        Self {
+            sy_interner,
+            ty_interner,
+            source_map,
+        }
+    }
+
+    /// Checks every function definition in the program.
+    pub fn check_program(&self, program: &Program) -> Result<(), SemanticErr> {
+        for decl in program.get_declarations_ref() {
+            if let Declaration::FunDecl(fun_decl) = decl {
+                self.check_function_decl(fun_decl)
+                    .map_err(|err| SemanticErr::new(err, self.source_map))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_function_decl(&self, function: &FunctionDecl) -> Result<(), ErrorType> {
+        let name = function.get_sp_identifier();
+        let is_main = self.sy_interner.lookup(name.get_identifier().get_symbol()) == "main";
+        let is_void = self.ty_interner.get(function.get_type_id()).ret == Type::Void;
+
+        match function.get_body_ref() {
+            Some(body) if !is_main && !is_void && Self::block_can_complete(body) => {
+                Err(ErrorType::MissingReturn(name.get_span()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether control can reach the end of the block.
+    /// Once a statement can't complete, the statements after it are unreachable.
+    fn block_can_complete(block: &Block) -> bool {
+        block.get_items_ref().iter().all(|item| match item {
+            BlockItem::S(stmt) => Self::statement_can_complete(stmt),
+            BlockItem::D(_) => true,
+        })
+    }
+
+    /// Whether control can reach the statement following `stmt`.
+    fn statement_can_complete(stmt: &Statement) -> bool {
+        match stmt.get_type_ref() {
+            // control leaves to the caller or to the enclosing loop
+            StatementType::Return(_) | StatementType::Break(_) | StatementType::Continue(_) => {
+                false
+            }
+
+            StatementType::IfStatement {
+                if_clause,
+                else_clause,
+                ..
+            } => match else_clause {
+                Some(else_clause) => {
+                    Self::statement_can_complete(if_clause)
+                        || Self::statement_can_complete(else_clause)
+                }
+                None => true,
+            },
+
+            StatementType::While {
+                condition,
+                body,
+                label,
+            } => !Self::is_always_true(Some(condition)) || Self::has_break(body, *label),
+            StatementType::For {
+                condition,
+                body,
+                label,
+                ..
+            } => !Self::is_always_true(condition.as_ref()) || Self::has_break(body, *label),
+
+            // the condition is reached by completing the body or through a `continue`
+            StatementType::DoWhile {
+                condition,
+                body,
+                label,
+            } => {
+                let reaches_condition =
+                    Self::statement_can_complete(body) || Self::has_continue(body, *label);
+                Self::has_break(body, *label)
+                    || (reaches_condition && !Self::is_always_true(Some(condition)))
+            }
+
+            StatementType::Compound(block) => Self::block_can_complete(block),
+            StatementType::ExprStatement(_) | StatementType::Null => true,
+        }
+    }
+
+    /// Whether a loop condition never becomes false: missing, or a non-zero constant.
+    fn is_always_true(condition: Option<&Expression>) -> bool {
+        match condition.map(|expr| expr.get_inner_ref()) {
+            None => true,
+            Some(InnerExpression::Constant(cons)) => !cons.is_zero(),
+            Some(_) => false,
+        }
+    }
+
+    /// Whether `stmt` contains a `break` out of the loop labeled `label`.
+    fn has_break(stmt: &Statement, label: Identifier) -> bool {
+        Self::any_statement(
+            stmt,
+            &|stmt| matches!(stmt.get_type_ref(), StatementType::Break(target) if *target == label),
+        )
+    }
+
+    /// Whether `stmt` contains a `continue` of the loop labeled `label`.
+    fn has_continue(stmt: &Statement, label: Identifier) -> bool {
+        Self::any_statement(
+            stmt,
+            &|stmt| matches!(stmt.get_type_ref(), StatementType::Continue(target) if *target == label),
+        )
+    }
+
+    /// Whether `pred` holds for `stmt` or any statement nested in it.
+    fn any_statement(stmt: &Statement, pred: &dyn Fn(&Statement) -> bool) -> bool {
+        if pred(stmt) {
+            return true;
+        }
+
+        match stmt.get_type_ref() {
+            StatementType::IfStatement {
+                if_clause,
+                else_clause,
+                ..
+            } => {
+                Self::any_statement(if_clause, pred)
+                    || else_clause
+                        .as_ref()
+                        .is_some_and(|else_clause| Self::any_statement(else_clause, pred))
+            }
+            StatementType::While { body, .. }
+            | StatementType::DoWhile { body, .. }
+            | StatementType::For { body, .. } => Self::any_statement(body, pred),
+            StatementType::Compound(block) => block.get_items_ref().iter().any(|item| match item {
+                BlockItem::S(stmt) => Self::any_statement(stmt, pred),
+                BlockItem::D(_) => false,
+            }),
+            _ => false,
+        }
+    }
+}
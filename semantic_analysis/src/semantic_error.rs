@@ -21,6 +21,7 @@ pub enum ErrorType {
         span: Span,      // Span of the call expression
         expected: usize, // Number of expected arguments
         got: usize,      // Number of arguments provided
+        variadic: bool,  // whether `expected` is only the minimum
     },
     /// Using an identifier that has not been declared
     UseOfUndeclared(Span),
@@ -34,8 +35,6 @@ pub enum ErrorType {
     NestedFunctionDecl(Span),
     /// Attempt to call a variable as a function
     VariableAsFunction(Span),
-    /// Attempt to use a function as a variable
-    FunctionAsVariable(Span),
     /// Incompatible linkage between the same identifier
     IncompatibleLinkage {
         first: Span,           // span of the first declatation
@@ -45,8 +44,70 @@ pub enum ErrorType {
     },
     /// Invalid initializer
     InvalidInitializer(Span),
+    /// A non-constant expression where C requires a constant (e.g., `int g = f();`)
+    NotConstant(Span, &'static str),
     /// locar static function declaration
     InvalidStaticDecl(Span, &'static str),
+    /// An operator applied to an operand type it doesn't support (e.g., `~` on a double)
+    InvalidOperand(Span, &'static str),
+    /// assignment to a variable declared with the `const` qualifier
+    AssignToConst(Span),
+    /// control can reach the end of a function other than `main` without a `return`
+    MissingReturn(Span),
+    /// A value converted to a type it can't be implicitly converted to (e.g., `int` to `int *`)
+    IncompatibleTypes(Span, &'static str),
+    /// The result of a call to a `void` function used as a value
+    VoidValue(Span),
+    /// A `return` with a value in a `void` function, or without one in any other function
+    InvalidReturn(Span, &'static str),
+    /// An array declared where only automatic locals can be arrays, or with an initializer
+    InvalidArray(Span, &'static str),
+}
+
+/// The errors found by a pass.
+///
+/// By default the first error stops the pass. With `keep_going` the pass records the error,
+/// replaces the node that failed with a recovered one and continues, so a run can report
+/// every error at once.
+pub(crate) struct ErrorRecovery {
+    keep_going: bool,
+    errors: Vec<ErrorType>,
+}
+
+impl ErrorRecovery {
+    pub(crate) fn new(keep_going: bool) -> Self {
+        Self {
+            keep_going,
+            errors: Vec::new(),
+        }
+    }
+
+    /// The result of a node, or the node made by `recovered` after recording the error
+    /// when errors are collected
+    pub(crate) fn recover<T>(
+        &mut self,
+        result: Result<T, ErrorType>,
+        recovered: impl FnOnce() -> T,
+    ) -> Result<T, ErrorType> {
+        match result {
+            Err(err) if self.keep_going => {
+                self.errors.push(err);
+                Ok(recovered())
+            }
+            result => result,
+        }
+    }
+
+    /// The errors collected so far as one error, `Ok` if there are none
+    pub(crate) fn take_errors(&mut self, source_map: &SourceMap) -> Result<(), SemanticErr> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(SemanticErr::from_errors(
+            std::mem::take(&mut self.errors),
+            source_map,
+        ))
+    }
 }
 
 /// SemanticErr wraps a formatted error message for display purposes
@@ -70,7 +131,8 @@ impl SemanticErr {
                 span,
                 expected,
                 got,
-            } => Self::format_wrong_number_of_args_err(source_map, span, expected, got),
+                variadic,
+            } => Self::format_wrong_number_of_args_err(source_map, span, expected, got, variadic),
             ErrorType::UseOfUndeclared(span) => {
                 Self::format_use_of_undeclared_err(source_map, span)
             }
@@ -85,9 +147,6 @@ impl SemanticErr {
             ErrorType::VariableAsFunction(span) => {
                 Self::format_variable_as_function_err(source_map, span)
             }
-            ErrorType::FunctionAsVariable(span) => {
-                Self::format_function_as_variable_err(source_map, span)
-            }
             ErrorType::IncompatibleLinkage {
                 first,
                 second,
@@ -103,14 +162,43 @@ impl SemanticErr {
             ErrorType::InvalidInitializer(span) => {
                 Self::format_invalid_initializer_err(source_map, span)
             }
+            ErrorType::NotConstant(span, message) => {
+                Self::format_not_constant_err(source_map, span, message)
+            }
             ErrorType::InvalidStaticDecl(span, message) => {
                 Self::format_invalid_static_function_decl_err(source_map, span, message)
             }
+            ErrorType::InvalidOperand(span, message) => {
+                Self::format_invalid_operand_err(source_map, span, message)
+            }
+            ErrorType::AssignToConst(span) => Self::format_assign_to_const_err(source_map, span),
+            ErrorType::MissingReturn(span) => Self::format_missing_return_err(source_map, span),
+            ErrorType::IncompatibleTypes(span, message) => {
+                Self::format_incompatible_types_err(source_map, span, message)
+            }
+            ErrorType::VoidValue(span) => Self::format_void_value_err(source_map, span),
+            ErrorType::InvalidReturn(span, message) => {
+                Self::format_invalid_return_err(source_map, span, message)
+            }
+            ErrorType::InvalidArray(span, message) => {
+                Self::format_invalid_array_err(source_map, span, message)
+            }
         };
 
         Self { formated_error }
     }
 
+    /// Construct one error reporting several, in the order they were found.
+    /// Every message after the first starts with `error: `, like the driver prints the first.
+    pub(crate) fn from_errors(errors: Vec<ErrorType>, source_map: &SourceMap) -> Self {
+        let formated_error = errors
+            .into_iter()
+            .map(|err| Self::new(err, source_map).formated_error)
+            .collect::<Vec<_>>()
+            .join("\nerror: ");
+        Self { formated_error }
+    }
+
     // Each of these functions generates a human-readable error message
     // including the relevant source code snippet using `source_map`.
 
@@ -139,9 +227,11 @@ impl SemanticErr {
         span: Span,
         expected: usize,
         got: usize,
+        variadic: bool,
     ) -> String {
         format!(
-            "expected {} argumnets, got {}\n{}",
+            "expected {}{} argumnets, got {}\n{}",
+            if variadic { "at least " } else { "" },
             expected,
             got,
             source_map.format_message("", span)
@@ -190,13 +280,6 @@ impl SemanticErr {
         )
     }
 
-    fn format_function_as_variable_err(source_map: &SourceMap, span: Span) -> String {
-        format!(
-            "can't use a function as a variable\n{}",
-            source_map.format_message("", span)
-        )
-    }
-
     fn format_incompatible_linkage_err(
         source_map: &SourceMap,
         first: Span,
@@ -228,6 +311,13 @@ impl SemanticErr {
         )
     }
 
+    fn format_not_constant_err(source_map: &SourceMap, span: Span, message: &str) -> String {
+        format!(
+            "expression is not a constant\n{}",
+            source_map.format_message(message, span)
+        )
+    }
+
     fn format_invalid_static_function_decl_err(
         source_map: &SourceMap,
         span: Span,
@@ -238,6 +328,55 @@ impl SemanticErr {
             source_map.format_message(message, span)
         )
     }
+
+    fn format_invalid_operand_err(source_map: &SourceMap, span: Span, message: &str) -> String {
+        format!(
+            "invalid operand\n{}",
+            source_map.format_message(message, span)
+        )
+    }
+
+    fn format_assign_to_const_err(source_map: &SourceMap, span: Span) -> String {
+        format!(
+            "assignment to a const variable\n{}",
+            source_map.format_message("variables declared `const` can only be initialized", span)
+        )
+    }
+
+    fn format_missing_return_err(source_map: &SourceMap, span: Span) -> String {
+        format!(
+            "missing return statement\n{}",
+            source_map.format_message("control can reach the end of this function", span)
+        )
+    }
+
+    fn format_incompatible_types_err(source_map: &SourceMap, span: Span, message: &str) -> String {
+        format!(
+            "incompatible types\n{}",
+            source_map.format_message(message, span)
+        )
+    }
+
+    fn format_void_value_err(source_map: &SourceMap, span: Span) -> String {
+        format!(
+            "void value used\n{}",
+            source_map.format_message("a call to a void function has no value to use", span)
+        )
+    }
+
+    fn format_invalid_return_err(source_map: &SourceMap, span: Span, message: &str) -> String {
+        format!(
+            "invalid return statement\n{}",
+            source_map.format_message(message, span)
+        )
+    }
+
+    fn format_invalid_array_err(source_map: &SourceMap, span: Span, message: &str) -> String {
+        format!(
+            "invalid array declaration\n{}",
+            source_map.format_message(message, span)
+        )
+    }
 }
 
 // Implement `Display` so semantic errors can be printed nicely
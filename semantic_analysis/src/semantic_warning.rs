@@ -0,0 +1,151 @@
+use core::fmt;
+use shared_context::source_map::SourceMap;
+use shared_context::{Const, Span, Type};
+
+/// Enum representing the non-fatal diagnostics semantic analysis can report.
+/// Unlike `ErrorType`, a warning never stops the compilation.
+#[derive(Debug)]
+pub enum WarningType {
+    /// A constant initializer that doesn't fit in the type of the variable it initializes
+    InitializerOverflow {
+        span: Span,   // Span of the initializer
+        value: Const, // The constant as written
+        target: Type, // The type of the variable
+    },
+    /// A comparison with an operand that is itself a comparison without parentheses,
+    /// `a < b < c` compares the 0 or 1 result of `a < b` with `c`
+    ChainedComparison(Span), // Span of the outer comparison
+    /// An assignment of a variable to itself, `x = x`, which has no effect
+    SelfAssignment(Span), // Span of the assignment
+    /// An implicit conversion between a signed and an unsigned type of the same size,
+    /// reported with `-Wsign-conversion`
+    SignConversion {
+        span: Span,           // Span of the converted expression
+        value: Option<Const>, // The constant whose sign changes, `None` if it isn't a constant
+        source: Type,         // The type of the expression
+        target: Type,         // The type it's converted to
+    },
+}
+
+/// SemanticWarning wraps a formatted warning message for display purposes
+#[derive(Debug)]
+pub struct SemanticWarning {
+    formated_warning: String,
+}
+
+impl SemanticWarning {
+    /// Construct a new semantic warning from a WarningType
+    /// The `source_map` is used to generate human-readable source code references
+    pub fn new(warning: WarningType, source_map: &SourceMap) -> Self {
+        let formated_warning = match warning {
+            WarningType::InitializerOverflow {
+                span,
+                value,
+                target,
+            } => Self::format_initializer_overflow_warning(source_map, span, value, target),
+            WarningType::ChainedComparison(span) => {
+                Self::format_chained_comparison_warning(source_map, span)
+            }
+            WarningType::SelfAssignment(span) => {
+                Self::format_self_assignment_warning(source_map, span)
+            }
+            WarningType::SignConversion {
+                span,
+                value,
+                source,
+                target,
+            } => Self::format_sign_conversion_warning(source_map, span, value, source, target),
+        };
+
+        Self { formated_warning }
+    }
+
+    fn format_initializer_overflow_warning(
+        source_map: &SourceMap,
+        span: Span,
+        value: Const,
+        target: Type,
+    ) -> String {
+        format!(
+            "overflow in conversion to {} changes the value of the initializer\n{}",
+            target.c_name(),
+            source_map.format_message(
+                &format!(
+                    "{} doesn't fit in {}",
+                    Self::format_const(value),
+                    target.c_name()
+                ),
+                span
+            )
+        )
+    }
+
+    fn format_chained_comparison_warning(source_map: &SourceMap, span: Span) -> String {
+        format!(
+            "comparisons like `a < b < c` don't have their mathematical meaning\n{}",
+            source_map.format_message(
+                "the result of the inner comparison (0 or 1) is compared, add parentheses to silence this warning",
+                span
+            )
+        )
+    }
+
+    fn format_self_assignment_warning(source_map: &SourceMap, span: Span) -> String {
+        format!(
+            "a variable is assigned to itself\n{}",
+            source_map.format_message("this assignment has no effect", span)
+        )
+    }
+
+    fn format_sign_conversion_warning(
+        source_map: &SourceMap,
+        span: Span,
+        value: Option<Const>,
+        source: Type,
+        target: Type,
+    ) -> String {
+        match value {
+            Some(value) => format!(
+                "conversion from {} to {} changes the sign of the value\n{}",
+                source.c_name(),
+                target.c_name(),
+                source_map.format_message(
+                    &format!(
+                        "{} becomes {}",
+                        Self::format_const(value),
+                        Self::format_const(value.convert_to(target))
+                    ),
+                    span
+                )
+            ),
+            None => format!(
+                "conversion from {} to {} may change the sign of the value\n{}",
+                source.c_name(),
+                target.c_name(),
+                source_map.format_message(
+                    "the value is converted implicitly, add a cast to silence this warning",
+                    span
+                )
+            ),
+        }
+    }
+
+    // helper to print a constant the way it was written
+    fn format_const(value: Const) -> String {
+        match value {
+            Const::ConstInt(v) => v.to_string(),
+            Const::ConstLong(v) => v.to_string(),
+            Const::ConstUint(v) => v.to_string(),
+            Const::ConstUlong(v) => v.to_string(),
+            Const::ConstDouble(v) => v.to_string(),
+            Const::ConstBool(v) => (v as i32).to_string(),
+        }
+    }
+}
+
+// Implement `Display` so semantic warnings can be printed nicely
+impl fmt::Display for SemanticWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.formated_warning)
+    }
+}
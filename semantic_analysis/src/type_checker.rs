@@ -1,11 +1,14 @@
-use crate::semantic_error::{ErrorType, SemanticErr};
+use crate::semantic_error::{ErrorRecovery, ErrorType, SemanticErr};
+use crate::semantic_warning::{SemanticWarning, WarningType};
 use parser::ast::*;
 use shared_context::{
+    Const, Type,
     source_map::SourceMap,
-    symbol_table::SymbolTable,
+    symbol_table::{EntryType, IdenAttrs, SymbolTable},
     type_interner::{TypeID, TypeInterner},
 };
 
+mod typecheck_constants;
 mod typecheck_expressions;
 mod typecheck_functions;
 mod typecheck_statements;
@@ -14,9 +17,12 @@ mod typecheck_variables;
 /// Third pass: type checking
 /// Ensures static typing rules are respected and expressions are correctly typed
 pub(crate) struct TypeChecker<'src, 'ctx> {
-    ty_interner: &'ctx TypeInterner<'src>,
+    ty_interner: &'ctx mut TypeInterner<'src>,
     symbol_table: &'ctx mut SymbolTable,
     source_map: &'ctx SourceMap<'src>,
+    warn_sign_conversion: bool, // report implicit conversions that can change a value's sign
+    errors: ErrorRecovery,      // the errors recovered from in `--keep-going` mode
+    warnings: Vec<WarningType>, // the warnings reported so far, formatted once checking ends
 }
 
 impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
@@ -29,15 +35,24 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     /// 1. Every expression and statement is type-consistent.
     /// 2. All variable and function references adhere to declared types.
     /// 3. No invalid operations occur between incompatible types.
+    ///
+    /// `warn_sign_conversion` enables the `-Wsign-conversion` warning.
+    /// With `keep_going` a declaration or statement that fails is dropped and checking
+    /// continues, all the errors are reported at the end.
     pub fn new(
         symbol_table: &'ctx mut SymbolTable,
-        ty_interner: &'ctx TypeInterner<'src>,
+        ty_interner: &'ctx mut TypeInterner<'src>,
         source_map: &'ctx SourceMap<'src>,
+        warn_sign_conversion: bool,
+        keep_going: bool,
     ) -> Self {
         Self {
             ty_interner,
             symbol_table,
             source_map,
+            warn_sign_conversion,
+            errors: ErrorRecovery::new(keep_going),
+            warnings: Vec::new(),
         }
     }
 
@@ -49,18 +64,34 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     pub fn typecheck_program(&mut self, program: Program) -> Result<Program, SemanticErr> {
         let declarations = program.into_parts();
         let mut typechecked_declarations = Vec::new();
+        self.register_file_scope_functions(&declarations);
 
         for decl in declarations {
-            let checked_decl = match self.typecheck_global_declaration(decl) {
-                Ok(fun) => fun,
+            let checked_decl = self.typecheck_global_declaration(decl);
+            match self.errors.recover(checked_decl.map(Some), || None) {
+                Ok(Some(fun)) => typechecked_declarations.push(fun),
+                Ok(None) => {}
                 Err(err) => return Err(SemanticErr::new(err, &self.source_map)),
             };
-            typechecked_declarations.push(checked_decl);
         }
 
+        self.errors.take_errors(self.source_map)?;
         Ok(Program::new(typechecked_declarations))
     }
 
+    /// Record a non-fatal diagnostic, type checking continues afterwards.
+    fn report_warning(&mut self, warning: WarningType) {
+        self.warnings.push(warning);
+    }
+
+    /// The warnings reported while checking the program, in the order they were found
+    pub fn take_warnings(&mut self) -> Vec<SemanticWarning> {
+        std::mem::take(&mut self.warnings)
+            .into_iter()
+            .map(|warning| SemanticWarning::new(warning, self.source_map))
+            .collect()
+    }
+
     /// Recursively type checks all statements and declarations in a block.
     /// curr_fun store the ID the current enclosing function, this is used to typecheck return statements
     fn typecheck_block(&mut self, block: Block, curr_fun: TypeID) -> Result<Block, ErrorType> {
@@ -68,7 +99,10 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         let mut checked_block_items = Vec::new();
 
         for item in block_items {
-            checked_block_items.push(self.typecheck_block_item(item, curr_fun)?);
+            let checked_item = self.typecheck_block_item(item, curr_fun);
+            if let Some(checked_item) = self.errors.recover(checked_item.map(Some), || None)? {
+                checked_block_items.push(checked_item);
+            }
         }
 
         Ok(Block::new(checked_block_items, span))
@@ -86,7 +120,7 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         }
     }
 
-    /// Type checks a declaration node (function or variable).
+    /// Type checks a declaration node (function, variable or enum).
     fn typecheck_local_declaration(&mut self, decl: Declaration) -> Result<Declaration, ErrorType> {
         match decl {
             Declaration::FunDecl(fun_decl) => Ok(Declaration::FunDecl(
@@ -95,6 +129,9 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             Declaration::VarDecl(var_decl) => Ok(Declaration::VarDecl(
                 self.typecheck_local_variable_declaration(var_decl)?,
             )),
+            Declaration::EnumDecl(enum_decl) => Ok(Declaration::EnumDecl(
+                self.typecheck_enum_declaration(enum_decl)?,
+            )),
         }
     }
 
@@ -109,6 +146,39 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             Declaration::VarDecl(var_decl) => Ok(Declaration::VarDecl(
                 self.typecheck_global_variable_declaration(var_decl)?,
             )),
+            Declaration::EnumDecl(enum_decl) => Ok(Declaration::EnumDecl(
+                self.typecheck_enum_declaration(enum_decl)?,
+            )),
+        }
+    }
+
+    /// Registers the enumerators of an enum declaration as `int` constants.
+    ///
+    /// A value is a constant expression truncated to `int`, which can use the enumerators
+    /// before it. An enumerator without a value is one more than the previous one.
+    fn typecheck_enum_declaration(&mut self, enum_decl: EnumDecl) -> Result<EnumDecl, ErrorType> {
+        let mut next_value: i32 = 0;
+        for (name, value) in enum_decl.get_enumerators_ref() {
+            if let Some(expr) = value {
+                let cons = self.eval_const_expr(expr)?;
+                if cons.get_type().is_double() {
+                    return Err(ErrorType::NotConstant(
+                        expr.get_span(),
+                        "enumerator value must be an integer constant",
+                    ));
+                }
+                if let Const::ConstInt(v) = cons.convert_to(Type::Int) {
+                    next_value = v;
+                }
+            }
+            self.symbol_table.add(
+                name.get_identifier(),
+                EntryType::Scalar(Type::Int),
+                IdenAttrs::ConstantAttrs(next_value),
+                name.get_span(),
+            );
+            next_value = next_value.wrapping_add(1);
         }
+        Ok(enum_decl)
     }
 }
@@ -0,0 +1,164 @@
+use crate::{TypeChecker, semantic_error::ErrorType};
+use parser::ast::{BinaryOP, Expression, InnerExpression, UnaryOP};
+use shared_context::{Const, Span, Type, symbol_table::IdenAttrs};
+use std::cmp::Ordering;
+
+// This file contains the evaluation of constant expressions at compile time,
+// used wherever C requires a constant, like the initializer of a static variable.
+
+impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
+    /// Evaluate a constant expression: constants, enumerators, casts and the
+    /// unary, binary and conditional operators applied to them.
+    ///
+    /// The expression doesn't need to be type checked, operands are converted to their
+    /// common type like type checking would, so `2 + 3` is `5` and `1 + 2L` is `3L`.
+    /// Integer operations wrap around their type's width like they would at run time.
+    pub(crate) fn eval_const_expr(&self, expr: &Expression) -> Result<Const, ErrorType> {
+        let span = expr.get_span();
+        match expr.get_inner_ref() {
+            InnerExpression::Constant(cons) => Ok(*cons),
+
+            InnerExpression::Var(sp_ident) => match self
+                .symbol_table
+                .lookup(sp_ident.get_identifier())
+                .map(|entry| entry.attributes)
+            {
+                Some(IdenAttrs::ConstantAttrs(value)) => Ok(Const::ConstInt(value)),
+                _ => Err(ErrorType::NotConstant(
+                    span,
+                    "only enumerators can be used in a constant expression, not variables",
+                )),
+            },
+
+            InnerExpression::Cast {
+                target_type: Type::Void,
+                ..
+            } => Err(ErrorType::NotConstant(
+                span,
+                "a cast to void discards the value",
+            )),
+            InnerExpression::Cast { target_type, expr } => {
+                Ok(self.eval_const_expr(expr)?.convert_to(*target_type))
+            }
+
+            InnerExpression::Unary { operator, operand } => {
+                let value = self.eval_const_expr(operand)?;
+                match operator {
+                    UnaryOP::Neg => Ok(-value),
+                    UnaryOP::LogicalNot => Ok(Self::const_from_bool(value.is_zero())),
+                    UnaryOP::BitwiseNot if value.get_type().is_double() => {
+                        Err(ErrorType::InvalidOperand(
+                            span,
+                            "bitwise complement can't be applied to a double",
+                        ))
+                    }
+                    // `~x` is `-x - 1` in two's complement
+                    UnaryOP::BitwiseNot => {
+                        let one = Const::ConstInt(1).convert_to(value.get_type());
+                        Ok(-value - one)
+                    }
+                }
+            }
+
+            InnerExpression::Binary {
+                operator,
+                operand1,
+                operand2,
+            } => {
+                let value1 = self.eval_const_expr(operand1)?;
+                let value2 = self.eval_const_expr(operand2)?;
+                Self::eval_const_binary(*operator, value1, value2, span)
+            }
+
+            InnerExpression::Conditional { cond, cons, alt } => {
+                let cond = self.eval_const_expr(cond)?;
+                let (cons, alt) = (self.eval_const_expr(cons)?, self.eval_const_expr(alt)?);
+                let common_type = Self::get_common_type(cons.get_type(), alt.get_type());
+                let value = if cond.to_bool() { cons } else { alt };
+                Ok(value.convert_to(common_type))
+            }
+
+            InnerExpression::Assignment { .. }
+            | InnerExpression::FunctionCall { .. }
+            | InnerExpression::IndirectCall { .. }
+            | InnerExpression::Dereference(_)
+            | InnerExpression::AddrOf(_)
+            | InnerExpression::Subscript { .. } => Err(ErrorType::NotConstant(
+                span,
+                "assignments, calls and pointer operations are evaluated at run time",
+            )),
+        }
+    }
+
+    /// Apply a binary operator to two constants, after converting them to their common type.
+    fn eval_const_binary(
+        operator: BinaryOP,
+        value1: Const,
+        value2: Const,
+        span: Span,
+    ) -> Result<Const, ErrorType> {
+        if operator.is_logical() {
+            let result = match operator {
+                BinaryOP::LogicalAnd => value1.to_bool() && value2.to_bool(),
+                _ => value1.to_bool() || value2.to_bool(),
+            };
+            return Ok(Self::const_from_bool(result));
+        }
+
+        let common_type = Self::get_common_type(value1.get_type(), value2.get_type());
+        let (value1, value2) = (
+            value1.convert_to(common_type),
+            value2.convert_to(common_type),
+        );
+
+        match operator {
+            BinaryOP::Add => Ok(value1 + value2),
+            BinaryOP::Sub => Ok(value1 - value2),
+            BinaryOP::Mul => Ok(value1 * value2),
+            BinaryOP::Mod if common_type == Type::Double => Err(ErrorType::InvalidOperand(
+                span,
+                "the remainder operator can't be applied to a double",
+            )),
+            BinaryOP::Div => value1.checked_div(value2).ok_or(ErrorType::NotConstant(
+                span,
+                "the division by zero or overflowing division has no value",
+            )),
+            BinaryOP::Mod => value1.checked_rem(value2).ok_or(ErrorType::NotConstant(
+                span,
+                "the division by zero or overflowing division has no value",
+            )),
+            _ => {
+                // a comparison with a NaN operand is false, except for `!=`
+                let ordering = Self::compare_constants(value1, value2);
+                let result = match operator {
+                    BinaryOP::Equal => ordering == Some(Ordering::Equal),
+                    BinaryOP::NotEqual => ordering != Some(Ordering::Equal),
+                    BinaryOP::LessThan => ordering == Some(Ordering::Less),
+                    BinaryOP::GreaterThan => ordering == Some(Ordering::Greater),
+                    BinaryOP::LessThanOrEq => {
+                        matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                    }
+                    _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                };
+                Ok(Self::const_from_bool(result))
+            }
+        }
+    }
+
+    /// Compare two constants of the same type, `None` if either is a NaN
+    fn compare_constants(value1: Const, value2: Const) -> Option<Ordering> {
+        match (value1, value2) {
+            (Const::ConstInt(a), Const::ConstInt(b)) => a.partial_cmp(&b),
+            (Const::ConstLong(a), Const::ConstLong(b)) => a.partial_cmp(&b),
+            (Const::ConstUint(a), Const::ConstUint(b)) => a.partial_cmp(&b),
+            (Const::ConstUlong(a), Const::ConstUlong(b)) => a.partial_cmp(&b),
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => a.partial_cmp(&b),
+            _ => unreachable!("constants are converted to their common type before comparing"),
+        }
+    }
+
+    /// comparisons and logical operators are `int`s, 1 when true and 0 otherwise
+    fn const_from_bool(value: bool) -> Const {
+        Const::ConstInt(value as i32)
+    }
+}
@@ -1,13 +1,22 @@
-use crate::{TypeChecker, semantic_error::ErrorType};
+use crate::{TypeChecker, semantic_error::ErrorType, semantic_warning::WarningType};
 use parser::ast::*;
-use shared_context::{Span, SpannedIdentifier, Type, symbol_table::EntryType};
+use shared_context::{
+    Const, Span, SpannedIdentifier, Type,
+    symbol_table::{EntryType, IdenAttrs},
+    type_interner::TypeID,
+};
 
 impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     /// uses C conversion rules to get the common type between two types
     /// the common type is the type that an expression having type1 and type2 as operands should have
-    fn get_common_type(type1: Type, type2: Type) -> Type {
+    ///
+    /// both types are integer promoted first, so two narrow operands meet at `int`
+    pub(crate) fn get_common_type(type1: Type, type2: Type) -> Type {
+        let (type1, type2) = (type1.promote(), type2.promote());
         if type1 == type2 {
             type1
+        } else if type1.is_double() || type2.is_double() {
+            Type::Double
         } else if type1.size() == type2.size() {
             if type1.is_signed() { type2 } else { type1 }
         } else if type1.size() > type2.size() {
@@ -18,17 +27,165 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     }
 
     /// convert an Expression by wrapping it in a cast
-    pub(crate) fn convert_to(expr: Expression, target_type: Type) -> Expression {
+    ///
+    /// every implicit conversion goes through here, so it's where `-Wsign-conversion` is checked
+    pub(crate) fn convert_to(&mut self, expr: Expression, target_type: Type) -> Expression {
         let expr_type = expr.get_type();
         if target_type == expr_type {
             expr
         } else {
+            self.check_sign_conversion(&expr, target_type);
             let span = expr.get_span();
-            let inner = InnerExpression::Cast {
-                target_type: target_type,
-                expr: Box::new(expr),
+            Self::make_cast(expr, target_type, span)
+        }
+    }
+
+    /// wrap an expression in a cast to `target_type`, folding it with a cast the expression is
+    ///
+    /// between integer types, a cast that doesn't widen only keeps bits of the cast below it
+    /// that are the operand's own bits or extended like a direct cast would extend them, so the
+    /// inner cast is dropped: `(int)(long)i` is `i` and `(unsigned)(long)i` is `(unsigned)i`.
+    /// A widening cast keeps the one below it, narrowing then widening loses bits
+    /// (`(long)(int)l`) and `(long)(unsigned)i` zero extends where `(long)i` sign extends.
+    fn make_cast(expr: Expression, target_type: Type, span: Span) -> Expression {
+        let expr_type = expr.get_type();
+        if expr_type == target_type {
+            return expr;
+        }
+
+        let folds = target_type.is_integer()
+            && expr_type.is_integer()
+            && target_type.size() <= expr_type.size()
+            && matches!(
+                expr.get_inner_ref(),
+                InnerExpression::Cast { expr: operand, .. } if operand.get_type().is_integer()
+            );
+        if folds {
+            let (InnerExpression::Cast { expr: operand, .. }, _, _) = expr.into_parts() else {
+                unreachable!("only a cast is folded")
             };
-            Expression::new(inner, target_type, span)
+            return Self::make_cast(*operand, target_type, span);
+        }
+
+        let inner = InnerExpression::Cast {
+            target_type,
+            expr: Box::new(expr),
+        };
+        Expression::new(inner, target_type, span)
+    }
+
+    /// With `-Wsign-conversion`, warn when an expression is converted between a signed and
+    /// an unsigned integer type of the same size, where the bits are kept but their meaning
+    /// changes. A constant expression only warns when its sign does change (`unsigned u = -1;`
+    /// but not `unsigned u = 1;`), any other expression might.
+    fn check_sign_conversion(&mut self, expr: &Expression, target_type: Type) {
+        if !self.warn_sign_conversion || !Self::changes_signedness(expr.get_type(), target_type) {
+            return;
+        }
+
+        match self.eval_const_expr(expr) {
+            Ok(value) => self.check_constant_sign_conversion(
+                value.convert_to(expr.get_type()),
+                target_type,
+                expr.get_span(),
+            ),
+            Err(_) => self.report_warning(WarningType::SignConversion {
+                span: expr.get_span(),
+                value: None,
+                source: expr.get_type(),
+                target: target_type,
+            }),
+        }
+    }
+
+    /// With `-Wsign-conversion`, warn when converting a constant between a signed and an
+    /// unsigned integer type of the same size changes its value, which happens exactly
+    /// when the constant or its converted value is negative.
+    pub(crate) fn check_constant_sign_conversion(
+        &mut self,
+        value: Const,
+        target_type: Type,
+        span: Span,
+    ) {
+        if !self.warn_sign_conversion || !Self::changes_signedness(value.get_type(), target_type) {
+            return;
+        }
+
+        let is_negative = |value: Const| match value {
+            Const::ConstInt(v) => v < 0,
+            Const::ConstLong(v) => v < 0,
+            _ => false,
+        };
+        if is_negative(value) || is_negative(value.convert_to(target_type)) {
+            self.report_warning(WarningType::SignConversion {
+                span,
+                value: Some(value),
+                source: value.get_type(),
+                target: target_type,
+            });
+        }
+    }
+
+    /// whether converting between the two types reinterprets the sign bit,
+    /// they're integer types of the same size and only one of them is signed
+    fn changes_signedness(source: Type, target: Type) -> bool {
+        source.is_integer()
+            && target.is_integer()
+            && source.size() == target.size()
+            && source.is_signed() != target.is_signed()
+    }
+
+    /// whether an expression is a null pointer constant, an integer constant equal to zero
+    pub(crate) fn is_null_pointer_constant(expr: &Expression) -> bool {
+        matches!(
+            expr.get_inner_ref(),
+            InnerExpression::Constant(cons) if !matches!(cons, Const::ConstDouble(_)) && cons.is_zero()
+        )
+    }
+
+    /// get the common type of two operands where at least one is a pointer
+    ///
+    /// the other operand must be a pointer of the same type or a null pointer constant
+    fn get_common_pointer_type(
+        operand1: &Expression,
+        operand2: &Expression,
+        span: Span,
+    ) -> Result<Type, ErrorType> {
+        let (op1_type, op2_type) = (operand1.get_type(), operand2.get_type());
+        if op1_type == op2_type || Self::is_null_pointer_constant(operand2) {
+            Ok(op1_type)
+        } else if Self::is_null_pointer_constant(operand1) {
+            Ok(op2_type)
+        } else {
+            Err(ErrorType::IncompatibleTypes(
+                span,
+                "operands have incompatible pointer types",
+            ))
+        }
+    }
+
+    /// convert an expression to `target_type` as if by assignment
+    ///
+    /// arithmetic types convert to each other, but a pointer only accepts
+    /// a pointer of the same type or a null pointer constant.
+    /// A pointer converts to `_Bool`, whether it's null.
+    pub(crate) fn convert_by_assignment(
+        &mut self,
+        expr: Expression,
+        target_type: Type,
+    ) -> Result<Expression, ErrorType> {
+        let expr_type = expr.get_type();
+        if expr_type == target_type
+            || (!expr_type.is_pointer() && !target_type.is_pointer())
+            || (target_type.is_pointer() && Self::is_null_pointer_constant(&expr))
+            || (expr_type.is_pointer() && target_type == Type::Bool)
+        {
+            Ok(self.convert_to(expr, target_type))
+        } else {
+            Err(ErrorType::IncompatibleTypes(
+                expr.get_span(),
+                "can't implicitly convert between a pointer and another type",
+            ))
         }
     }
 
@@ -36,19 +193,39 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     ///
     /// return the tuple (converted_op1, converted_op2, common_type)
     fn equalize_operands(
+        &mut self,
         operand1: Expression,
         operand2: Expression,
     ) -> (Expression, Expression, Type) {
         let op1_type = operand1.get_type();
         let op2_type = operand2.get_type();
         let common_type = Self::get_common_type(op1_type, op2_type);
-        let converted_op1 = Self::convert_to(operand1, common_type);
-        let converted_op2 = Self::convert_to(operand2, common_type);
+        let converted_op1 = self.convert_to(operand1, common_type);
+        let converted_op2 = self.convert_to(operand2, common_type);
         (converted_op1, converted_op2, common_type)
     }
 
-    /// Type checks an expression recursively.
-    pub(crate) fn typecheck_expression(&self, expr: Expression) -> Result<Expression, ErrorType> {
+    /// Type checks an expression whose value is used, which can't be `void`.
+    ///
+    /// Every operand is checked with it, so a call to a `void` function can only be
+    /// the whole expression of a statement discarding it.
+    pub(crate) fn typecheck_expression(
+        &mut self,
+        expr: Expression,
+    ) -> Result<Expression, ErrorType> {
+        let checked_expr = self.typecheck_discarded_expression(expr)?;
+        if checked_expr.get_type() == Type::Void {
+            return Err(ErrorType::VoidValue(checked_expr.get_span()));
+        }
+        Ok(checked_expr)
+    }
+
+    /// Type checks an expression recursively, its value is discarded so it can be `void`:
+    /// an expression statement, the init and post expressions of a `for` loop and a cast to `void`.
+    pub(crate) fn typecheck_discarded_expression(
+        &mut self,
+        expr: Expression,
+    ) -> Result<Expression, ErrorType> {
         let (inner, expr_type, span) = expr.into_parts();
 
         match inner {
@@ -71,40 +248,181 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             InnerExpression::FunctionCall { name, args } => {
                 self.typecheck_function_call_expression(name, args, span)
             }
+            InnerExpression::IndirectCall { callee, args } => {
+                self.typecheck_indirect_call_expression(*callee, args, span)
+            }
             InnerExpression::Cast { target_type, expr } => {
                 self.typecheck_cast_expression(*expr, target_type, span)
             }
+            InnerExpression::Dereference(inner) => {
+                self.typecheck_dereference_expression(*inner, span)
+            }
+            InnerExpression::AddrOf(inner) => self.typecheck_address_of_expression(*inner, span),
+            InnerExpression::Subscript { array, index } => {
+                self.typecheck_subscript_expression(*array, *index, span)
+            }
+        }
+    }
+
+    /// Type check a subscript, one operand must be a pointer to an object and the other
+    /// an integer, `a[i]` and `i[a]` are the same element.
+    ///
+    /// The index is converted to `long`, so it can be scaled by the element size
+    /// and added to the pointer without another conversion.
+    fn typecheck_subscript_expression(
+        &mut self,
+        array: Expression,
+        index: Expression,
+        span: Span,
+    ) -> Result<Expression, ErrorType> {
+        let checked_array = self.typecheck_expression(array)?;
+        let checked_index = self.typecheck_expression(index)?;
+        let (pointer, index) = if checked_array.get_type().is_pointer() {
+            (checked_array, checked_index)
+        } else {
+            (checked_index, checked_array)
+        };
+
+        let element = match pointer.get_type() {
+            Type::Pointer(id) => self.ty_interner.get_referenced(id),
+            _ => {
+                return Err(ErrorType::InvalidOperand(
+                    span,
+                    "only an array or a pointer can be subscripted",
+                ));
+            }
+        };
+        if element.is_function() || element == Type::Void {
+            return Err(ErrorType::InvalidOperand(
+                span,
+                "only a pointer to an object can be subscripted",
+            ));
+        }
+        if !index.get_type().is_integer() {
+            return Err(ErrorType::InvalidOperand(
+                index.get_span(),
+                "an array index must be an integer",
+            ));
+        }
+
+        let inner = InnerExpression::Subscript {
+            array: Box::new(pointer),
+            index: Box::new(self.convert_to(index, Type::Long)),
+        };
+        Ok(Expression::new(inner, element, span))
+    }
+
+    /// Type check a dereference, the operand must be a pointer
+    ///
+    /// dereferencing a function pointer gives a function, which decays back to the pointer
+    fn typecheck_dereference_expression(
+        &mut self,
+        inner: Expression,
+        span: Span,
+    ) -> Result<Expression, ErrorType> {
+        let checked_inner = self.typecheck_expression(inner)?;
+        match checked_inner.get_type() {
+            Type::Pointer(id) => {
+                let pointer_type = checked_inner.get_type();
+                let referenced = self.ty_interner.get_referenced(id);
+                let inner = InnerExpression::Dereference(Box::new(checked_inner));
+                let deref = Expression::new(inner, referenced, span);
+                if referenced.is_function() {
+                    return Ok(Self::decay_function(deref, pointer_type));
+                }
+                Ok(deref)
+            }
+            _ => Err(ErrorType::InvalidOperand(
+                span,
+                "only a pointer can be dereferenced",
+            )),
+        }
+    }
+
+    /// Type check an address-of expression, its type is a pointer to the operand type
+    ///
+    /// a function already decayed to its address, `&f` is the same as `f`
+    fn typecheck_address_of_expression(
+        &mut self,
+        inner: Expression,
+        span: Span,
+    ) -> Result<Expression, ErrorType> {
+        let checked_inner = self.typecheck_expression(inner)?;
+        if Self::is_function_address(&checked_inner) {
+            let (inner, pointer_type, _) = checked_inner.into_parts();
+            return Ok(Expression::new(inner, pointer_type, span));
+        }
+        if !checked_inner.is_lvalue() {
+            return Err(ErrorType::InvalidOperand(
+                checked_inner.get_span(),
+                "can't take the address of a value that isn't an l-value",
+            ));
         }
+        let pointer_type = self.ty_interner.intern_pointer(checked_inner.get_type());
+        let inner = InnerExpression::AddrOf(Box::new(checked_inner));
+        Ok(Expression::new(inner, pointer_type, span))
     }
 
     /// type check cast expressions
+    ///
+    /// a cast to `void` discards the value, so its operand can be `void` as well
     fn typecheck_cast_expression(
-        &self,
+        &mut self,
         expr: Expression,
         target_type: Type,
         span: Span,
     ) -> Result<Expression, ErrorType> {
-        let checked_expr = self.typecheck_expression(expr)?;
-        if target_type != checked_expr.get_type() {
-            let inner = InnerExpression::Cast {
-                target_type,
-                expr: Box::new(checked_expr),
-            };
-            Ok(Expression::new(inner, target_type, span))
+        let checked_expr = if target_type == Type::Void {
+            self.typecheck_discarded_expression(expr)?
         } else {
-            Ok(checked_expr)
+            self.typecheck_expression(expr)?
+        };
+        let expr_type = checked_expr.get_type();
+        if (target_type.is_pointer() && expr_type.is_double())
+            || (target_type.is_double() && expr_type.is_pointer())
+        {
+            return Err(ErrorType::InvalidOperand(
+                span,
+                "can't cast between a pointer and a double",
+            ));
         }
+
+        Ok(Self::make_cast(checked_expr, target_type, span))
     }
 
     /// Type check unary expressions
     fn typecheck_unary_expression(
-        &self,
+        &mut self,
         operator: UnaryOP,
         operand: Expression,
         span: Span,
     ) -> Result<Expression, ErrorType> {
         let checked_operand = self.typecheck_expression(operand)?;
+        // `-` and `~` operate on the promoted operand, `!` only looks at its truthness
+        let checked_operand = match operator {
+            UnaryOP::LogicalNot => checked_operand,
+            _ => {
+                let promoted_type = checked_operand.get_type().promote();
+                self.convert_to(checked_operand, promoted_type)
+            }
+        };
+
+        if !matches!(operator, UnaryOP::LogicalNot) && checked_operand.get_type().is_pointer() {
+            return Err(ErrorType::InvalidOperand(
+                span,
+                "only `!` can be applied to a pointer",
+            ));
+        }
+
+        if matches!(operator, UnaryOP::BitwiseNot) && checked_operand.get_type().is_double() {
+            return Err(ErrorType::InvalidOperand(
+                span,
+                "bitwise complement can't be applied to a double",
+            ));
+        }
 
+        // `!` is always an `int` 0 or 1, whatever the operand type; in a wider context
+        // `convert_to` wraps it in a cast, which sign extends the `int` result
         let new_expr_type = match operator {
             UnaryOP::LogicalNot => Type::Int,
             _ => checked_operand.get_type(),
@@ -120,12 +438,18 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
 
     /// Type check binary expressions
     fn typecheck_binary_expression(
-        &self,
+        &mut self,
         operator: BinaryOP,
         operand1: Expression,
         operand2: Expression,
         span: Span,
     ) -> Result<Expression, ErrorType> {
+        if operator.is_comparison()
+            && (Self::is_bare_comparison(&operand1) || Self::is_bare_comparison(&operand2))
+        {
+            self.report_warning(WarningType::ChainedComparison(span));
+        }
+
         let checked_op1 = self.typecheck_expression(operand1)?;
         let checked_op2 = self.typecheck_expression(operand2)?;
 
@@ -140,14 +464,41 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             return Ok(Expression::new(inner, Type::Int, span));
         }
 
-        let (con_op1, con_op2, common_type) = Self::equalize_operands(checked_op1, checked_op2);
+        // pointers can only be compared for equality, with a pointer of the same type
+        // or a null pointer constant
+        if checked_op1.get_type().is_pointer() || checked_op2.get_type().is_pointer() {
+            if !matches!(operator, BinaryOP::Equal | BinaryOP::NotEqual) {
+                return Err(ErrorType::InvalidOperand(
+                    span,
+                    "pointers can only be compared with `==` and `!=`",
+                ));
+            }
+
+            let common_type = Self::get_common_pointer_type(&checked_op1, &checked_op2, span)?;
+            let inner = InnerExpression::Binary {
+                operator,
+                operand1: Box::new(self.convert_to(checked_op1, common_type)),
+                operand2: Box::new(self.convert_to(checked_op2, common_type)),
+            };
+            return Ok(Expression::new(inner, Type::Int, span));
+        }
+
+        let (con_op1, con_op2, common_type) = self.equalize_operands(checked_op1, checked_op2);
+        if matches!(operator, BinaryOP::Mod) && common_type.is_double() {
+            return Err(ErrorType::InvalidOperand(
+                span,
+                "the remainder operator can't be applied to a double",
+            ));
+        }
+
         let inner = InnerExpression::Binary {
             operator,
             operand1: Box::new(con_op1),
             operand2: Box::new(con_op2),
         };
 
-        // make the expression type int for comparison operators
+        // make the expression type int for comparison operators,
+        // like `!` the 0 or 1 result is sign extended when converted to a wider type
         if operator.is_arithmetic() {
             Ok(Expression::new(inner, common_type, span))
         } else {
@@ -155,9 +506,15 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         }
     }
 
+    /// whether the expression is a comparison written without parentheses around it
+    fn is_bare_comparison(expr: &Expression) -> bool {
+        matches!(expr.get_inner_ref(), InnerExpression::Binary { operator, .. } if operator.is_comparison())
+            && !expr.is_parenthesized()
+    }
+
     /// Type check conditional expressions (ternary operator)
     fn typecheck_conditional_expression(
-        &self,
+        &mut self,
         cond: Expression,
         cons: Expression,
         alt: Expression,
@@ -166,7 +523,17 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         let checked_cond = self.typecheck_expression(cond)?;
         let checked_cons = self.typecheck_expression(cons)?;
         let checked_alt = self.typecheck_expression(alt)?;
-        let (con_cons, con_alt, common_type) = Self::equalize_operands(checked_cons, checked_alt);
+        let (con_cons, con_alt, common_type) =
+            if checked_cons.get_type().is_pointer() || checked_alt.get_type().is_pointer() {
+                let common_type = Self::get_common_pointer_type(&checked_cons, &checked_alt, span)?;
+                (
+                    self.convert_to(checked_cons, common_type),
+                    self.convert_to(checked_alt, common_type),
+                    common_type,
+                )
+            } else {
+                self.equalize_operands(checked_cons, checked_alt)
+            };
         let inner = InnerExpression::Conditional {
             cond: Box::new(checked_cond),
             cons: Box::new(con_cons),
@@ -177,33 +544,99 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
 
     /// Type check variable usage
     fn typecheck_var_expression(
-        &self,
+        &mut self,
         sp_ident: SpannedIdentifier,
         span: Span,
     ) -> Result<Expression, ErrorType> {
-        // after the identifier resolution pass, it's guaranteed that all variables expressions are in the symbol table.
-        let entry = self.symbol_table.unsafe_lookup(sp_ident.get_identifier());
+        // identifier resolution should have added every variable to the symbol table,
+        // report a missing one as undeclared rather than panicking
+        let entry = self
+            .symbol_table
+            .lookup(sp_ident.get_identifier())
+            .ok_or(ErrorType::UseOfUndeclared(span))?;
 
-        if let EntryType::Scalar(var_type) = entry.entry_type {
-            let inner = InnerExpression::Var(sp_ident);
-            Ok(Expression::new(inner, var_type, span))
-        } else {
-            // Attempting to use a function or incompatible type as a variable
-            Err(ErrorType::FunctionAsVariable(span))
+        // an enumerator is replaced by its value
+        if let IdenAttrs::ConstantAttrs(value) = entry.attributes {
+            let inner = InnerExpression::Constant(Const::ConstInt(value));
+            return Ok(Expression::new(inner, Type::Int, span));
+        }
+
+        match entry.entry_type {
+            EntryType::Scalar(var_type) => {
+                let inner = InnerExpression::Var(sp_ident);
+                Ok(Expression::new(inner, var_type, span))
+            }
+            // a function name used as a value is its address
+            EntryType::Func(type_id) => {
+                let fun_type = Type::Function(type_id);
+                let pointer_type = self.ty_interner.intern_pointer(fun_type);
+                let designator = Expression::new(InnerExpression::Var(sp_ident), fun_type, span);
+                Ok(Self::decay_function(designator, pointer_type))
+            }
+            // an array name used as a value is the address of its first element
+            EntryType::Array { element, .. } => {
+                let pointer_type = self.ty_interner.intern_pointer(element);
+                let array = Expression::new(InnerExpression::Var(sp_ident), element, span);
+                let inner = InnerExpression::AddrOf(Box::new(array));
+                Ok(Expression::new(inner, pointer_type, span))
+            }
         }
     }
 
+    /// A function designator (a function name or a dereferenced function pointer)
+    /// converted to a pointer to the function, the address of the designator.
+    /// It isn't an l-value, a function can't be assigned to.
+    fn decay_function(designator: Expression, pointer_type: Type) -> Expression {
+        let span = designator.get_span();
+        let inner = InnerExpression::AddrOf(Box::new(designator));
+        Expression::new(inner, pointer_type, span)
+    }
+
+    /// whether the expression is a function designator that decayed to its address
+    fn is_function_address(expr: &Expression) -> bool {
+        matches!(
+            expr.get_inner_ref(),
+            InnerExpression::AddrOf(designator) if designator.get_type().is_function()
+        )
+    }
+
     /// Type check assignments
     fn typecheck_assignment_expression(
-        &self,
+        &mut self,
         lvalue: Expression,
         rvalue: Expression,
         span: Span,
     ) -> Result<Expression, ErrorType> {
+        // resolved identifiers are unique, a shadowing variable of the same name is another one
+        let is_self_assignment = matches!(
+            (lvalue.get_inner_ref(), rvalue.get_inner_ref()),
+            (InnerExpression::Var(left), InnerExpression::Var(right))
+                if left.get_identifier() == right.get_identifier()
+        );
+
         let checked_lvalue = self.typecheck_expression(lvalue)?;
+        // an enumerator looks like a variable until it's replaced by its value
+        if !checked_lvalue.is_lvalue() {
+            return Err(ErrorType::InvalidLeftValue(checked_lvalue.get_span()));
+        }
+
+        // a `const` variable only gets a value from its initializer
+        if let InnerExpression::Var(sp_iden) = checked_lvalue.get_inner_ref()
+            && self
+                .symbol_table
+                .lookup(sp_iden.get_identifier())
+                .is_some_and(|entry| entry.is_const)
+        {
+            return Err(ErrorType::AssignToConst(span));
+        }
+
+        if is_self_assignment {
+            self.report_warning(WarningType::SelfAssignment(span));
+        }
+
         let checked_rvalue = self.typecheck_expression(rvalue)?;
         let left_type = checked_lvalue.get_type();
-        let con_rvalue = Self::convert_to(checked_rvalue, left_type);
+        let con_rvalue = self.convert_by_assignment(checked_rvalue, left_type)?;
 
         let inner = InnerExpression::Assignment {
             lvalue: Box::new(checked_lvalue),
@@ -213,39 +646,112 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     }
 
     /// Type check function calls
+    ///
+    /// a call through a variable holding a function pointer (`fp(x)`) is an indirect call
     fn typecheck_function_call_expression(
-        &self,
+        &mut self,
         sp_iden: SpannedIdentifier,
-        args: Vec<Box<Expression>>,
+        args: Vec<Expression>,
+        span: Span,
+    ) -> Result<Expression, ErrorType> {
+        // the function's declaration should already be in the symbol table,
+        // report a missing one as undeclared rather than panicking
+        let entry = self
+            .symbol_table
+            .lookup(sp_iden.get_identifier())
+            .ok_or(ErrorType::UseOfUndeclared(span))?;
+
+        match entry.entry_type {
+            EntryType::Func(type_id) => {
+                let (converted_args, ret) = self.typecheck_call_args(type_id, args, span)?;
+                let inner = InnerExpression::FunctionCall {
+                    name: sp_iden,
+                    args: converted_args,
+                };
+                Ok(Expression::new(inner, ret, span))
+            }
+            EntryType::Scalar(var_type) if self.get_pointed_function(var_type).is_some() => {
+                let callee =
+                    Expression::new(InnerExpression::Var(sp_iden), var_type, sp_iden.get_span());
+                self.typecheck_indirect_call_expression(callee, args, span)
+            }
+            EntryType::Scalar(_) | EntryType::Array { .. } => {
+                Err(ErrorType::VariableAsFunction(span))
+            }
+        }
+    }
+
+    /// Type check a call through a function pointer, the callee must be a pointer to a function
+    fn typecheck_indirect_call_expression(
+        &mut self,
+        callee: Expression,
+        args: Vec<Expression>,
         span: Span,
     ) -> Result<Expression, ErrorType> {
-        // at this point in semantic analysis, it's guaranteed that the identifier is in the symbol table
-        let entry = self.symbol_table.unsafe_lookup(sp_iden.get_identifier());
+        let checked_callee = self.typecheck_expression(callee)?;
+        let type_id = self.get_pointed_function(checked_callee.get_type()).ok_or(
+            ErrorType::InvalidOperand(
+                checked_callee.get_span(),
+                "only a function or a function pointer can be called",
+            ),
+        )?;
 
-        // Ensure the identifier refers to a function
-        if let EntryType::Func(type_id) = entry.entry_type {
-            let fun_type = self.ty_interner.get(type_id);
+        let (converted_args, ret) = self.typecheck_call_args(type_id, args, span)?;
+        let inner = InnerExpression::IndirectCall {
+            callee: Box::new(checked_callee),
+            args: converted_args,
+        };
+        Ok(Expression::new(inner, ret, span))
+    }
 
-            let params_count = fun_type.params.len();
-            if args.len() != params_count {
-                return Err(ErrorType::WrongNumberOfArgs {
-                    span,
-                    expected: params_count,
-                    got: args.len(),
-                });
-            }
-            let mut converted_args = Vec::new();
-            for (arg, param_type) in args.into_iter().zip(fun_type.params) {
-                let checked_arg = self.typecheck_expression(*arg)?;
-                converted_args.push(Box::new(Self::convert_to(checked_arg, *param_type)));
-            }
-            let inner = InnerExpression::FunctionCall {
-                name: sp_iden,
-                args: converted_args,
-            };
-            Ok(Expression::new(inner, fun_type.ret, span))
+    /// the function type a pointer type points to, `None` for any other type
+    fn get_pointed_function(&self, ty: Type) -> Option<TypeID> {
+        match ty {
+            Type::Pointer(id) => match self.ty_interner.get_referenced(id) {
+                Type::Function(type_id) => Some(type_id),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Type check the arguments of a call to a function of the given type,
+    /// returns the converted arguments and the type the call returns.
+    fn typecheck_call_args(
+        &mut self,
+        type_id: TypeID,
+        args: Vec<Expression>,
+        span: Span,
+    ) -> Result<(Vec<Expression>, Type), ErrorType> {
+        let fun_type = self.ty_interner.get(type_id);
+
+        // a variadic function takes any number of arguments after its fixed parameters
+        let params_count = fun_type.params.len();
+        let count_matches = if fun_type.is_variadic {
+            args.len() >= params_count
         } else {
-            return Err(ErrorType::VariableAsFunction(span));
+            args.len() == params_count
+        };
+        if !count_matches {
+            return Err(ErrorType::WrongNumberOfArgs {
+                span,
+                expected: params_count,
+                got: args.len(),
+                variadic: fun_type.is_variadic,
+            });
+        }
+
+        // arguments for the fixed parameters are converted to the parameter types,
+        // the extra ones of a variadic call keep their own type
+        let mut converted_args = Vec::new();
+        for (i, arg) in args.into_iter().enumerate() {
+            let checked_arg = self.typecheck_expression(arg)?;
+            let converted_arg = match fun_type.params.get(i) {
+                Some(param_type) => self.convert_by_assignment(checked_arg, *param_type)?,
+                None => checked_arg,
+            };
+            converted_args.push(converted_arg);
         }
+        Ok((converted_args, fun_type.ret))
     }
 }
@@ -10,7 +10,8 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     ///
     /// # Behavior
     /// - Checks if a function with the same name was previously declared.
-    ///   - Ensures consistent parameter counts.
+    ///   - Ensures consistent return and parameter types, so `int f(int); int f(int, int);`
+    ///     is an `IncompatibleDecl` error.
     ///   - Detects duplicate definitions.
     /// - Registers the function in the symbol table.
     /// - Type checks the function body if present.
@@ -86,7 +87,8 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     ) -> Result<Option<(bool, bool)>, ErrorType> {
         // if there is a previous declaration with the same identifier
         if let Some(prev_entry) = self.symbol_table.lookup(sp_iden.get_identifier()) {
-            // chick if they have the same type
+            // check if they have the same type, the type interner gives structurally equal
+            // function types the same id, so comparing ids compares return and parameter types
             if EntryType::Func(ty_id) != prev_entry.entry_type {
                 return Err(ErrorType::IncompatibleDecl {
                     first: prev_entry.span,
@@ -95,11 +97,12 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             }
             let external = prev_entry.attributes.is_external();
             let defined = prev_entry.attributes.is_defined();
-            // check if the previous declaration is also a definition
+            // check if the previous declaration is also a definition, prototypes
+            // can be repeated but a function has a single body
             if defined && has_body {
                 return Err(ErrorType::DuplicateDefintion {
                     first: prev_entry.span,
-                    second: sp_iden.get_span(),
+                    second: span,
                 });
             }
             // check if they have compatible storage class
@@ -117,6 +120,22 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         Ok(None)
     }
 
+    /// Registers each function declared at file scope with the type and linkage of its
+    /// first declaration, not yet defined, so a call before the declaration is checked
+    /// against it. The declarations are still checked against each other in order.
+    pub(super) fn register_file_scope_functions(&mut self, declarations: &[Declaration]) {
+        for decl in declarations {
+            if let Declaration::FunDecl(fun_decl) = decl {
+                let iden = fun_decl.get_sp_identifier().get_identifier();
+                if self.symbol_table.lookup(iden).is_none() {
+                    let external = fun_decl.get_storage_class() != StorageClass::Static;
+                    let (ty_id, span) = (fun_decl.get_type_id(), fun_decl.get_span());
+                    self.register_function(iden, ty_id, span, external, false);
+                }
+            }
+        }
+    }
+
     /// Registers a function in the symbol table.
     ///
     /// Marks whether it is already defined.
@@ -134,14 +153,16 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     }
 
     /// Registers the parameters of a function as local variables.
+    ///
+    /// only called for definitions, which the parser guarantees name every parameter
     fn register_function_params(
         &mut self,
-        params: &Vec<SpannedIdentifier>,
+        params: &[Option<SpannedIdentifier>],
         ty_id: TypeID,
         span: Span,
     ) {
         let params_types = self.ty_interner.get(ty_id).params;
-        for (sp_iden, param_type) in params.iter().zip(params_types) {
+        for (sp_iden, param_type) in params.iter().flatten().zip(params_types) {
             self.symbol_table.add(
                 sp_iden.get_identifier(),
                 EntryType::Scalar(*param_type),
@@ -1,6 +1,6 @@
 use crate::{TypeChecker, semantic_error::ErrorType};
 use parser::ast::*;
-use shared_context::{Identifier, type_interner::TypeID};
+use shared_context::{Identifier, Span, Type, type_interner::TypeID};
 
 impl<'src, 'c> TypeChecker<'src, 'c> {
     /// Type check a statement.
@@ -12,7 +12,7 @@ impl<'src, 'c> TypeChecker<'src, 'c> {
         let (stmt_type, span) = stmt.into_parts();
 
         let checked_stmt_type = match stmt_type {
-            StatementType::Return(expr) => self.typecheck_return_statement(expr, curr_fun)?,
+            StatementType::Return(expr) => self.typecheck_return_statement(expr, curr_fun, span)?,
             StatementType::ExprStatement(expr) => self.typecheck_expr_statement(expr)?,
             StatementType::IfStatement {
                 condition,
@@ -46,21 +46,37 @@ impl<'src, 'c> TypeChecker<'src, 'c> {
     }
 
     /// Type check a return statement.
+    ///
+    /// A `void` function returns without an expression, any other function needs one.
     fn typecheck_return_statement(
         &mut self,
-        expr: Expression,
+        expr: Option<Expression>,
         curr_fun: TypeID,
+        span: Span,
     ) -> Result<StatementType, ErrorType> {
-        // convert the expression to the type of the enclosing function return type.
         let ret_type = self.ty_interner.get(curr_fun).ret;
-        let checked_expr = self.typecheck_expression(expr)?;
-        let con_expr = Self::convert_to(checked_expr, ret_type);
-        Ok(StatementType::Return(con_expr))
+        match (expr, ret_type) {
+            (None, Type::Void) => Ok(StatementType::Return(None)),
+            (Some(_), Type::Void) => Err(ErrorType::InvalidReturn(
+                span,
+                "a void function can't return a value",
+            )),
+            (None, _) => Err(ErrorType::InvalidReturn(
+                span,
+                "this function must return a value",
+            )),
+            (Some(expr), _) => {
+                // convert the expression to the type of the enclosing function return type.
+                let checked_expr = self.typecheck_expression(expr)?;
+                let con_expr = self.convert_by_assignment(checked_expr, ret_type)?;
+                Ok(StatementType::Return(Some(con_expr)))
+            }
+        }
     }
 
-    /// Type check an expression statement.
+    /// Type check an expression statement, its value is discarded so it can be `void`.
     fn typecheck_expr_statement(&mut self, expr: Expression) -> Result<StatementType, ErrorType> {
-        let checked_expr = self.typecheck_expression(expr)?;
+        let checked_expr = self.typecheck_discarded_expression(expr)?;
         Ok(StatementType::ExprStatement(checked_expr))
     }
 
@@ -136,7 +152,7 @@ impl<'src, 'c> TypeChecker<'src, 'c> {
             None => None,
         };
         let checked_post = match post {
-            Some(expr) => Some(self.typecheck_expression(expr)?),
+            Some(expr) => Some(self.typecheck_discarded_expression(expr)?),
             None => None,
         };
         let checked_body = Box::new(self.typecheck_statement(body, curr_fun)?);
@@ -153,11 +169,14 @@ impl<'src, 'c> TypeChecker<'src, 'c> {
     /// Type check the initialization part of a for loop.
     fn typecheck_for_init(&mut self, for_init: ForInit) -> Result<ForInit, ErrorType> {
         match for_init {
-            ForInit::D(var_decl) => Ok(ForInit::D(
-                self.typecheck_local_variable_declaration(var_decl)?,
+            ForInit::D(var_decls) => Ok(ForInit::D(
+                var_decls
+                    .into_iter()
+                    .map(|var_decl| self.typecheck_local_variable_declaration(var_decl))
+                    .collect::<Result<_, _>>()?,
             )),
             ForInit::E(option_expr) => Ok(ForInit::E(match option_expr {
-                Some(expr) => Some(self.typecheck_expression(expr)?),
+                Some(expr) => Some(self.typecheck_discarded_expression(expr)?),
                 None => None,
             })),
         }
@@ -1,7 +1,7 @@
-use crate::{TypeChecker, semantic_error::ErrorType};
-use parser::ast::{Expression, InnerExpression, StorageClass, VariableDecl};
+use crate::{TypeChecker, semantic_error::ErrorType, semantic_warning::WarningType};
+use parser::ast::{Expression, StorageClass, VariableDecl};
 use shared_context::{
-    Span, SpannedIdentifier, StaticInit, Type, convert_constant_value_to_static_init,
+    Const, Span, SpannedIdentifier, StaticInit, Type, convert_constant_value_to_static_init,
     symbol_table::{EntryType, IdenAttrs, InitValue, SymbolEntry},
 };
 
@@ -14,7 +14,16 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         &mut self,
         var_decl: VariableDecl,
     ) -> Result<VariableDecl, ErrorType> {
+        // only automatic locals can be arrays so far
+        if var_decl.get_array_len_ref().is_some() {
+            return Err(ErrorType::InvalidArray(
+                var_decl.get_span(),
+                "only local variables without a storage class can be arrays",
+            ));
+        }
+
         // Decompose the variable declaration into its components.
+        let is_const = var_decl.is_const();
         let (name, var_type, init, storage_class, span) = var_decl.into_parts();
 
         // Determine the effective initialization value and linkage (external/internal)
@@ -35,8 +44,11 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
             attrs,
             span,
         );
+        if is_const {
+            self.symbol_table.mark_const(name.get_identifier());
+        }
 
-        Ok(VariableDecl::new(name, var_type, init, storage_class, span))
+        Ok(VariableDecl::new(name, var_type, init, storage_class, span).with_const(is_const))
     }
 
     /// Check the initializer expression for a variable declaration.
@@ -46,19 +58,20 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     ///
     /// Returns the evaluated `InitValue` or an error if invalid.
     fn check_declaration_init(
-        &self,
+        &mut self,
         init: &Option<Expression>,
         storage_class: StorageClass,
         var_type: Type,
     ) -> Result<InitValue, ErrorType> {
         if let Some(expr) = init {
             // Only constant expressions are allowed for global initializers.
-            match expr.get_inner_ref() {
-                InnerExpression::Constant(cons_val) => Ok(InitValue::Initial(
-                    convert_constant_value_to_static_init(*cons_val, var_type),
-                )),
-                _ => Err(ErrorType::InvalidInitializer(expr.get_span())),
-            }
+            let cons_val = self.eval_const_expr(expr)?;
+            Self::check_static_pointer_init(expr, var_type)?;
+            self.check_initializer_overflow(cons_val, var_type, expr.get_span());
+            self.check_constant_sign_conversion(cons_val, var_type, expr.get_span());
+            Ok(InitValue::Initial(convert_constant_value_to_static_init(
+                cons_val, var_type,
+            )))
         } else {
             if storage_class == StorageClass::Extern {
                 Ok(InitValue::NoInitializer)
@@ -68,6 +81,57 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         }
     }
 
+    /// A static pointer can only be initialized with a null pointer constant.
+    fn check_static_pointer_init(expr: &Expression, var_type: Type) -> Result<(), ErrorType> {
+        if var_type.is_pointer() && !Self::is_null_pointer_constant(expr) {
+            return Err(ErrorType::InvalidInitializer(expr.get_span()));
+        }
+        Ok(())
+    }
+
+    /// Warn when a constant initializer changes value once converted to the variable's type.
+    ///
+    /// A value fits if it can be represented in `var_type.size()` bytes, either as a signed
+    /// or an unsigned number (so `unsigned x = -1;` is silent). Converting a negative or too
+    /// large value to a signed type must also stay in the signed range, unless the constant
+    /// is an unsigned one of the same size whose bits are only reinterpreted.
+    /// A double fits if its integer part does, and every constant fits in a double
+    /// or a `_Bool`, which holds whether the value is nonzero.
+    fn check_initializer_overflow(&mut self, cons_val: Const, var_type: Type, span: Span) {
+        if var_type.is_double() || var_type == Type::Bool {
+            return;
+        }
+
+        let (value, source_signed, source_size) = match cons_val {
+            Const::ConstInt(v) => (v as i128, true, 4),
+            Const::ConstLong(v) => (v as i128, true, 8),
+            Const::ConstUint(v) => (v as i128, false, 4),
+            Const::ConstUlong(v) => (v as i128, false, 8),
+            Const::ConstDouble(v) => (v as i128, true, 8),
+            Const::ConstBool(v) => (v as i128, false, 1),
+        };
+
+        let bits = var_type.size() as u32 * 8;
+        let signed_min = -(1i128 << (bits - 1));
+        let signed_max = (1i128 << (bits - 1)) - 1;
+        let unsigned_max = (1i128 << bits) - 1;
+
+        let reinterpreted = !source_signed && source_size == var_type.size();
+        let fits = if var_type.is_signed() && !reinterpreted {
+            (signed_min..=signed_max).contains(&value)
+        } else {
+            (signed_min..=unsigned_max).contains(&value)
+        };
+
+        if !fits {
+            self.report_warning(WarningType::InitializerOverflow {
+                span,
+                value: cons_val,
+                target: var_type,
+            });
+        }
+    }
+
     /// determine a gloabl variable's initial value and linkage
     ///
     /// - Verifies compatibility between multiple declarations of the same variable.
@@ -77,7 +141,7 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     /// Returns a tuple:
     /// `(resolved_init_value, is_external)`
     fn get_variable_init_and_linkage(
-        &self,
+        &mut self,
         name: SpannedIdentifier,
         init: &Option<Expression>,
         span: Span,
@@ -163,6 +227,10 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     }
 
     /// Merge initializer states for redeclarations of the same variable.
+    ///
+    /// A definition may come before or after an `extern` declaration of the variable,
+    /// the declaration without an initializer keeps the other one's value.
+    /// Only two initializers are a duplicate definition.
     fn merge_initial_values(
         &self,
         prev_attrs: IdenAttrs,
@@ -170,9 +238,14 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         current_span: Span,
         prev_span: Span,
     ) -> Result<Option<InitValue>, ErrorType> {
-        // at this point it is gaurnteed that prev_attrs have static attributes
-        // thus this method will never return None
-        let prev_value = prev_attrs.get_init_value().unwrap();
+        // the previous declaration has static attributes once its linkage was resolved,
+        // if it somehow doesn't, the two declarations can't refer to the same variable
+        let Some(prev_value) = prev_attrs.get_init_value() else {
+            return Err(ErrorType::IncompatibleDecl {
+                first: prev_span,
+                second: current_span,
+            });
+        };
         match (prev_value, current_value) {
             (InitValue::Initial(_), InitValue::Initial(_)) => Err(ErrorType::DuplicateDefintion {
                 first: prev_span,
@@ -201,11 +274,15 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     /// Performs semantic checks for each case according to C language rules.
     pub(crate) fn typecheck_local_variable_declaration(
         &mut self,
-        decl: VariableDecl,
+        mut decl: VariableDecl,
     ) -> Result<VariableDecl, ErrorType> {
+        let is_const = decl.is_const();
+        if let Some(len) = decl.take_array_len() {
+            return self.handle_local_array_declaration(decl, len);
+        }
         let (name, var_type, init, storage_class, span) = decl.into_parts();
 
-        match storage_class {
+        let checked_decl = match storage_class {
             StorageClass::Extern => {
                 self.handle_local_extern_declaration(name, init, span, storage_class, var_type)
             }
@@ -213,7 +290,65 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
                 self.handle_local_static_declaration(name, init, storage_class, span, var_type)
             }
             _ => self.handle_automatic_local_declaration(name, init, span, storage_class, var_type),
+        }?;
+        if is_const {
+            self.symbol_table.mark_const(name.get_identifier());
+        }
+
+        Ok(checked_decl.with_const(is_const))
+    }
+
+    /// Handle a local array declaration, `int a[3];`.
+    ///
+    /// Only automatic locals can be arrays and they can't be initialized, the length is
+    /// a positive integer constant expression, like `int a[2 + 1];`.
+    /// The symbol table records the element type and the number of elements.
+    fn handle_local_array_declaration(
+        &mut self,
+        decl: VariableDecl,
+        len: Expression,
+    ) -> Result<VariableDecl, ErrorType> {
+        let span = decl.get_span();
+        if decl.get_storage_class() != StorageClass::None {
+            return Err(ErrorType::InvalidArray(
+                span,
+                "only local variables without a storage class can be arrays",
+            ));
         }
+        if decl.is_const() {
+            return Err(ErrorType::InvalidArray(span, "an array can't be `const`"));
+        }
+        let (name, element, init, storage_class, span) = decl.into_parts();
+        if init.is_some() {
+            return Err(ErrorType::InvalidArray(
+                span,
+                "an array can't be initialized",
+            ));
+        }
+        let count = self.eval_array_length(&len)?;
+
+        self.symbol_table.add(
+            name.get_identifier(),
+            EntryType::Array { element, count },
+            IdenAttrs::LocalAttrs,
+            span,
+        );
+        Ok(VariableDecl::new(name, element, None, storage_class, span).with_array_len(Some(len)))
+    }
+
+    /// Evaluate the length of an array, which must be a positive integer constant.
+    fn eval_array_length(&self, len: &Expression) -> Result<usize, ErrorType> {
+        let cons = self.eval_const_expr(len)?;
+        let count = match cons.convert_to(Type::Long) {
+            Const::ConstLong(v) if !cons.get_type().is_double() => usize::try_from(v).ok(),
+            _ => None,
+        };
+        count
+            .filter(|&count| count > 0)
+            .ok_or(ErrorType::InvalidArray(
+                len.get_span(),
+                "the length of an array must be a positive integer constant",
+            ))
     }
 
     /// Handle a local variable declared with the `extern` storage class.
@@ -264,7 +399,9 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     /// Rules enforced:
     /// - Must have a constant integer initializer or none.
     /// - If no initializer is provided, defaults to zero-initialization.
-    /// - The variable is added to the symbol table as a static local.
+    /// - The variable is added to the symbol table as a static local,
+    ///   under the unique id identifier resolution gave it, so it never clashes
+    ///   with a static local of the same name in another function.
     fn handle_local_static_declaration(
         &mut self,
         name: SpannedIdentifier,
@@ -275,14 +412,11 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
     ) -> Result<VariableDecl, ErrorType> {
         // Local static: must have constant or no initializer
         let initial_value = if let Some(expr) = &init {
-            match expr.get_inner_ref() {
-                InnerExpression::Constant(const_val) => {
-                    InitValue::Initial(convert_constant_value_to_static_init(*const_val, var_type))
-                }
-                _ => {
-                    return Err(ErrorType::InvalidInitializer(expr.get_span()));
-                }
-            }
+            let const_val = self.eval_const_expr(expr)?;
+            Self::check_static_pointer_init(expr, var_type)?;
+            self.check_initializer_overflow(const_val, var_type, expr.get_span());
+            self.check_constant_sign_conversion(const_val, var_type, expr.get_span());
+            InitValue::Initial(convert_constant_value_to_static_init(const_val, var_type))
         } else {
             // No initializer, default integer zero initialization
             InitValue::Initial(StaticInit::IntInit(0))
@@ -328,7 +462,7 @@ impl<'src, 'ctx> TypeChecker<'src, 'ctx> {
         let checked_init = if let Some(expr) = init {
             // we convert the initializer to the type of the declaration
             let checked_expr = self.typecheck_expression(expr)?;
-            let con_expr = Self::convert_to(checked_expr, var_type);
+            let con_expr = self.convert_by_assignment(checked_expr, var_type)?;
             Some(con_expr)
         } else {
             None
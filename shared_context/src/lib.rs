@@ -1,18 +1,21 @@
 // Crate-level imports and re-exports
 use crate::symbol_interner::SymbolInterner;
-use crate::type_interner::TypeInterner;
+use crate::type_interner::{PointerID, TypeID, TypeInterner};
 pub use bumpalo::Bump; // Memory arena used for efficient allocation
+use std::ops;
 use symbol_interner::Symbol;
 
+pub use opt_passes::OptPasses;
 pub use symbol_registry::SymbolRegistery;
 pub use symbol_table::SymbolTable;
 
 // Submodules
+pub mod opt_passes; // Selects which optimization passes run
 pub mod source_map; // Maps AST positions to source code positions
 pub mod symbol_interner; // Deduplicates strings and creates Symbols
 pub mod symbol_registry; // Stores symbols types, and their metadata after type checking for infallible access
 pub mod symbol_table; // Stores symbols, types, and their metadata while typechecking
-pub mod type_interner; // Deduplicate function types
+pub mod type_interner; // Deduplicate function and pointer types
 
 /// Used to deduplicate identifiers and complex types, stores them in an arena
 pub struct Interner<'arena> {
@@ -124,6 +127,7 @@ impl SpannedIdentifier {
 /// a static variable in the IR
 ///
 /// exach static variable contains, its identifier, linkage and initializer.
+#[derive(Clone)]
 pub struct StaticVariable {
     name: Identifier,
     external: bool,
@@ -149,25 +153,85 @@ impl StaticVariable {
 
 /// The `Type` enum represents the type of an identifier in the symbol table.
 /// - `Int` represents a simple integer type.
-/// - `FunType(usize)` represents a function type, where `usize` is the number of parameters.
+/// - `Bool` is `_Bool`, a one byte unsigned integer that only holds 0 or 1,
+///   a conversion to it yields 1 for any nonzero value.
+/// - `Pointer` points to the type interned under its id, see `TypeInterner::get_referenced`.
+/// - `Function` is the function type interned under its id, it's only the type a pointer
+///   points to and of a function name whose address is taken, never of a variable.
+/// - `Void` is only the return type of a function that returns no value,
+///   and the type of a call to it, there are no `void` objects or values.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Type {
     Int,
     Long,
     Uint,
     Ulong,
+    Double,
+    Bool,
+    Pointer(PointerID),
+    Function(TypeID),
+    Void,
 }
 
 impl Type {
+    /// the size of a function is the size of its address, the only way it's used as a value
     pub fn size(&self) -> usize {
         match self {
-            Type::Long | Type::Ulong => 8,
+            Type::Long | Type::Ulong | Type::Double | Type::Pointer(_) | Type::Function(_) => 8,
             Type::Int | Type::Uint => 4,
+            Type::Bool => 1,
+            Type::Void => unreachable!("void has no values, so no size"),
         }
     }
 
+    /// whether the type can represent negative values, which includes `double`
     pub fn is_signed(&self) -> bool {
-        matches!(self, Type::Int | Type::Long)
+        matches!(self, Type::Int | Type::Long | Type::Double)
+    }
+
+    /// `int`, `long` and their unsigned versions, the types conversions only change the width
+    /// or the signedness of
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int | Type::Long | Type::Uint | Type::Ulong)
+    }
+
+    pub fn is_double(&self) -> bool {
+        matches!(self, Type::Double)
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Pointer(_))
+    }
+
+    pub fn is_function(&self) -> bool {
+        matches!(self, Type::Function(_))
+    }
+
+    /// the type as it's spelled in C, for diagnostics. A pointer or a function is only
+    /// named by its kind, spelling out what it points to needs the type interner.
+    pub fn c_name(&self) -> &'static str {
+        match self {
+            Type::Int => "int",
+            Type::Long => "long",
+            Type::Uint => "unsigned int",
+            Type::Ulong => "unsigned long",
+            Type::Double => "double",
+            Type::Bool => "_Bool",
+            Type::Pointer(_) => "a pointer",
+            Type::Function(_) => "a function",
+            Type::Void => "void",
+        }
+    }
+
+    /// the type an operand has after integer promotion: integer types narrower
+    /// than `int` are promoted to `int`, which can represent all of their values.
+    /// Only `_Bool` is narrower, the other integer types promote to themselves.
+    pub fn promote(&self) -> Type {
+        if !self.is_double() && self.size() < Type::Int.size() {
+            Type::Int
+        } else {
+            *self
+        }
     }
 }
 
@@ -184,6 +248,180 @@ pub enum Const {
     ConstLong(i64),
     ConstUint(u32),
     ConstUlong(u64),
+    ConstDouble(f64),
+    ConstBool(bool),
+}
+
+impl Const {
+    /// the zero constant of the given type
+    pub fn zero(var_type: Type) -> Const {
+        match var_type {
+            Type::Int => Const::ConstInt(0),
+            Type::Long => Const::ConstLong(0),
+            Type::Uint => Const::ConstUint(0),
+            Type::Ulong => Const::ConstUlong(0),
+            Type::Double => Const::ConstDouble(0.0),
+            Type::Bool => Const::ConstBool(false),
+            // a null pointer is all zero bits
+            Type::Pointer(_) | Type::Function(_) => Const::ConstUlong(0),
+            Type::Void => unreachable!("void has no values"),
+        }
+    }
+
+    /// whether the constant is false when used as a condition
+    pub fn is_zero(&self) -> bool {
+        match *self {
+            Const::ConstInt(v) => v == 0,
+            Const::ConstLong(v) => v == 0,
+            Const::ConstUint(v) => v == 0,
+            Const::ConstUlong(v) => v == 0,
+            Const::ConstDouble(v) => v == 0.0,
+            Const::ConstBool(v) => !v,
+        }
+    }
+
+    /// whether the constant is true when used as a condition
+    pub fn to_bool(&self) -> bool {
+        !self.is_zero()
+    }
+
+    /// the type of the constant
+    pub fn get_type(&self) -> Type {
+        match self {
+            Const::ConstInt(_) => Type::Int,
+            Const::ConstLong(_) => Type::Long,
+            Const::ConstUint(_) => Type::Uint,
+            Const::ConstUlong(_) => Type::Ulong,
+            Const::ConstDouble(_) => Type::Double,
+            Const::ConstBool(_) => Type::Bool,
+        }
+    }
+
+    /// the constant converted to `target`, truncated or extended the same way
+    /// the cast instructions do at run time; a double is truncated toward zero
+    /// and an integer rounds to the nearest double. Converting to `_Bool` is a
+    /// comparison with zero, so `0.5` becomes true where truncating it wouldn't.
+    pub fn convert_to(self, target: Type) -> Const {
+        if target == Type::Bool {
+            return Const::ConstBool(self.to_bool());
+        }
+        if let Const::ConstDouble(v) = self
+            && target.is_double()
+        {
+            return Const::ConstDouble(v);
+        }
+        Self::from_value(self.value(), target)
+    }
+
+    /// the quotient of two constants of the same type, `None` when it isn't defined:
+    /// an integer division by zero, or one that overflows like `INT_MIN / -1`,
+    /// a double division always has a result
+    pub fn checked_div(self, other: Const) -> Option<Const> {
+        match (self, other) {
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => Some(Const::ConstDouble(a / b)),
+            _ => {
+                let (a, b) = (self.value(), other.value());
+                self.checked_integer_result(a.checked_div(b)?)
+            }
+        }
+    }
+
+    /// the remainder of an integer division, `None` when the division isn't defined
+    /// (see `checked_div`) or for doubles, which have no `%`
+    pub fn checked_rem(self, other: Const) -> Option<Const> {
+        if self.get_type().is_double() {
+            return None;
+        }
+        self.checked_div(other)?;
+        self.checked_integer_result(self.value() % other.value())
+    }
+
+    /// The mathematical value of a constant, signed or unsigned according to its type,
+    /// 128 bits hold every result of the 64 bit operations exactly.
+    /// A double is truncated toward zero, which is all an integer conversion keeps of it.
+    fn value(&self) -> i128 {
+        match *self {
+            Const::ConstInt(v) => v as i128,
+            Const::ConstLong(v) => v as i128,
+            Const::ConstUint(v) => v as i128,
+            Const::ConstUlong(v) => v as i128,
+            Const::ConstDouble(v) => v as i128,
+            Const::ConstBool(v) => v as i128,
+        }
+    }
+
+    /// Build a constant of the given type from a value, keeping only the low bits.
+    fn from_value(value: i128, target: Type) -> Const {
+        match target {
+            Type::Int => Const::ConstInt(value as i32),
+            Type::Long => Const::ConstLong(value as i64),
+            Type::Uint => Const::ConstUint(value as u32),
+            Type::Ulong | Type::Pointer(_) | Type::Function(_) => Const::ConstUlong(value as u64),
+            // rounds to the nearest double
+            Type::Double => Const::ConstDouble(value as f64),
+            Type::Bool => Const::ConstBool(value != 0),
+            Type::Void => unreachable!("void has no values"),
+        }
+    }
+
+    /// an integer result in the type of `self`, wrapped around its width
+    fn integer_result(&self, value: i128) -> Const {
+        Self::from_value(value, self.get_type())
+    }
+
+    /// an integer result in the type of `self`, `None` if it doesn't fit
+    fn checked_integer_result(&self, value: i128) -> Option<Const> {
+        let result = self.integer_result(value);
+        (result.value() == value).then_some(result)
+    }
+}
+
+// The arithmetic operators take two constants of the same type and return a constant of that
+// type, integers wrap around their width like the instructions computing them at run time
+
+impl ops::Add for Const {
+    type Output = Const;
+
+    fn add(self, other: Const) -> Const {
+        match (self, other) {
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => Const::ConstDouble(a + b),
+            _ => self.integer_result(self.value().wrapping_add(other.value())),
+        }
+    }
+}
+
+impl ops::Sub for Const {
+    type Output = Const;
+
+    fn sub(self, other: Const) -> Const {
+        match (self, other) {
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => Const::ConstDouble(a - b),
+            _ => self.integer_result(self.value().wrapping_sub(other.value())),
+        }
+    }
+}
+
+impl ops::Mul for Const {
+    type Output = Const;
+
+    fn mul(self, other: Const) -> Const {
+        match (self, other) {
+            (Const::ConstDouble(a), Const::ConstDouble(b)) => Const::ConstDouble(a * b),
+            _ => self.integer_result(self.value().wrapping_mul(other.value())),
+        }
+    }
+}
+
+impl ops::Neg for Const {
+    type Output = Const;
+
+    /// the constant negated in its own type, integers wrap like `neg` does at run time
+    fn neg(self) -> Const {
+        match self {
+            Const::ConstDouble(v) => Const::ConstDouble(-v),
+            _ => self.integer_result(self.value().wrapping_neg()),
+        }
+    }
 }
 
 // holds the type of initlizer a static variable can have.
@@ -193,37 +431,55 @@ pub enum StaticInit {
     LongInit(i64),
     UintInit(u32),
     UlongInit(u64),
+    DoubleInit(f64),
+    BoolInit(bool),
+}
+
+impl StaticInit {
+    /// whether every byte of the initializer is zero, which is the case for the
+    /// `get_tentative_init` value of its type but not for `-0.0`
+    pub fn is_zero(&self) -> bool {
+        match *self {
+            StaticInit::IntInit(v) => v == 0,
+            StaticInit::LongInit(v) => v == 0,
+            StaticInit::UintInit(v) => v == 0,
+            StaticInit::UlongInit(v) => v == 0,
+            StaticInit::DoubleInit(v) => v.to_bits() == 0,
+            StaticInit::BoolInit(v) => !v,
+        }
+    }
 }
 
 /// Represents an operand size in assembly
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum OperandSize {
+    Byte,     // byte for 1 byte operands
     LongWord, // long word for 4 byte operands
     QuadWord, // quad word for 8 byte operands
+    Double,   // 8 byte floating point operands, held in XMM registers
 }
 
 pub fn convert_type_to_operand_size(t: Type) -> OperandSize {
     match t {
+        Type::Bool => OperandSize::Byte,
         Type::Int | Type::Uint => OperandSize::LongWord,
-        Type::Long | Type::Ulong => OperandSize::QuadWord,
+        Type::Long | Type::Ulong | Type::Pointer(_) | Type::Function(_) => OperandSize::QuadWord,
+        Type::Double => OperandSize::Double,
+        Type::Void => unreachable!("void has no values, so no operands"),
     }
 }
 
 /// convert a const into StaticInit according to the variable type
 pub fn convert_constant_value_to_static_init(cons_val: Const, var_type: Type) -> StaticInit {
-    // Normalize constant value to a u64 or i64 depending on signedness
-    let (unsigned_value, signed_value) = match cons_val {
-        Const::ConstInt(v) => (v as u64, v as i64),
-        Const::ConstLong(v) => (v as u64, v),
-        Const::ConstUint(v) => (v as u64, v as i64),
-        Const::ConstUlong(v) => (v, v as i64),
-    };
-
-    match var_type {
-        Type::Int => StaticInit::IntInit(signed_value as i32),
-        Type::Long => StaticInit::LongInit(signed_value as i64),
-        Type::Uint => StaticInit::UintInit(unsigned_value as u32),
-        Type::Ulong => StaticInit::UlongInit(unsigned_value as u64),
+    // the constant is converted like a run time conversion would,
+    // the type checker only accepts a null pointer constant for a pointer
+    match cons_val.convert_to(var_type) {
+        Const::ConstInt(v) => StaticInit::IntInit(v),
+        Const::ConstLong(v) => StaticInit::LongInit(v),
+        Const::ConstUint(v) => StaticInit::UintInit(v),
+        Const::ConstUlong(v) => StaticInit::UlongInit(v),
+        Const::ConstDouble(v) => StaticInit::DoubleInit(v),
+        Const::ConstBool(v) => StaticInit::BoolInit(v),
     }
 }
 
@@ -233,6 +489,66 @@ pub fn get_tentative_init(var_type: Type) -> StaticInit {
         Type::Int => StaticInit::IntInit(0),
         Type::Long => StaticInit::LongInit(0),
         Type::Uint => StaticInit::UintInit(0),
-        Type::Ulong => StaticInit::UlongInit(0),
+        Type::Ulong | Type::Pointer(_) | Type::Function(_) => StaticInit::UlongInit(0),
+        Type::Double => StaticInit::DoubleInit(0.0),
+        Type::Bool => StaticInit::BoolInit(false),
+        Type::Void => unreachable!("there are no void variables"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_arithmetic_wraps_around() {
+        let max = Const::ConstInt(i32::MAX);
+        let min = Const::ConstInt(i32::MIN);
+        assert!(matches!(
+            max + Const::ConstInt(1),
+            Const::ConstInt(i32::MIN)
+        ));
+        assert!(matches!(
+            min - Const::ConstInt(1),
+            Const::ConstInt(i32::MAX)
+        ));
+        assert!(matches!(max * Const::ConstInt(2), Const::ConstInt(-2)));
+        assert!(matches!(-min, Const::ConstInt(i32::MIN)));
+    }
+
+    #[test]
+    fn unsigned_and_long_arithmetic_wraps_around() {
+        assert!(matches!(
+            Const::ConstUint(0) - Const::ConstUint(1),
+            Const::ConstUint(u32::MAX)
+        ));
+        assert!(matches!(
+            Const::ConstLong(i64::MAX) + Const::ConstLong(1),
+            Const::ConstLong(i64::MIN)
+        ));
+        assert!(matches!(
+            Const::ConstUlong(u64::MAX) * Const::ConstUlong(u64::MAX),
+            Const::ConstUlong(1)
+        ));
+    }
+
+    #[test]
+    fn overflowing_division_has_no_result() {
+        let min = Const::ConstInt(i32::MIN);
+        assert!(min.checked_div(Const::ConstInt(-1)).is_none());
+        assert!(min.checked_rem(Const::ConstInt(-1)).is_none());
+        assert!(Const::ConstInt(1).checked_div(Const::ConstInt(0)).is_none());
+        assert!(matches!(
+            Const::ConstInt(-7).checked_div(Const::ConstInt(2)),
+            Some(Const::ConstInt(-3))
+        ));
+    }
+
+    #[test]
+    fn only_bool_is_promoted() {
+        assert_eq!(Type::Bool.promote(), Type::Int);
+        for t in [Type::Int, Type::Long, Type::Uint, Type::Ulong, Type::Double] {
+            assert_eq!(t.promote(), t);
+        }
     }
 }
@@ -0,0 +1,34 @@
+/// Selects which optimization passes run over the IR and the assembly.
+///
+/// The default runs no passes, which keeps the output identical to an
+/// unoptimized build.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OptPasses {
+    pub constant_folding: bool, // fold operations on constant operands in the IR
+    pub dead_code_elimination: bool, // remove unreachable IR instructions and unused labels
+    pub strength_reduction: bool, // multiply and divide by powers of two with shifts
+    pub peephole: bool,         // remove redundant assembly instructions
+    pub branchless_select: bool, // lower `c ? a : b` on plain values to a `cmov`
+    pub inlining: bool,         // replace calls to small functions with their body
+    pub linear_scan: bool,      // keep variables in callee-saved registers instead of stack slots
+}
+
+impl OptPasses {
+    /// Builds the pass selection for an optimization level:
+    /// - `0` runs no passes
+    /// - `1` runs constant folding and dead code elimination, and replaces multiplications
+    ///   and unsigned divisions by a power of two with shifts
+    /// - `2` and above also run the peephole pass, lower simple conditionals without branches,
+    ///   inline small functions and allocate registers with linear scan
+    pub fn from_level(level: u8) -> Self {
+        Self {
+            constant_folding: level >= 1,
+            dead_code_elimination: level >= 1,
+            strength_reduction: level >= 1,
+            peephole: level >= 2,
+            branchless_select: level >= 2,
+            inlining: level >= 2,
+            linear_scan: level >= 2,
+        }
+    }
+}
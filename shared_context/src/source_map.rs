@@ -1,12 +1,14 @@
 use colored::Colorize;
+use std::collections::HashMap;
 
 use crate::Span;
 
 /// Represents the mapping between AST positions and source code positions.
 /// Useful for error reporting, so we can highlight exactly where an error occurs.
 pub struct SourceMap<'a> {
-    source_code: &'a str, // The full source code as a string slice
-    file_name: &'a str,   // Name of the file containing the source code
+    source_code: &'a str,    // The full source code as a string slice
+    file_name: &'a str,      // Name of the file containing the source code
+    line_starts: Vec<usize>, // offset of the first byte of each line, in increasing order
 }
 
 impl<'a> SourceMap<'a> {
@@ -16,12 +18,29 @@ impl<'a> SourceMap<'a> {
     /// - `file_name`: name of the source file
     /// - `source_code`: the content of the file
     pub fn new(file_name: &'a str, source_code: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source_code.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
         Self {
             source_code,
             file_name,
+            line_starts,
         }
     }
 
+    /// Returns the 1-based line and column of a byte offset in the source.
+    ///
+    /// Lines are counted in the source as given, unlike a span's line which follows
+    /// the preprocessor's line markers. The column counts bytes, a newline is the last
+    /// column of its line and the offset one past the end is on the last line.
+    pub fn line_and_column(&self, offset: usize) -> (usize, usize) {
+        debug_assert!(offset <= self.source_code.len(), "offset out of the source");
+        // the number of lines starting at or before the offset is the line it's on
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let column = offset - self.line_starts[line - 1] + 1;
+        (line, column)
+    }
+
     /// Formats an error message with source context.
     /// Highlights the part of the source code indicated by `span` and appends the `message`.
     ///
@@ -34,11 +53,11 @@ impl<'a> SourceMap<'a> {
     /// ```
     pub fn format_message(&self, message: &str, span: Span) -> String {
         let line_text = self.get_line_text(span.start); // Get the text of the line containing the error
-        let column = self.get_col_number(span.start); // Determine the column number of the error
+        let (_, column) = self.line_and_column(span.start); // Determine the column number of the error
 
         let mut marker_line = String::new();
         let marker_start = column.saturating_sub(1); // Column index starts at 0
-        let marker_len = (span.end - span.start).max(1); // At least one character should be marked
+        let marker_len = span.end.saturating_sub(span.start).max(1); // At least one character should be marked
 
         // Construct a line with markers (^) and (~) showing the span
         let tilde = "~".red().bold().to_string();
@@ -86,17 +105,51 @@ impl<'a> SourceMap<'a> {
         &self.source_code[start..end]
     }
 
-    /// Returns the column number (1-based) corresponding to the given offset in the source
-    fn get_col_number(&self, offset: usize) -> usize {
-        let source = self.get_source_code();
-        let last_newline_index = source[..offset]
-            .char_indices()
-            .rfind(|&(_, ch)| ch == '\n')
-            .map(|(index, _)| index)
-            .unwrap_or(0);
-
-        let col = source[last_newline_index..offset].len();
-        col
+    /// Returns the text of each line of the main source file, by line number.
+    ///
+    /// Lines are numbered like a span's line, following the preprocessor's line markers
+    /// (`# 42 "source.c"` or `#line 42`). The main file is the one the first marker names,
+    /// the lines of the headers it includes are left out.
+    pub fn main_file_lines(&self) -> HashMap<usize, &'a str> {
+        let mut lines = HashMap::new();
+        let mut main_file = None;
+        let mut in_main_file = true;
+        let mut line_number = 1;
+
+        for text in self.source_code.lines() {
+            match Self::parse_line_marker(text) {
+                Some((number, file)) => {
+                    if let Some(file) = file {
+                        let main_file = *main_file.get_or_insert(file);
+                        in_main_file = file == main_file;
+                    }
+                    line_number = number;
+                }
+                None => {
+                    if in_main_file {
+                        lines.insert(line_number, text);
+                    }
+                    line_number += 1;
+                }
+            }
+        }
+
+        lines
+    }
+
+    /// The line number and file name of a line marker, `None` for any other line
+    fn parse_line_marker(text: &str) -> Option<(usize, Option<&str>)> {
+        let rest = text.trim_start().strip_prefix('#')?.trim_start();
+        let rest = rest.strip_prefix("line").unwrap_or(rest).trim_start();
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number = rest[..digits].parse().ok()?;
+        let file = rest[digits..]
+            .trim_start()
+            .strip_prefix('"')
+            .and_then(|name| name.split('"').next());
+        Some((number, file))
     }
 
     /// Returns the file name associated with this SourceMap
@@ -109,3 +162,36 @@ impl<'a> SourceMap<'a> {
         self.source_code
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_and_column_at_line_boundaries() {
+        let smap = SourceMap::new("test.c", "ab\ncd\n\nx");
+        assert_eq!(smap.line_and_column(0), (1, 1));
+        // the newline is the last column of its line
+        assert_eq!(smap.line_and_column(2), (1, 3));
+        assert_eq!(smap.line_and_column(3), (2, 1));
+        assert_eq!(smap.line_and_column(5), (2, 3));
+        // an empty line only holds its newline
+        assert_eq!(smap.line_and_column(6), (3, 1));
+        assert_eq!(smap.line_and_column(7), (4, 1));
+        // the offset one past the end is on the last line
+        assert_eq!(smap.line_and_column(8), (4, 2));
+    }
+
+    #[test]
+    fn line_and_column_of_an_empty_source() {
+        let smap = SourceMap::new("test.c", "");
+        assert_eq!(smap.line_and_column(0), (1, 1));
+    }
+
+    #[test]
+    fn line_and_column_after_a_trailing_newline() {
+        let smap = SourceMap::new("test.c", "a\n");
+        assert_eq!(smap.line_and_column(1), (1, 2));
+        assert_eq!(smap.line_and_column(2), (2, 1));
+    }
+}
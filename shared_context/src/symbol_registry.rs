@@ -33,6 +33,10 @@ impl SymbolRegistery {
                     let var_sy = VarSy::new(ty, true);
                     var_table.insert(iden, var_sy);
                 }
+                (EntryType::Array { element, count }, IdenAttrs::LocalAttrs) => {
+                    let var_sy = VarSy::new(element, false).with_array_len(count);
+                    var_table.insert(iden, var_sy);
+                }
                 (
                     EntryType::Func(ty_id),
                     IdenAttrs::FunAttrs {
@@ -62,6 +66,11 @@ impl SymbolRegistery {
         &self.fun_table[iden]
     }
 
+    /// Whether the identifier names a function rather than a variable.
+    pub fn is_function(&self, iden: &Identifier) -> bool {
+        self.fun_table.contains_key(iden)
+    }
+
     /// Retrieves a static variable symbol entry by its `Identifier`.
     ///
     /// # Panics
@@ -69,21 +78,43 @@ impl SymbolRegistery {
     pub fn get_variable(&self, iden: &Identifier) -> &VarSy {
         &self.var_table[iden]
     }
+
+    /// Returns every variable symbol entry, in no particular order.
+    pub fn get_variables(&self) -> impl Iterator<Item = (&Identifier, &VarSy)> {
+        self.var_table.iter()
+    }
+
+    /// Returns every function symbol entry, in no particular order.
+    pub fn get_functions(&self) -> impl Iterator<Item = (&Identifier, &FunSy)> {
+        self.fun_table.iter()
+    }
 }
 
 /// Symbol data for a **static variable**.
 ///
 /// Contains its declared type, whether it’s external (`extern`),
 /// and its initial value.
+/// The type of an array is its element type.
 pub struct VarSy {
     ty: Type,
     is_static: bool,
+    array_len: Option<usize>, // the number of elements of an array
 }
 
 impl VarSy {
     /// Creates a new `VarSy` entry.
     pub fn new(ty: Type, is_static: bool) -> Self {
-        Self { ty, is_static }
+        Self {
+            ty,
+            is_static,
+            array_len: None,
+        }
+    }
+
+    /// Records the variable is an array of `array_len` elements of its type.
+    pub fn with_array_len(mut self, array_len: usize) -> Self {
+        self.array_len = Some(array_len);
+        self
     }
 
     /// Returns a reference to the type of this static variable.
@@ -91,6 +122,16 @@ impl VarSy {
         self.ty
     }
 
+    /// Returns the number of elements if the variable is an array.
+    pub fn get_array_len(&self) -> Option<usize> {
+        self.array_len
+    }
+
+    /// Returns the size of the variable in bytes, all of an array's elements.
+    pub fn get_size(&self) -> usize {
+        self.ty.size() * self.array_len.unwrap_or(1)
+    }
+
     pub fn is_static(&self) -> bool {
         self.is_static
     }
@@ -39,10 +39,18 @@ impl SymbolTable {
                 attributes,
                 entry_type,
                 span,
+                is_const: false,
             },
         );
     }
 
+    /// Marks an identifier as declared with the `const` qualifier.
+    pub fn mark_const(&mut self, key: Identifier) {
+        if let Some(entry) = self.table.get_mut(&key) {
+            entry.is_const = true;
+        }
+    }
+
     /// get a reference to the hash map in symbol table
     pub fn get_table(self) -> HashMap<Identifier, SymbolEntry> {
         self.table
@@ -60,6 +68,7 @@ pub struct SymbolEntry {
     pub entry_type: EntryType, // Type of the identifier (variable or function)
     pub attributes: IdenAttrs, // hold metadata about the identifier
     pub span: Span,            // Source code span where the identifier was declared
+    pub is_const: bool,        // declared with the `const` qualifier, so it can't be assigned to
 }
 
 impl SymbolEntry {
@@ -83,6 +92,7 @@ impl SymbolEntry {
 pub enum EntryType {
     Scalar(Type),
     Func(TypeID),
+    Array { element: Type, count: usize }, // a local array of `count` elements
 }
 
 // the identifier attributes type hold metadata about the identifier
@@ -100,6 +110,8 @@ pub enum IdenAttrs {
     },
     // repressent variables with automatic storage duration
     LocalAttrs,
+    // an enumerator, every use of it is replaced with its value
+    ConstantAttrs(i32),
 }
 
 impl IdenAttrs {
@@ -113,7 +125,7 @@ impl IdenAttrs {
                 init_value: _,
                 external,
             } => *external,
-            IdenAttrs::LocalAttrs => false,
+            IdenAttrs::LocalAttrs | IdenAttrs::ConstantAttrs(_) => false,
         }
     }
 
@@ -127,7 +139,7 @@ impl IdenAttrs {
                 init_value: _,
                 external: _,
             } => true,
-            IdenAttrs::LocalAttrs => true,
+            IdenAttrs::LocalAttrs | IdenAttrs::ConstantAttrs(_) => true,
         }
     }
 
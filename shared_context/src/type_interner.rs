@@ -11,6 +11,13 @@ use std::collections::HashMap;
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct TypeID(u32);
 
+/// A unique identifier for an interned pointer type, `Type::Pointer` holds it.
+///
+/// Pointers to the same type share an ID, so two pointer types
+/// are the same type exactly when their IDs are equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PointerID(u32);
+
 /// Represents a function type.
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct FunctionType<'a> {
@@ -18,14 +25,16 @@ pub struct FunctionType<'a> {
     pub ret: Type,
     /// A slice of parameter types (allocated in a bump arena).
     pub params: &'a [Type],
+    /// Whether the parameter list ends in `...`, accepting extra arguments.
+    pub is_variadic: bool,
 }
 
 /// Stores all unique (interned) types within a compilation context.
 ///
 /// This interner ensures that structurally identical types share a
-/// single canonical representation in memory. For now, it only handles
-/// function types, but it can be extended later to intern other type
-/// forms such as structs, enums, pointers, and arrays.
+/// single canonical representation in memory. It handles function
+/// and pointer types, but it can be extended later to intern other type
+/// forms such as structs, enums, and arrays.
 pub struct TypeInterner<'a> {
     /// The bump allocator used for storing immutable type data.
     arena: &'a Bump,
@@ -33,6 +42,10 @@ pub struct TypeInterner<'a> {
     map: HashMap<FunctionType<'a>, TypeID>,
     /// Stores all interned types; the index corresponds to the ID.
     types: Vec<&'a FunctionType<'a>>,
+    /// Maps referenced types to the ID of the pointer type to them.
+    pointer_map: HashMap<Type, PointerID>,
+    /// Stores the referenced type of every pointer type; the index corresponds to the ID.
+    referenced: Vec<Type>,
 }
 
 impl<'a> TypeInterner<'a> {
@@ -42,17 +55,23 @@ impl<'a> TypeInterner<'a> {
             arena,
             map: HashMap::new(),
             types: Vec::new(),
+            pointer_map: HashMap::new(),
+            referenced: Vec::new(),
         }
     }
 
-    /// Interns a function type composed of `ret` and `params`.
+    /// Interns a function type composed of `ret`, `params`, and whether it's variadic.
     ///
     /// If an identical function type already exists, its existing ID
     /// is returned instead of creating a duplicate. Otherwise, the
     /// type and its parameter list are allocated in the bump arena.
-    pub fn intern(&mut self, ret: Type, params: &[Type]) -> TypeID {
+    pub fn intern(&mut self, ret: Type, params: &[Type], is_variadic: bool) -> TypeID {
         // Temporary key for deduplication
-        let key = FunctionType { ret, params };
+        let key = FunctionType {
+            ret,
+            params,
+            is_variadic,
+        };
 
         // If this function type was already interned, return its ID
         if let Some(&id) = self.map.get(&key) {
@@ -66,6 +85,7 @@ impl<'a> TypeInterner<'a> {
         let fty = self.arena.alloc(FunctionType {
             ret,
             params: params_copy,
+            is_variadic,
         });
 
         // Assign the next available ID
@@ -76,6 +96,7 @@ impl<'a> TypeInterner<'a> {
             FunctionType {
                 ret,
                 params: params_copy,
+                is_variadic,
             },
             id,
         );
@@ -91,4 +112,50 @@ impl<'a> TypeInterner<'a> {
     pub fn get(&self, id: TypeID) -> &'a FunctionType<'a> {
         self.types[id.0 as usize]
     }
+
+    /// Interns the type of a pointer to `referenced`, returning the existing
+    /// pointer type if one to the same type was already interned.
+    pub fn intern_pointer(&mut self, referenced: Type) -> Type {
+        if let Some(&id) = self.pointer_map.get(&referenced) {
+            return Type::Pointer(id);
+        }
+
+        let id = PointerID(self.referenced.len() as u32);
+        self.pointer_map.insert(referenced, id);
+        self.referenced.push(referenced);
+        Type::Pointer(id)
+    }
+
+    /// Retrieves the type a pointer type points to.
+    ///
+    /// # Panics
+    /// Panics if the given ID does not correspond to a valid interned pointer type.
+    pub fn get_referenced(&self, id: PointerID) -> Type {
+        self.referenced[id.0 as usize]
+    }
+
+    /// Formats a type for diagnostics and debug output, pointers show what they point to
+    /// and functions their return and parameter types, e.g. `Pointer(Func(Int(Int, Long)))`.
+    pub fn format_type(&self, ty: Type) -> String {
+        match ty {
+            Type::Pointer(id) => format!("Pointer({})", self.format_type(self.get_referenced(id))),
+            Type::Function(id) => {
+                let fun_type = self.get(id);
+                let mut params: Vec<String> = fun_type
+                    .params
+                    .iter()
+                    .map(|t| self.format_type(*t))
+                    .collect();
+                if fun_type.is_variadic {
+                    params.push("...".to_string());
+                }
+                format!(
+                    "Func({}({}))",
+                    self.format_type(fun_type.ret),
+                    params.join(", ")
+                )
+            }
+            _ => format!("{:?}", ty),
+        }
+    }
 }